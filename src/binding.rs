@@ -1,16 +1,75 @@
 use crate::{
-    block::PlaneBlock as PlaneBlockBase,
-    components::flight::MechanicalModel,
+    block::{PlaneBlock as PlaneBlockBase, PlaneBlockSnapshot as PlaneBlockSnapshotBase},
+    components::{
+        atmosphere::{
+            AirData as AirDataBase, AirProperties as AirPropertiesBase,
+            Atmosphere as AtmosphereBase, SimpleAtmosphere, StandardAtmosphere,
+            TabulatedAtmosphere,
+        },
+        autopilot::{
+            Autopilot as AutopilotBase, AutopilotGains, LateralMode, SpeedMode, VerticalMode,
+        },
+        basic::{LinearModel as LinearModelBase, LinearModelSnapshot as LinearModelSnapshotBase},
+        control::{
+            Block, Pid as PidBase, RateLimiter as RateLimiterBase, Saturation as SaturationBase,
+            StateSpace as StateSpaceBase, TransferFunction as TransferFunctionBase,
+            TransportDelay as TransportDelayBase, ZeroOrderHold as ZeroOrderHoldBase,
+        },
+        earth::Geodetic as GeodeticBase,
+        engine::{
+            power_rate, tgear, throttle_for_power, EngineModel, EngineState as EngineStateBase,
+        },
+        flcs::{Flcs as FlcsBase, FlcsGains},
+        flight::MechanicalModel,
+        group::{
+            ActuatorDynamics, ActuatorFailure as ActuatorFailureBase,
+            ActuatorModel as ActuatorModelBase,
+        },
+        mass::{
+            FuelConsumption as FuelConsumptionBase, MassModel as MassModelBase, Tank as TankBase,
+        },
+        sensor::{
+            total_pressure, Ahrs as AhrsBase, AirDataSensor as AirDataSensorBase, Imu as ImuBase,
+            SensorModel as SensorModelBase, Sensors as SensorsBase,
+        },
+        signal::{Signal as SignalBase, Sweep as SweepBase},
+        wind::{
+            AirRelative as AirRelativeBase, Gust as GustBase, Shear as ShearBase,
+            Spectrum as SpectrumBase, Turbulence as TurbulenceBase, WindModel,
+            WindSample as WindSampleBase,
+        },
+    },
+    event::{Direction as DirectionBase, Event as EventBase, EventRecord as EventRecordBase},
+    handling::{
+        handling_qualities as handling_qualities_base, AircraftClass, Category,
+        Criterion as CriterionBase, HandlingOptions, HandlingQualities as HandlingQualitiesBase,
+    },
+    linearize::{
+        linearize as linearize_base, Linearization as LinearizationBase, LinearizeOptions,
+    },
+    modal::{
+        modal_analysis as modal_analysis_base, ModalAnalysis as ModalAnalysisBase,
+        Mode as ModeBase, ModeKind,
+    },
     model::{
-        Control as ControlBase, ControlLimit as ControlLimitBase, CoreInit as CoreInitBase,
-        CoreOutput as CoreOutputBase, FlightCondition as FlightConditionBase,
+        Attitude as AttitudeBase, Control as ControlBase, ControlLimit as ControlLimitBase,
+        CoreInit as CoreInitBase, CoreOutput as CoreOutputBase,
+        FlightCondition as FlightConditionBase, Formulation as FormulationBase,
+        MassState as MassStateBase, Measurements as MeasurementsBase, Navigation as NavigationBase,
         PlaneConstants as PlaneConstantsBase, State as StateBase, StateExtend as StateExtendBase,
+        Velocity as VelocityBase,
     },
     optimizer::nelder_mead::{
         NelderMeadOptions as NelderMeadOptionsBase, NelderMeadResult as NelderMeadResultBase,
     },
     plugin::{AerodynamicModel as AerodynamicModelBase, AsPlugin},
+    runner::{
+        simulate, simulate_closed_loop, ControlSchedule as ControlScheduleBase, DiscreteController,
+        Interpolation as InterpolationBase, Trajectory as TrajectoryBase,
+    },
     solver::{
+        adaptive::{RK45Solver, SolverStats as SolverStatsBase},
+        implicit::{BackwardEulerSolver, NewtonOptions, SDIRK2Solver, TrapezoidalSolver},
         rk::{RK1Solver, RK2Solver, RK3Solver, RK4Solver},
         VectorODESolver,
     },
@@ -18,11 +77,15 @@ use crate::{
         trim as trim_base, TrimInit as TrimInitBase, TrimOutput as TrimOutputBase,
         TrimTarget as TrimTargetBase,
     },
-    utils::Vector,
+    utils::{error::FatalCoreError, Matrix, Vector},
 };
 use log::error;
-use pyo3::{exceptions::PyValueError, prelude::*, types::PyTuple};
-use std::{cell::RefCell, rc::Rc, sync::Arc};
+use pyo3::{
+    exceptions::PyValueError,
+    prelude::*,
+    types::{PyComplex, PyTuple},
+};
+use std::{cell::RefCell, collections::HashMap, rc::Rc, sync::Arc};
 
 #[pyclass]
 struct PlaneConstants(PlaneConstantsBase);
@@ -803,6 +866,22 @@ impl CoreOutput {
     fn state_extend(&self) -> StateExtend {
         StateExtend(self.0.state_extend.clone())
     }
+
+    #[getter]
+    fn mass(&self) -> Option<MassState> {
+        self.0.mass.map(MassState)
+    }
+
+    #[getter]
+    fn sensors(&self) -> Option<Measurements> {
+        self.0.sensors.map(Measurements)
+    }
+
+    /// Airspeed, alpha and beta relative to the air
+    #[getter]
+    fn air(&self) -> AirRelative {
+        AirRelative(self.0.air_relative())
+    }
 }
 
 #[pyclass]
@@ -839,169 +918,4012 @@ impl CoreInit {
     }
 }
 
-#[pyfunction]
-#[pyo3(signature = (model, trim_target, ctrl_limit, trim_init=None, flight_condition=None, optim_options=None))]
-fn trim(
-    model: &AerodynamicModel,
-    trim_target: &TrimTarget,
-    ctrl_limit: &ControlLimit,
-    trim_init: Option<&TrimInit>,
-    flight_condition: Option<&FlightCondition>,
-    optim_options: Option<&NelderMeadOptions>,
-) -> PyResult<TrimOutput> {
-    match MechanicalModel::new(&model.0) {
-        Ok(m) => {
-            let model = Rc::new(RefCell::new(m));
-            let res = trim_base(
-                model.clone(),
-                trim_target.0,
-                trim_init.map(|x| x.0),
-                ctrl_limit.0,
-                flight_condition.map(|x| x.0),
-                optim_options.map(|x| x.0),
-            );
-            match res {
-                Ok(o) => Ok(TrimOutput(o)),
-                Err(e) => {
-                    error!("{}", e);
-                    Err(PyValueError::new_err(e.to_string()))
-                }
-            }
+#[pyclass]
+struct Interpolation(InterpolationBase);
+
+#[pymethods]
+impl Interpolation {
+    #[new]
+    fn new(value: i32) -> Self {
+        match value {
+            0 => Self(InterpolationBase::Step),
+            1 => Self(InterpolationBase::Linear),
+            _ => panic!("Invalid value for Interpolation"),
         }
-        Err(e) => {
-            error!("{}", e);
-            return Err(PyValueError::new_err(e.to_string()));
+    }
+
+    #[getter]
+    fn value(&self) -> i32 {
+        match self.0 {
+            InterpolationBase::Step => 0,
+            InterpolationBase::Linear => 1,
         }
     }
+
+    #[staticmethod]
+    fn step() -> Self {
+        Self(InterpolationBase::Step)
+    }
+
+    #[staticmethod]
+    fn linear() -> Self {
+        Self(InterpolationBase::Linear)
+    }
 }
 
-macro_rules! create_simple_solver {
-    ($name:ident, $solver:ty) => {
-        #[pyclass]
-        struct $name {
-            solver: $solver,
+#[pyclass]
+struct ControlSchedule(ControlScheduleBase);
+
+#[pymethods]
+impl ControlSchedule {
+    #[new]
+    #[pyo3(signature = (time, controls, interpolation=None))]
+    fn new(
+        time: Vec<f64>,
+        controls: Vec<PyRef<Control>>,
+        interpolation: Option<&Interpolation>,
+    ) -> PyResult<Self> {
+        let controls = controls.iter().map(|c| c.0).collect();
+        Self::build(time, controls, interpolation)
+    }
+
+    #[staticmethod]
+    #[pyo3(signature = (time, controls, interpolation=None))]
+    fn from_list(
+        time: Vec<f64>,
+        controls: Vec<Vec<f64>>,
+        interpolation: Option<&Interpolation>,
+    ) -> PyResult<Self> {
+        if let Some(c) = controls.iter().find(|c| c.len() != 4) {
+            return Err(PyValueError::new_err(format!(
+                "each control must have exactly 4 elements, got {}",
+                c.len()
+            )));
         }
+        let controls = controls.into_iter().map(ControlBase::from).collect();
+        Self::build(time, controls, interpolation)
+    }
 
-        #[pymethods]
-        impl $name {
-            #[new]
-            fn new(step: f64) -> Self {
-                Self {
-                    solver: <$solver>::new(step),
-                }
-            }
+    #[staticmethod]
+    fn constant(control: &Control) -> Self {
+        Self(ControlScheduleBase::constant(control.0))
+    }
 
-            fn solve(
-                &self,
-                dynamics: PyObject,
-                t: f64,
-                state: Vec<f64>,
-                input: Vec<f64>,
-            ) -> PyResult<Vec<f64>> {
-                let dynamics_closure = {
-                    move |t: f64, state: &Vector, input: &Vector| -> Vector {
-                        let state = state.data.clone();
-                        let input = input.data.clone();
-                        Python::with_gil(|py| {
-                            let args = (t, state, input);
-                            let result: Vec<f64> = dynamics
-                                .call1(py, args)
-                                .expect("Dynamics function call failed")
-                                .extract(py)
-                                .expect("Failed to extract f64 from PyAny");
-                            let result = Vector::from(result);
-                            result
-                        })
-                    }
-                };
-                let state = Vector::from(state);
-                let input = Vector::from(input);
-                let result = self.solver.solve(&dynamics_closure, t, &state, &input);
-                Ok(result.data.clone())
-            }
+    fn at(&self, t: f64) -> Control {
+        Control(self.0.at(t))
+    }
 
-            #[getter]
-            fn delta_t(&self) -> f64 {
-                self.solver.delta_t()
+    #[getter]
+    fn time(&self) -> Vec<f64> {
+        self.0.time().to_vec()
+    }
+
+    #[getter]
+    fn controls(&self) -> Vec<Vec<f64>> {
+        self.0.control().iter().map(|&c| c.into()).collect()
+    }
+
+    #[getter]
+    fn interpolation(&self) -> Interpolation {
+        Interpolation(self.0.interpolation())
+    }
+}
+
+impl ControlSchedule {
+    fn build(
+        time: Vec<f64>,
+        controls: Vec<ControlBase>,
+        interpolation: Option<&Interpolation>,
+    ) -> PyResult<Self> {
+        let interpolation = interpolation.map(|i| i.0).unwrap_or_default();
+        match ControlScheduleBase::new(time, controls, interpolation) {
+            Ok(s) => Ok(Self(s)),
+            Err(e) => {
+                error!("{}", e);
+                Err(PyValueError::new_err(e.to_string()))
             }
         }
-    };
+    }
 }
 
-create_simple_solver!(SimpleSolverRK1, RK1Solver);
-create_simple_solver!(SimpleSolverRK2, RK2Solver);
-create_simple_solver!(SimpleSolverRK3, RK3Solver);
-create_simple_solver!(SimpleSolverRK4, RK4Solver);
+#[pyclass]
+struct Trajectory(TrajectoryBase);
 
-macro_rules! create_plane_block {
-    ($name:ident, $solver:ty) => {
-        #[pyclass]
-        struct $name(PlaneBlockBase<$solver>);
+#[pymethods]
+impl Trajectory {
+    fn __len__(&self) -> usize {
+        self.0.len()
+    }
 
-        #[pymethods]
-        impl $name {
-            #[new]
-            fn new(
-                step: f64,
-                model: &AerodynamicModel,
-                init: &CoreInit,
-                deflection: Vec<f64>,
-                ctrl_limit: &ControlLimit,
-            ) -> PyResult<Self> {
-                if deflection.len() != 3 {
-                    return Err(PyValueError::new_err(
-                        "deflection must have exactly 3 elements",
-                    ));
-                }
-                let deflection_array: [f64; 3] = [deflection[0], deflection[1], deflection[2]];
-                let solver = <$solver>::new(step);
-                let solver = Arc::new(solver);
-                let plane =
-                    PlaneBlockBase::new(solver, &model.0, &init.0, &deflection_array, ctrl_limit.0);
-                match plane {
-                    Ok(p) => Ok(Self(p)),
-                    Err(e) => {
-                        error!("{}", e);
-                        Err(PyValueError::new_err(e.to_string()))
-                    }
-                }
-            }
+    #[getter]
+    fn time(&self) -> Vec<f64> {
+        self.0.time.clone()
+    }
 
-            fn update(&mut self, control: &Control, t: f64) -> PyResult<CoreOutput> {
-                match self.0.update(control.0, t) {
-                    Ok(o) => Ok(CoreOutput(o)),
-                    Err(e) => {
-                        error!("{}", e);
-                        Err(PyValueError::new_err(e.to_string()))
-                    }
-                }
-            }
+    #[getter]
+    fn state(&self) -> Vec<Vec<f64>> {
+        self.0.state.iter().map(|&s| s.into()).collect()
+    }
 
-            fn reset(&mut self, init: &CoreInit) {
-                self.0.reset(&init.0);
-            }
+    #[getter]
+    fn control(&self) -> Vec<Vec<f64>> {
+        self.0.control.iter().map(|&c| c.into()).collect()
+    }
 
-            #[getter]
-            fn state(&self) -> CoreOutput {
-                CoreOutput(self.0.state())
-            }
+    #[getter]
+    fn state_extend(&self) -> Vec<Vec<f64>> {
+        self.0.state_extend.iter().map(|&e| e.into()).collect()
+    }
 
-            #[getter]
-            fn state_dot(&self) -> State {
-                State(self.0.state_dot())
-            }
+    #[getter]
+    fn state_dot(&self) -> Vec<Vec<f64>> {
+        self.0.state_dot.iter().map(|&s| s.into()).collect()
+    }
 
-            fn delete_model(&self) {
-                self.0.delete_model();
-            }
+    #[getter]
+    fn command(&self) -> Vec<Vec<f64>> {
+        self.0.command.iter().map(|&c| c.into()).collect()
+    }
+
+    #[getter]
+    fn mass(&self) -> Vec<Option<Vec<f64>>> {
+        self.0.mass.iter().map(|m| m.map(|m| m.into())).collect()
+    }
+
+    #[getter]
+    fn sensors(&self) -> Vec<Option<Measurements>> {
+        self.0.sensors.iter().map(|m| m.map(Measurements)).collect()
+    }
+
+    #[getter]
+    fn events(&self) -> Vec<EventRecord> {
+        self.0.events.iter().cloned().map(EventRecord).collect()
+    }
+}
+
+#[pyclass]
+struct Direction(DirectionBase);
+
+#[pymethods]
+impl Direction {
+    #[new]
+    fn new(value: i32) -> Self {
+        match value {
+            0 => Self(DirectionBase::Rising),
+            1 => Self(DirectionBase::Falling),
+            2 => Self(DirectionBase::Both),
+            _ => panic!("Invalid value for Direction"),
         }
-    };
+    }
+
+    #[getter]
+    fn value(&self) -> i32 {
+        match self.0 {
+            DirectionBase::Rising => 0,
+            DirectionBase::Falling => 1,
+            DirectionBase::Both => 2,
+        }
+    }
+
+    #[staticmethod]
+    fn rising() -> Self {
+        Self(DirectionBase::Rising)
+    }
+
+    #[staticmethod]
+    fn falling() -> Self {
+        Self(DirectionBase::Falling)
+    }
+
+    #[staticmethod]
+    fn both() -> Self {
+        Self(DirectionBase::Both)
+    }
 }
 
-create_plane_block!(PlaneBlockRK1, RK1Solver);
-create_plane_block!(PlaneBlockRK2, RK2Solver);
-create_plane_block!(PlaneBlockRK3, RK3Solver);
-create_plane_block!(PlaneBlockRK4, RK4Solver);
+#[pyclass]
+struct Attitude(AttitudeBase);
+
+#[pymethods]
+impl Attitude {
+    #[new]
+    fn new(value: i32) -> Self {
+        match value {
+            0 => Self(AttitudeBase::Euler),
+            1 => Self(AttitudeBase::Quaternion),
+            _ => panic!("Invalid value for Attitude"),
+        }
+    }
+
+    #[getter]
+    fn value(&self) -> i32 {
+        match self.0 {
+            AttitudeBase::Euler => 0,
+            AttitudeBase::Quaternion => 1,
+        }
+    }
+
+    #[staticmethod]
+    fn euler() -> Self {
+        Self(AttitudeBase::Euler)
+    }
+
+    #[staticmethod]
+    fn quaternion() -> Self {
+        Self(AttitudeBase::Quaternion)
+    }
+}
+
+#[pyclass]
+struct Velocity(VelocityBase);
+
+#[pymethods]
+impl Velocity {
+    #[new]
+    fn new(value: i32) -> Self {
+        match value {
+            0 => Self(VelocityBase::Wind),
+            1 => Self(VelocityBase::Body),
+            _ => panic!("Invalid value for Velocity"),
+        }
+    }
+
+    #[getter]
+    fn value(&self) -> i32 {
+        match self.0 {
+            VelocityBase::Wind => 0,
+            VelocityBase::Body => 1,
+        }
+    }
+
+    #[staticmethod]
+    fn wind() -> Self {
+        Self(VelocityBase::Wind)
+    }
+
+    #[staticmethod]
+    fn body() -> Self {
+        Self(VelocityBase::Body)
+    }
+}
+
+#[pyclass]
+struct Navigation(NavigationBase);
+
+#[pymethods]
+impl Navigation {
+    #[staticmethod]
+    fn flat() -> Self {
+        Self(NavigationBase::Flat)
+    }
+
+    #[staticmethod]
+    fn geodetic(latitude: f64, longitude: f64) -> Self {
+        Self(NavigationBase::Geodetic {
+            latitude,
+            longitude,
+        })
+    }
+
+    #[getter]
+    fn is_geodetic(&self) -> bool {
+        matches!(self.0, NavigationBase::Geodetic { .. })
+    }
+
+    #[getter]
+    fn latitude(&self) -> Option<f64> {
+        match self.0 {
+            NavigationBase::Flat => None,
+            NavigationBase::Geodetic { latitude, .. } => Some(latitude),
+        }
+    }
+
+    #[getter]
+    fn longitude(&self) -> Option<f64> {
+        match self.0 {
+            NavigationBase::Flat => None,
+            NavigationBase::Geodetic { longitude, .. } => Some(longitude),
+        }
+    }
+}
+
+#[pyclass]
+struct Geodetic(GeodeticBase);
+
+#[pymethods]
+impl Geodetic {
+    #[new]
+    fn new(latitude: f64, longitude: f64, altitude: f64) -> Self {
+        Self(GeodeticBase::new(latitude, longitude, altitude))
+    }
+
+    #[getter]
+    fn latitude(&self) -> f64 {
+        self.0.latitude
+    }
+
+    #[getter]
+    fn longitude(&self) -> f64 {
+        self.0.longitude
+    }
+
+    #[getter]
+    fn altitude(&self) -> f64 {
+        self.0.altitude
+    }
+}
+
+#[pyclass]
+struct AirProperties(AirPropertiesBase);
+
+#[pymethods]
+impl AirProperties {
+    #[getter]
+    fn temperature(&self) -> f64 {
+        self.0.temperature
+    }
+
+    #[getter]
+    fn pressure(&self) -> f64 {
+        self.0.pressure
+    }
+
+    #[getter]
+    fn density(&self) -> f64 {
+        self.0.density
+    }
+
+    #[getter]
+    fn speed_of_sound(&self) -> f64 {
+        self.0.speed_of_sound
+    }
+
+    #[getter]
+    fn viscosity(&self) -> f64 {
+        self.0.viscosity
+    }
+}
+
+#[pyclass]
+struct AirData(AirDataBase);
+
+#[pymethods]
+impl AirData {
+    #[getter]
+    fn air(&self) -> AirProperties {
+        AirProperties(self.0.air)
+    }
+
+    #[getter]
+    fn mach(&self) -> f64 {
+        self.0.mach
+    }
+
+    #[getter]
+    fn qbar(&self) -> f64 {
+        self.0.qbar
+    }
+
+    #[getter]
+    fn tas(&self) -> f64 {
+        self.0.tas
+    }
+
+    #[getter]
+    fn cas(&self) -> f64 {
+        self.0.cas
+    }
+
+    #[getter]
+    fn eas(&self) -> f64 {
+        self.0.eas
+    }
+}
+
+#[pyclass]
+#[derive(Clone)]
+struct Atmosphere(Arc<dyn AtmosphereBase>);
+
+#[pymethods]
+impl Atmosphere {
+    #[staticmethod]
+    fn simple() -> Self {
+        Self(Arc::new(SimpleAtmosphere))
+    }
+
+    #[staticmethod]
+    #[pyo3(signature = (temperature_offset=0.0))]
+    fn standard(temperature_offset: f64) -> Self {
+        Self(Arc::new(StandardAtmosphere::new(temperature_offset)))
+    }
+
+    #[staticmethod]
+    fn tabulated(altitude: Vec<f64>, temperature: Vec<f64>, pressure: Vec<f64>) -> PyResult<Self> {
+        match TabulatedAtmosphere::new(altitude, temperature, pressure) {
+            Ok(a) => Ok(Self(Arc::new(a))),
+            Err(e) => {
+                error!("{}", e);
+                Err(PyValueError::new_err(e.to_string()))
+            }
+        }
+    }
+
+    fn properties(&self, altitude: f64) -> AirProperties {
+        AirProperties(self.0.properties(altitude))
+    }
+
+    fn air_data(&self, altitude: f64, velocity: f64) -> AirData {
+        AirData(self.0.air_data(altitude, velocity))
+    }
+}
+
+#[pyclass]
+#[derive(Clone)]
+struct Shear(ShearBase);
+
+#[pymethods]
+impl Shear {
+    #[new]
+    #[pyo3(signature = (wind_20, direction, roughness=2.0))]
+    fn new(wind_20: f64, direction: f64, roughness: f64) -> Self {
+        Self(ShearBase::new(wind_20, direction, roughness))
+    }
+
+    fn velocity(&self, altitude: f64) -> [f64; 3] {
+        self.0.velocity(altitude)
+    }
+
+    #[getter]
+    fn wind_20(&self) -> f64 {
+        self.0.wind_20
+    }
+
+    #[getter]
+    fn direction(&self) -> f64 {
+        self.0.direction
+    }
+
+    #[getter]
+    fn roughness(&self) -> f64 {
+        self.0.roughness
+    }
+}
+
+#[pyclass]
+#[derive(Clone)]
+struct Gust(GustBase);
+
+#[pymethods]
+impl Gust {
+    #[new]
+    fn new(start_time: f64, gradient: f64, amplitude: [f64; 3]) -> Self {
+        Self(GustBase::new(start_time, gradient, amplitude))
+    }
+
+    fn velocity(&self, t: f64, tas: f64) -> [f64; 3] {
+        self.0.velocity(t, tas)
+    }
+
+    #[getter]
+    fn start_time(&self) -> f64 {
+        self.0.start_time
+    }
+
+    #[getter]
+    fn gradient(&self) -> f64 {
+        self.0.gradient
+    }
+
+    #[getter]
+    fn amplitude(&self) -> [f64; 3] {
+        self.0.amplitude
+    }
+}
+
+#[pyclass]
+#[derive(Clone)]
+struct Spectrum(SpectrumBase);
+
+#[pymethods]
+impl Spectrum {
+    #[new]
+    fn new(value: i32) -> Self {
+        match value {
+            0 => Self(SpectrumBase::Dryden),
+            1 => Self(SpectrumBase::VonKarman),
+            _ => panic!("Invalid value for Spectrum"),
+        }
+    }
+
+    #[getter]
+    fn value(&self) -> i32 {
+        match self.0 {
+            SpectrumBase::Dryden => 0,
+            SpectrumBase::VonKarman => 1,
+        }
+    }
+
+    #[staticmethod]
+    fn dryden() -> Self {
+        Self(SpectrumBase::Dryden)
+    }
+
+    #[staticmethod]
+    fn von_karman() -> Self {
+        Self(SpectrumBase::VonKarman)
+    }
+}
+
+#[pyclass]
+#[derive(Clone)]
+struct Turbulence(TurbulenceBase);
+
+#[pymethods]
+impl Turbulence {
+    #[new]
+    #[pyo3(signature = (spectrum, wind_20, seed=0, intensity=None))]
+    fn new(spectrum: &Spectrum, wind_20: f64, seed: u32, intensity: Option<f64>) -> Self {
+        let turbulence = TurbulenceBase::new(spectrum.0, wind_20, seed);
+        match intensity {
+            Some(i) => Self(turbulence.with_intensity(i)),
+            None => Self(turbulence),
+        }
+    }
+
+    /// intensities and scale lengths of u, v and w
+    fn scales(&self, altitude: f64) -> ([f64; 3], [f64; 3]) {
+        self.0.scales(altitude)
+    }
+
+    fn step(&mut self, dt: f64, altitude: f64, tas: f64) -> [f64; 3] {
+        self.0.step(dt, altitude, tas)
+    }
+
+    fn reset(&mut self) {
+        self.0.reset();
+    }
+
+    #[getter]
+    fn spectrum(&self) -> Spectrum {
+        Spectrum(self.0.spectrum())
+    }
+
+    #[getter]
+    fn seed(&self) -> u32 {
+        self.0.seed()
+    }
+}
+
+#[pyclass]
+#[derive(Clone)]
+struct Wind(WindModel);
+
+#[pymethods]
+impl Wind {
+    #[new]
+    #[pyo3(signature = (steady=None, shear=None, gusts=None, turbulence=None))]
+    fn new(
+        steady: Option<[f64; 3]>,
+        shear: Option<&Shear>,
+        gusts: Option<Vec<PyRef<Gust>>>,
+        turbulence: Option<&Turbulence>,
+    ) -> Self {
+        let mut wind = WindModel::new(steady.unwrap_or_default());
+        wind.shear = shear.map(|s| s.0);
+        wind.gusts = gusts.unwrap_or_default().iter().map(|g| g.0).collect();
+        wind.turbulence = turbulence.map(|t| t.0.clone());
+        Self(wind)
+    }
+
+    fn update(&mut self, t: f64, dt: f64, altitude: f64, tas: f64) -> WindSample {
+        WindSample(self.0.update(t, dt, altitude, tas))
+    }
+
+    fn reset(&mut self) {
+        self.0.reset();
+    }
+
+    #[getter]
+    fn steady(&self) -> [f64; 3] {
+        self.0.steady
+    }
+
+    #[getter]
+    fn shear(&self) -> Option<Shear> {
+        self.0.shear.map(Shear)
+    }
+
+    #[getter]
+    fn gusts(&self) -> Vec<Gust> {
+        self.0.gusts.iter().map(|g| Gust(*g)).collect()
+    }
+
+    #[getter]
+    fn turbulence(&self) -> Option<Turbulence> {
+        self.0.turbulence.clone().map(Turbulence)
+    }
+}
+
+#[pyclass]
+struct WindSample(WindSampleBase);
+
+#[pymethods]
+impl WindSample {
+    #[getter]
+    fn ned(&self) -> [f64; 3] {
+        self.0.ned
+    }
+
+    #[getter]
+    fn body(&self) -> [f64; 3] {
+        self.0.body
+    }
+}
+
+#[pyclass]
+struct AirRelative(AirRelativeBase);
+
+#[pymethods]
+impl AirRelative {
+    #[getter]
+    fn velocity(&self) -> f64 {
+        self.0.velocity
+    }
+
+    #[getter]
+    fn alpha(&self) -> f64 {
+        self.0.alpha
+    }
+
+    #[getter]
+    fn beta(&self) -> f64 {
+        self.0.beta
+    }
+}
+
+#[pyclass]
+#[derive(Clone)]
+struct Tank(TankBase);
+
+#[pymethods]
+impl Tank {
+    #[new]
+    #[pyo3(signature = (capacity, position, fuel=None, stage=0))]
+    fn new(capacity: f64, position: [f64; 3], fuel: Option<f64>, stage: usize) -> Self {
+        Self(
+            TankBase::new(capacity, position)
+                .with_fuel(fuel.unwrap_or(capacity))
+                .with_stage(stage),
+        )
+    }
+
+    #[getter]
+    fn capacity(&self) -> f64 {
+        self.0.capacity
+    }
+
+    #[getter]
+    fn fuel(&self) -> f64 {
+        self.0.fuel
+    }
+
+    #[getter]
+    fn position(&self) -> [f64; 3] {
+        self.0.position
+    }
+
+    #[getter]
+    fn stage(&self) -> usize {
+        self.0.stage
+    }
+}
+
+#[pyclass]
+#[derive(Clone)]
+struct FuelConsumption(FuelConsumptionBase);
+
+#[pymethods]
+impl FuelConsumption {
+    #[new]
+    #[pyo3(signature = (tsfc, mach_slope=0.0))]
+    fn new(tsfc: f64, mach_slope: f64) -> Self {
+        Self(FuelConsumptionBase::new(tsfc, mach_slope))
+    }
+
+    fn fuel_flow(&self, thrust: f64, air: &AirData) -> f64 {
+        self.0.fuel_flow(thrust, &air.0)
+    }
+
+    #[getter]
+    fn tsfc(&self) -> f64 {
+        self.0.tsfc
+    }
+
+    #[getter]
+    fn mach_slope(&self) -> f64 {
+        self.0.mach_slope
+    }
+}
+
+#[pyclass]
+#[derive(Clone)]
+struct MassModel(MassModelBase);
+
+#[pymethods]
+impl MassModel {
+    #[new]
+    fn new(
+        constants: &PlaneConstants,
+        tanks: Vec<PyRef<Tank>>,
+        consumption: &FuelConsumption,
+    ) -> PyResult<Self> {
+        let tanks = tanks.iter().map(|t| t.0).collect();
+        match MassModelBase::new(constants.0, tanks, consumption.0) {
+            Ok(m) => Ok(Self(m)),
+            Err(e) => {
+                error!("{}", e);
+                Err(PyValueError::new_err(e.to_string()))
+            }
+        }
+    }
+
+    fn burn(&mut self, dt: f64, thrust: f64, air: &AirData) -> f64 {
+        self.0.burn(dt, thrust, &air.0)
+    }
+
+    fn set_fuel(&mut self, fuel: Vec<f64>) -> PyResult<()> {
+        match self.0.set_fuel(&fuel) {
+            Ok(_) => Ok(()),
+            Err(e) => {
+                error!("{}", e);
+                Err(PyValueError::new_err(e.to_string()))
+            }
+        }
+    }
+
+    fn reset(&mut self) {
+        self.0.reset();
+    }
+
+    #[getter]
+    fn fuel(&self) -> f64 {
+        self.0.fuel()
+    }
+
+    #[getter]
+    fn tanks(&self) -> Vec<Tank> {
+        self.0.tanks().iter().map(|t| Tank(*t)).collect()
+    }
+
+    #[getter]
+    fn constants(&self) -> PlaneConstants {
+        PlaneConstants(self.0.constants())
+    }
+
+    #[getter]
+    fn cg(&self) -> [f64; 3] {
+        self.0.cg()
+    }
+
+    #[getter]
+    fn consumption(&self) -> FuelConsumption {
+        FuelConsumption(self.0.consumption())
+    }
+
+    #[getter]
+    fn state(&self) -> MassState {
+        MassState(self.0.state())
+    }
+}
+
+#[pyclass]
+struct MassState(MassStateBase);
+
+#[pymethods]
+impl MassState {
+    #[getter]
+    fn mass(&self) -> f64 {
+        self.0.mass
+    }
+
+    #[getter]
+    fn fuel(&self) -> f64 {
+        self.0.fuel
+    }
+
+    #[getter]
+    fn fuel_flow(&self) -> f64 {
+        self.0.fuel_flow
+    }
+
+    #[getter]
+    fn x_cg(&self) -> f64 {
+        self.0.x_cg
+    }
+}
+
+#[pyclass]
+#[derive(Clone)]
+struct Engine(EngineModel);
+
+#[pymethods]
+impl Engine {
+    #[new]
+    fn new(
+        mach: Vec<f64>,
+        altitude: Vec<f64>,
+        idle: Vec<Vec<f64>>,
+        military: Vec<Vec<f64>>,
+        maximum: Vec<Vec<f64>>,
+        angular_momentum: f64,
+    ) -> PyResult<Self> {
+        match EngineModel::new(mach, altitude, idle, military, maximum, angular_momentum) {
+            Ok(e) => Ok(Self(e)),
+            Err(e) => {
+                error!("{}", e);
+                Err(PyValueError::new_err(e.to_string()))
+            }
+        }
+    }
+
+    #[staticmethod]
+    fn f16() -> Self {
+        Self(EngineModel::f16())
+    }
+
+    #[staticmethod]
+    fn tgear(throttle: f64) -> f64 {
+        tgear(throttle)
+    }
+
+    #[staticmethod]
+    fn throttle_for_power(power: f64) -> f64 {
+        throttle_for_power(power)
+    }
+
+    #[staticmethod]
+    fn power_rate(power: f64, command: f64) -> f64 {
+        power_rate(power, command)
+    }
+
+    fn thrust(&self, power: f64, altitude: f64, mach: f64) -> f64 {
+        self.0.thrust(power, altitude, mach)
+    }
+
+    fn power_for_thrust(&self, thrust: f64, altitude: f64, mach: f64) -> f64 {
+        self.0.power_for_thrust(thrust, altitude, mach)
+    }
+
+    #[getter]
+    fn angular_momentum(&self) -> f64 {
+        self.0.angular_momentum
+    }
+
+    #[setter]
+    fn set_angular_momentum(&mut self, value: f64) {
+        self.0.angular_momentum = value;
+    }
+}
+
+#[pyclass]
+struct EngineState(EngineStateBase);
+
+#[pymethods]
+impl EngineState {
+    #[getter]
+    fn power(&self) -> f64 {
+        self.0.power
+    }
+
+    #[getter]
+    fn thrust(&self) -> f64 {
+        self.0.thrust
+    }
+}
+
+#[pyclass]
+#[derive(Clone)]
+struct SensorModel(SensorModelBase);
+
+#[pymethods]
+impl SensorModel {
+    #[new]
+    #[pyo3(signature = (noise=0.0, bias=0.0, random_walk=0.0, scale_factor=0.0, quantization=0.0, limits=None, lag=0.0))]
+    fn new(
+        noise: f64,
+        bias: f64,
+        random_walk: f64,
+        scale_factor: f64,
+        quantization: f64,
+        limits: Option<[f64; 2]>,
+        lag: f64,
+    ) -> Self {
+        Self(SensorModelBase {
+            noise,
+            bias,
+            random_walk,
+            scale_factor,
+            quantization,
+            limits,
+            lag,
+        })
+    }
+
+    #[getter]
+    fn noise(&self) -> f64 {
+        self.0.noise
+    }
+
+    #[getter]
+    fn bias(&self) -> f64 {
+        self.0.bias
+    }
+
+    #[getter]
+    fn random_walk(&self) -> f64 {
+        self.0.random_walk
+    }
+
+    #[getter]
+    fn scale_factor(&self) -> f64 {
+        self.0.scale_factor
+    }
+
+    #[getter]
+    fn quantization(&self) -> f64 {
+        self.0.quantization
+    }
+
+    #[getter]
+    fn limits(&self) -> Option<[f64; 2]> {
+        self.0.limits
+    }
+
+    #[getter]
+    fn lag(&self) -> f64 {
+        self.0.lag
+    }
+
+    fn __repr__(&self) -> String {
+        format!("{:?}", self.0)
+    }
+}
+
+#[pyclass]
+#[derive(Clone)]
+struct Imu(ImuBase);
+
+#[pymethods]
+impl Imu {
+    #[new]
+    #[pyo3(signature = (sample_rate=0.0, seed=0, position=None, gyro=None, accel=None))]
+    fn new(
+        sample_rate: f64,
+        seed: u32,
+        position: Option<[f64; 3]>,
+        gyro: Option<&SensorModel>,
+        accel: Option<&SensorModel>,
+    ) -> Self {
+        let mut imu = ImuBase::new(sample_rate, seed).with_position(position.unwrap_or_default());
+        imu.gyro = gyro.map(|m| m.0).unwrap_or_default();
+        imu.accel = accel.map(|m| m.0).unwrap_or_default();
+        Self(imu)
+    }
+
+    #[getter]
+    fn sample_rate(&self) -> f64 {
+        self.0.sample_rate()
+    }
+
+    #[getter]
+    fn seed(&self) -> u32 {
+        self.0.seed()
+    }
+
+    #[getter]
+    fn position(&self) -> [f64; 3] {
+        self.0.position
+    }
+
+    #[getter]
+    fn gyro(&self) -> SensorModel {
+        SensorModel(self.0.gyro)
+    }
+
+    #[getter]
+    fn accel(&self) -> SensorModel {
+        SensorModel(self.0.accel)
+    }
+
+    /// The load factors in g at the position of the IMU
+    fn accels(&self, state: &State, state_dot: &State, extend: &StateExtend) -> [f64; 3] {
+        self.0.accels(&state.0, &state_dot.0, &extend.0)
+    }
+}
+
+#[pyclass]
+#[derive(Clone)]
+struct AirDataSensor(AirDataSensorBase);
+
+#[pymethods]
+impl AirDataSensor {
+    #[new]
+    #[pyo3(signature = (sample_rate=0.0, seed=0, position=None, alpha=None, beta=None, mach=None, static_pressure=None, total_pressure=None))]
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        sample_rate: f64,
+        seed: u32,
+        position: Option<[f64; 3]>,
+        alpha: Option<&SensorModel>,
+        beta: Option<&SensorModel>,
+        mach: Option<&SensorModel>,
+        static_pressure: Option<&SensorModel>,
+        total_pressure: Option<&SensorModel>,
+    ) -> Self {
+        let model = |m: Option<&SensorModel>| m.map(|m| m.0).unwrap_or_default();
+        let mut air_data =
+            AirDataSensorBase::new(sample_rate, seed).with_position(position.unwrap_or_default());
+        air_data.alpha = model(alpha);
+        air_data.beta = model(beta);
+        air_data.mach = model(mach);
+        air_data.static_pressure = model(static_pressure);
+        air_data.total_pressure = model(total_pressure);
+        Self(air_data)
+    }
+
+    /// Total pressure at a pitot tube, behind the normal shock above Mach 1
+    #[staticmethod]
+    fn pitot_pressure(static_pressure: f64, mach: f64) -> f64 {
+        total_pressure(static_pressure, mach)
+    }
+
+    #[getter]
+    fn sample_rate(&self) -> f64 {
+        self.0.sample_rate()
+    }
+
+    #[getter]
+    fn seed(&self) -> u32 {
+        self.0.seed()
+    }
+
+    #[getter]
+    fn position(&self) -> [f64; 3] {
+        self.0.position
+    }
+
+    #[getter]
+    fn alpha(&self) -> SensorModel {
+        SensorModel(self.0.alpha)
+    }
+
+    #[getter]
+    fn beta(&self) -> SensorModel {
+        SensorModel(self.0.beta)
+    }
+
+    #[getter]
+    fn mach(&self) -> SensorModel {
+        SensorModel(self.0.mach)
+    }
+
+    #[getter]
+    fn static_pressure(&self) -> SensorModel {
+        SensorModel(self.0.static_pressure)
+    }
+
+    #[getter]
+    fn total_pressure(&self) -> SensorModel {
+        SensorModel(self.0.total_pressure)
+    }
+}
+
+#[pyclass]
+#[derive(Clone)]
+struct Ahrs(AhrsBase);
+
+#[pymethods]
+impl Ahrs {
+    #[new]
+    #[pyo3(signature = (sample_rate=0.0, seed=0, attitude=None, heading=None))]
+    fn new(
+        sample_rate: f64,
+        seed: u32,
+        attitude: Option<&SensorModel>,
+        heading: Option<&SensorModel>,
+    ) -> Self {
+        let mut ahrs = AhrsBase::new(sample_rate, seed);
+        ahrs.attitude = attitude.map(|m| m.0).unwrap_or_default();
+        ahrs.heading = heading.map(|m| m.0).unwrap_or_default();
+        Self(ahrs)
+    }
+
+    #[getter]
+    fn sample_rate(&self) -> f64 {
+        self.0.sample_rate()
+    }
+
+    #[getter]
+    fn seed(&self) -> u32 {
+        self.0.seed()
+    }
+
+    #[getter]
+    fn attitude(&self) -> SensorModel {
+        SensorModel(self.0.attitude)
+    }
+
+    #[getter]
+    fn heading(&self) -> SensorModel {
+        SensorModel(self.0.heading)
+    }
+}
+
+#[pyclass]
+#[derive(Clone)]
+struct Sensors(SensorsBase);
+
+#[pymethods]
+impl Sensors {
+    #[new]
+    #[pyo3(signature = (imu=None, air_data=None, ahrs=None))]
+    fn new(imu: Option<&Imu>, air_data: Option<&AirDataSensor>, ahrs: Option<&Ahrs>) -> Self {
+        let mut sensors = SensorsBase::new();
+        sensors.imu = imu.map(|s| s.0.clone());
+        sensors.air_data = air_data.map(|s| s.0.clone());
+        sensors.ahrs = ahrs.map(|s| s.0.clone());
+        Self(sensors)
+    }
+
+    #[getter]
+    fn imu(&self) -> Option<Imu> {
+        self.0.imu.clone().map(Imu)
+    }
+
+    #[getter]
+    fn air_data(&self) -> Option<AirDataSensor> {
+        self.0.air_data.clone().map(AirDataSensor)
+    }
+
+    #[getter]
+    fn ahrs(&self) -> Option<Ahrs> {
+        self.0.ahrs.clone().map(Ahrs)
+    }
+
+    #[getter]
+    fn measurements(&self) -> Measurements {
+        Measurements(self.0.measurements())
+    }
+
+    fn reset(&mut self) {
+        self.0.reset();
+    }
+}
+
+#[pyclass]
+struct Measurements(MeasurementsBase);
+
+#[pymethods]
+impl Measurements {
+    /// [p, q, r] in rad/s
+    #[getter]
+    fn rates(&self) -> Option<[f64; 3]> {
+        self.0.imu.map(|m| m.rates)
+    }
+
+    /// [nx, ny, nz] in g
+    #[getter]
+    fn accels(&self) -> Option<[f64; 3]> {
+        self.0.imu.map(|m| m.accels)
+    }
+
+    #[getter]
+    fn alpha(&self) -> Option<f64> {
+        self.0.air_data.map(|m| m.alpha)
+    }
+
+    #[getter]
+    fn beta(&self) -> Option<f64> {
+        self.0.air_data.map(|m| m.beta)
+    }
+
+    #[getter]
+    fn mach(&self) -> Option<f64> {
+        self.0.air_data.map(|m| m.mach)
+    }
+
+    #[getter]
+    fn static_pressure(&self) -> Option<f64> {
+        self.0.air_data.map(|m| m.static_pressure)
+    }
+
+    #[getter]
+    fn total_pressure(&self) -> Option<f64> {
+        self.0.air_data.map(|m| m.total_pressure)
+    }
+
+    /// [phi, theta, psi] in rad
+    #[getter]
+    fn attitude(&self) -> Option<[f64; 3]> {
+        self.0.ahrs.map(|m| [m.phi, m.theta, m.psi])
+    }
+
+    fn __repr__(&self) -> String {
+        format!("{:?}", self.0)
+    }
+}
+
+#[pyclass]
+struct ActuatorModel(ActuatorModelBase);
+
+#[pymethods]
+impl ActuatorModel {
+    #[staticmethod]
+    #[pyo3(signature = (command_limit_top, command_limit_bottom, rate_limit, gain, deadband=0.0, backlash=0.0, delay=0.0, stall_deflection=None))]
+    #[allow(clippy::too_many_arguments)]
+    fn first_order(
+        command_limit_top: f64,
+        command_limit_bottom: f64,
+        rate_limit: f64,
+        gain: f64,
+        deadband: f64,
+        backlash: f64,
+        delay: f64,
+        stall_deflection: Option<f64>,
+    ) -> Self {
+        let model = ActuatorModelBase::first_order(
+            command_limit_top,
+            command_limit_bottom,
+            rate_limit,
+            gain,
+        );
+        Self(ActuatorModelBase {
+            deadband,
+            backlash,
+            delay,
+            stall_deflection,
+            ..model
+        })
+    }
+
+    #[staticmethod]
+    #[pyo3(signature = (command_limit_top, command_limit_bottom, rate_limit, natural_frequency, damping, deadband=0.0, backlash=0.0, delay=0.0, stall_deflection=None))]
+    #[allow(clippy::too_many_arguments)]
+    fn second_order(
+        command_limit_top: f64,
+        command_limit_bottom: f64,
+        rate_limit: f64,
+        natural_frequency: f64,
+        damping: f64,
+        deadband: f64,
+        backlash: f64,
+        delay: f64,
+        stall_deflection: Option<f64>,
+    ) -> Self {
+        let model = ActuatorModelBase::second_order(
+            command_limit_top,
+            command_limit_bottom,
+            rate_limit,
+            natural_frequency,
+            damping,
+        );
+        Self(ActuatorModelBase {
+            deadband,
+            backlash,
+            delay,
+            stall_deflection,
+            ..model
+        })
+    }
+
+    /// "first_order" or "second_order"
+    #[getter]
+    fn order(&self) -> &'static str {
+        match self.0.dynamics {
+            ActuatorDynamics::FirstOrder { .. } => "first_order",
+            ActuatorDynamics::SecondOrder { .. } => "second_order",
+        }
+    }
+
+    #[getter]
+    fn gain(&self) -> Option<f64> {
+        match self.0.dynamics {
+            ActuatorDynamics::FirstOrder { gain } => Some(gain),
+            ActuatorDynamics::SecondOrder { .. } => None,
+        }
+    }
+
+    #[getter]
+    fn natural_frequency(&self) -> Option<f64> {
+        match self.0.dynamics {
+            ActuatorDynamics::SecondOrder {
+                natural_frequency, ..
+            } => Some(natural_frequency),
+            ActuatorDynamics::FirstOrder { .. } => None,
+        }
+    }
+
+    #[getter]
+    fn damping(&self) -> Option<f64> {
+        match self.0.dynamics {
+            ActuatorDynamics::SecondOrder { damping, .. } => Some(damping),
+            ActuatorDynamics::FirstOrder { .. } => None,
+        }
+    }
+
+    #[getter]
+    fn command_limit_top(&self) -> f64 {
+        self.0.command_limit_top
+    }
+
+    #[getter]
+    fn command_limit_bottom(&self) -> f64 {
+        self.0.command_limit_bottom
+    }
+
+    #[getter]
+    fn rate_limit(&self) -> f64 {
+        self.0.rate_limit
+    }
+
+    #[getter]
+    fn deadband(&self) -> f64 {
+        self.0.deadband
+    }
+
+    #[getter]
+    fn backlash(&self) -> f64 {
+        self.0.backlash
+    }
+
+    #[getter]
+    fn delay(&self) -> f64 {
+        self.0.delay
+    }
+
+    #[getter]
+    fn stall_deflection(&self) -> Option<f64> {
+        self.0.stall_deflection
+    }
+
+    fn __repr__(&self) -> String {
+        format!("{:?}", self.0)
+    }
+}
+
+#[pyclass]
+struct ActuatorFailure(ActuatorFailureBase);
+
+#[pymethods]
+impl ActuatorFailure {
+    #[staticmethod]
+    #[pyo3(signature = (position=None))]
+    fn stuck(position: Option<f64>) -> Self {
+        Self(ActuatorFailureBase::Stuck { position })
+    }
+
+    #[staticmethod]
+    #[pyo3(signature = (top=true))]
+    fn hardover(top: bool) -> Self {
+        Self(ActuatorFailureBase::Hardover { top })
+    }
+
+    #[staticmethod]
+    #[pyo3(signature = (time_constant=0.1))]
+    fn floating(time_constant: f64) -> Self {
+        Self(ActuatorFailureBase::Floating { time_constant })
+    }
+
+    #[staticmethod]
+    fn loss_of_effectiveness(effectiveness: f64) -> Self {
+        Self(ActuatorFailureBase::LossOfEffectiveness { effectiveness })
+    }
+
+    #[staticmethod]
+    fn oscillatory(amplitude: f64, frequency: f64) -> Self {
+        Self(ActuatorFailureBase::Oscillatory {
+            amplitude,
+            frequency,
+        })
+    }
+
+    /// "stuck", "hardover", "floating", "loss_of_effectiveness" or "oscillatory"
+    #[getter]
+    fn kind(&self) -> &'static str {
+        match self.0 {
+            ActuatorFailureBase::Stuck { .. } => "stuck",
+            ActuatorFailureBase::Hardover { .. } => "hardover",
+            ActuatorFailureBase::Floating { .. } => "floating",
+            ActuatorFailureBase::LossOfEffectiveness { .. } => "loss_of_effectiveness",
+            ActuatorFailureBase::Oscillatory { .. } => "oscillatory",
+        }
+    }
+
+    fn __repr__(&self) -> String {
+        format!("{:?}", self.0)
+    }
+}
+
+#[pyclass]
+#[derive(Clone)]
+struct TransferFunction(TransferFunctionBase<RK4Solver>);
+
+#[pymethods]
+impl TransferFunction {
+    /// num, den: coefficients from the highest power of s down,
+    /// integrated with RK4 in steps of at most delta_t
+    #[new]
+    #[pyo3(signature = (num, den, delta_t=0.01))]
+    fn new(num: Vec<f64>, den: Vec<f64>, delta_t: f64) -> PyResult<Self> {
+        let solver = Arc::new(RK4Solver::new(delta_t));
+        match TransferFunctionBase::new(solver, &num, &den) {
+            Ok(b) => Ok(Self(b)),
+            Err(e) => {
+                error!("{}", e);
+                Err(PyValueError::new_err(e.to_string()))
+            }
+        }
+    }
+
+    #[staticmethod]
+    #[pyo3(signature = (tau, delta_t=0.01))]
+    fn first_order(tau: f64, delta_t: f64) -> PyResult<Self> {
+        let solver = Arc::new(RK4Solver::new(delta_t));
+        match TransferFunctionBase::first_order(solver, tau) {
+            Ok(b) => Ok(Self(b)),
+            Err(e) => {
+                error!("{}", e);
+                Err(PyValueError::new_err(e.to_string()))
+            }
+        }
+    }
+
+    #[staticmethod]
+    #[pyo3(signature = (w, zeta, delta_t=0.01))]
+    fn second_order(w: f64, zeta: f64, delta_t: f64) -> PyResult<Self> {
+        let solver = Arc::new(RK4Solver::new(delta_t));
+        match TransferFunctionBase::second_order(solver, w, zeta) {
+            Ok(b) => Ok(Self(b)),
+            Err(e) => {
+                error!("{}", e);
+                Err(PyValueError::new_err(e.to_string()))
+            }
+        }
+    }
+
+    #[staticmethod]
+    #[pyo3(signature = (w, zeta_zero, zeta_pole, delta_t=0.01))]
+    fn notch(w: f64, zeta_zero: f64, zeta_pole: f64, delta_t: f64) -> PyResult<Self> {
+        let solver = Arc::new(RK4Solver::new(delta_t));
+        match TransferFunctionBase::notch(solver, w, zeta_zero, zeta_pole) {
+            Ok(b) => Ok(Self(b)),
+            Err(e) => {
+                error!("{}", e);
+                Err(PyValueError::new_err(e.to_string()))
+            }
+        }
+    }
+
+    #[staticmethod]
+    #[pyo3(signature = (t_lead, t_lag, delta_t=0.01))]
+    fn lead_lag(t_lead: f64, t_lag: f64, delta_t: f64) -> PyResult<Self> {
+        let solver = Arc::new(RK4Solver::new(delta_t));
+        match TransferFunctionBase::lead_lag(solver, t_lead, t_lag) {
+            Ok(b) => Ok(Self(b)),
+            Err(e) => {
+                error!("{}", e);
+                Err(PyValueError::new_err(e.to_string()))
+            }
+        }
+    }
+
+    #[staticmethod]
+    #[pyo3(signature = (tau, delta_t=0.01))]
+    fn washout(tau: f64, delta_t: f64) -> PyResult<Self> {
+        let solver = Arc::new(RK4Solver::new(delta_t));
+        match TransferFunctionBase::washout(solver, tau) {
+            Ok(b) => Ok(Self(b)),
+            Err(e) => {
+                error!("{}", e);
+                Err(PyValueError::new_err(e.to_string()))
+            }
+        }
+    }
+
+    #[getter]
+    fn num(&self) -> Vec<f64> {
+        self.0.num().to_vec()
+    }
+
+    #[getter]
+    fn den(&self) -> Vec<f64> {
+        self.0.den().to_vec()
+    }
+
+    /// The controllable canonical realization
+    fn state_space(&self) -> StateSpace {
+        StateSpace(self.0.state_space().clone())
+    }
+
+    /// Advance to `t` with `input` held since the last update, the output at `t`
+    fn update(&mut self, input: f64, t: f64) -> PyResult<f64> {
+        match self.0.update(input, t) {
+            Ok(y) => Ok(y),
+            Err(e) => {
+                error!("{}", e);
+                Err(PyValueError::new_err(e.to_string()))
+            }
+        }
+    }
+
+    #[getter]
+    fn output(&self) -> f64 {
+        self.0.output()
+    }
+
+    fn reset(&mut self) {
+        self.0.reset();
+    }
+}
+
+#[pyclass]
+#[derive(Clone)]
+struct StateSpace(StateSpaceBase<RK4Solver>);
+
+#[pymethods]
+impl StateSpace {
+    /// x' = A x + B u, y = C x + D u, integrated with RK4 in steps of at most delta_t
+    #[new]
+    #[pyo3(signature = (a, b, c, d, x0=None, delta_t=0.01))]
+    fn new(
+        a: Vec<Vec<f64>>,
+        b: Vec<Vec<f64>>,
+        c: Vec<Vec<f64>>,
+        d: Vec<Vec<f64>>,
+        x0: Option<Vec<f64>>,
+        delta_t: f64,
+    ) -> PyResult<Self> {
+        let solver = Arc::new(RK4Solver::new(delta_t));
+        let system = StateSpaceBase::new(
+            solver,
+            Matrix::from(a),
+            Matrix::from(b),
+            Matrix::from(c),
+            Matrix::from(d),
+        );
+        let system = match x0 {
+            Some(x0) => system.and_then(|s| s.with_state(&x0)),
+            None => system,
+        };
+        match system {
+            Ok(b) => Ok(Self(b)),
+            Err(e) => {
+                error!("{}", e);
+                Err(PyValueError::new_err(e.to_string()))
+            }
+        }
+    }
+
+    /// (states, inputs, outputs)
+    #[getter]
+    fn shape(&self) -> (usize, usize, usize) {
+        self.0.shape()
+    }
+
+    #[getter]
+    fn state(&self) -> Vec<f64> {
+        self.0.state().to_vec()
+    }
+
+    #[getter]
+    fn outputs(&self) -> Vec<f64> {
+        self.0.outputs().to_vec()
+    }
+
+    /// Advance to `t` with `input` held since the last update, the outputs at `t`
+    fn update_vector(&mut self, input: Vec<f64>, t: f64) -> PyResult<Vec<f64>> {
+        match self.0.update_vector(&input, t) {
+            Ok(y) => Ok(y.to_vec()),
+            Err(e) => {
+                error!("{}", e);
+                Err(PyValueError::new_err(e.to_string()))
+            }
+        }
+    }
+
+    /// Advance to `t` with `input` held since the last update, the output at `t`
+    fn update(&mut self, input: f64, t: f64) -> PyResult<f64> {
+        match self.0.update(input, t) {
+            Ok(y) => Ok(y),
+            Err(e) => {
+                error!("{}", e);
+                Err(PyValueError::new_err(e.to_string()))
+            }
+        }
+    }
+
+    #[getter]
+    fn output(&self) -> f64 {
+        self.0.output()
+    }
+
+    fn reset(&mut self) {
+        self.0.reset();
+    }
+}
+
+#[pyclass]
+#[derive(Clone)]
+struct Pid(PidBase<RK4Solver>);
+
+#[pymethods]
+impl Pid {
+    /// tau: time constant of the derivative filter, 0 for a raw derivative,
+    /// limits: (bottom, top) of the output with the anti-windup tracking gain k_aw
+    #[new]
+    #[pyo3(signature = (kp, ki=0.0, kd=0.0, tau=0.0, limits=None, k_aw=0.0, delta_t=0.01))]
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        kp: f64,
+        ki: f64,
+        kd: f64,
+        tau: f64,
+        limits: Option<(f64, f64)>,
+        k_aw: f64,
+        delta_t: f64,
+    ) -> PyResult<Self> {
+        let solver = Arc::new(RK4Solver::new(delta_t));
+        let pid = PidBase::new(solver, kp, ki, kd).with_filter(tau);
+        let pid = match limits {
+            Some((bottom, top)) => pid.with_limits(bottom, top, k_aw),
+            None => Ok(pid),
+        };
+        match pid {
+            Ok(b) => Ok(Self(b)),
+            Err(e) => {
+                error!("{}", e);
+                Err(PyValueError::new_err(e.to_string()))
+            }
+        }
+    }
+
+    #[getter]
+    fn kp(&self) -> f64 {
+        self.0.kp
+    }
+
+    #[getter]
+    fn ki(&self) -> f64 {
+        self.0.ki
+    }
+
+    #[getter]
+    fn kd(&self) -> f64 {
+        self.0.kd
+    }
+
+    #[getter]
+    fn tau(&self) -> f64 {
+        self.0.tau
+    }
+
+    #[getter]
+    fn limits(&self) -> [f64; 2] {
+        self.0.limits()
+    }
+
+    #[getter]
+    fn integral(&self) -> f64 {
+        self.0.integral()
+    }
+
+    /// Advance to `t` with `input` held since the last update, the output at `t`
+    fn update(&mut self, input: f64, t: f64) -> PyResult<f64> {
+        match self.0.update(input, t) {
+            Ok(y) => Ok(y),
+            Err(e) => {
+                error!("{}", e);
+                Err(PyValueError::new_err(e.to_string()))
+            }
+        }
+    }
+
+    #[getter]
+    fn output(&self) -> f64 {
+        self.0.output()
+    }
+
+    fn reset(&mut self) {
+        self.0.reset();
+    }
+}
+
+#[pyclass]
+#[derive(Clone)]
+struct RateLimiter(RateLimiterBase);
+
+#[pymethods]
+impl RateLimiter {
+    /// rising, falling: positive rates per s, falling defaults to rising
+    #[new]
+    #[pyo3(signature = (rising, falling=None))]
+    fn new(rising: f64, falling: Option<f64>) -> PyResult<Self> {
+        match RateLimiterBase::new(rising, falling.unwrap_or(rising)) {
+            Ok(b) => Ok(Self(b)),
+            Err(e) => {
+                error!("{}", e);
+                Err(PyValueError::new_err(e.to_string()))
+            }
+        }
+    }
+
+    /// Advance to `t` with `input` held since the last update, the output at `t`
+    fn update(&mut self, input: f64, t: f64) -> PyResult<f64> {
+        match self.0.update(input, t) {
+            Ok(y) => Ok(y),
+            Err(e) => {
+                error!("{}", e);
+                Err(PyValueError::new_err(e.to_string()))
+            }
+        }
+    }
+
+    #[getter]
+    fn output(&self) -> f64 {
+        self.0.output()
+    }
+
+    fn reset(&mut self) {
+        self.0.reset();
+    }
+}
+
+#[pyclass]
+#[derive(Clone)]
+struct Saturation(SaturationBase);
+
+#[pymethods]
+impl Saturation {
+    #[new]
+    fn new(bottom: f64, top: f64) -> PyResult<Self> {
+        match SaturationBase::new(bottom, top) {
+            Ok(b) => Ok(Self(b)),
+            Err(e) => {
+                error!("{}", e);
+                Err(PyValueError::new_err(e.to_string()))
+            }
+        }
+    }
+
+    /// Advance to `t` with `input` held since the last update, the output at `t`
+    fn update(&mut self, input: f64, t: f64) -> PyResult<f64> {
+        match self.0.update(input, t) {
+            Ok(y) => Ok(y),
+            Err(e) => {
+                error!("{}", e);
+                Err(PyValueError::new_err(e.to_string()))
+            }
+        }
+    }
+
+    #[getter]
+    fn output(&self) -> f64 {
+        self.0.output()
+    }
+
+    fn reset(&mut self) {
+        self.0.reset();
+    }
+}
+
+#[pyclass]
+#[derive(Clone)]
+struct TransportDelay(TransportDelayBase);
+
+#[pymethods]
+impl TransportDelay {
+    #[new]
+    #[pyo3(signature = (delay, init=0.0))]
+    fn new(delay: f64, init: f64) -> PyResult<Self> {
+        match TransportDelayBase::new(delay, init) {
+            Ok(b) => Ok(Self(b)),
+            Err(e) => {
+                error!("{}", e);
+                Err(PyValueError::new_err(e.to_string()))
+            }
+        }
+    }
+
+    /// Advance to `t` with `input` held since the last update, the output at `t`
+    fn update(&mut self, input: f64, t: f64) -> PyResult<f64> {
+        match self.0.update(input, t) {
+            Ok(y) => Ok(y),
+            Err(e) => {
+                error!("{}", e);
+                Err(PyValueError::new_err(e.to_string()))
+            }
+        }
+    }
+
+    #[getter]
+    fn output(&self) -> f64 {
+        self.0.output()
+    }
+
+    fn reset(&mut self) {
+        self.0.reset();
+    }
+}
+
+#[pyclass]
+#[derive(Clone)]
+struct ZeroOrderHold(ZeroOrderHoldBase);
+
+#[pymethods]
+impl ZeroOrderHold {
+    #[new]
+    fn new(sample_time: f64) -> PyResult<Self> {
+        match ZeroOrderHoldBase::new(sample_time) {
+            Ok(b) => Ok(Self(b)),
+            Err(e) => {
+                error!("{}", e);
+                Err(PyValueError::new_err(e.to_string()))
+            }
+        }
+    }
+
+    /// Advance to `t` with `input` held since the last update, the output at `t`
+    fn update(&mut self, input: f64, t: f64) -> PyResult<f64> {
+        match self.0.update(input, t) {
+            Ok(y) => Ok(y),
+            Err(e) => {
+                error!("{}", e);
+                Err(PyValueError::new_err(e.to_string()))
+            }
+        }
+    }
+
+    #[getter]
+    fn output(&self) -> f64 {
+        self.0.output()
+    }
+
+    fn reset(&mut self) {
+        self.0.reset();
+    }
+}
+
+#[pyclass]
+#[derive(Clone)]
+struct Autopilot(AutopilotBase);
+
+#[pymethods]
+impl Autopilot {
+    /// The gains left out keep the values of the F-16, no channel is engaged
+    #[new]
+    #[pyo3(signature = (k_h=None, max_vertical_speed=None, k_gamma=None, k_gamma_i=None, k_q=None, k_psi=None, bank_limit=None, k_phi=None, k_p=None, k_beta=None, k_r=None, k_v=None, k_v_i=None, sample_time=None))]
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        k_h: Option<f64>,
+        max_vertical_speed: Option<f64>,
+        k_gamma: Option<f64>,
+        k_gamma_i: Option<f64>,
+        k_q: Option<f64>,
+        k_psi: Option<f64>,
+        bank_limit: Option<f64>,
+        k_phi: Option<f64>,
+        k_p: Option<f64>,
+        k_beta: Option<f64>,
+        k_r: Option<f64>,
+        k_v: Option<f64>,
+        k_v_i: Option<f64>,
+        sample_time: Option<f64>,
+    ) -> PyResult<Self> {
+        let f16 = AutopilotGains::f16();
+        let gains = AutopilotGains {
+            k_h: k_h.unwrap_or(f16.k_h),
+            max_vertical_speed: max_vertical_speed.unwrap_or(f16.max_vertical_speed),
+            k_gamma: k_gamma.unwrap_or(f16.k_gamma),
+            k_gamma_i: k_gamma_i.unwrap_or(f16.k_gamma_i),
+            k_q: k_q.unwrap_or(f16.k_q),
+            k_psi: k_psi.unwrap_or(f16.k_psi),
+            bank_limit: bank_limit.unwrap_or(f16.bank_limit),
+            k_phi: k_phi.unwrap_or(f16.k_phi),
+            k_p: k_p.unwrap_or(f16.k_p),
+            k_beta: k_beta.unwrap_or(f16.k_beta),
+            k_r: k_r.unwrap_or(f16.k_r),
+            k_v: k_v.unwrap_or(f16.k_v),
+            k_v_i: k_v_i.unwrap_or(f16.k_v_i),
+            sample_time: sample_time.unwrap_or(f16.sample_time),
+        };
+        if gains.sample_time <= 0.0 {
+            let e = FatalCoreError::InvalidInput("sample_time must be positive".to_string());
+            error!("{}", e);
+            return Err(PyValueError::new_err(e.to_string()));
+        }
+        Ok(Self(AutopilotBase::new(gains)))
+    }
+
+    #[staticmethod]
+    fn f16() -> Self {
+        Self(AutopilotBase::f16())
+    }
+
+    /// altitude: ft
+    fn set_altitude(&mut self, altitude: f64) {
+        self.0.vertical = Some(VerticalMode::Altitude(altitude));
+    }
+
+    /// vertical_speed: ft/s, positive up
+    fn set_vertical_speed(&mut self, vertical_speed: f64) {
+        self.0.vertical = Some(VerticalMode::VerticalSpeed(vertical_speed));
+    }
+
+    /// gamma: rad
+    fn set_flight_path_angle(&mut self, gamma: f64) {
+        self.0.vertical = Some(VerticalMode::FlightPathAngle(gamma));
+    }
+
+    /// heading: rad from north
+    fn set_heading(&mut self, heading: f64) {
+        self.0.lateral = Some(LateralMode::Heading(heading));
+    }
+
+    /// track: rad from north
+    fn set_track(&mut self, track: f64) {
+        self.0.lateral = Some(LateralMode::Track(track));
+    }
+
+    /// airspeed: ft/s
+    fn set_airspeed(&mut self, airspeed: f64) {
+        self.0.speed = Some(SpeedMode::Airspeed(airspeed));
+    }
+
+    fn set_mach(&mut self, mach: f64) {
+        self.0.speed = Some(SpeedMode::Mach(mach));
+    }
+
+    fn disengage_vertical(&mut self) {
+        self.0.vertical = None;
+    }
+
+    fn disengage_lateral(&mut self) {
+        self.0.lateral = None;
+    }
+
+    fn disengage_speed(&mut self) {
+        self.0.speed = None;
+    }
+
+    /// ("altitude" | "vertical_speed" | "flight_path_angle", target) or None
+    #[getter]
+    fn vertical(&self) -> Option<(&'static str, f64)> {
+        self.0.vertical.map(|mode| match mode {
+            VerticalMode::Altitude(h) => ("altitude", h),
+            VerticalMode::VerticalSpeed(vs) => ("vertical_speed", vs),
+            VerticalMode::FlightPathAngle(gamma) => ("flight_path_angle", gamma),
+        })
+    }
+
+    /// ("heading" | "track", target) or None
+    #[getter]
+    fn lateral(&self) -> Option<(&'static str, f64)> {
+        self.0.lateral.map(|mode| match mode {
+            LateralMode::Heading(psi) => ("heading", psi),
+            LateralMode::Track(track) => ("track", track),
+        })
+    }
+
+    /// ("airspeed" | "mach", target) or None
+    #[getter]
+    fn speed(&self) -> Option<(&'static str, f64)> {
+        self.0.speed.map(|mode| match mode {
+            SpeedMode::Airspeed(v) => ("airspeed", v),
+            SpeedMode::Mach(mach) => ("mach", mach),
+        })
+    }
+
+    /// The thrust the autothrottle may command, (0, 1) for the throttle of an engine
+    fn set_thrust_range(&mut self, bottom: f64, top: f64) -> PyResult<()> {
+        match self.0.clone().with_thrust_range(bottom, top) {
+            Ok(a) => {
+                self.0 = a;
+                Ok(())
+            }
+            Err(e) => {
+                error!("{}", e);
+                Err(PyValueError::new_err(e.to_string()))
+            }
+        }
+    }
+
+    #[getter]
+    fn thrust_range(&self) -> [f64; 2] {
+        self.0.thrust_range()
+    }
+
+    /// The gains and limits by name
+    #[getter]
+    fn gains(&self) -> HashMap<String, f64> {
+        let g = self.0.gains;
+        [
+            ("k_h", g.k_h),
+            ("max_vertical_speed", g.max_vertical_speed),
+            ("k_gamma", g.k_gamma),
+            ("k_gamma_i", g.k_gamma_i),
+            ("k_q", g.k_q),
+            ("k_psi", g.k_psi),
+            ("bank_limit", g.bank_limit),
+            ("k_phi", g.k_phi),
+            ("k_p", g.k_p),
+            ("k_beta", g.k_beta),
+            ("k_r", g.k_r),
+            ("k_v", g.k_v),
+            ("k_v_i", g.k_v_i),
+            ("sample_time", g.sample_time),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v))
+        .collect()
+    }
+
+    #[getter]
+    fn sample_time(&self) -> f64 {
+        self.0.gains.sample_time
+    }
+
+    fn reset(&mut self) {
+        self.0.reset();
+    }
+
+    /// The command at `t` for a plane at `output` around `reference`
+    fn step(&mut self, t: f64, output: &CoreOutput, reference: &Control) -> Control {
+        Control(self.0.step(t, &output.0, &reference.0))
+    }
+
+    fn __repr__(&self) -> String {
+        format!("{:?}", self.0)
+    }
+}
+
+#[pyclass]
+#[derive(Clone)]
+struct Flcs(FlcsBase);
+
+#[pymethods]
+impl Flcs {
+    /// The gains left out keep the values of the F-16
+    #[new]
+    #[pyo3(signature = (breakout=None, nz_max=None, nz_min=None, roll_rate_max=None, rudder_max=None, pitch_prefilter=None, roll_prefilter=None, k_nz=None, k_i=None, k_q=None, alpha_limit=None, k_alpha=None, k_p=None, k_r=None, washout=None, k_ari=None, qbar_ref=None, sample_time=None))]
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        breakout: Option<f64>,
+        nz_max: Option<f64>,
+        nz_min: Option<f64>,
+        roll_rate_max: Option<f64>,
+        rudder_max: Option<f64>,
+        pitch_prefilter: Option<f64>,
+        roll_prefilter: Option<f64>,
+        k_nz: Option<f64>,
+        k_i: Option<f64>,
+        k_q: Option<f64>,
+        alpha_limit: Option<f64>,
+        k_alpha: Option<f64>,
+        k_p: Option<f64>,
+        k_r: Option<f64>,
+        washout: Option<f64>,
+        k_ari: Option<f64>,
+        qbar_ref: Option<f64>,
+        sample_time: Option<f64>,
+    ) -> PyResult<Self> {
+        let f16 = FlcsGains::f16();
+        let gains = FlcsGains {
+            breakout: breakout.unwrap_or(f16.breakout),
+            nz_max: nz_max.unwrap_or(f16.nz_max),
+            nz_min: nz_min.unwrap_or(f16.nz_min),
+            roll_rate_max: roll_rate_max.unwrap_or(f16.roll_rate_max),
+            rudder_max: rudder_max.unwrap_or(f16.rudder_max),
+            pitch_prefilter: pitch_prefilter.unwrap_or(f16.pitch_prefilter),
+            roll_prefilter: roll_prefilter.unwrap_or(f16.roll_prefilter),
+            k_nz: k_nz.unwrap_or(f16.k_nz),
+            k_i: k_i.unwrap_or(f16.k_i),
+            k_q: k_q.unwrap_or(f16.k_q),
+            alpha_limit: alpha_limit.unwrap_or(f16.alpha_limit),
+            k_alpha: k_alpha.unwrap_or(f16.k_alpha),
+            k_p: k_p.unwrap_or(f16.k_p),
+            k_r: k_r.unwrap_or(f16.k_r),
+            washout: washout.unwrap_or(f16.washout),
+            k_ari: k_ari.unwrap_or(f16.k_ari),
+            qbar_ref: qbar_ref.unwrap_or(f16.qbar_ref),
+            sample_time: sample_time.unwrap_or(f16.sample_time),
+        };
+        if gains.sample_time <= 0.0 {
+            let e = FatalCoreError::InvalidInput("sample_time must be positive".to_string());
+            error!("{}", e);
+            return Err(PyValueError::new_err(e.to_string()));
+        }
+        Ok(Self(FlcsBase::new(gains)))
+    }
+
+    #[staticmethod]
+    fn f16() -> Self {
+        Self(FlcsBase::f16())
+    }
+
+    /// The gains and limits by name
+    #[getter]
+    fn gains(&self) -> HashMap<String, f64> {
+        let g = self.0.gains;
+        [
+            ("breakout", g.breakout),
+            ("nz_max", g.nz_max),
+            ("nz_min", g.nz_min),
+            ("roll_rate_max", g.roll_rate_max),
+            ("rudder_max", g.rudder_max),
+            ("pitch_prefilter", g.pitch_prefilter),
+            ("roll_prefilter", g.roll_prefilter),
+            ("k_nz", g.k_nz),
+            ("k_i", g.k_i),
+            ("k_q", g.k_q),
+            ("alpha_limit", g.alpha_limit),
+            ("k_alpha", g.k_alpha),
+            ("k_p", g.k_p),
+            ("k_r", g.k_r),
+            ("washout", g.washout),
+            ("k_ari", g.k_ari),
+            ("qbar_ref", g.qbar_ref),
+            ("sample_time", g.sample_time),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v))
+        .collect()
+    }
+
+    #[getter]
+    fn sample_time(&self) -> f64 {
+        self.0.gains.sample_time
+    }
+
+    /// The [bottom, top] travel of elevator, aileron and rudder in deg
+    #[getter]
+    fn limits(&self) -> [[f64; 2]; 3] {
+        self.0.limits()
+    }
+
+    #[setter]
+    fn set_limits(&mut self, limits: [[f64; 2]; 3]) {
+        self.0.set_limits(limits);
+    }
+
+    /// The surfaces held with the stick centered, None before the first frame
+    #[getter]
+    fn trim(&self) -> Option<Control> {
+        self.0.trim().map(Control)
+    }
+
+    #[pyo3(signature = (trim=None))]
+    fn reset(&mut self, trim: Option<&Control>) {
+        self.0.reset(trim.map(|c| c.0));
+    }
+
+    /// Run one frame, pilot: thrust, longitudinal stick, lateral stick and pedals
+    fn step(&mut self, t: f64, output: &CoreOutput, pilot: &Control) -> Control {
+        Control(self.0.step(t, &output.0, &pilot.0))
+    }
+
+    fn __repr__(&self) -> String {
+        format!("{:?}", self.0)
+    }
+}
+
+#[pyclass]
+#[derive(Clone)]
+struct Sweep(SweepBase);
+
+#[pymethods]
+impl Sweep {
+    #[new]
+    fn new(value: i32) -> Self {
+        match value {
+            0 => Self(SweepBase::Linear),
+            1 => Self(SweepBase::Logarithmic),
+            _ => panic!("Invalid value for Sweep"),
+        }
+    }
+
+    #[getter]
+    fn value(&self) -> i32 {
+        match self.0 {
+            SweepBase::Linear => 0,
+            SweepBase::Logarithmic => 1,
+        }
+    }
+
+    #[staticmethod]
+    fn linear() -> Self {
+        Self(SweepBase::Linear)
+    }
+
+    #[staticmethod]
+    fn logarithmic() -> Self {
+        Self(SweepBase::Logarithmic)
+    }
+}
+
+#[pyclass]
+#[derive(Clone)]
+struct Signal(SignalBase);
+
+fn signal_from(res: Result<SignalBase, FatalCoreError>) -> PyResult<Signal> {
+    match res {
+        Ok(s) => Ok(Signal(s)),
+        Err(e) => {
+            error!("{}", e);
+            Err(PyValueError::new_err(e.to_string()))
+        }
+    }
+}
+
+#[pymethods]
+impl Signal {
+    #[staticmethod]
+    #[pyo3(signature = (start_time, amplitude, duration=f64::INFINITY))]
+    fn step(start_time: f64, amplitude: f64, duration: f64) -> Self {
+        Self(SignalBase::step(start_time, amplitude, duration))
+    }
+
+    #[staticmethod]
+    fn ramp(start_time: f64, amplitude: f64, duration: f64) -> Self {
+        Self(SignalBase::ramp(start_time, amplitude, duration))
+    }
+
+    #[staticmethod]
+    fn pulse(start_time: f64, amplitude: f64, duration: f64, period: f64, width: f64) -> Self {
+        Self(SignalBase::pulse(
+            start_time, amplitude, duration, period, width,
+        ))
+    }
+
+    #[staticmethod]
+    fn doublet(start_time: f64, amplitude: f64, duration: f64) -> Self {
+        Self(SignalBase::doublet(start_time, amplitude, duration))
+    }
+
+    #[staticmethod]
+    fn multistep_3211(start_time: f64, amplitude: f64, duration: f64) -> Self {
+        Self(SignalBase::multistep_3211(start_time, amplitude, duration))
+    }
+
+    #[staticmethod]
+    #[pyo3(signature = (start_time, amplitude, duration, f_0, f_1, sweep=None))]
+    fn chirp(
+        start_time: f64,
+        amplitude: f64,
+        duration: f64,
+        f_0: f64,
+        f_1: f64,
+        sweep: Option<&Sweep>,
+    ) -> PyResult<Self> {
+        let sweep = sweep.map(|s| s.0).unwrap_or_default();
+        signal_from(SignalBase::chirp(
+            start_time, amplitude, duration, f_0, f_1, sweep,
+        ))
+    }
+
+    #[staticmethod]
+    fn multisine(
+        start_time: f64,
+        amplitude: f64,
+        duration: f64,
+        frequencies: Vec<f64>,
+    ) -> PyResult<Self> {
+        signal_from(SignalBase::multisine(
+            start_time,
+            amplitude,
+            duration,
+            frequencies,
+        ))
+    }
+
+    #[staticmethod]
+    #[pyo3(signature = (start_time, amplitude, duration, clock, order=7))]
+    fn prbs(
+        start_time: f64,
+        amplitude: f64,
+        duration: f64,
+        clock: f64,
+        order: u32,
+    ) -> PyResult<Self> {
+        signal_from(SignalBase::prbs(
+            start_time, amplitude, duration, clock, order,
+        ))
+    }
+
+    #[staticmethod]
+    fn sum(signals: Vec<PyRef<Signal>>) -> Self {
+        Self(SignalBase::sum(
+            signals.iter().map(|s| s.0.clone()).collect(),
+        ))
+    }
+
+    fn value(&self, t: f64) -> f64 {
+        self.0.value(t)
+    }
+
+    fn __call__(&self, t: f64) -> f64 {
+        self.0.value(t)
+    }
+
+    fn __add__(&self, other: &Signal) -> Self {
+        Self(self.0.clone() + other.0.clone())
+    }
+
+    #[getter]
+    fn start_time(&self) -> f64 {
+        self.0.start_time
+    }
+
+    #[getter]
+    fn amplitude(&self) -> f64 {
+        self.0.amplitude
+    }
+
+    #[getter]
+    fn duration(&self) -> f64 {
+        self.0.duration
+    }
+}
+
+#[pyclass]
+struct Formulation(FormulationBase);
+
+#[pymethods]
+impl Formulation {
+    #[new]
+    #[pyo3(signature = (attitude=None, velocity=None, navigation=None))]
+    fn new(
+        attitude: Option<&Attitude>,
+        velocity: Option<&Velocity>,
+        navigation: Option<&Navigation>,
+    ) -> Self {
+        Self(FormulationBase::new(
+            attitude.map(|a| a.0).unwrap_or_default(),
+            velocity.map(|v| v.0).unwrap_or_default(),
+            navigation.map(|n| n.0).unwrap_or_default(),
+        ))
+    }
+
+    #[getter]
+    fn navigation(&self) -> Navigation {
+        Navigation(self.0.navigation)
+    }
+
+    #[getter]
+    fn attitude(&self) -> Attitude {
+        Attitude(self.0.attitude)
+    }
+
+    #[getter]
+    fn velocity(&self) -> Velocity {
+        Velocity(self.0.velocity)
+    }
+
+    #[getter]
+    fn dim(&self) -> usize {
+        self.0.dim()
+    }
+}
+
+#[pyclass]
+struct Event(EventBase);
+
+#[pymethods]
+impl Event {
+    /// function: a python callable `(State, StateExtend) -> float`
+    #[new]
+    #[pyo3(signature = (name, function, direction=None, terminal=false))]
+    fn new(name: &str, function: PyObject, direction: Option<&Direction>, terminal: bool) -> Self {
+        let direction = direction.map(|d| d.0).unwrap_or_default();
+        let function = move |state: &StateBase, extend: &StateExtendBase| {
+            Python::with_gil(|py| {
+                function
+                    .call1(py, (State(*state), StateExtend(*extend)))
+                    .and_then(|v| v.extract::<f64>(py))
+                    .map_err(|e| FatalCoreError::Callback(e.to_string()))
+            })
+        };
+        Self(EventBase::new(name, function, direction, terminal))
+    }
+
+    #[staticmethod]
+    fn ground() -> Self {
+        Self(EventBase::ground())
+    }
+
+    #[staticmethod]
+    #[pyo3(signature = (value, direction=None, terminal=false))]
+    fn altitude(value: f64, direction: Option<&Direction>, terminal: bool) -> Self {
+        let direction = direction.map(|d| d.0).unwrap_or_default();
+        Self(EventBase::altitude(value, direction, terminal))
+    }
+
+    #[staticmethod]
+    #[pyo3(signature = (value, direction=None, terminal=false))]
+    fn alpha(value: f64, direction: Option<&Direction>, terminal: bool) -> Self {
+        let direction = direction.map(|d| d.0).unwrap_or_default();
+        Self(EventBase::alpha(value, direction, terminal))
+    }
+
+    #[staticmethod]
+    #[pyo3(signature = (value, direction=None, terminal=false))]
+    fn mach(value: f64, direction: Option<&Direction>, terminal: bool) -> Self {
+        let direction = direction.map(|d| d.0).unwrap_or_default();
+        Self(EventBase::mach(value, direction, terminal))
+    }
+
+    #[getter]
+    fn name(&self) -> String {
+        self.0.name().to_string()
+    }
+
+    #[getter]
+    fn direction(&self) -> Direction {
+        Direction(self.0.direction())
+    }
+
+    #[getter]
+    fn terminal(&self) -> bool {
+        self.0.terminal()
+    }
+}
+
+/// Collect the events passed from python
+fn events_from_py(events: Option<Vec<PyRef<Event>>>) -> Vec<EventBase> {
+    events
+        .unwrap_or_default()
+        .iter()
+        .map(|e| e.0.clone())
+        .collect()
+}
+
+#[pyclass]
+struct EventRecord(EventRecordBase);
+
+#[pymethods]
+impl EventRecord {
+    #[getter]
+    fn name(&self) -> String {
+        self.0.name.clone()
+    }
+
+    #[getter]
+    fn index(&self) -> usize {
+        self.0.index
+    }
+
+    #[getter]
+    fn time(&self) -> f64 {
+        self.0.time
+    }
+
+    #[getter]
+    fn direction(&self) -> Direction {
+        Direction(self.0.direction)
+    }
+
+    #[getter]
+    fn terminal(&self) -> bool {
+        self.0.terminal
+    }
+
+    #[getter]
+    fn state(&self) -> State {
+        State(self.0.state)
+    }
+
+    #[getter]
+    fn state_extend(&self) -> StateExtend {
+        StateExtend(self.0.state_extend)
+    }
+}
+
+/// A python callable `(t, output, reference) -> Control` sampled every `sample_time`
+struct PyController {
+    controller: PyObject,
+    sample_time: f64,
+}
+
+impl DiscreteController for PyController {
+    fn sample_time(&self) -> f64 {
+        self.sample_time
+    }
+
+    fn sample(
+        &mut self,
+        t: f64,
+        output: &CoreOutputBase,
+        reference: &ControlBase,
+    ) -> Result<ControlBase, FatalCoreError> {
+        Python::with_gil(|py| {
+            let args = (t, CoreOutput(*output), Control(*reference));
+            let command = self
+                .controller
+                .call1(py, args)
+                .and_then(|c| c.extract::<PyRef<Control>>(py).map(|c| c.0));
+            command.map_err(|e| FatalCoreError::Callback(e.to_string()))
+        })
+    }
+}
+
+#[pyclass(module = "pyf16._core")]
+struct PlaneBlockSnapshot(PlaneBlockSnapshotBase);
+
+#[pymethods]
+impl PlaneBlockSnapshot {
+    // Deserialize a snapshot from the output of `to_toml`
+    #[new]
+    fn new(content: &str) -> PyResult<Self> {
+        match toml::from_str(content) {
+            Ok(s) => Ok(Self(s)),
+            Err(e) => {
+                error!("{}", e);
+                Err(PyValueError::new_err(e.to_string()))
+            }
+        }
+    }
+
+    fn to_toml(&self) -> PyResult<String> {
+        match toml::to_string(&self.0) {
+            Ok(s) => Ok(s),
+            Err(e) => {
+                error!("{}", e);
+                Err(PyValueError::new_err(e.to_string()))
+            }
+        }
+    }
+
+    fn __reduce__<'py>(slf: &Bound<'py, Self>) -> PyResult<(Bound<'py, PyAny>, (String,))> {
+        let content = slf.borrow().to_toml()?;
+        Ok((slf.get_type().into_any(), (content,)))
+    }
+
+    #[getter]
+    fn start_time(&self) -> Option<f64> {
+        self.0.start_time
+    }
+
+    #[getter]
+    fn formulation(&self) -> Formulation {
+        Formulation(self.0.formulation)
+    }
+
+    #[getter]
+    fn vector(&self) -> Vec<f64> {
+        self.0.vector.clone()
+    }
+
+    #[getter]
+    fn last_time(&self) -> Option<f64> {
+        self.0.last_time
+    }
+
+    #[getter]
+    fn state(&self) -> State {
+        State(self.0.state)
+    }
+
+    #[getter]
+    fn state_dot(&self) -> State {
+        State(self.0.state_dot)
+    }
+
+    #[getter]
+    fn state_extend(&self) -> Option<StateExtend> {
+        self.0.extend.map(StateExtend)
+    }
+
+    #[getter]
+    fn actuators(&self) -> Vec<f64> {
+        self.0.actuators.iter().map(|a| a.state).collect()
+    }
+
+    #[getter]
+    fn lef(&self) -> Vec<f64> {
+        vec![self.0.lef.actuator.state, self.0.lef.state]
+    }
+}
+
+#[pyclass(module = "pyf16._core")]
+struct LinearModelSnapshot(LinearModelSnapshotBase);
+
+#[pymethods]
+impl LinearModelSnapshot {
+    // Deserialize a snapshot from the output of `to_toml`
+    #[new]
+    fn new(content: &str) -> PyResult<Self> {
+        match toml::from_str(content) {
+            Ok(s) => Ok(Self(s)),
+            Err(e) => {
+                error!("{}", e);
+                Err(PyValueError::new_err(e.to_string()))
+            }
+        }
+    }
+
+    fn to_toml(&self) -> PyResult<String> {
+        match toml::to_string(&self.0) {
+            Ok(s) => Ok(s),
+            Err(e) => {
+                error!("{}", e);
+                Err(PyValueError::new_err(e.to_string()))
+            }
+        }
+    }
+
+    fn __reduce__<'py>(slf: &Bound<'py, Self>) -> PyResult<(Bound<'py, PyAny>, (String,))> {
+        let content = slf.borrow().to_toml()?;
+        Ok((slf.get_type().into_any(), (content,)))
+    }
+
+    /// The deviation of the states from the operating point
+    #[getter]
+    fn state(&self) -> Vec<f64> {
+        self.0.state.clone()
+    }
+
+    #[getter]
+    fn control(&self) -> Control {
+        Control(self.0.control)
+    }
+
+    #[getter]
+    fn last_time(&self) -> Option<f64> {
+        self.0.last_time
+    }
+}
+
+#[pyfunction]
+#[pyo3(signature = (model, trim_target, ctrl_limit, trim_init=None, flight_condition=None, optim_options=None, atmosphere=None))]
+fn trim(
+    model: &AerodynamicModel,
+    trim_target: &TrimTarget,
+    ctrl_limit: &ControlLimit,
+    trim_init: Option<&TrimInit>,
+    flight_condition: Option<&FlightCondition>,
+    optim_options: Option<&NelderMeadOptions>,
+    atmosphere: Option<&Atmosphere>,
+) -> PyResult<TrimOutput> {
+    match MechanicalModel::new(&model.0) {
+        Ok(mut m) => {
+            if let Some(a) = atmosphere {
+                m.set_atmosphere(a.0.clone());
+            }
+            let model = Rc::new(RefCell::new(m));
+            let res = trim_base(
+                model.clone(),
+                trim_target.0,
+                trim_init.map(|x| x.0),
+                ctrl_limit.0,
+                flight_condition.map(|x| x.0),
+                optim_options.map(|x| x.0),
+            );
+            match res {
+                Ok(o) => Ok(TrimOutput(o)),
+                Err(e) => {
+                    error!("{}", e);
+                    Err(PyValueError::new_err(e.to_string()))
+                }
+            }
+        }
+        Err(e) => {
+            error!("{}", e);
+            return Err(PyValueError::new_err(e.to_string()));
+        }
+    }
+}
+
+/// actuators: True for the gains of `PlaneBlock`, or the four gains (1/s) of the
+/// thrust, elevator, aileron and rudder actuators
+#[pyfunction]
+#[pyo3(signature = (model, trim_output, actuators=None, lef=false, step=None, min_step=None, atmosphere=None))]
+fn linearize(
+    model: &AerodynamicModel,
+    trim_output: &TrimOutput,
+    actuators: Option<&Bound<'_, PyAny>>,
+    lef: bool,
+    step: Option<f64>,
+    min_step: Option<f64>,
+    atmosphere: Option<&Atmosphere>,
+) -> PyResult<Linearization> {
+    let mut options = LinearizeOptions {
+        lef,
+        ..Default::default()
+    };
+    if let Some(a) = actuators {
+        if let Ok(gains) = a.extract::<[f64; 4]>() {
+            options.actuators = Some(gains);
+        } else if a.extract::<bool>()? {
+            options = options.with_actuators();
+        }
+    }
+    options.step = step.unwrap_or(options.step);
+    options.min_step = min_step.unwrap_or(options.min_step);
+    let res = MechanicalModel::new(&model.0).and_then(|mut m| {
+        if let Some(a) = atmosphere {
+            m.set_atmosphere(a.0.clone());
+        }
+        linearize_base(&m, &trim_output.0, Some(options))
+    });
+    match res {
+        Ok(l) => Ok(Linearization(l)),
+        Err(e) => {
+            error!("{}", e);
+            Err(PyValueError::new_err(e.to_string()))
+        }
+    }
+}
+
+fn rows(m: &Matrix) -> Vec<Vec<f64>> {
+    m.data.iter().map(|row| row.data.clone()).collect()
+}
+
+/// x_dot = a x + b u, y = c x + d u about a trim point with named rows and columns
+#[pyclass]
+#[derive(Clone)]
+struct Linearization(LinearizationBase);
+
+#[pymethods]
+impl Linearization {
+    #[getter]
+    fn a(&self) -> Vec<Vec<f64>> {
+        rows(&self.0.a)
+    }
+
+    #[getter]
+    fn b(&self) -> Vec<Vec<f64>> {
+        rows(&self.0.b)
+    }
+
+    #[getter]
+    fn c(&self) -> Vec<Vec<f64>> {
+        rows(&self.0.c)
+    }
+
+    #[getter]
+    fn d(&self) -> Vec<Vec<f64>> {
+        rows(&self.0.d)
+    }
+
+    #[getter]
+    fn states(&self) -> Vec<String> {
+        self.0.states.clone()
+    }
+
+    #[getter]
+    fn inputs(&self) -> Vec<String> {
+        self.0.inputs.clone()
+    }
+
+    #[getter]
+    fn outputs(&self) -> Vec<String> {
+        self.0.outputs.clone()
+    }
+
+    /// The operating point of the states
+    #[getter]
+    fn state(&self) -> Vec<f64> {
+        self.0.state.clone()
+    }
+
+    #[getter]
+    fn input(&self) -> Vec<f64> {
+        self.0.input.clone()
+    }
+
+    #[getter]
+    fn output(&self) -> Vec<f64> {
+        self.0.output.clone()
+    }
+
+    fn subset(
+        &self,
+        states: Vec<String>,
+        inputs: Vec<String>,
+        outputs: Vec<String>,
+    ) -> PyResult<Self> {
+        let states: Vec<&str> = states.iter().map(String::as_str).collect();
+        let inputs: Vec<&str> = inputs.iter().map(String::as_str).collect();
+        let outputs: Vec<&str> = outputs.iter().map(String::as_str).collect();
+        match self.0.subset(&states, &inputs, &outputs) {
+            Ok(l) => Ok(Self(l)),
+            Err(e) => {
+                error!("{}", e);
+                Err(PyValueError::new_err(e.to_string()))
+            }
+        }
+    }
+
+    fn longitudinal(&self) -> Self {
+        Self(self.0.longitudinal())
+    }
+
+    fn lateral(&self) -> Self {
+        Self(self.0.lateral())
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Linearization(states={:?}, inputs={:?}, outputs={:?})",
+            self.0.states, self.0.inputs, self.0.outputs
+        )
+    }
+}
+
+#[pyfunction]
+fn modal_analysis(linearization: &Linearization) -> PyResult<ModalAnalysis> {
+    match modal_analysis_base(&linearization.0) {
+        Ok(m) => Ok(ModalAnalysis(m)),
+        Err(e) => {
+            error!("{}", e);
+            Err(PyValueError::new_err(e.to_string()))
+        }
+    }
+}
+
+fn mode_kind(kind: &str) -> PyResult<ModeKind> {
+    match ModeKind::ALL.iter().find(|k| k.to_string() == kind) {
+        Some(k) => Ok(*k),
+        None => {
+            let names: Vec<String> = ModeKind::ALL.iter().map(|k| k.to_string()).collect();
+            Err(PyValueError::new_err(format!(
+                "unknown mode {kind}, expected one of {names:?}"
+            )))
+        }
+    }
+}
+
+/// One real eigenvalue or complex pair of a linearization
+#[pyclass]
+#[derive(Clone)]
+struct Mode(ModeBase);
+
+#[pymethods]
+impl Mode {
+    /// "short_period", "phugoid", "dutch_roll", "roll", "spiral", "actuator" or "other"
+    #[getter]
+    fn kind(&self) -> String {
+        self.0.kind.to_string()
+    }
+
+    /// The member of a pair with positive imaginary part
+    #[getter]
+    fn eigenvalue<'py>(&self, py: Python<'py>) -> Bound<'py, PyComplex> {
+        PyComplex::from_doubles(py, self.0.eigenvalue.re, self.0.eigenvalue.im)
+    }
+
+    #[getter]
+    fn natural_frequency(&self) -> f64 {
+        self.0.natural_frequency
+    }
+
+    #[getter]
+    fn damping(&self) -> f64 {
+        self.0.damping
+    }
+
+    #[getter]
+    fn damped_frequency(&self) -> f64 {
+        self.0.damped_frequency
+    }
+
+    #[getter]
+    fn period(&self) -> Option<f64> {
+        self.0.period
+    }
+
+    #[getter]
+    fn time_constant(&self) -> Option<f64> {
+        self.0.time_constant
+    }
+
+    #[getter]
+    fn time_to_half(&self) -> Option<f64> {
+        self.0.time_to_half
+    }
+
+    #[getter]
+    fn time_to_double(&self) -> Option<f64> {
+        self.0.time_to_double
+    }
+
+    /// The right eigenvector in the order of `ModalAnalysis.states`
+    #[getter]
+    fn shape<'py>(&self, py: Python<'py>) -> Vec<Bound<'py, PyComplex>> {
+        self.0
+            .shape
+            .iter()
+            .map(|v| PyComplex::from_doubles(py, v.re, v.im))
+            .collect()
+    }
+
+    #[getter]
+    fn participation(&self) -> Vec<f64> {
+        self.0.participation.clone()
+    }
+
+    #[getter]
+    fn dominant(&self) -> String {
+        self.0.dominant.clone()
+    }
+
+    fn is_oscillatory(&self) -> bool {
+        self.0.is_oscillatory()
+    }
+
+    fn is_stable(&self) -> bool {
+        self.0.is_stable()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Mode(kind={}, eigenvalue={}, natural_frequency={:.4}, damping={:.4}, dominant={})",
+            self.0.kind,
+            self.0.eigenvalue,
+            self.0.natural_frequency,
+            self.0.damping,
+            self.0.dominant
+        )
+    }
+}
+
+/// The modes of a linearization in the order of descending real part
+#[pyclass]
+#[derive(Clone)]
+struct ModalAnalysis(ModalAnalysisBase);
+
+#[pymethods]
+impl ModalAnalysis {
+    #[getter]
+    fn states(&self) -> Vec<String> {
+        self.0.states.clone()
+    }
+
+    #[getter]
+    fn modes(&self) -> Vec<Mode> {
+        self.0.modes.iter().cloned().map(Mode).collect()
+    }
+
+    /// The least stable mode of `kind`, None if there is none
+    fn mode(&self, kind: &str) -> PyResult<Option<Mode>> {
+        let kind = mode_kind(kind)?;
+        Ok(self.0.mode(kind).cloned().map(Mode))
+    }
+
+    fn modes_of(&self, kind: &str) -> PyResult<Vec<Mode>> {
+        let kind = mode_kind(kind)?;
+        Ok(self
+            .0
+            .modes_of(kind)
+            .into_iter()
+            .cloned()
+            .map(Mode)
+            .collect())
+    }
+
+    fn __repr__(&self) -> String {
+        let kinds: Vec<String> = self.0.modes.iter().map(|m| m.kind.to_string()).collect();
+        format!(
+            "ModalAnalysis(states={:?}, modes={:?})",
+            self.0.states, kinds
+        )
+    }
+}
+
+/// category: "A", "B" or "C", aircraft_class: "I", "II", "III" or "IV"
+#[pyfunction]
+#[pyo3(signature = (linearization, category="A", aircraft_class="IV"))]
+fn handling_qualities(
+    linearization: &Linearization,
+    category: &str,
+    aircraft_class: &str,
+) -> PyResult<HandlingQualities> {
+    let category = match category {
+        "A" => Category::A,
+        "B" => Category::B,
+        "C" => Category::C,
+        _ => {
+            return Err(PyValueError::new_err(format!(
+                "unknown flight phase category {category}, expected A, B or C"
+            )))
+        }
+    };
+    let class = match aircraft_class {
+        "I" => AircraftClass::I,
+        "II" => AircraftClass::II,
+        "III" => AircraftClass::III,
+        "IV" => AircraftClass::IV,
+        _ => {
+            return Err(PyValueError::new_err(format!(
+                "unknown aircraft class {aircraft_class}, expected I, II, III or IV"
+            )))
+        }
+    };
+    let options = HandlingOptions { category, class };
+    match handling_qualities_base(&linearization.0, Some(options)) {
+        Ok(h) => Ok(HandlingQualities(h)),
+        Err(e) => {
+            error!("{}", e);
+            Err(PyValueError::new_err(e.to_string()))
+        }
+    }
+}
+
+/// One handling-qualities requirement and the level it is met at
+#[pyclass]
+#[derive(Clone)]
+struct Criterion(CriterionBase);
+
+#[pymethods]
+impl Criterion {
+    #[getter]
+    fn name(&self) -> String {
+        self.0.name.clone()
+    }
+
+    #[getter]
+    fn value(&self) -> f64 {
+        self.0.value
+    }
+
+    /// The (min, max) range of Level 1, 2 and 3
+    #[getter]
+    fn limits(&self) -> Vec<(f64, f64)> {
+        self.0.limits.to_vec()
+    }
+
+    /// 1, 2, 3 or 4 when even the Level 3 limits are not met
+    #[getter]
+    fn level(&self) -> u8 {
+        self.0.level.number()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Criterion(name={}, value={:.4}, level={})",
+            self.0.name,
+            self.0.value,
+            self.0.level.number()
+        )
+    }
+}
+
+#[pyclass]
+#[derive(Clone)]
+struct HandlingQualities(HandlingQualitiesBase);
+
+#[pymethods]
+impl HandlingQualities {
+    #[getter]
+    fn category(&self) -> String {
+        format!("{:?}", self.0.options.category)
+    }
+
+    #[getter]
+    fn aircraft_class(&self) -> String {
+        format!("{:?}", self.0.options.class)
+    }
+
+    #[getter]
+    fn short_period_frequency(&self) -> f64 {
+        self.0.short_period_frequency
+    }
+
+    #[getter]
+    fn short_period_damping(&self) -> f64 {
+        self.0.short_period_damping
+    }
+
+    #[getter]
+    fn n_alpha(&self) -> f64 {
+        self.0.n_alpha
+    }
+
+    #[getter]
+    fn cap(&self) -> f64 {
+        self.0.cap
+    }
+
+    #[getter]
+    fn dutch_roll_frequency(&self) -> f64 {
+        self.0.dutch_roll_frequency
+    }
+
+    #[getter]
+    fn dutch_roll_damping(&self) -> f64 {
+        self.0.dutch_roll_damping
+    }
+
+    #[getter]
+    fn roll_time_constant(&self) -> f64 {
+        self.0.roll_time_constant
+    }
+
+    #[getter]
+    fn spiral_time_to_double(&self) -> f64 {
+        self.0.spiral_time_to_double
+    }
+
+    #[getter]
+    fn bandwidth(&self) -> Option<f64> {
+        self.0.bandwidth
+    }
+
+    #[getter]
+    fn phase_delay(&self) -> Option<f64> {
+        self.0.phase_delay
+    }
+
+    #[getter]
+    fn criteria(&self) -> Vec<Criterion> {
+        self.0.criteria.iter().cloned().map(Criterion).collect()
+    }
+
+    /// The worst level of all criteria
+    #[getter]
+    fn level(&self) -> u8 {
+        self.0.level.number()
+    }
+
+    fn criterion(&self, name: &str) -> Option<Criterion> {
+        self.0.criterion(name).cloned().map(Criterion)
+    }
+
+    fn __repr__(&self) -> String {
+        let levels: Vec<String> = self
+            .0
+            .criteria
+            .iter()
+            .map(|c| format!("{}: {}", c.name, c.level.number()))
+            .collect();
+        format!(
+            "HandlingQualities(category={:?}, level={}, criteria={{{}}})",
+            self.0.options.category,
+            self.0.level.number(),
+            levels.join(", ")
+        )
+    }
+}
+
+#[pyclass]
+struct SolverStats(SolverStatsBase);
+
+#[pymethods]
+impl SolverStats {
+    #[getter]
+    fn accepted_steps(&self) -> usize {
+        self.0.accepted_steps
+    }
+
+    #[getter]
+    fn rejected_steps(&self) -> usize {
+        self.0.rejected_steps
+    }
+
+    #[getter]
+    fn fun_evals(&self) -> usize {
+        self.0.fun_evals
+    }
+}
+
+/// Wrap a python callable `(t, state, input) -> state_dot` as `VectorDynamics`
+fn py_dynamics(dynamics: PyObject) -> impl Fn(f64, &Vector, &Vector) -> Vector {
+    move |t: f64, state: &Vector, input: &Vector| -> Vector {
+        let state = state.data.clone();
+        let input = input.data.clone();
+        Python::with_gil(|py| {
+            let args = (t, state, input);
+            let result: Vec<f64> = dynamics
+                .call1(py, args)
+                .expect("Dynamics function call failed")
+                .extract(py)
+                .expect("Failed to extract f64 from PyAny");
+            Vector::from(result)
+        })
+    }
+}
+
+macro_rules! create_simple_solver {
+    ($name:ident, $solver:ty) => {
+        create_simple_solver!($name, $solver, {
+            #[new]
+            fn new(step: f64) -> Self {
+                Self {
+                    solver: <$solver>::new(step),
+                }
+            }
+        });
+    };
+    ($name:ident, $solver:ty, implicit) => {
+        create_simple_solver!($name, $solver, {
+            #[new]
+            #[pyo3(signature = (step, tol=1e-10, max_iter=20))]
+            fn new(step: f64, tol: f64, max_iter: usize) -> Self {
+                let options = NewtonOptions { tol, max_iter };
+                Self {
+                    solver: <$solver>::with_options(step, options),
+                }
+            }
+        });
+    };
+    ($name:ident, $solver:ty, { $($methods:tt)* }) => {
+        #[pyclass]
+        struct $name {
+            solver: $solver,
+        }
+
+        #[pymethods]
+        impl $name {
+            $($methods)*
+
+            fn solve(
+                &self,
+                dynamics: PyObject,
+                t: f64,
+                state: Vec<f64>,
+                input: Vec<f64>,
+            ) -> PyResult<Vec<f64>> {
+                let dynamics_closure = py_dynamics(dynamics);
+                let state = Vector::from(state);
+                let input = Vector::from(input);
+                let result = self.solver.solve(&dynamics_closure, t, &state, &input);
+                Ok(result.data.clone())
+            }
+
+            fn solve_step(
+                &self,
+                dynamics: PyObject,
+                t: f64,
+                h: f64,
+                state: Vec<f64>,
+                input: Vec<f64>,
+            ) -> PyResult<Vec<f64>> {
+                let dynamics_closure = py_dynamics(dynamics);
+                let state = Vector::from(state);
+                let input = Vector::from(input);
+                let result = self
+                    .solver
+                    .solve_step(&dynamics_closure, t, h, &state, &input);
+                Ok(result.data)
+            }
+
+            fn integrate(
+                &self,
+                dynamics: PyObject,
+                t_0: f64,
+                t_1: f64,
+                state: Vec<f64>,
+                input: Vec<f64>,
+            ) -> PyResult<Vec<f64>> {
+                if t_1 < t_0 {
+                    return Err(PyValueError::new_err(format!(
+                        "t_1 ({t_1}) must not be earlier than t_0 ({t_0})"
+                    )));
+                }
+                let dynamics_closure = py_dynamics(dynamics);
+                let state = Vector::from(state);
+                let input = Vector::from(input);
+                let result = self
+                    .solver
+                    .integrate(&dynamics_closure, t_0, t_1, &state, &input);
+                Ok(result.data)
+            }
+
+            #[getter]
+            fn delta_t(&self) -> f64 {
+                self.solver.delta_t()
+            }
+        }
+    };
+}
+
+create_simple_solver!(SimpleSolverRK1, RK1Solver);
+create_simple_solver!(SimpleSolverRK2, RK2Solver);
+create_simple_solver!(SimpleSolverRK3, RK3Solver);
+create_simple_solver!(SimpleSolverRK4, RK4Solver);
+create_simple_solver!(SimpleSolverBackwardEuler, BackwardEulerSolver, implicit);
+create_simple_solver!(SimpleSolverTrapezoidal, TrapezoidalSolver, implicit);
+create_simple_solver!(SimpleSolverSDIRK2, SDIRK2Solver, implicit);
+create_simple_solver!(SimpleSolverRK45, RK45Solver, {
+    #[new]
+    #[pyo3(signature = (step, rtol=1e-6, atol=1e-8, min_step=1e-6))]
+    fn new(step: f64, rtol: f64, atol: f64, min_step: f64) -> PyResult<Self> {
+        match RK45Solver::new(step, rtol, atol, min_step) {
+            Ok(solver) => Ok(Self { solver }),
+            Err(e) => {
+                error!("{}", e);
+                Err(PyValueError::new_err(e.to_string()))
+            }
+        }
+    }
+
+    #[getter]
+    fn stats(&self) -> SolverStats {
+        SolverStats(self.solver.stats())
+    }
+
+    fn reset_stats(&self) {
+        self.solver.reset_stats();
+    }
+});
+
+fn new_plane_block<S>(
+    solver: S,
+    model: &AerodynamicModel,
+    init: &CoreInit,
+    deflection: Vec<f64>,
+    ctrl_limit: &ControlLimit,
+    formulation: Option<&Formulation>,
+) -> PyResult<PlaneBlockBase<S>>
+where
+    S: crate::solver::ODESolver + VectorODESolver,
+{
+    if deflection.len() != 3 {
+        return Err(PyValueError::new_err(
+            "deflection must have exactly 3 elements",
+        ));
+    }
+    let deflection_array: [f64; 3] = [deflection[0], deflection[1], deflection[2]];
+    let solver = Arc::new(solver);
+    let formulation = formulation.map(|f| f.0).unwrap_or_default();
+    let plane = PlaneBlockBase::with_formulation(
+        solver,
+        &model.0,
+        &init.0,
+        &deflection_array,
+        ctrl_limit.0,
+        formulation,
+    );
+    match plane {
+        Ok(p) => Ok(p),
+        Err(e) => {
+            error!("{}", e);
+            Err(PyValueError::new_err(e.to_string()))
+        }
+    }
+}
+
+macro_rules! create_plane_block {
+    ($name:ident, $solver:ty) => {
+        create_plane_block!($name, $solver, {
+            #[new]
+            #[pyo3(signature = (step, model, init, deflection, ctrl_limit, formulation=None))]
+            fn new(
+                step: f64,
+                model: &AerodynamicModel,
+                init: &CoreInit,
+                deflection: Vec<f64>,
+                ctrl_limit: &ControlLimit,
+                formulation: Option<&Formulation>,
+            ) -> PyResult<Self> {
+                let solver = <$solver>::new(step);
+                new_plane_block(solver, model, init, deflection, ctrl_limit, formulation).map(Self)
+            }
+        });
+    };
+    ($name:ident, $solver:ty, implicit) => {
+        create_plane_block!($name, $solver, {
+            #[new]
+            #[pyo3(signature = (step, model, init, deflection, ctrl_limit, formulation=None, tol=1e-10, max_iter=20))]
+            #[allow(clippy::too_many_arguments)]
+            fn new(
+                step: f64,
+                model: &AerodynamicModel,
+                init: &CoreInit,
+                deflection: Vec<f64>,
+                ctrl_limit: &ControlLimit,
+                formulation: Option<&Formulation>,
+                tol: f64,
+                max_iter: usize,
+            ) -> PyResult<Self> {
+                let solver = <$solver>::with_options(step, NewtonOptions { tol, max_iter });
+                new_plane_block(solver, model, init, deflection, ctrl_limit, formulation).map(Self)
+            }
+        });
+    };
+    ($name:ident, $solver:ty, { $($methods:tt)* }) => {
+        #[pyclass]
+        struct $name(PlaneBlockBase<$solver>);
+
+        #[pymethods]
+        impl $name {
+            $($methods)*
+
+            fn update(&mut self, control: &Control, t: f64) -> PyResult<CoreOutput> {
+                match self.0.update(control.0, t) {
+                    Ok(o) => Ok(CoreOutput(o)),
+                    Err(e) => {
+                        error!("{}", e);
+                        Err(PyValueError::new_err(e.to_string()))
+                    }
+                }
+            }
+
+            fn reset(&mut self, init: &CoreInit) {
+                self.0.reset(&init.0);
+            }
+
+            fn snapshot(&self) -> PlaneBlockSnapshot {
+                PlaneBlockSnapshot(self.0.snapshot())
+            }
+
+            fn restore(&mut self, snapshot: &PlaneBlockSnapshot) -> PyResult<()> {
+                match self.0.restore(&snapshot.0) {
+                    Ok(_) => Ok(()),
+                    Err(e) => {
+                        error!("{}", e);
+                        Err(PyValueError::new_err(e.to_string()))
+                    }
+                }
+            }
+
+            #[pyo3(signature = (schedule, duration, delta_t, decimation=1, events=None))]
+            fn run(
+                &mut self,
+                schedule: &ControlSchedule,
+                duration: f64,
+                delta_t: f64,
+                decimation: usize,
+                events: Option<Vec<PyRef<Event>>>,
+            ) -> PyResult<Trajectory> {
+                let events = events_from_py(events);
+                let res = simulate(
+                    &mut self.0,
+                    &schedule.0,
+                    duration,
+                    delta_t,
+                    decimation,
+                    &events,
+                );
+                match res {
+                    Ok(t) => Ok(Trajectory(t)),
+                    Err(e) => {
+                        error!("{}", e);
+                        Err(PyValueError::new_err(e.to_string()))
+                    }
+                }
+            }
+
+            #[pyo3(signature = (controller, sample_time, schedule, duration, delta_t, decimation=1, events=None))]
+            #[allow(clippy::too_many_arguments)]
+            fn run_closed_loop(
+                &mut self,
+                controller: PyObject,
+                sample_time: f64,
+                schedule: &ControlSchedule,
+                duration: f64,
+                delta_t: f64,
+                decimation: usize,
+                events: Option<Vec<PyRef<Event>>>,
+            ) -> PyResult<Trajectory> {
+                let mut controller = PyController {
+                    controller,
+                    sample_time,
+                };
+                let events = events_from_py(events);
+                let res = simulate_closed_loop(
+                    &mut self.0,
+                    &mut controller,
+                    &schedule.0,
+                    duration,
+                    delta_t,
+                    decimation,
+                    &events,
+                );
+                match res {
+                    Ok(t) => Ok(Trajectory(t)),
+                    Err(e) => {
+                        error!("{}", e);
+                        Err(PyValueError::new_err(e.to_string()))
+                    }
+                }
+            }
+
+            #[pyo3(signature = (autopilot, schedule, duration, delta_t, decimation=1, events=None))]
+            #[allow(clippy::too_many_arguments)]
+            fn run_autopilot(
+                &mut self,
+                mut autopilot: PyRefMut<Autopilot>,
+                schedule: &ControlSchedule,
+                duration: f64,
+                delta_t: f64,
+                decimation: usize,
+                events: Option<Vec<PyRef<Event>>>,
+            ) -> PyResult<Trajectory> {
+                let events = events_from_py(events);
+                let res = simulate_closed_loop(
+                    &mut self.0,
+                    &mut autopilot.0,
+                    &schedule.0,
+                    duration,
+                    delta_t,
+                    decimation,
+                    &events,
+                );
+                match res {
+                    Ok(t) => Ok(Trajectory(t)),
+                    Err(e) => {
+                        error!("{}", e);
+                        Err(PyValueError::new_err(e.to_string()))
+                    }
+                }
+            }
+
+            #[getter]
+            fn subsystem_substeps(&self) -> usize {
+                self.0.subsystem_substeps()
+            }
+
+            fn set_subsystem_substeps(&mut self, n: usize) -> PyResult<()> {
+                match self.0.set_subsystem_substeps(n) {
+                    Ok(_) => Ok(()),
+                    Err(e) => {
+                        error!("{}", e);
+                        Err(PyValueError::new_err(e.to_string()))
+                    }
+                }
+            }
+
+            #[getter]
+            fn state(&self) -> CoreOutput {
+                CoreOutput(self.0.state())
+            }
+
+            #[getter]
+            fn state_dot(&self) -> State {
+                State(self.0.state_dot())
+            }
+
+            #[getter]
+            fn formulation(&self) -> Formulation {
+                Formulation(self.0.formulation())
+            }
+
+            #[getter]
+            fn geodetic(&self) -> Option<Geodetic> {
+                self.0.geodetic().map(Geodetic)
+            }
+
+            #[getter]
+            fn atmosphere(&self) -> Atmosphere {
+                Atmosphere(self.0.atmosphere())
+            }
+
+            fn set_atmosphere(&mut self, atmosphere: &Atmosphere) -> PyResult<()> {
+                match self.0.set_atmosphere(atmosphere.0.clone()) {
+                    Ok(_) => Ok(()),
+                    Err(e) => {
+                        error!("{}", e);
+                        Err(PyValueError::new_err(e.to_string()))
+                    }
+                }
+            }
+
+            #[getter]
+            fn air_data(&self) -> AirData {
+                AirData(self.0.air_data())
+            }
+
+            #[pyo3(signature = (channel, signal=None))]
+            fn set_excitation(&mut self, channel: usize, signal: Option<&Signal>) -> PyResult<()> {
+                match self.0.set_excitation(channel, signal.map(|s| s.0.clone())) {
+                    Ok(_) => Ok(()),
+                    Err(e) => {
+                        error!("{}", e);
+                        Err(PyValueError::new_err(e.to_string()))
+                    }
+                }
+            }
+
+            fn excitation(&self, channel: usize) -> Option<Signal> {
+                self.0.excitation(channel).cloned().map(Signal)
+            }
+
+            #[pyo3(signature = (wind=None))]
+            fn set_wind(&mut self, wind: Option<&Wind>) {
+                self.0.set_wind(wind.map(|w| w.0.clone()));
+            }
+
+            #[getter]
+            fn wind(&self) -> WindSample {
+                WindSample(self.0.wind())
+            }
+
+            #[getter]
+            fn air_relative(&self) -> AirRelative {
+                AirRelative(self.0.air_relative())
+            }
+
+            #[pyo3(signature = (mass=None))]
+            fn set_mass_model(&mut self, mass: Option<&MassModel>) {
+                self.0.set_mass_model(mass.map(|m| m.0.clone()));
+            }
+
+            #[getter]
+            fn mass_model(&self) -> Option<MassModel> {
+                self.0.mass_model().cloned().map(MassModel)
+            }
+
+            #[getter]
+            fn mass_state(&self) -> Option<MassState> {
+                self.0.mass_state().map(MassState)
+            }
+
+            #[getter]
+            fn constants(&self) -> PlaneConstants {
+                PlaneConstants(self.0.constants())
+            }
+
+            #[pyo3(signature = (engine=None))]
+            fn set_engine(&mut self, engine: Option<&Engine>) {
+                self.0.set_engine(engine.map(|e| e.0.clone()));
+            }
+
+            #[getter]
+            fn engine(&self) -> Option<Engine> {
+                self.0.engine().cloned().map(Engine)
+            }
+
+            #[getter]
+            fn engine_state(&self) -> Option<EngineState> {
+                self.0.engine_state().map(EngineState)
+            }
+
+            #[pyo3(signature = (sensors=None))]
+            fn set_sensors(&mut self, sensors: Option<&Sensors>) {
+                self.0.set_sensors(sensors.map(|s| s.0.clone()));
+            }
+
+            #[getter]
+            fn sensors(&self) -> Option<Sensors> {
+                self.0.sensors().cloned().map(Sensors)
+            }
+
+            #[getter]
+            fn measurements(&self) -> Option<Measurements> {
+                self.0.measurements().map(Measurements)
+            }
+
+            #[pyo3(signature = (flcs=None))]
+            fn set_flcs(&mut self, flcs: Option<&Flcs>) {
+                self.0.set_flcs(flcs.map(|f| f.0.clone()));
+            }
+
+            #[getter]
+            fn flcs(&self) -> Option<Flcs> {
+                self.0.flcs().cloned().map(Flcs)
+            }
+
+            /// channel: 0 thrust, 1 elevator, 2 aileron, 3 rudder, 4 leading edge flap
+            fn set_actuator(&mut self, channel: usize, model: &ActuatorModel) -> PyResult<()> {
+                match self.0.set_actuator(channel, model.0) {
+                    Ok(_) => Ok(()),
+                    Err(e) => {
+                        error!("{}", e);
+                        Err(PyValueError::new_err(e.to_string()))
+                    }
+                }
+            }
+
+            fn actuator(&mut self, channel: usize) -> PyResult<ActuatorModel> {
+                match self.0.actuator(channel) {
+                    Ok(m) => Ok(ActuatorModel(m)),
+                    Err(e) => {
+                        error!("{}", e);
+                        Err(PyValueError::new_err(e.to_string()))
+                    }
+                }
+            }
+
+            /// time: relative to the start of the run, failure None repairs the actuator
+            #[pyo3(signature = (channel, time, failure=None))]
+            fn set_actuator_failure(
+                &mut self,
+                channel: usize,
+                time: f64,
+                failure: Option<&ActuatorFailure>,
+            ) -> PyResult<()> {
+                match self.0.set_actuator_failure(channel, time, failure.map(|f| f.0)) {
+                    Ok(_) => Ok(()),
+                    Err(e) => {
+                        error!("{}", e);
+                        Err(PyValueError::new_err(e.to_string()))
+                    }
+                }
+            }
+
+            /// (time, failure) or None
+            fn actuator_failure(
+                &mut self,
+                channel: usize,
+            ) -> PyResult<Option<(f64, ActuatorFailure)>> {
+                match self.0.actuator_failure(channel) {
+                    Ok(f) => Ok(f.map(|(t, f)| (t, ActuatorFailure(f)))),
+                    Err(e) => {
+                        error!("{}", e);
+                        Err(PyValueError::new_err(e.to_string()))
+                    }
+                }
+            }
+
+            fn delete_model(&self) {
+                self.0.delete_model();
+            }
+        }
+    };
+}
+
+create_plane_block!(PlaneBlockRK1, RK1Solver);
+create_plane_block!(PlaneBlockRK2, RK2Solver);
+create_plane_block!(PlaneBlockRK3, RK3Solver);
+create_plane_block!(PlaneBlockRK4, RK4Solver);
+create_plane_block!(PlaneBlockBackwardEuler, BackwardEulerSolver, implicit);
+create_plane_block!(PlaneBlockTrapezoidal, TrapezoidalSolver, implicit);
+create_plane_block!(PlaneBlockSDIRK2, SDIRK2Solver, implicit);
+create_plane_block!(PlaneBlockRK45, RK45Solver, {
+    #[new]
+    #[pyo3(signature = (step, model, init, deflection, ctrl_limit, formulation=None, rtol=1e-6, atol=1e-8, min_step=1e-6))]
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        step: f64,
+        model: &AerodynamicModel,
+        init: &CoreInit,
+        deflection: Vec<f64>,
+        ctrl_limit: &ControlLimit,
+        formulation: Option<&Formulation>,
+        rtol: f64,
+        atol: f64,
+        min_step: f64,
+    ) -> PyResult<Self> {
+        let solver = match RK45Solver::new(step, rtol, atol, min_step) {
+            Ok(solver) => solver,
+            Err(e) => {
+                error!("{}", e);
+                return Err(PyValueError::new_err(e.to_string()));
+            }
+        };
+        new_plane_block(solver, model, init, deflection, ctrl_limit, formulation).map(Self)
+    }
+
+    #[getter]
+    fn stats(&self) -> SolverStats {
+        SolverStats(self.0.solver().stats())
+    }
+
+    fn reset_stats(&self) {
+        self.0.solver().reset_stats();
+    }
+});
+
+macro_rules! create_linear_model {
+    ($name:ident, $solver:ty) => {
+        create_linear_model!($name, $solver, {
+            #[new]
+            fn new(step: f64, linearization: &Linearization) -> PyResult<Self> {
+                let solver = Arc::new(<$solver>::new(step));
+                new_linear_model(solver, linearization).map(Self)
+            }
+        });
+    };
+    ($name:ident, $solver:ty, implicit) => {
+        create_linear_model!($name, $solver, {
+            #[new]
+            #[pyo3(signature = (step, linearization, tol=1e-10, max_iter=20))]
+            fn new(
+                step: f64,
+                linearization: &Linearization,
+                tol: f64,
+                max_iter: usize,
+            ) -> PyResult<Self> {
+                let solver = <$solver>::with_options(step, NewtonOptions { tol, max_iter });
+                new_linear_model(Arc::new(solver), linearization).map(Self)
+            }
+        });
+    };
+    ($name:ident, $solver:ty, { $($methods:tt)* }) => {
+        #[pyclass]
+        struct $name(LinearModelBase<$solver>);
+
+        #[pymethods]
+        impl $name {
+            $($methods)*
+
+            fn update(&mut self, control: &Control, t: f64) -> PyResult<CoreOutput> {
+                match self.0.update(control.0, t) {
+                    Ok(o) => Ok(CoreOutput(o)),
+                    Err(e) => {
+                        error!("{}", e);
+                        Err(PyValueError::new_err(e.to_string()))
+                    }
+                }
+            }
+
+            fn reset(&mut self) {
+                self.0.reset();
+            }
+
+            fn snapshot(&self) -> LinearModelSnapshot {
+                LinearModelSnapshot(self.0.snapshot())
+            }
+
+            fn restore(&mut self, snapshot: &LinearModelSnapshot) -> PyResult<()> {
+                match self.0.restore(&snapshot.0) {
+                    Ok(_) => Ok(()),
+                    Err(e) => {
+                        error!("{}", e);
+                        Err(PyValueError::new_err(e.to_string()))
+                    }
+                }
+            }
+
+            #[pyo3(signature = (schedule, duration, delta_t, decimation=1, events=None))]
+            fn run(
+                &mut self,
+                schedule: &ControlSchedule,
+                duration: f64,
+                delta_t: f64,
+                decimation: usize,
+                events: Option<Vec<PyRef<Event>>>,
+            ) -> PyResult<Trajectory> {
+                let events = events_from_py(events);
+                let res = simulate(
+                    &mut self.0,
+                    &schedule.0,
+                    duration,
+                    delta_t,
+                    decimation,
+                    &events,
+                );
+                match res {
+                    Ok(t) => Ok(Trajectory(t)),
+                    Err(e) => {
+                        error!("{}", e);
+                        Err(PyValueError::new_err(e.to_string()))
+                    }
+                }
+            }
+
+            #[pyo3(signature = (controller, sample_time, schedule, duration, delta_t, decimation=1, events=None))]
+            #[allow(clippy::too_many_arguments)]
+            fn run_closed_loop(
+                &mut self,
+                controller: PyObject,
+                sample_time: f64,
+                schedule: &ControlSchedule,
+                duration: f64,
+                delta_t: f64,
+                decimation: usize,
+                events: Option<Vec<PyRef<Event>>>,
+            ) -> PyResult<Trajectory> {
+                let mut controller = PyController {
+                    controller,
+                    sample_time,
+                };
+                let events = events_from_py(events);
+                let res = simulate_closed_loop(
+                    &mut self.0,
+                    &mut controller,
+                    &schedule.0,
+                    duration,
+                    delta_t,
+                    decimation,
+                    &events,
+                );
+                match res {
+                    Ok(t) => Ok(Trajectory(t)),
+                    Err(e) => {
+                        error!("{}", e);
+                        Err(PyValueError::new_err(e.to_string()))
+                    }
+                }
+            }
+
+            #[pyo3(signature = (autopilot, schedule, duration, delta_t, decimation=1, events=None))]
+            #[allow(clippy::too_many_arguments)]
+            fn run_autopilot(
+                &mut self,
+                mut autopilot: PyRefMut<Autopilot>,
+                schedule: &ControlSchedule,
+                duration: f64,
+                delta_t: f64,
+                decimation: usize,
+                events: Option<Vec<PyRef<Event>>>,
+            ) -> PyResult<Trajectory> {
+                let events = events_from_py(events);
+                let res = simulate_closed_loop(
+                    &mut self.0,
+                    &mut autopilot.0,
+                    &schedule.0,
+                    duration,
+                    delta_t,
+                    decimation,
+                    &events,
+                );
+                match res {
+                    Ok(t) => Ok(Trajectory(t)),
+                    Err(e) => {
+                        error!("{}", e);
+                        Err(PyValueError::new_err(e.to_string()))
+                    }
+                }
+            }
+
+            #[getter]
+            fn state(&self) -> CoreOutput {
+                CoreOutput(self.0.state())
+            }
+
+            #[getter]
+            fn state_dot(&self) -> State {
+                State(self.0.state_dot())
+            }
+
+            /// The states of the linearization, not their deviations
+            #[getter]
+            fn states(&self) -> Vec<f64> {
+                self.0.states()
+            }
+
+            #[getter]
+            fn linearization(&self) -> Linearization {
+                Linearization(self.0.linearization().clone())
+            }
+        }
+    };
+}
+
+fn new_linear_model<S>(
+    solver: Arc<S>,
+    linearization: &Linearization,
+) -> PyResult<LinearModelBase<S>>
+where
+    S: crate::solver::ODESolver + VectorODESolver,
+{
+    match LinearModelBase::new(solver, linearization.0.clone()) {
+        Ok(l) => Ok(l),
+        Err(e) => {
+            error!("{}", e);
+            Err(PyValueError::new_err(e.to_string()))
+        }
+    }
+}
+
+create_linear_model!(LinearModelRK1, RK1Solver);
+create_linear_model!(LinearModelRK2, RK2Solver);
+create_linear_model!(LinearModelRK3, RK3Solver);
+create_linear_model!(LinearModelRK4, RK4Solver);
+create_linear_model!(LinearModelBackwardEuler, BackwardEulerSolver, implicit);
+create_linear_model!(LinearModelTrapezoidal, TrapezoidalSolver, implicit);
+create_linear_model!(LinearModelSDIRK2, SDIRK2Solver, implicit);
+create_linear_model!(LinearModelRK45, RK45Solver, {
+    #[new]
+    #[pyo3(signature = (step, linearization, rtol=1e-6, atol=1e-8, min_step=1e-6))]
+    fn new(
+        step: f64,
+        linearization: &Linearization,
+        rtol: f64,
+        atol: f64,
+        min_step: f64,
+    ) -> PyResult<Self> {
+        let solver = match RK45Solver::new(step, rtol, atol, min_step) {
+            Ok(solver) => solver,
+            Err(e) => {
+                error!("{}", e);
+                return Err(PyValueError::new_err(e.to_string()));
+            }
+        };
+        new_linear_model(Arc::new(solver), linearization).map(Self)
+    }
+});
 
 pub(crate) fn register(m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_class::<AerodynamicModel>()?;
@@ -1009,6 +4931,19 @@ pub(crate) fn register(m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_class::<PlaneBlockRK2>()?;
     m.add_class::<PlaneBlockRK3>()?;
     m.add_class::<PlaneBlockRK4>()?;
+    m.add_class::<PlaneBlockRK45>()?;
+    m.add_class::<PlaneBlockBackwardEuler>()?;
+    m.add_class::<PlaneBlockTrapezoidal>()?;
+    m.add_class::<PlaneBlockSDIRK2>()?;
+    m.add_class::<LinearModelRK1>()?;
+    m.add_class::<LinearModelRK2>()?;
+    m.add_class::<LinearModelRK3>()?;
+    m.add_class::<LinearModelRK4>()?;
+    m.add_class::<LinearModelRK45>()?;
+    m.add_class::<LinearModelBackwardEuler>()?;
+    m.add_class::<LinearModelTrapezoidal>()?;
+    m.add_class::<LinearModelSDIRK2>()?;
+    m.add_class::<LinearModelSnapshot>()?;
     m.add_class::<PlaneConstants>()?;
     m.add_class::<ControlLimit>()?;
     m.add_class::<Control>()?;
@@ -1023,9 +4958,69 @@ pub(crate) fn register(m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_class::<CoreInit>()?;
     m.add_class::<CoreOutput>()?;
     m.add_function(wrap_pyfunction!(trim, m)?)?;
+    m.add_class::<Linearization>()?;
+    m.add_function(wrap_pyfunction!(linearize, m)?)?;
+    m.add_class::<Mode>()?;
+    m.add_class::<ModalAnalysis>()?;
+    m.add_function(wrap_pyfunction!(modal_analysis, m)?)?;
+    m.add_class::<Criterion>()?;
+    m.add_class::<HandlingQualities>()?;
+    m.add_function(wrap_pyfunction!(handling_qualities, m)?)?;
+    m.add_class::<Interpolation>()?;
+    m.add_class::<ControlSchedule>()?;
+    m.add_class::<Trajectory>()?;
+    m.add_class::<PlaneBlockSnapshot>()?;
+    m.add_class::<Attitude>()?;
+    m.add_class::<Velocity>()?;
+    m.add_class::<Navigation>()?;
+    m.add_class::<Geodetic>()?;
+    m.add_class::<Formulation>()?;
+    m.add_class::<AirProperties>()?;
+    m.add_class::<AirData>()?;
+    m.add_class::<Atmosphere>()?;
+    m.add_class::<Sweep>()?;
+    m.add_class::<Signal>()?;
+    m.add_class::<Shear>()?;
+    m.add_class::<Gust>()?;
+    m.add_class::<Spectrum>()?;
+    m.add_class::<Turbulence>()?;
+    m.add_class::<Wind>()?;
+    m.add_class::<WindSample>()?;
+    m.add_class::<AirRelative>()?;
+    m.add_class::<Tank>()?;
+    m.add_class::<FuelConsumption>()?;
+    m.add_class::<MassModel>()?;
+    m.add_class::<MassState>()?;
+    m.add_class::<Engine>()?;
+    m.add_class::<EngineState>()?;
+    m.add_class::<TransferFunction>()?;
+    m.add_class::<StateSpace>()?;
+    m.add_class::<Pid>()?;
+    m.add_class::<RateLimiter>()?;
+    m.add_class::<Saturation>()?;
+    m.add_class::<TransportDelay>()?;
+    m.add_class::<ZeroOrderHold>()?;
+    m.add_class::<Autopilot>()?;
+    m.add_class::<Flcs>()?;
+    m.add_class::<ActuatorModel>()?;
+    m.add_class::<ActuatorFailure>()?;
+    m.add_class::<SensorModel>()?;
+    m.add_class::<Imu>()?;
+    m.add_class::<AirDataSensor>()?;
+    m.add_class::<Ahrs>()?;
+    m.add_class::<Sensors>()?;
+    m.add_class::<Measurements>()?;
+    m.add_class::<Direction>()?;
+    m.add_class::<Event>()?;
+    m.add_class::<EventRecord>()?;
     m.add_class::<SimpleSolverRK1>()?;
     m.add_class::<SimpleSolverRK2>()?;
     m.add_class::<SimpleSolverRK3>()?;
     m.add_class::<SimpleSolverRK4>()?;
+    m.add_class::<SimpleSolverRK45>()?;
+    m.add_class::<SimpleSolverBackwardEuler>()?;
+    m.add_class::<SimpleSolverTrapezoidal>()?;
+    m.add_class::<SimpleSolverSDIRK2>()?;
+    m.add_class::<SolverStats>()?;
     Ok(())
 }
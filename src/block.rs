@@ -1,27 +1,50 @@
+use crate::components::atmosphere::{AirData, Atmosphere, SimpleAtmosphere};
+use crate::components::earth::Geodetic;
+use crate::components::engine::{power_rate, tgear, throttle_for_power, EngineModel, EngineState};
+use crate::components::flcs::Flcs;
 use crate::components::flight::{get_lef, Atmos};
-use crate::model::{Control, ControlLimit, CoreOutput, MechanicalModelInput, State, StateExtend};
+use crate::components::sensor::Sensors;
+use crate::components::wind::{AirRelative, WindModel, WindSample};
+use crate::model::{
+    Control, ControlLimit, CoreOutput, Formulation, MassState, Measurements, State, StateExtend,
+    Velocity,
+};
 use crate::plugin::{AerodynamicModel, AsPlugin};
-use crate::solver::{ODESolver, VectorODESolver};
+use crate::solver::{substeps, ODESolver, VectorODESolver};
 use crate::utils::{error::FatalCoreError, Vector};
 use crate::{
     components::{
-        flight::{disturbance, MechanicalModel},
-        group::Actuator,
+        flight::MechanicalModel,
+        group::{Actuator, ActuatorFailure, ActuatorModel, ActuatorSnapshot},
+        mass::MassModel,
+        signal::Signal,
     },
-    model::CoreInit,
+    model::{CoreInit, PlaneConstants},
 };
 use log::{debug, trace};
+use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
-pub(crate) struct ControllerBlock<S: ODESolver> {
+/// Gain of the first-order elevator, aileron and rudder actuators in 1/s
+pub const SURFACE_GAIN: f64 = 20.2;
+/// Gain of the first-order leading edge flap actuator in 1/s
+pub const LEF_GAIN: f64 = 1.0 / 0.136;
+
+pub(crate) struct ControllerBlock<S: ODESolver + VectorODESolver> {
     actuators: Vec<Actuator<S>>,
-    deflection: Vec<f64>,
+    /// signals added to the commands of thrust, elevator, aileron and rudder
+    excitations: Vec<Option<Signal>>,
+    /// the thrust channel carries a throttle position in [0, 1] for an engine,
+    /// which passes the thrust actuator untouched
+    throttle: bool,
 }
 
 impl<S> ControllerBlock<S>
 where
-    S: ODESolver,
+    S: ODESolver + VectorODESolver,
 {
+    /// deflection: a doublet of this amplitude on the elevator, aileron and rudder,
+    /// positive from 1 s to 3 s and negative from 3 s to 5 s, none if zero
     pub fn new(
         solver: Arc<S>,
         control_init: impl Into<Control>,
@@ -51,7 +74,7 @@ where
             control_limit.ele_cmd_limit_top,
             control_limit.ele_cmd_limit_bottom,
             control_limit.ele_rate_limit,
-            20.2,
+            SURFACE_GAIN,
         );
         let aileron_ac = Actuator::new(
             solver.clone(),
@@ -59,7 +82,7 @@ where
             control_limit.ail_cmd_limit_top,
             control_limit.ail_cmd_limit_bottom,
             control_limit.ail_rate_limit,
-            20.2,
+            SURFACE_GAIN,
         );
         let rudder_ac = Actuator::new(
             solver.clone(),
@@ -67,15 +90,27 @@ where
             control_limit.rud_cmd_limit_top,
             control_limit.rud_cmd_limit_bottom,
             control_limit.rud_rate_limit,
-            20.2,
+            SURFACE_GAIN,
+        );
+        let mut excitations = vec![None];
+        excitations.extend(
+            deflection
+                .iter()
+                .map(|d| (*d != 0.0).then(|| Signal::doublet(1.0, *d, 4.0))),
         );
         ControllerBlock {
             actuators: vec![thrust_ac, elevator_ac, aileron_ac, rudder_ac],
-            deflection: deflection.to_vec(),
+            excitations,
+            throttle: false,
         }
     }
 
-    pub fn update(&mut self, control_input: impl Into<Control>, t: f64) -> Control {
+    /// Integrate the actuators from their last update time to `t`
+    pub fn update(
+        &mut self,
+        control_input: impl Into<Control>,
+        t: f64,
+    ) -> Result<Control, FatalCoreError> {
         let mut control_input: Control = control_input.into();
 
         trace!(
@@ -84,22 +119,51 @@ where
             t
         );
 
-        control_input.thrust = self.actuators[0].update(control_input[0], t);
         for i in 0..4 {
-            if i < 3 {
-                if self.deflection[i].abs() < 1e-10 {
-                    control_input[i + 1] += disturbance(self.deflection[i], t);
-                }
+            if let Some(signal) = &self.excitations[i] {
+                control_input[i] += signal.value(t);
+            }
+            if i == 0 && self.throttle {
+                control_input[0] = control_input[0].clamp(0.0, 1.0);
+                self.actuators[0].reset(control_input[0]);
+                continue;
             }
             // if control_input[i] < 1e-10 {
             //     let last = self.actuators[i].last();
             //     control_input[i] = self.actuators[i].update(last, t)
             // } else {
-            control_input[i] = self.actuators[i].update(control_input[i], t);
+            control_input[i] = self.actuators[i].update(control_input[i], t)?;
             // }
         }
         trace!("correctional control input: \n{}", control_input);
-        control_input
+        Ok(control_input)
+    }
+
+    pub fn set_excitation(&mut self, channel: usize, signal: Option<Signal>) {
+        self.excitations[channel] = signal;
+    }
+
+    pub fn excitation(&self, channel: usize) -> Option<&Signal> {
+        self.excitations.get(channel).and_then(|s| s.as_ref())
+    }
+
+    pub fn set_actuator(&mut self, channel: usize, model: ActuatorModel) {
+        self.actuators[channel].set_model(model);
+    }
+
+    pub fn actuator(&self, channel: usize) -> &Actuator<S> {
+        &self.actuators[channel]
+    }
+
+    pub fn set_failure(&mut self, channel: usize, failure: Option<(f64, ActuatorFailure)>) {
+        self.actuators[channel].set_failure(failure);
+    }
+
+    /// Carry a throttle position on the thrust channel if `throttle`, otherwise thrust in lbf,
+    /// starting from `value`
+    pub fn set_thrust_channel(&mut self, throttle: bool, value: f64) {
+        self.throttle = throttle;
+        self.actuators[0].reset(value);
     }
 
     pub fn state(&self) -> Control {
@@ -117,44 +181,93 @@ where
         self.actuators[2].reset(control.aileron);
         self.actuators[3].reset(control.rudder);
     }
+
+    pub fn snapshot(&self) -> Vec<ActuatorSnapshot> {
+        self.actuators.iter().map(|a| a.snapshot()).collect()
+    }
+
+    pub fn restore(&mut self, snapshot: &[ActuatorSnapshot]) -> Result<(), FatalCoreError> {
+        if snapshot.len() != self.actuators.len() {
+            return Err(FatalCoreError::InvalidInput(format!(
+                "snapshot has {} actuators but the controller has {}",
+                snapshot.len(),
+                self.actuators.len()
+            )));
+        }
+        self.actuators
+            .iter_mut()
+            .zip(snapshot.iter())
+            .for_each(|(a, s)| a.restore(s));
+        Ok(())
+    }
+}
+
+/// Everything a `LeadingEdgeFlapBlock` needs to resume from where it was
+/// state: the state of the integrator in the flap's feedback loop
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LeadingEdgeFlapSnapshot {
+    pub actuator: ActuatorSnapshot,
+    pub state: f64,
+    pub last_time: Option<f64>,
 }
 
-pub(crate) struct LeadingEdgeFlapBlock<S: ODESolver> {
+pub(crate) struct LeadingEdgeFlapBlock<S: ODESolver + VectorODESolver> {
     solver: Arc<S>,
     actuator: Actuator<S>,
+    atmosphere: Arc<dyn Atmosphere>,
     // integrator: Integrator,
     // feedback: f64,
     state: f64,
+    last_time: Option<f64>,
 }
 
 impl<S> LeadingEdgeFlapBlock<S>
 where
-    S: ODESolver,
+    S: ODESolver + VectorODESolver,
 {
-    pub fn new(solver: Arc<S>, altitude: f64, velocity: f64, alpha: f64) -> Self {
-        let d_lef = get_lef(altitude, velocity, alpha);
+    pub fn new(
+        solver: Arc<S>,
+        atmosphere: Arc<dyn Atmosphere>,
+        altitude: f64,
+        velocity: f64,
+        alpha: f64,
+    ) -> Self {
+        let d_lef = get_lef(&*atmosphere, altitude, velocity, alpha);
 
         trace!("LEFBlock: alpha_init: {}, d_lef: {}", alpha, d_lef);
-        let actuator = Actuator::new(solver.clone(), d_lef, 25.0, 0.0, 25.0, 1.0 / 0.136);
+        let actuator = Actuator::new(solver.clone(), d_lef, 25.0, 0.0, 25.0, LEF_GAIN);
         // let integrator = Integrator::new(-alpha.to_degrees());
 
         LeadingEdgeFlapBlock {
             solver: solver.clone(),
             actuator,
+            atmosphere,
             // integrator,
             // feedback: 0.0,
             state: -alpha.to_degrees(),
+            last_time: None,
         }
     }
 
-    pub fn update(&mut self, altitude: f64, velocity: f64, alpha: f64, t: f64) -> f64 {
+    /// Integrate the flap from its last update time to `t`
+    pub fn update(
+        &mut self,
+        altitude: f64,
+        velocity: f64,
+        alpha: f64,
+        t: f64,
+    ) -> Result<f64, FatalCoreError> {
+        let last_time = self.last_time.unwrap_or(t);
+        if t < last_time {
+            return Err(FatalCoreError::NonMonotonicTime(last_time, t));
+        }
         trace!(
             "LEFBlock: alpha: {}, altitude: {}, velocity: {}",
             alpha,
             altitude,
             velocity
         );
-        let atmos = Atmos::atmos(altitude, velocity);
+        let atmos = Atmos::from(&self.atmosphere.air_data(altitude, velocity));
         let r_1 = atmos.qbar / atmos.ps * 9.05;
         let alpha = alpha.to_degrees();
         // let r_2 = (alpha - self.feedback) * 7.25;
@@ -164,17 +277,25 @@ where
             let r_2 = (alpha - feedback) * 7.25;
             r_2
         };
-        let r_3 = self.solver.solve(&dynamics, t, self.state, alpha);
+        let r_3 = ODESolver::integrate(
+            self.solver.as_ref(),
+            &dynamics,
+            last_time,
+            t,
+            self.state,
+            alpha,
+        );
         self.state = r_3;
+        self.last_time = Some(t);
         // let r_3 = self.integrator.integrate(r_2, t);
 
         let r_4 = r_3 + 2.0 * alpha;
         // self.feedback = r_4;
         let r_5 = r_4 * 1.38;
-        let r_6 = self.actuator.update(1.45 + r_5 - r_1, t);
+        let r_6 = self.actuator.update(1.45 + r_5 - r_1, t)?;
 
         trace!("LEFBlock: lef: {}", r_6);
-        r_6
+        Ok(r_6)
     }
 
     pub fn state(&self) -> [f64; 2] {
@@ -182,16 +303,158 @@ where
     }
 
     pub fn reset(&mut self, altitude: f64, velocity: f64, alpha: f64) {
-        let d_lef = get_lef(altitude, velocity, alpha);
+        let d_lef = get_lef(&*self.atmosphere, altitude, velocity, alpha);
         self.actuator.reset(d_lef);
         self.state = -alpha.to_degrees();
+        self.last_time = None;
         // self.integrator.reset();
         // self.feedback = 0.0;
     }
+
+    pub fn snapshot(&self) -> LeadingEdgeFlapSnapshot {
+        LeadingEdgeFlapSnapshot {
+            actuator: self.actuator.snapshot(),
+            state: self.state,
+            last_time: self.last_time,
+        }
+    }
+
+    pub fn set_atmosphere(&mut self, atmosphere: Arc<dyn Atmosphere>) {
+        self.atmosphere = atmosphere;
+    }
+
+    pub fn actuator(&mut self) -> &mut Actuator<S> {
+        &mut self.actuator
+    }
+
+    pub fn restore(&mut self, snapshot: &LeadingEdgeFlapSnapshot) {
+        self.actuator.restore(&snapshot.actuator);
+        self.state = snapshot.state;
+        self.last_time = snapshot.last_time;
+    }
+}
+
+/// Everything an `EngineBlock` needs to resume from where it was
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct EngineSnapshot {
+    pub state: EngineState,
+    pub last_time: Option<f64>,
+}
+
+/// The power lag of an engine driven by a throttle
+pub(crate) struct EngineBlock<S: ODESolver> {
+    solver: Arc<S>,
+    model: EngineModel,
+    state: EngineState,
+    last_time: Option<f64>,
+}
+
+impl<S> EngineBlock<S>
+where
+    S: ODESolver,
+{
+    /// An engine settled at `thrust` (lbf) at `altitude` (ft) and `mach`
+    pub fn new(solver: Arc<S>, model: EngineModel, thrust: f64, altitude: f64, mach: f64) -> Self {
+        let mut engine = Self {
+            solver,
+            model,
+            state: EngineState::default(),
+            last_time: None,
+        };
+        let power = engine.model.power_for_thrust(thrust, altitude, mach);
+        engine.reset(power, altitude, mach);
+        engine
+    }
+
+    /// Integrate the power from its last update time to `t`, return the thrust at `t`
+    pub fn update(
+        &mut self,
+        throttle: f64,
+        altitude: f64,
+        mach: f64,
+        t: f64,
+    ) -> Result<f64, FatalCoreError> {
+        let last_time = self.last_time.unwrap_or(t);
+        if t < last_time {
+            return Err(FatalCoreError::NonMonotonicTime(last_time, t));
+        }
+        let dynamics = |_t: f64, power: f64, command: f64| -> f64 { power_rate(power, command) };
+        let power =
+            self.solver
+                .integrate(&dynamics, last_time, t, self.state.power, tgear(throttle));
+        self.state = EngineState {
+            power,
+            thrust: self.model.thrust(power, altitude, mach),
+        };
+        self.last_time = Some(t);
+        Ok(self.state.thrust)
+    }
+
+    /// The throttle position holding the engine where it is
+    pub fn throttle(&self) -> f64 {
+        throttle_for_power(self.state.power)
+    }
+
+    pub fn state(&self) -> EngineState {
+        self.state
+    }
+
+    pub fn model(&self) -> &EngineModel {
+        &self.model
+    }
+
+    pub fn reset(&mut self, power: f64, altitude: f64, mach: f64) {
+        self.state = EngineState {
+            power,
+            thrust: self.model.thrust(power, altitude, mach),
+        };
+        self.last_time = None;
+    }
+
+    pub fn snapshot(&self) -> EngineSnapshot {
+        EngineSnapshot {
+            state: self.state,
+            last_time: self.last_time,
+        }
+    }
+
+    pub fn restore(&mut self, snapshot: &EngineSnapshot) {
+        self.state = snapshot.state;
+        self.last_time = snapshot.last_time;
+    }
+}
+
+/// Everything a `PlaneBlock` needs to resume a run bit-exactly
+/// times are the raw values passed to `PlaneBlock::update`, `last_time` is relative to `start_time`
+/// actuators: thrust, elevator, aileron and rudder
+/// vector: the integrated vector laid out by `formulation`, `state` is its `State` view
+/// wind: the wind model with its turbulence filters and random generator
+/// fuel: the fuel in each tank of the mass model, lb
+/// engine: the power lag of the engine
+/// sensors: the sensors with their lags, drifts and random generators
+/// flcs: the flight control system with its filters
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlaneBlockSnapshot {
+    pub start_time: Option<f64>,
+    pub last_time: Option<f64>,
+    pub formulation: Formulation,
+    pub vector: Vec<f64>,
+    pub state: State,
+    pub state_dot: State,
+    pub extend: Option<StateExtend>,
+    pub actuators: Vec<ActuatorSnapshot>,
+    pub lef: LeadingEdgeFlapSnapshot,
+    pub wind: Option<WindModel>,
+    pub fuel: Option<Vec<f64>>,
+    pub engine: Option<EngineSnapshot>,
+    pub sensors: Option<Sensors>,
+    pub flcs: Option<Flcs>,
 }
 
 pub struct PlaneBlock<S: ODESolver + VectorODESolver> {
     start_time: Option<f64>,
+    /// last update time relative to `start_time`
+    last_time: Option<f64>,
     control: ControllerBlock<S>,
     lef: LeadingEdgeFlapBlock<S>,
     // integrator: VectorIntegrator,
@@ -202,8 +465,17 @@ pub struct PlaneBlock<S: ODESolver + VectorODESolver> {
     alpha_limit_bottom: f64,
     beta_limit_top: f64,
     beta_limit_bottom: f64,
+    formulation: Formulation,
+    /// the integrated vector laid out by `formulation`
     state: Vector,
     state_dot: Vector,
+    /// actuator and flap updates per airframe step
+    subsystem_substeps: usize,
+    wind: Option<WindModel>,
+    mass: Option<MassModel>,
+    engine: Option<EngineBlock<S>>,
+    sensors: Option<Sensors>,
+    flcs: Option<Flcs>,
 }
 
 impl<S> PlaneBlock<S>
@@ -216,17 +488,38 @@ where
         init: &CoreInit,
         deflection: &[f64; 3],
         ctrl_limit: ControlLimit,
+    ) -> Result<Self, FatalCoreError> {
+        Self::with_formulation(
+            solver,
+            model,
+            init,
+            deflection,
+            ctrl_limit,
+            Formulation::default(),
+        )
+    }
+
+    /// Create a plane block which integrates the equations of motion in `formulation`
+    pub fn with_formulation(
+        solver: Arc<S>,
+        model: &AerodynamicModel,
+        init: &CoreInit,
+        deflection: &[f64; 3],
+        ctrl_limit: ControlLimit,
+        formulation: Formulation,
     ) -> Result<Self, FatalCoreError> {
         trace!(
-            "create plane block with model: {}, init: {:?}, deflection: {:?}, ctrl_limit: {:?}",
+            "create plane block with model: {}, init: {:?}, deflection: {:?}, ctrl_limit: {:?}, formulation: {:?}",
             model.info().name,
             init,
             deflection,
-            ctrl_limit
+            ctrl_limit,
+            formulation
         );
         let control = ControllerBlock::new(solver.clone(), init.control, deflection, ctrl_limit);
         let lef = LeadingEdgeFlapBlock::new(
             solver.clone(),
+            Arc::new(SimpleAtmosphere),
             init.state.altitude,
             init.state.velocity,
             init.state.alpha,
@@ -235,8 +528,7 @@ where
         let mut plane = MechanicalModel::new(model)?;
         plane.init()?;
 
-        let init_state = Into::<Vector>::into(init.state);
-        let init_state_dim = init_state.dim();
+        let init_state = formulation.from_state(&init.state);
         Ok(PlaneBlock {
             control,
             lef,
@@ -249,126 +541,730 @@ where
             beta_limit_top: ctrl_limit.beta_limit_top,
             beta_limit_bottom: ctrl_limit.beta_limit_bottom,
             start_time: None,
+            last_time: None,
+            formulation,
             state: init_state,
-            state_dot: Vector::zero(init_state_dim),
+            state_dot: Vector::zero(12),
+            subsystem_substeps: 1,
+            wind: None,
+            mass: None,
+            engine: None,
+            sensors: None,
+            flcs: None,
         })
     }
 
+    /// Integrate the plane from the last update time to `t`
+    /// the interval is split into sub-steps no longer than the solver's `delta_t`,
+    /// the control is held constant over the interval
     pub fn update(
         &mut self,
         control: impl Into<Control>,
         t: f64,
     ) -> Result<CoreOutput, FatalCoreError> {
         trace!("update time: {}", t);
+        let control: Control = control.into();
 
         if self.start_time.is_none() {
             self.start_time = Some(t);
             debug!("start time {}", t);
         }
-        let t = (t - self.start_time.unwrap()).max(1e-3);
-        // let state = &mut self.integrator.past();
-        let mut state = self.state.clone();
-        let control = self.control.update(control, t);
-
-        let altitude = state[2];
-        let velocity = state[6];
-        let alpha = state[7];
-        let d_lef = self.lef.update(altitude, velocity, alpha, t);
-
-        state.data[7] = state[7].clamp(
-            self.alpha_limit_bottom.to_radians(),
-            self.alpha_limit_top.to_radians(),
-        );
+        let start_time = self.start_time.unwrap();
+        // `last_time()` may not round trip through the subtraction
+        let t = match self.last_time {
+            Some(last_time) if t == start_time + last_time => last_time,
+            _ => t - start_time,
+        };
+        let last_time = self.last_time.unwrap_or(t);
+        if t < last_time {
+            return Err(FatalCoreError::NonMonotonicTime(
+                last_time + start_time,
+                t + start_time,
+            ));
+        }
 
-        state.data[8] = state[8].clamp(
-            self.beta_limit_bottom.to_radians(),
-            self.beta_limit_top.to_radians(),
-        );
+        let (n, h) = substeps(t - last_time, self.solver.delta_t());
+        let mut t_0 = last_time;
+        for i in 1..=n {
+            // the last sub-step ends exactly at `t`
+            let t_i = if i == n { t } else { last_time + h * i as f64 };
+            self.advance(control, t_0, t_i)?;
+            t_0 = t_i;
+        }
+        self.last_time = Some(t);
+
+        // both blocks are already at `t`, so this only reads their outputs
+        let control = self.surface_command(control);
+        let control = self.control.update(control, t)?;
+        let (altitude, velocity, alpha) = self.lef_input();
+        let d_lef = self.lef.update(altitude, velocity, alpha, t)?;
+        let mach = self.air_data().mach;
+        if let Some(engine) = &mut self.engine {
+            engine.update(control.thrust, altitude, mach, t)?;
+        }
+        let control = self.airframe_control(control);
 
-        let model_output = self.plane.step(&MechanicalModelInput::new(
-            state.data.clone(),
+        let (alpha_limit, beta_limit) = self.air_angle_limits();
+        let (_, model_output) = self.plane.step_formulation(
+            &self.formulation,
+            &self.constants(),
+            &self.clamped_state().data,
             control,
             d_lef,
-        ))?;
+            &self.wind(),
+            alpha_limit,
+            beta_limit,
+        )?;
 
         trace!("model_output:\n{}", model_output);
 
         self.state_dot = model_output.state_dot.into();
 
+        let control = self.control.state();
+        if Into::<Vec<f64>>::into(control).iter().any(|x| x.is_nan()) {
+            return Err(FatalCoreError::Nan);
+        }
+
+        let extend = model_output.state_extend;
+        if Into::<Vec<f64>>::into(extend).iter().any(|x| x.is_nan()) {
+            return Err(FatalCoreError::Nan);
+        }
+
+        self.extend = Some(extend);
+
+        let block_output = CoreOutput::new(
+            self.formulation.to_state(&self.state.data),
+            control,
+            self.extend.unwrap(),
+        )
+        .with_mass(self.mass_state())
+        .with_sensors(self.update_sensors(t))
+        .with_air(Some(self.air_relative()));
+        trace!("block_output:\n{}", block_output);
+
+        Ok(block_output)
+    }
+
+    /// Run the actuators and the leading edge flap `n` times per solver step,
+    /// the airframe follows each sub-step with its output held over it
+    pub fn set_subsystem_substeps(&mut self, n: usize) -> Result<(), FatalCoreError> {
+        if n == 0 {
+            return Err(FatalCoreError::InvalidInput(
+                "subsystem substeps must be at least 1".to_string(),
+            ));
+        }
+        self.subsystem_substeps = n;
+        Ok(())
+    }
+
+    pub fn subsystem_substeps(&self) -> usize {
+        self.subsystem_substeps
+    }
+
+    /// Integrate one sub-step from `t` to `t_end`
+    fn advance(
+        &mut self,
+        control_input: Control,
+        t: f64,
+        t_end: f64,
+    ) -> Result<(), FatalCoreError> {
+        let h = t_end - t;
+        let control_input = self.run_flcs(control_input, t)?;
+        let wind = self.update_wind(t, h);
+
+        let n = self.subsystem_substeps;
+        let mut t_0 = t;
+        for k in 1..=n {
+            let t_k = if k == n {
+                t_end
+            } else {
+                t + h * k as f64 / n as f64
+            };
+            let (altitude, velocity, alpha) = self.lef_input();
+            let mach = self.air_data().mach;
+            let control = self.control.update(control_input, t_k)?;
+            let d_lef = self.lef.update(altitude, velocity, alpha, t_k)?;
+            if let Some(engine) = &mut self.engine {
+                engine.update(control.thrust, altitude, mach, t_k)?;
+            }
+            // the airframe sees the output of each sub-step held over it
+            self.integrate_airframe(control, d_lef, &wind, t_0, t_k)?;
+            t_0 = t_k;
+        }
+        Ok(())
+    }
+
+    /// Integrate the airframe from `t` to `t_end` with `control` and `d_lef` held
+    fn integrate_airframe(
+        &mut self,
+        control: Control,
+        d_lef: f64,
+        wind: &WindSample,
+        t: f64,
+        t_end: f64,
+    ) -> Result<(), FatalCoreError> {
+        let h = t_end - t;
+        let control = self.airframe_control(control);
+        let constants = self.constants();
+
+        let state = self.clamped_state();
+
         // let state = self
         //     .integrator
         //     .derivative_add(Into::<Vector>::into(model_output.state_dot), t);
 
         let plane = self.plane.clone();
+        let formulation = self.formulation;
+        let wind = *wind;
+        let (alpha_limit, beta_limit) = self.air_angle_limits();
         let dynamics = move |_t: f64, state: &Vector, _input: &Vector| -> Vector {
-            let model_output = plane
-                .step(&MechanicalModelInput::new(
-                    state.data.clone(),
+            let (state_dot, _) = plane
+                .step_formulation(
+                    &formulation,
+                    &constants,
+                    &state.data,
                     control,
                     d_lef,
-                ))
+                    &wind,
+                    alpha_limit,
+                    beta_limit,
+                )
                 .unwrap();
-            model_output.state_dot.into()
+            state_dot
         };
 
-        let state = VectorODESolver::solve(&*self.solver, &dynamics, t, &state, &Vector::zero(0));
-        self.state = state.clone();
-
-        let state = state.data;
-        if state.iter().any(|x| x.is_nan()) {
+        let mut state =
+            VectorODESolver::solve_step(&*self.solver, &dynamics, t, h, &state, &Vector::zero(0));
+        self.formulation.normalize(&mut state);
+        if state.data.iter().any(|x| x.is_nan()) {
             return Err(FatalCoreError::Nan);
         }
+        self.state = state;
 
-        let control = self.control.state();
-        if Into::<Vec<f64>>::into(control).iter().any(|x| x.is_nan()) {
-            return Err(FatalCoreError::Nan);
+        if self.mass.is_some() {
+            let air_data = self.air_data();
+            if let Some(mass) = &mut self.mass {
+                mass.burn(h, control.thrust, &air_data);
+            }
         }
+        Ok(())
+    }
 
-        let extend = model_output.state_extend;
-        if Into::<Vec<f64>>::into(extend).iter().any(|x| x.is_nan()) {
-            return Err(FatalCoreError::Nan);
+    /// The mass properties of the plane, those of the aerodynamic model without a mass model,
+    /// `h_eng` is that of the engine when there is one
+    pub fn constants(&self) -> PlaneConstants {
+        let mut constants = match &self.mass {
+            Some(mass) => mass.constants(),
+            None => self.plane.constants(),
+        };
+        if let Some(engine) = &self.engine {
+            constants.h_eng = engine.model().angular_momentum;
         }
+        constants
+    }
 
-        self.extend = Some(StateExtend::from(extend));
+    /// The control reaching the airframe, with the thrust of the engine when there is one,
+    /// the engine flames out once the tanks run dry
+    fn airframe_control(&self, mut control: Control) -> Control {
+        if let Some(engine) = &self.engine {
+            control.thrust = engine.state().thrust;
+        }
+        if self.mass.as_ref().is_some_and(|m| m.is_empty()) {
+            control.thrust = 0.0;
+        }
+        control
+    }
 
-        let block_output = CoreOutput::new(
-            State::from(state),
-            Control::from(control),
-            self.extend.unwrap(),
+    /// The state with alpha and beta limited to the control limits,
+    /// body-axis velocities are integrated freely and only limited for the aerodynamic model
+    fn clamped_state(&self) -> Vector {
+        let mut state = self.state.clone();
+        if self.formulation.velocity == Velocity::Body {
+            return state;
+        }
+        let v = self.formulation.velocity_index();
+        state.data[v + 1] = state[v + 1].clamp(
+            self.alpha_limit_bottom.to_radians(),
+            self.alpha_limit_top.to_radians(),
         );
-        trace!("block_output:\n{}", block_output);
+        state.data[v + 2] = state[v + 2].clamp(
+            self.beta_limit_bottom.to_radians(),
+            self.beta_limit_top.to_radians(),
+        );
+        state
+    }
 
-        Ok(block_output)
+    fn air_angle_limits(&self) -> ([f64; 2], [f64; 2]) {
+        (
+            [self.alpha_limit_bottom, self.alpha_limit_top],
+            [self.beta_limit_bottom, self.beta_limit_top],
+        )
+    }
+
+    /// altitude, airspeed and alpha relative to the air which drive the leading edge flap
+    fn lef_input(&self) -> (f64, f64, f64) {
+        let state = self.formulation.to_state(&self.state.data);
+        let air = AirRelative::new(&state, &self.wind());
+        (state.altitude, air.velocity, air.alpha)
+    }
+
+    /// Advance the wind model by `dt` to `t`, the wind is then held over the next sub-step
+    fn update_wind(&mut self, t: f64, dt: f64) -> WindSample {
+        let state = self.formulation.to_state(&self.state.data);
+        match &mut self.wind {
+            Some(wind) => {
+                let tas = AirRelative::new(&state, &wind.sample()).velocity;
+                wind.update(t, dt, state.altitude, tas)
+            }
+            None => WindSample::default(),
+        }
+    }
+
+    /// Fly through `wind`, None for still air
+    /// before the first update the leading edge flap is retrimmed to the air-relative state
+    pub fn set_wind(&mut self, wind: Option<WindModel>) {
+        self.wind = wind;
+        if let Some(wind) = &mut self.wind {
+            wind.reset();
+        }
+        let t = self.last_time.unwrap_or(0.0);
+        self.update_wind(t, 0.0);
+        if self.start_time.is_none() {
+            let (altitude, velocity, alpha) = self.lef_input();
+            self.lef.reset(altitude, velocity, alpha);
+        }
+    }
+
+    /// Add `signal` to the command of `channel`, 0 thrust, 1 elevator, 2 aileron, 3 rudder,
+    /// signal times are relative to the start of the run, None removes the excitation
+    pub fn set_excitation(
+        &mut self,
+        channel: usize,
+        signal: Option<Signal>,
+    ) -> Result<(), FatalCoreError> {
+        if channel >= 4 {
+            return Err(FatalCoreError::InvalidInput(format!(
+                "control channel {} is not in 0..4",
+                channel
+            )));
+        }
+        self.control.set_excitation(channel, signal);
+        Ok(())
+    }
+
+    pub fn excitation(&self, channel: usize) -> Option<&Signal> {
+        self.control.excitation(channel)
+    }
+
+    /// Replace the model of the actuator of `channel`,
+    /// 0 thrust, 1 elevator, 2 aileron, 3 rudder, 4 leading edge flap
+    pub fn set_actuator(
+        &mut self,
+        channel: usize,
+        model: ActuatorModel,
+    ) -> Result<(), FatalCoreError> {
+        match channel {
+            0..=3 => self.control.set_actuator(channel, model),
+            4 => self.lef.actuator().set_model(model),
+            _ => return Err(Self::actuator_channel_error(channel)),
+        }
+        self.sync_flcs_limits();
+        Ok(())
+    }
+
+    pub fn actuator(&mut self, channel: usize) -> Result<ActuatorModel, FatalCoreError> {
+        match channel {
+            0..=3 => Ok(self.control.actuator(channel).model()),
+            4 => Ok(self.lef.actuator().model()),
+            _ => Err(Self::actuator_channel_error(channel)),
+        }
+    }
+
+    /// Fail the actuator of `channel` as `failure` from time `t` on,
+    /// relative to the start of the run like the excitation signals, None repairs it
+    pub fn set_actuator_failure(
+        &mut self,
+        channel: usize,
+        t: f64,
+        failure: Option<ActuatorFailure>,
+    ) -> Result<(), FatalCoreError> {
+        let failure = failure.map(|f| (t, f));
+        match channel {
+            0..=3 => self.control.set_failure(channel, failure),
+            4 => self.lef.actuator().set_failure(failure),
+            _ => return Err(Self::actuator_channel_error(channel)),
+        }
+        Ok(())
+    }
+
+    /// The failure of the actuator of `channel` and the time it strikes
+    pub fn actuator_failure(
+        &mut self,
+        channel: usize,
+    ) -> Result<Option<(f64, ActuatorFailure)>, FatalCoreError> {
+        match channel {
+            0..=3 => Ok(self.control.actuator(channel).failure()),
+            4 => Ok(self.lef.actuator().failure()),
+            _ => Err(Self::actuator_channel_error(channel)),
+        }
+    }
+
+    fn actuator_channel_error(channel: usize) -> FatalCoreError {
+        FatalCoreError::InvalidInput(format!("actuator channel {} is not in 0..5", channel))
+    }
+
+    /// Burn fuel from the tanks of `mass`, None keeps the constants of the aerodynamic model
+    /// the constants of `mass` should be those of the model, which trim uses,
+    /// so that the plane stays trimmed at the fuel it starts with
+    pub fn set_mass_model(&mut self, mass: Option<MassModel>) {
+        self.mass = mass;
+    }
+
+    pub fn mass_model(&self) -> Option<&MassModel> {
+        self.mass.as_ref()
+    }
+
+    pub fn mass_state(&self) -> Option<MassState> {
+        self.mass.as_ref().map(|m| m.state())
+    }
+
+    /// Drive the thrust through `engine`, None for thrust commanded directly in lbf
+    /// with an engine the thrust channel of the control carries the throttle position in [0, 1],
+    /// the engine is settled at the current thrust and the thrust actuator is set to the
+    /// throttle position holding it, so a trimmed plane stays trimmed
+    pub fn set_engine(&mut self, engine: Option<EngineModel>) {
+        let thrust = match &self.engine {
+            Some(engine) => engine.state().thrust,
+            None => self.control.state().thrust,
+        };
+        let altitude = self.formulation.to_state(&self.state.data).altitude;
+        let mach = self.air_data().mach;
+        self.engine = engine.map(|model| {
+            let mut engine = EngineBlock::new(self.solver.clone(), model, thrust, altitude, mach);
+            engine.last_time = self.last_time;
+            engine
+        });
+        match &self.engine {
+            Some(engine) => self.control.set_thrust_channel(true, engine.throttle()),
+            None => self.control.set_thrust_channel(false, thrust),
+        }
+    }
+
+    pub fn engine(&self) -> Option<&EngineModel> {
+        self.engine.as_ref().map(|e| e.model())
+    }
+
+    /// Power and thrust of the engine, None without one
+    pub fn engine_state(&self) -> Option<EngineState> {
+        self.engine.as_ref().map(|e| e.state())
+    }
+
+    /// Fly through the flight control system `flcs`, None to command the surfaces directly
+    /// with an FLCS the elevator, aileron and rudder of the control given to `update` are the
+    /// stick and pedals in [-1, 1], the surfaces at the time are held with them centered
+    /// and the commands are clamped to the travel of the actuators
+    pub fn set_flcs(&mut self, flcs: Option<Flcs>) {
+        self.flcs = flcs;
+        let trim = self.control.state();
+        if let Some(flcs) = &mut self.flcs {
+            flcs.reset(Some(trim));
+        }
+        self.sync_flcs_limits();
+    }
+
+    fn sync_flcs_limits(&mut self) {
+        if let Some(flcs) = &mut self.flcs {
+            let travel = |channel: usize| {
+                let model = self.control.actuator(channel).model();
+                [model.command_limit_bottom, model.command_limit_top]
+            };
+            flcs.set_limits([travel(1), travel(2), travel(3)]);
+        }
+    }
+
+    pub fn flcs(&self) -> Option<&Flcs> {
+        self.flcs.as_ref()
+    }
+
+    /// The surfaces commanded for `control`, by the flight control system when there is one
+    fn surface_command(&self, control: Control) -> Control {
+        match &self.flcs {
+            Some(flcs) => flcs.command(&control),
+            None => control,
+        }
+    }
+
+    /// Run a frame of the flight control system if one starts at `t`,
+    /// the load factors are those of the plane at `t`
+    fn run_flcs(&mut self, pilot: Control, t: f64) -> Result<Control, FatalCoreError> {
+        if !self.flcs.as_ref().is_some_and(|f| f.is_due(t)) {
+            return Ok(self.surface_command(pilot));
+        }
+        let control = self.airframe_control(self.control.state());
+        let d_lef = self.lef.state()[0];
+        let (alpha_limit, beta_limit) = self.air_angle_limits();
+        let (_, model_output) = self.plane.step_formulation(
+            &self.formulation,
+            &self.constants(),
+            &self.clamped_state().data,
+            control,
+            d_lef,
+            &self.wind(),
+            alpha_limit,
+            beta_limit,
+        )?;
+        let output = CoreOutput::new(
+            self.formulation.to_state(&self.state.data),
+            self.control.state(),
+            model_output.state_extend,
+        )
+        .with_air(Some(self.air_relative()));
+        match &mut self.flcs {
+            Some(flcs) => Ok(flcs.step(t, &output, &pilot)),
+            None => Ok(pilot),
+        }
+    }
+
+    /// Measure the plane with `sensors`, None for perfect measurements only
+    /// the sensors start from their seeds and first sample at the next update
+    pub fn set_sensors(&mut self, sensors: Option<Sensors>) {
+        self.sensors = sensors;
+        if let Some(sensors) = &mut self.sensors {
+            sensors.reset();
+        }
+    }
+
+    pub fn sensors(&self) -> Option<&Sensors> {
+        self.sensors.as_ref()
+    }
+
+    /// The readings of the last update, None without sensors
+    pub fn measurements(&self) -> Option<Measurements> {
+        self.sensors.as_ref().map(|s| s.measurements())
+    }
+
+    /// Sample the sensors at `t`, relative to the start of the run
+    fn update_sensors(&mut self, t: f64) -> Option<Measurements> {
+        let state = self.formulation.to_state(&self.state.data);
+        let state_dot = State::from(self.state_dot.clone());
+        let extend = self.extend.unwrap_or_default();
+        let air = self.air_relative();
+        self.sensors.as_mut().map(|sensors| {
+            sensors.update(
+                t,
+                &state,
+                &state_dot,
+                &extend,
+                [air.velocity, air.alpha, air.beta],
+            )
+        })
+    }
+
+    /// The wind at the plane, calm without a wind model
+    pub fn wind(&self) -> WindSample {
+        self.wind.as_ref().map(|w| w.sample()).unwrap_or_default()
+    }
+
+    /// Airspeed, alpha and beta relative to the air
+    pub fn air_relative(&self) -> AirRelative {
+        let state = self.formulation.to_state(&self.state.data);
+        AirRelative::new(&state, &self.wind())
     }
 
     pub fn reset(&mut self, init: &CoreInit) {
         self.control.reset(init.control);
 
-        let altitude = init.state.altitude;
-        let velocity = init.state.velocity;
-        let alpha = init.state.alpha;
-        self.lef.reset(altitude, velocity, alpha);
-
         // self.integrator.reset();
-        self.state = init.state.into();
+        self.state = self.formulation.from_state(&init.state);
+        self.start_time = None;
+        self.last_time = None;
+        if let Some(wind) = &mut self.wind {
+            wind.reset();
+        }
+        if let Some(mass) = &mut self.mass {
+            mass.reset();
+        }
+        if let Some(sensors) = &mut self.sensors {
+            sensors.reset();
+        }
+        if let Some(flcs) = &mut self.flcs {
+            flcs.reset(Some(init.control));
+        }
+        let wind = self.update_wind(0.0, 0.0);
+
+        let air = AirRelative::new(&init.state, &wind);
+        self.lef.reset(init.state.altitude, air.velocity, air.alpha);
+        let mach = self.air_data().mach;
+        if let Some(engine) = &mut self.engine {
+            let throttle = init.control.thrust.clamp(0.0, 1.0);
+            engine.reset(tgear(throttle), init.state.altitude, mach);
+        }
+    }
+
+    pub fn snapshot(&self) -> PlaneBlockSnapshot {
+        PlaneBlockSnapshot {
+            start_time: self.start_time,
+            last_time: self.last_time,
+            formulation: self.formulation,
+            vector: self.state.data.clone(),
+            state: self.formulation.to_state(&self.state.data),
+            state_dot: State::from(self.state_dot.clone()),
+            extend: self.extend,
+            actuators: self.control.snapshot(),
+            lef: self.lef.snapshot(),
+            wind: self.wind.clone(),
+            fuel: self
+                .mass
+                .as_ref()
+                .map(|m| m.tanks().iter().map(|t| t.fuel).collect()),
+            engine: self.engine.as_ref().map(|e| e.snapshot()),
+            sensors: self.sensors.clone(),
+            flcs: self.flcs.clone(),
+        }
+    }
+
+    pub fn restore(&mut self, snapshot: &PlaneBlockSnapshot) -> Result<(), FatalCoreError> {
+        if snapshot.formulation != self.formulation
+            || snapshot.vector.len() != self.formulation.dim()
+        {
+            return Err(FatalCoreError::InvalidInput(format!(
+                "snapshot of a block with {} ({} states) cannot restore a block with {}",
+                snapshot.formulation,
+                snapshot.vector.len(),
+                self.formulation
+            )));
+        }
+        match (&self.mass, &snapshot.fuel) {
+            (Some(mass), Some(fuel)) => mass.check_fuel(fuel)?,
+            (None, None) => {}
+            _ => {
+                return Err(FatalCoreError::InvalidInput(
+                    "snapshot and block differ in having a mass model".to_string(),
+                ))
+            }
+        }
+        if snapshot.actuators.len() != self.control.actuators.len() {
+            return Err(FatalCoreError::InvalidInput(format!(
+                "snapshot has {} actuators but the block has {}",
+                snapshot.actuators.len(),
+                self.control.actuators.len()
+            )));
+        }
+        if self.engine.is_some() != snapshot.engine.is_some() {
+            return Err(FatalCoreError::InvalidInput(
+                "snapshot and block differ in having an engine".to_string(),
+            ));
+        }
+        for (name, block, snapshot) in [
+            ("wind", self.wind.is_some(), snapshot.wind.is_some()),
+            (
+                "sensors",
+                self.sensors.is_some(),
+                snapshot.sensors.is_some(),
+            ),
+            (
+                "a flight control system",
+                self.flcs.is_some(),
+                snapshot.flcs.is_some(),
+            ),
+        ] {
+            if block != snapshot {
+                return Err(FatalCoreError::InvalidInput(format!(
+                    "snapshot and block differ in having {}",
+                    name
+                )));
+            }
+        }
+        // nothing is touched until the whole snapshot is known to fit
+        if let (Some(mass), Some(fuel)) = (&mut self.mass, &snapshot.fuel) {
+            mass.set_fuel(fuel)?;
+        }
+        if let (Some(engine), Some(state)) = (&mut self.engine, &snapshot.engine) {
+            engine.restore(state);
+        }
+        self.control.restore(&snapshot.actuators)?;
+        self.lef.restore(&snapshot.lef);
+        self.start_time = snapshot.start_time;
+        self.last_time = snapshot.last_time;
+        self.state = Vector::from(snapshot.vector.clone());
+        self.state_dot = snapshot.state_dot.into();
+        self.extend = snapshot.extend;
+        self.wind = snapshot.wind.clone();
+        self.sensors = snapshot.sensors.clone();
+        self.flcs = snapshot.flcs.clone();
+        Ok(())
     }
 
     pub fn state(&self) -> CoreOutput {
         // let state = &self.integrator.past();
-        let state = self.state.clone();
+        let state = self.formulation.to_state(&self.state.data);
         let control = self.control.state();
 
         CoreOutput::new(
-            State::from(state.clone()),
+            state,
             Control::from(control),
             self.extend.unwrap_or_default(),
         )
+        .with_mass(self.mass_state())
+        .with_sensors(self.measurements())
+        .with_air(Some(self.air_relative()))
     }
 
     pub fn state_dot(&self) -> State {
         State::from(self.state_dot.clone())
     }
 
+    /// The time of the last update as passed to `update`, None before the first one
+    pub fn last_time(&self) -> Option<f64> {
+        Some(self.start_time? + self.last_time?)
+    }
+
+    pub fn formulation(&self) -> Formulation {
+        self.formulation
+    }
+
+    /// latitude, longitude and altitude on the WGS-84 ellipsoid, None for flat navigation
+    pub fn geodetic(&self) -> Option<Geodetic> {
+        self.formulation.geodetic(&self.state.data)
+    }
+
+    /// Fly through `atmosphere` instead of the `SimpleAtmosphere`,
+    /// only before the first update, the leading edge flap is retrimmed to the current state
+    pub fn set_atmosphere(
+        &mut self,
+        atmosphere: Arc<dyn Atmosphere>,
+    ) -> Result<(), FatalCoreError> {
+        if self.start_time.is_some() {
+            return Err(FatalCoreError::InvalidInput(
+                "the atmosphere can only be set before the first update".to_string(),
+            ));
+        }
+        let plane = Arc::get_mut(&mut self.plane).ok_or_else(|| {
+            FatalCoreError::InvalidInput("the model is shared by another block".to_string())
+        })?;
+        plane.set_atmosphere(atmosphere.clone());
+        self.lef.set_atmosphere(atmosphere);
+        let (altitude, velocity, alpha) = self.lef_input();
+        self.lef.reset(altitude, velocity, alpha);
+        Ok(())
+    }
+
+    pub fn atmosphere(&self) -> Arc<dyn Atmosphere> {
+        self.plane.atmosphere()
+    }
+
+    /// Air data at the current altitude and airspeed relative to the air
+    pub fn air_data(&self) -> AirData {
+        let state = self.formulation.to_state(&self.state.data);
+        self.plane
+            .air_data(state.altitude, self.air_relative().velocity)
+    }
+
+    /// The solver shared by the airframe, the actuators and the leading edge flap
+    pub fn solver(&self) -> &S {
+        &self.solver
+    }
+
     pub fn delete_model(&self) {
         self.plane.delete();
     }
@@ -379,7 +1275,15 @@ unsafe impl<S> Sync for PlaneBlock<S> where S: ODESolver + VectorODESolver {}
 #[cfg(test)]
 mod block_tests {
     use super::*;
-    use crate::model::ControlLimit;
+    use crate::components::atmosphere::StandardAtmosphere;
+    use crate::components::engine::{tgear, EngineModel};
+    use crate::components::flcs::Flcs;
+    use crate::components::flight::G;
+    use crate::components::mass::{FuelConsumption, Tank};
+    use crate::components::sensor::{Ahrs, AirDataSensor, Imu, SensorModel};
+    use crate::components::signal::Signal;
+    use crate::components::wind::{Gust, Spectrum, Turbulence};
+    use crate::model::{Attitude, ControlLimit, Navigation};
     use crate::optimizer::nelder_mead::NelderMeadOptions;
     use crate::plugin::{AerodynamicModel, AsPlugin};
     use crate::trim::{trim, TrimOutput, TrimTarget};
@@ -474,20 +1378,22 @@ mod block_tests {
             let current_time = SystemTime::now();
             let delta_time = current_time.duration_since(start_time).unwrap();
 
-            let result = control.update(
-                [
-                    step(
-                        control_init.thrust,
-                        2.0 * control_init.thrust * 2.0,
-                        1.0,
-                        delta_time.as_secs_f64(),
-                    ),
-                    control_init.elevator,
-                    control_init.aileron,
-                    control_init.rudder,
-                ],
-                delta_time.as_secs_f64(),
-            );
+            let result = control
+                .update(
+                    [
+                        step(
+                            control_init.thrust,
+                            2.0 * control_init.thrust * 2.0,
+                            1.0,
+                            delta_time.as_secs_f64(),
+                        ),
+                        control_init.elevator,
+                        control_init.aileron,
+                        control_init.rudder,
+                    ],
+                    delta_time.as_secs_f64(),
+                )
+                .unwrap();
             trace!("time: {:?} \n{:?}\n", delta_time, result);
 
             let data: Vec<String> = Into::<Vec<f64>>::into(result)
@@ -508,6 +1414,874 @@ mod block_tests {
         test_core_fin(model)
     }
 
+    #[test]
+    fn test_time_consistency() {
+        let (model, result) = test_core_init();
+
+        let solver = Arc::new(solver::rk::RK4Solver::new(0.01));
+        let control: [f64; 4] = result.control.into();
+        let init: CoreInit = result.into();
+
+        let mut regular =
+            PlaneBlock::new(solver.clone(), &model, &init, &[0.0, 0.0, 0.0], CL).unwrap();
+        let mut irregular =
+            PlaneBlock::new(solver.clone(), &model, &init, &[0.0, 0.0, 0.0], CL).unwrap();
+
+        for i in 0..=200 {
+            regular.update(control, 10.0 + 0.01 * i as f64).unwrap();
+        }
+        for t in [10.0, 10.003, 10.37, 11.0, 11.999, 12.0] {
+            irregular.update(control, t).unwrap();
+        }
+
+        let a: Vec<f64> = regular.state().state.into();
+        let b: Vec<f64> = irregular.state().state.into();
+        for (a, b) in a.iter().zip(b.iter()) {
+            assert!((a - b).abs() < 1e-3 * a.abs().max(1.0), "{a} != {b}");
+        }
+        // 2 seconds of flight at about 500 ft/s
+        assert!((a[0] - init.state.npos - 1000.0).abs() < 20.0);
+
+        let res = irregular.update(control, 11.5);
+        assert!(matches!(res, Err(FatalCoreError::NonMonotonicTime(..))));
+
+        // the clock restarts after a reset
+        irregular.reset(&init);
+        assert!(irregular.update(control, 0.0).is_ok());
+
+        regular.delete_model();
+        test_core_fin(model)
+    }
+
+    #[test]
+    fn test_subsystem_substeps() {
+        let (model, result) = test_core_init();
+
+        let control: [f64; 4] = result.control.into();
+        let pulled = [control[0], control[1] - 2.0, control[2], control[3]];
+        let init: CoreInit = result.into();
+
+        // 4 sub-steps of a 25 Hz step follow a plain 100 Hz run
+        let mut substepped = PlaneBlock::new(
+            Arc::new(solver::rk::RK4Solver::new(0.04)),
+            &model,
+            &init,
+            &[0.0, 0.0, 0.0],
+            CL,
+        )
+        .unwrap();
+        substepped.set_subsystem_substeps(4).unwrap();
+        let mut fine = PlaneBlock::new(
+            Arc::new(solver::rk::RK4Solver::new(0.01)),
+            &model,
+            &init,
+            &[0.0, 0.0, 0.0],
+            CL,
+        )
+        .unwrap();
+
+        for i in 0..=50 {
+            let t = 0.04 * i as f64;
+            let a = substepped.update(pulled, t).unwrap();
+            let b = fine.update(pulled, t).unwrap();
+            let a: Vec<f64> = [Vec::from(a.state), Vec::from(a.control)].concat();
+            let b: Vec<f64> = [Vec::from(b.state), Vec::from(b.control)].concat();
+            for (a, b) in a.iter().zip(b.iter()) {
+                assert!((a - b).abs() < 1e-9 * a.abs().max(1.0), "{a} != {b}");
+            }
+        }
+
+        substepped.delete_model();
+        test_core_fin(model)
+    }
+
+    #[test]
+    fn test_adaptive() {
+        let (model, result) = test_core_init();
+
+        let control: [f64; 4] = result.control.into();
+        let pulled = [control[0], control[1] - 2.0, control[2], control[3]];
+        let init: CoreInit = result.into();
+
+        // the actuators are held over each step, so both blocks share the update grid
+        let fixed = Arc::new(solver::rk::RK4Solver::new(0.02));
+        let adaptive = Arc::new(solver::adaptive::RK45Solver::new(0.02, 1e-6, 1e-8, 1e-6).unwrap());
+        let mut reference = PlaneBlock::new(fixed, &model, &init, &[0.0, 0.0, 0.0], CL).unwrap();
+        let mut block = PlaneBlock::new(adaptive, &model, &init, &[0.0, 0.0, 0.0], CL).unwrap();
+
+        for i in 0..=100 {
+            let t = 0.02 * i as f64;
+            reference.update(pulled, t).unwrap();
+            block.update(pulled, t).unwrap();
+        }
+
+        let a: Vec<f64> = reference.state().state.into();
+        let b: Vec<f64> = block.state().state.into();
+        for (a, b) in a.iter().zip(b.iter()) {
+            assert!((a - b).abs() < 1e-3 * a.abs().max(1.0), "{a} != {b}");
+        }
+
+        let stats = block.solver().stats();
+        assert!(stats.accepted_steps > 0);
+        assert!(stats.fun_evals > 6 * stats.accepted_steps);
+
+        block.delete_model();
+        test_core_fin(model)
+    }
+
+    #[test]
+    fn test_implicit() {
+        let (model, result) = test_core_init();
+
+        let control: [f64; 4] = result.control.into();
+        let pulled = [control[0], control[1] - 2.0, control[2], control[3]];
+        let init: CoreInit = result.into();
+
+        // a coarse step at which the explicit Euler actuator loops oscillate
+        let solver = Arc::new(solver::implicit::SDIRK2Solver::new(0.12));
+        let mut block = PlaneBlock::new(solver, &model, &init, &[0.0, 0.0, 0.0], CL).unwrap();
+        for i in 0..=25 {
+            block.update(pulled, 0.12 * i as f64).unwrap();
+        }
+
+        let state = block.state();
+        assert!((state.control.elevator - pulled[1]).abs() < 1e-3);
+        assert!(state.state.altitude.is_finite());
+
+        block.delete_model();
+        test_core_fin(model)
+    }
+
+    #[test]
+    fn test_quaternion() {
+        let (model, result) = test_core_init();
+
+        let solver = Arc::new(solver::rk::RK4Solver::new(0.01));
+        let control: [f64; 4] = result.control.into();
+        let pulled = [control[0], control[1] - 2.0, control[2], control[3]];
+        let init: CoreInit = result.into();
+        let quaternion = Formulation::new(Attitude::Quaternion, Velocity::Wind, Navigation::Flat);
+
+        let mut euler =
+            PlaneBlock::new(solver.clone(), &model, &init, &[0.0, 0.0, 0.0], CL).unwrap();
+        let mut block = PlaneBlock::with_formulation(
+            solver.clone(),
+            &model,
+            &init,
+            &[0.0, 0.0, 0.0],
+            CL,
+            quaternion,
+        )
+        .unwrap();
+
+        // both formulations agree away from the singularity
+        for i in 0..=100 {
+            let t = 0.02 * i as f64;
+            euler.update(pulled, t).unwrap();
+            block.update(pulled, t).unwrap();
+        }
+        let a: Vec<f64> = euler.state().state.into();
+        let b: Vec<f64> = block.state().state.into();
+        for (a, b) in a.iter().zip(b.iter()) {
+            assert!((a - b).abs() < 1e-6 * a.abs().max(1.0), "{a} != {b}");
+        }
+
+        // pitch up through the vertical
+        let mut vertical = init;
+        vertical.state.theta = 85.0_f64.to_radians();
+        vertical.state.q = 0.5;
+        block.reset(&vertical);
+        let mut max_theta = f64::MIN;
+        let mut output = block.state();
+        for i in 0..=50 {
+            output = block.update(pulled, 0.02 * i as f64).unwrap();
+            max_theta = max_theta.max(output.state.theta.to_degrees());
+            let snapshot = block.snapshot();
+            let norm: f64 = snapshot.vector[3..7].iter().map(|q| q * q).sum();
+            assert!((norm - 1.0).abs() < 1e-12);
+        }
+        assert!(max_theta > 89.0, "max theta {max_theta}");
+        // over the top the Euler angles flip to inverted with the heading reversed
+        assert!(output.state.theta.to_degrees() < 80.0);
+        assert!(output.state.phi.to_degrees().abs() > 170.0);
+        assert!(output.state.psi.to_degrees().abs() > 170.0);
+
+        let res = euler.restore(&block.snapshot());
+        assert!(matches!(res, Err(FatalCoreError::InvalidInput(_))));
+
+        block.delete_model();
+        test_core_fin(model)
+    }
+
+    #[test]
+    fn test_body_velocity() {
+        let (model, result) = test_core_init();
+
+        let solver = Arc::new(solver::rk::RK4Solver::new(0.01));
+        let control: [f64; 4] = result.control.into();
+        let pulled = [control[0], control[1] - 2.0, control[2], control[3]];
+        let init: CoreInit = result.into();
+        let body = Formulation::new(Attitude::Quaternion, Velocity::Body, Navigation::Flat);
+
+        let mut wind =
+            PlaneBlock::new(solver.clone(), &model, &init, &[0.0, 0.0, 0.0], CL).unwrap();
+        let mut block =
+            PlaneBlock::with_formulation(solver.clone(), &model, &init, &[0.0, 0.0, 0.0], CL, body)
+                .unwrap();
+
+        for i in 0..=100 {
+            let t = 0.02 * i as f64;
+            wind.update(pulled, t).unwrap();
+            block.update(pulled, t).unwrap();
+        }
+        let a: Vec<f64> = wind.state().state.into();
+        let b: Vec<f64> = block.state().state.into();
+        for (a, b) in a.iter().zip(b.iter()) {
+            assert!((a - b).abs() < 1e-6 * a.abs().max(1.0), "{a} != {b}");
+        }
+
+        // a slow vertical climb at idle which runs out of airspeed and slides back
+        let mut slow = init;
+        slow.state.theta = 80.0_f64.to_radians();
+        slow.state.velocity = 60.0;
+        block.reset(&slow);
+        let idle = [1000.0, 0.0, 0.0, 0.0];
+        let mut min_velocity = f64::MAX;
+        let mut output = block.state();
+        for i in 0..=300 {
+            output = block.update(idle, 0.01 * i as f64).unwrap();
+            min_velocity = min_velocity.min(output.state.velocity);
+        }
+        assert!(min_velocity < 20.0);
+        assert!(output.state.alpha.to_degrees() > 90.0);
+        let state: Vec<f64> = output.state.into();
+        assert!(state.iter().all(|x| x.is_finite()));
+
+        block.delete_model();
+        test_core_fin(model)
+    }
+
+    #[test]
+    fn test_geodetic() {
+        let (model, result) = test_core_init();
+
+        let solver = Arc::new(solver::rk::RK4Solver::new(0.01));
+        let control: [f64; 4] = result.control.into();
+        let init: CoreInit = result.into();
+        let navigation = Navigation::Geodetic {
+            latitude: 45.0_f64.to_radians(),
+            longitude: 10.0_f64.to_radians(),
+        };
+        let geodetic = Formulation::new(Attitude::Euler, Velocity::Wind, navigation);
+
+        let mut flat =
+            PlaneBlock::new(solver.clone(), &model, &init, &[0.0, 0.0, 0.0], CL).unwrap();
+        let mut block = PlaneBlock::with_formulation(
+            solver.clone(),
+            &model,
+            &init,
+            &[0.0, 0.0, 0.0],
+            CL,
+            geodetic,
+        )
+        .unwrap();
+        assert!(flat.geodetic().is_none());
+
+        for i in 0..=500 {
+            let t = 0.02 * i as f64;
+            flat.update(control, t).unwrap();
+            block.update(control, t).unwrap();
+        }
+
+        // 10 s is too short for the earth to matter much, the tangent plane view stays close
+        let a = flat.state().state;
+        let b = block.state().state;
+        assert!((a.npos - b.npos).abs() < 10.0, "{} != {}", a.npos, b.npos);
+        assert!((a.epos - b.epos).abs() < 10.0, "{} != {}", a.epos, b.epos);
+        assert!((a.altitude - b.altitude).abs() < 10.0);
+        assert!((a.theta - b.theta).abs() < 1e-3);
+
+        // the geodetic position matches the tangent plane view over 5000 ft
+        let point = block.geodetic().unwrap();
+        let (m, n) = crate::components::earth::radii(point.latitude);
+        let north = (point.latitude - 45.0_f64.to_radians()) * (m + point.altitude);
+        let east =
+            (point.longitude - 10.0_f64.to_radians()) * (n + point.altitude) * point.latitude.cos();
+        assert!(b.npos > 4000.0);
+        assert!((north - b.npos).abs() < 1.0, "{north} != {}", b.npos);
+        assert!((east - b.epos).abs() < 1.0, "{east} != {}", b.epos);
+
+        block.delete_model();
+        test_core_fin(model)
+    }
+
+    #[test]
+    fn test_atmosphere() {
+        let (model, result) = test_core_init();
+
+        let solver = Arc::new(solver::rk::RK4Solver::new(0.01));
+        let control: [f64; 4] = result.control.into();
+        let init: CoreInit = result.into();
+
+        let mut simple =
+            PlaneBlock::new(solver.clone(), &model, &init, &[0.0, 0.0, 0.0], CL).unwrap();
+        let mut hot = PlaneBlock::new(solver.clone(), &model, &init, &[0.0, 0.0, 0.0], CL).unwrap();
+        hot.set_atmosphere(Arc::new(StandardAtmosphere::new(20.0)))
+            .unwrap();
+
+        // thinner air on a hot day, the true airspeed is the same
+        let (a, b) = (simple.air_data(), hot.air_data());
+        assert_eq!(a.tas, b.tas);
+        assert!(b.air.density < a.air.density);
+        assert!(b.eas < a.eas && b.cas < a.cas);
+
+        for i in 0..=100 {
+            let t = 0.02 * i as f64;
+            simple.update(control, t).unwrap();
+            hot.update(control, t).unwrap();
+        }
+        // trimmed for denser air, the plane sinks
+        assert!(hot.state().state.altitude < simple.state().state.altitude - 1.0);
+        assert!(hot.set_atmosphere(Arc::new(SimpleAtmosphere)).is_err());
+
+        simple.delete_model();
+        hot.delete_model();
+        test_core_fin(model)
+    }
+
+    #[test]
+    fn test_wind() {
+        let (model, result) = test_core_init();
+
+        let solver = Arc::new(solver::rk::RK4Solver::new(0.01));
+        let control: [f64; 4] = result.control.into();
+        let init: CoreInit = result.into();
+
+        // a 20 ft/s headwind, the ground speed is 20 ft/s below the trimmed airspeed
+        let mut slow = init;
+        slow.state.velocity -= 20.0;
+        let mut still =
+            PlaneBlock::new(solver.clone(), &model, &init, &[0.0, 0.0, 0.0], CL).unwrap();
+        let mut headwind =
+            PlaneBlock::new(solver.clone(), &model, &slow, &[0.0, 0.0, 0.0], CL).unwrap();
+        headwind.set_wind(Some(WindModel::new([-20.0, 0.0, 0.0])));
+        assert!((headwind.air_relative().velocity - init.state.velocity).abs() < 1e-9);
+        assert!((headwind.air_data().qbar - still.air_data().qbar).abs() < 1e-9);
+
+        for i in 0..=250 {
+            let t = 0.02 * i as f64;
+            still.update(control, t).unwrap();
+            headwind.update(control, t).unwrap();
+        }
+        let (a, b) = (still.state(), headwind.state());
+        assert!((a.state.altitude - b.state.altitude).abs() < 1.0);
+        assert!((a.state.alpha - b.state.alpha).abs() < 1e-4);
+        assert!((a.state_extend.qbar - b.state_extend.qbar).abs() < 1e-3 * a.state_extend.qbar);
+        // navigation integrates the ground speed
+        let lag = a.state.npos - b.state.npos;
+        assert!((lag - 100.0).abs() < 1.0, "{lag}");
+
+        // an updraft raises alpha and the load factor
+        let gust = Gust::new(1.0, 250.0, [0.0, 0.0, -30.0]);
+        let mut gusty =
+            PlaneBlock::new(solver.clone(), &model, &init, &[0.0, 0.0, 0.0], CL).unwrap();
+        gusty.set_wind(Some(WindModel::default().with_gust(gust)));
+        still.reset(&init);
+        let mut peak = 0.0_f64;
+        for i in 0..=100 {
+            let t = 0.02 * i as f64;
+            let a = still.update(control, t).unwrap();
+            let b = gusty.update(control, t).unwrap();
+            peak = peak.max(b.state_extend.nz - a.state_extend.nz);
+        }
+        assert!(peak > 0.5, "{peak}");
+
+        // seeded turbulence repeats, and resumes from a snapshot
+        let turbulence = Turbulence::new(Spectrum::VonKarman, 30.0, 11);
+        let wind = WindModel::default().with_turbulence(turbulence);
+        gusty.set_wind(Some(wind.clone()));
+        gusty.reset(&init);
+        let mut rough =
+            PlaneBlock::new(solver.clone(), &model, &init, &[0.0, 0.0, 0.0], CL).unwrap();
+        rough.set_wind(Some(wind));
+        let mut snapshot = None;
+        for i in 0..=100 {
+            let t = 0.02 * i as f64;
+            let a = gusty.update(control, t).unwrap();
+            let b = rough.update(control, t).unwrap();
+            assert_eq!(a.state, b.state);
+            if i == 50 {
+                snapshot = Some(gusty.snapshot());
+            }
+        }
+        assert!(rough.wind().body != [0.0; 3]);
+        let end = rough.state();
+        rough.restore(&snapshot.unwrap()).unwrap();
+        for i in 51..=100 {
+            rough.update(control, 0.02 * i as f64).unwrap();
+        }
+        assert_eq!(rough.state().state, end.state);
+
+        still.delete_model();
+        test_core_fin(model)
+    }
+
+    #[test]
+    fn test_excitation() {
+        let (model, result) = test_core_init();
+
+        let solver = Arc::new(solver::rk::RK4Solver::new(0.01));
+        let control: [f64; 4] = result.control.into();
+        let init: CoreInit = result.into();
+
+        // the deflection argument is an elevator doublet from 1 s to 5 s
+        let mut deflected =
+            PlaneBlock::new(solver.clone(), &model, &init, &[1.0, 0.0, 0.0], CL).unwrap();
+        let mut excited =
+            PlaneBlock::new(solver.clone(), &model, &init, &[0.0, 0.0, 0.0], CL).unwrap();
+        assert!(excited.excitation(1).is_none());
+        excited
+            .set_excitation(1, Some(Signal::doublet(1.0, 1.0, 4.0)))
+            .unwrap();
+        let prbs = Signal::prbs(0.5, 2.0, 4.0, 0.2, 5).unwrap();
+        excited
+            .set_excitation(2, Some(prbs + Signal::step(0.0, 0.0, 1.0)))
+            .unwrap();
+        assert!(excited.set_excitation(4, None).is_err());
+
+        for i in 0..=150 {
+            let t = 0.02 * i as f64;
+            let a = deflected.update(control, t).unwrap();
+            let b = excited.update(control, t).unwrap();
+            assert_eq!(a.control.elevator, b.control.elevator);
+            if t == 2.0 {
+                assert!((a.control.elevator - control[1] - 1.0).abs() < 1e-3);
+            }
+        }
+        // the aileron PRBS rolls the excited block away
+        let roll = excited.state().state.phi - deflected.state().state.phi;
+        assert!(roll.abs() > 1e-3, "{roll}");
+
+        deflected.delete_model();
+        test_core_fin(model)
+    }
+
+    #[test]
+    fn test_fuel() {
+        let (model, result) = test_core_init();
+
+        let solver = Arc::new(solver::rk::RK4Solver::new(0.01));
+        let control: [f64; 4] = result.control.into();
+        let init: CoreInit = result.into();
+        let constants = model.load_constants().unwrap();
+        let tanks = vec![
+            Tank::new(3000.0, [4.0, 0.0, 0.5]),
+            Tank::new(4000.0, [-5.0, 0.0, 0.5]).with_stage(1),
+        ];
+        let consumption = FuelConsumption::new(0.8, 0.2);
+
+        let mut dry = PlaneBlock::new(solver.clone(), &model, &init, &[0.0, 0.0, 0.0], CL).unwrap();
+        let mut fueled =
+            PlaneBlock::new(solver.clone(), &model, &init, &[0.0, 0.0, 0.0], CL).unwrap();
+        let mass = MassModel::new(constants, tanks.clone(), consumption).unwrap();
+        fueled.set_mass_model(Some(mass));
+        assert_eq!(fueled.constants(), constants);
+        assert!(dry.state().mass.is_none());
+
+        let mut outputs = Vec::new();
+        for i in 0..=500 {
+            let t = 0.02 * i as f64;
+            dry.update(control, t).unwrap();
+            outputs.push(fueled.update(control, t).unwrap());
+        }
+        let mass = outputs.last().unwrap().mass.unwrap();
+        let burned = 7000.0 - mass.fuel;
+        let flow = consumption.fuel_flow(control[0], &fueled.air_data());
+        assert!((mass.fuel_flow - flow).abs() < 1e-3 * flow);
+        assert!(
+            (burned - 10.0 * mass.fuel_flow).abs() < 0.05 * burned,
+            "{burned}"
+        );
+        assert!(outputs
+            .windows(2)
+            .all(|w| w[1].mass.unwrap().fuel <= w[0].mass.unwrap().fuel));
+        // the forward tank feeds first, the center of gravity moves aft
+        assert!(mass.x_cg > constants.x_cg);
+        assert!((mass.mass - (constants.m - burned / G)).abs() < 1e-9);
+        assert!(fueled.state().state.theta != dry.state().state.theta);
+
+        // snapshots carry the fuel
+        let snapshot = fueled.snapshot();
+        let content = toml::to_string(&snapshot).unwrap();
+        let snapshot: PlaneBlockSnapshot = toml::from_str(&content).unwrap();
+        assert_eq!(snapshot.fuel.as_ref().unwrap()[0], 3000.0 - burned);
+        assert!(dry.restore(&snapshot).is_err());
+        fueled.reset(&init);
+        assert_eq!(fueled.mass_state().unwrap().fuel, 7000.0);
+        fueled.restore(&snapshot).unwrap();
+        assert_eq!(fueled.mass_state().unwrap().fuel, 7000.0 - burned);
+        // a snapshot that fails anywhere leaves the fuel alone
+        let mut broken = snapshot.clone();
+        broken.fuel = broken.fuel.map(|f| vec![0.0; f.len()]);
+        broken.actuators.pop();
+        assert!(fueled.restore(&broken).is_err());
+        assert_eq!(fueled.mass_state().unwrap().fuel, 7000.0 - burned);
+
+        // the engine flames out once the tanks run dry
+        let tanks = tanks.into_iter().map(|t| t.with_fuel(0.1)).collect();
+        let mass = MassModel::new(constants, tanks, consumption).unwrap();
+        fueled.set_mass_model(Some(mass));
+        fueled.reset(&init);
+        dry.reset(&init);
+        for i in 0..=500 {
+            let t = 0.02 * i as f64;
+            dry.update(control, t).unwrap();
+            fueled.update(control, t).unwrap();
+        }
+        assert_eq!(fueled.mass_state().unwrap().fuel, 0.0);
+        assert!(fueled.state().state.velocity < dry.state().state.velocity - 1.0);
+
+        dry.delete_model();
+        test_core_fin(model)
+    }
+
+    #[test]
+    fn test_engine() {
+        let (model, result) = test_core_init();
+
+        let solver = Arc::new(solver::rk::RK4Solver::new(0.01));
+        let control: [f64; 4] = result.control.into();
+        let init: CoreInit = result.into();
+
+        let mut direct =
+            PlaneBlock::new(solver.clone(), &model, &init, &[0.0, 0.0, 0.0], CL).unwrap();
+        let mut engine =
+            PlaneBlock::new(solver.clone(), &model, &init, &[0.0, 0.0, 0.0], CL).unwrap();
+        engine.set_engine(Some(EngineModel::f16()));
+        assert_eq!(engine.constants().h_eng, 160.0);
+        // the engine settles at the trimmed thrust, the thrust channel holds the throttle
+        let throttle = engine.state().control.thrust;
+        assert!((0.0..=1.0).contains(&throttle));
+        assert!((engine.engine_state().unwrap().thrust - control[0]).abs() < 1e-6);
+        let held = [throttle, control[1], control[2], control[3]];
+
+        for i in 0..=250 {
+            let t = 0.02 * i as f64;
+            direct.update(control, t).unwrap();
+            engine.update(held, t).unwrap();
+        }
+        let (a, b) = (direct.state(), engine.state());
+        assert!((a.state.velocity - b.state.velocity).abs() < 1.0);
+        assert!((a.state.altitude - b.state.altitude).abs() < 5.0);
+
+        // the afterburner lights through military power with the lag of the core
+        let full = [1.0, control[1], control[2], control[3]];
+        let snapshot = engine.snapshot();
+        engine.update(full, 5.5).unwrap();
+        let spooling = engine.engine_state().unwrap();
+        assert!(spooling.power < 50.0 && spooling.power > tgear(throttle));
+        for i in 1..=50 {
+            engine.update(full, 5.5 + 0.2 * i as f64).unwrap();
+        }
+        let burner = engine.engine_state().unwrap();
+        assert!(burner.power > 95.0, "{}", burner.power);
+        assert!(engine.state().state.velocity > direct.state().state.velocity + 5.0);
+
+        let content = toml::to_string(&snapshot).unwrap();
+        let snapshot: PlaneBlockSnapshot = toml::from_str(&content).unwrap();
+        engine.restore(&snapshot).unwrap();
+        assert_eq!(engine.update(full, 5.5).unwrap().state, {
+            let mut other =
+                PlaneBlock::new(solver.clone(), &model, &init, &[0.0, 0.0, 0.0], CL).unwrap();
+            other.set_engine(Some(EngineModel::f16()));
+            other.restore(&snapshot).unwrap();
+            other.update(full, 5.5).unwrap().state
+        });
+        assert!(direct.restore(&snapshot).is_err());
+        // a snapshot that fails anywhere leaves the engine alone
+        let power = engine.engine_state().unwrap().power;
+        let mut broken = snapshot.clone();
+        broken.actuators.pop();
+        assert!(engine.restore(&broken).is_err());
+        assert_eq!(engine.engine_state().unwrap().power, power);
+
+        // reset takes the throttle, removing the engine brings back the thrust in lbf
+        let mut throttled = init;
+        throttled.control.thrust = throttle;
+        engine.reset(&throttled);
+        assert!((engine.engine_state().unwrap().thrust - control[0]).abs() < 1.0);
+        engine.set_engine(None);
+        assert!((engine.state().control.thrust - control[0]).abs() < 1.0);
+        assert_eq!(
+            engine.constants().h_eng,
+            model.load_constants().unwrap().h_eng
+        );
+
+        direct.delete_model();
+        test_core_fin(model)
+    }
+
+    #[test]
+    fn test_actuator_failure() {
+        let (model, result) = test_core_init();
+
+        let solver = Arc::new(solver::rk::RK4Solver::new(0.01));
+        let control: [f64; 4] = result.control.into();
+        let init: CoreInit = result.into();
+
+        let mut nominal =
+            PlaneBlock::new(solver.clone(), &model, &init, &[0.0, 0.0, 0.0], CL).unwrap();
+        let mut failed =
+            PlaneBlock::new(solver.clone(), &model, &init, &[0.0, 0.0, 0.0], CL).unwrap();
+        let elevator = ActuatorModel::second_order(25.0, -25.0, 60.0, 40.0, 0.7)
+            .with_deadband(0.05)
+            .with_delay(0.02);
+        for block in [&mut nominal, &mut failed] {
+            block.set_actuator(1, elevator).unwrap();
+        }
+        assert_eq!(failed.actuator(1).unwrap(), elevator);
+        assert!(failed.set_actuator(5, elevator).is_err());
+        assert!(failed.actuator_failure(1).unwrap().is_none());
+        failed
+            .set_actuator_failure(1, 1.0, Some(ActuatorFailure::Hardover { top: true }))
+            .unwrap();
+
+        // nothing changes before the failure strikes
+        for i in 0..50 {
+            let t = 0.02 * i as f64;
+            assert_eq!(
+                nominal.update(control, t).unwrap().state,
+                failed.update(control, t).unwrap().state
+            );
+        }
+        for i in 50..=100 {
+            let t = 0.02 * i as f64;
+            nominal.update(control, t).unwrap();
+            failed.update(control, t).unwrap();
+        }
+        assert!(failed.state().control.elevator > 20.0);
+        assert!((failed.state().state.theta - nominal.state().state.theta).abs() > 0.1);
+
+        // snapshots carry the actuators past the failure
+        let snapshot = failed.snapshot();
+        let content = toml::to_string(&snapshot).unwrap();
+        let snapshot: PlaneBlockSnapshot = toml::from_str(&content).unwrap();
+        let expected = failed.update(control, 2.1).unwrap().state;
+        failed.restore(&snapshot).unwrap();
+        assert_eq!(failed.update(control, 2.1).unwrap().state, expected);
+
+        // a stuck flap holds where it was, a repaired one follows again
+        failed.reset(&init);
+        failed.set_actuator_failure(1, 0.0, None).unwrap();
+        failed
+            .set_actuator_failure(
+                4,
+                0.0,
+                Some(ActuatorFailure::Stuck {
+                    position: Some(5.0),
+                }),
+            )
+            .unwrap();
+        failed.update(control, 0.0).unwrap();
+        failed.update(control, 0.5).unwrap();
+        assert_eq!(failed.lef.actuator().state(), 5.0);
+        assert!(nominal.lef.actuator().state() != 5.0);
+
+        nominal.delete_model();
+        test_core_fin(model)
+    }
+
+    #[test]
+    fn test_sensors() {
+        let (model, result) = test_core_init();
+
+        let solver = Arc::new(solver::rk::RK4Solver::new(0.01));
+        let control: [f64; 4] = result.control.into();
+        let init: CoreInit = result.into();
+
+        let gyro = SensorModel::default()
+            .with_noise(0.001)
+            .with_bias(0.002)
+            .with_quantization(1e-4);
+        let sensors = Sensors::new()
+            .with_imu(
+                Imu::new(50.0, 1)
+                    .with_position([5.0, 0.0, 0.5])
+                    .with_gyro(gyro),
+            )
+            .with_air_data(
+                AirDataSensor::new(25.0, 2)
+                    .with_alpha(SensorModel::default().with_noise(0.002).with_lag(0.05)),
+            )
+            .with_ahrs(Ahrs::new(0.0, 3));
+        let mut plane =
+            PlaneBlock::new(solver.clone(), &model, &init, &[0.0, 0.0, 0.0], CL).unwrap();
+        assert!(plane.state().sensors.is_none());
+        plane.set_sensors(Some(sensors));
+
+        let mut outputs = Vec::new();
+        for i in 0..=200 {
+            let t = 0.01 * i as f64;
+            outputs.push(plane.update(control, t).unwrap());
+        }
+        let measured: Vec<Measurements> = outputs.iter().map(|o| o.sensors.unwrap()).collect();
+        // the gyro reads the rate with its bias, the ahrs the truth on every update
+        let q: Vec<f64> = measured.iter().map(|m| m.imu.unwrap().rates[1]).collect();
+        let error = q
+            .iter()
+            .zip(&outputs)
+            .map(|(q, o)| q - o.state.q)
+            .sum::<f64>()
+            / q.len() as f64;
+        assert!((error - 0.002).abs() < 5e-4, "{error}");
+        assert!(q
+            .iter()
+            .all(|q| (q / 1e-4 - (q / 1e-4).round()).abs() < 1e-6));
+        assert!(measured
+            .iter()
+            .zip(&outputs)
+            .all(|(m, o)| m.ahrs.unwrap().theta == o.state.theta));
+        // sampled at 50 and 25 Hz, held in between
+        assert_eq!(q[1], q[0]);
+        assert_ne!(q[2], q[0]);
+        let alpha: Vec<f64> = measured.iter().map(|m| m.air_data.unwrap().alpha).collect();
+        assert_eq!(alpha[3], alpha[0]);
+        assert!((alpha[200] - outputs[200].state.alpha).abs() < 0.01);
+        assert_eq!(plane.measurements(), Some(measured[200]));
+
+        // snapshots carry the random generators
+        let snapshot = plane.snapshot();
+        let content = toml::to_string(&snapshot).unwrap();
+        let snapshot: PlaneBlockSnapshot = toml::from_str(&content).unwrap();
+        let expected = plane.update(control, 2.02).unwrap().sensors;
+        plane.restore(&snapshot).unwrap();
+        assert_eq!(plane.update(control, 2.02).unwrap().sensors, expected);
+
+        // a reset repeats the same noise
+        plane.reset(&init);
+        assert_eq!(
+            plane.update(control, 0.0).unwrap().sensors,
+            Some(measured[0])
+        );
+
+        plane.delete_model();
+        test_core_fin(model)
+    }
+
+    #[test]
+    fn test_flcs() {
+        let (model, result) = test_core_init();
+
+        let solver = Arc::new(solver::rk::RK4Solver::new(0.01));
+        let control: [f64; 4] = result.control.into();
+        let init: CoreInit = result.into();
+        let run = |stick: [f64; 3]| {
+            let mut plane =
+                PlaneBlock::new(solver.clone(), &model, &init, &[0.0, 0.0, 0.0], CL).unwrap();
+            plane.set_flcs(Some(Flcs::f16()));
+            let pilot = [control[0], stick[0], stick[1], stick[2]];
+            let outputs: Vec<CoreOutput> = (0..=300)
+                .map(|i| plane.update(pilot, 0.01 * i as f64).unwrap())
+                .collect();
+            (plane, outputs)
+        };
+
+        // stick centered, the trim is held
+        let (mut plane, free) = run([0.0, 0.0, 0.0]);
+        for o in &free {
+            assert!((o.state_extend.nz - 1.0).abs() < 0.01);
+            assert!(o.state.q.to_degrees().abs() < 0.1);
+        }
+
+        // a light pull commands about 1.5 g more, a push about 0.75 g less
+        let (_, pull) = run([0.3, 0.0, 0.0]);
+        let peak = pull.iter().map(|o| o.state_extend.nz).fold(0.0, f64::max);
+        assert!((2.2..2.9).contains(&peak));
+        let (_, push) = run([-0.3, 0.0, 0.0]);
+        let low = push.iter().map(|o| o.state_extend.nz).fold(1.0, f64::min);
+        assert!((0.0..0.5).contains(&low));
+
+        // full aft stick is stopped by the angle-of-attack limiter
+        let (_, full) = run([1.0, 0.0, 0.0]);
+        for o in &full {
+            assert!(o.state.alpha.to_degrees() < 27.0);
+        }
+
+        // the lateral stick commands a roll rate with little sideslip
+        let (_, roll) = run([0.0, 0.5, 0.0]);
+        for o in &roll[100..] {
+            assert!((80.0..120.0).contains(&o.state.p.to_degrees()));
+            assert!(o.state.beta.to_degrees().abs() < 3.0);
+        }
+
+        // right pedal, nose right, wind from the left
+        let (_, pedal) = run([0.0, 0.0, 0.3]);
+        assert!(pedal.last().unwrap().state.beta.to_degrees() < -1.0);
+        assert!(pedal.last().unwrap().control.rudder < -3.0);
+
+        // the flight control system is part of the snapshot
+        let snapshot = plane.snapshot();
+        let content = toml::to_string(&snapshot).unwrap();
+        let snapshot: PlaneBlockSnapshot = toml::from_str(&content).unwrap();
+        assert_eq!(snapshot.flcs, plane.snapshot().flcs);
+        plane.set_flcs(None);
+        assert!(plane.restore(&snapshot).is_err());
+        assert!(plane.flcs().is_none());
+        plane.set_flcs(snapshot.flcs.clone());
+        plane.restore(&snapshot).unwrap();
+        assert_eq!(plane.snapshot().flcs, snapshot.flcs);
+
+        // the commands are clamped to the travel of the actuators
+        let limits = plane.flcs().unwrap().limits();
+        assert_eq!(limits, [[-25.0, 25.0], [-21.5, 21.5], [-30.0, 30.0]]);
+        let elevator = ActuatorModel::first_order(10.0, -15.0, 60.0, 20.2);
+        plane.set_actuator(1, elevator).unwrap();
+        assert_eq!(plane.flcs().unwrap().limits()[0], [-15.0, 10.0]);
+
+        test_core_fin(model)
+    }
+
+    #[test]
+    fn test_snapshot() {
+        let (model, result) = test_core_init();
+
+        let solver = Arc::new(solver::rk::RK4Solver::new(0.01));
+        let control: [f64; 4] = result.control.into();
+        let pulled = [control[0], control[1] - 2.0, control[2], control[3]];
+        let init: CoreInit = result.into();
+
+        let mut origin =
+            PlaneBlock::new(solver.clone(), &model, &init, &[0.0, 0.0, 0.0], CL).unwrap();
+        let mut branch =
+            PlaneBlock::new(solver.clone(), &model, &init, &[0.0, 0.0, 0.0], CL).unwrap();
+
+        for i in 0..=50 {
+            origin.update(pulled, 3.0 + 0.02 * i as f64).unwrap();
+        }
+        let snapshot = origin.snapshot();
+        let content = toml::to_string(&snapshot).unwrap();
+        let snapshot: PlaneBlockSnapshot = toml::from_str(&content).unwrap();
+        assert_eq!(snapshot, origin.snapshot());
+
+        branch.restore(&snapshot).unwrap();
+        for i in 51..=100 {
+            let t = 3.0 + 0.02 * i as f64;
+            let a = origin.update(control, t).unwrap();
+            let b = branch.update(control, t).unwrap();
+            assert_eq!(a.state, b.state);
+            assert_eq!(a.state_extend, b.state_extend);
+        }
+        assert_eq!(origin.snapshot(), branch.snapshot());
+
+        let mut broken = snapshot.clone();
+        broken.actuators.pop();
+        let res = branch.restore(&broken);
+        assert!(matches!(res, Err(FatalCoreError::InvalidInput(_))));
+
+        origin.delete_model();
+        test_core_fin(model)
+    }
+
     #[test]
     fn test_plane() {
         let (model, result) = test_core_init();
@@ -0,0 +1,374 @@
+use crate::utils::error::FatalCoreError;
+use serde::{Deserialize, Serialize};
+
+/// specific gas constant of air in ft lbf / (slug °R)
+pub const GAS_CONSTANT: f64 = 1716.49;
+/// ratio of specific heats of air
+pub const GAMMA: f64 = 1.4;
+/// sea level standard temperature in °R
+pub const SEA_LEVEL_TEMPERATURE: f64 = 288.15 * RANKINE;
+/// sea level standard pressure in lbf/ft^2
+pub const SEA_LEVEL_PRESSURE: f64 = 101325.0 * PASCAL;
+/// sea level standard density in slug/ft^3
+pub const SEA_LEVEL_DENSITY: f64 = 1.225 * KG_PER_M3;
+/// sea level standard speed of sound in ft/s
+pub const SEA_LEVEL_SPEED_OF_SOUND: f64 = 340.294 / FT;
+
+const FT: f64 = 0.3048;
+/// lbf/ft^2 per Pa
+const PASCAL: f64 = 0.020885434;
+/// slug/ft^3 per kg/m^3
+const KG_PER_M3: f64 = 0.00194032;
+/// °R per K
+const RANKINE: f64 = 1.8;
+
+/// Properties of still air at one altitude
+/// temperature: °R, pressure: lbf/ft^2, density: slug/ft^3,
+/// speed_of_sound: ft/s, viscosity: dynamic viscosity in lbf s/ft^2
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct AirProperties {
+    pub temperature: f64,
+    pub pressure: f64,
+    pub density: f64,
+    pub speed_of_sound: f64,
+    pub viscosity: f64,
+}
+
+impl AirProperties {
+    /// The properties of a perfect gas at `temperature` (°R) and `pressure` (lbf/ft^2)
+    pub fn new(temperature: f64, pressure: f64) -> Self {
+        Self {
+            temperature,
+            pressure,
+            density: pressure / (GAS_CONSTANT * temperature),
+            speed_of_sound: (GAMMA * GAS_CONSTANT * temperature).sqrt(),
+            viscosity: sutherland(temperature),
+        }
+    }
+}
+
+/// Sutherland's law, `temperature` in °R, return lbf s/ft^2
+pub fn sutherland(temperature: f64) -> f64 {
+    let t = temperature / RANKINE;
+    1.458e-6 * t.powf(1.5) / (t + 110.4) * PASCAL
+}
+
+/// The air a plane flies through and the air data it measures
+/// mach, qbar (lbf/ft^2) and the true, calibrated and equivalent airspeeds (ft/s)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct AirData {
+    pub air: AirProperties,
+    pub mach: f64,
+    pub qbar: f64,
+    pub tas: f64,
+    pub cas: f64,
+    pub eas: f64,
+}
+
+/// Total to static pressure ratio seen by a pitot tube at `mach`,
+/// behind a normal shock when supersonic
+pub(crate) fn pitot_ratio(mach: f64) -> f64 {
+    let m2 = mach * mach;
+    if mach <= 1.0 {
+        (1.0 + 0.2 * m2).powf(3.5)
+    } else {
+        166.92158 * mach.powi(7) / (7.0 * m2 - 1.0).powf(2.5)
+    }
+}
+
+/// The Mach number whose pitot pressure ratio is `ratio`
+fn pitot_mach(ratio: f64) -> f64 {
+    if ratio <= pitot_ratio(1.0) {
+        return (5.0 * (ratio.powf(1.0 / 3.5) - 1.0)).max(0.0).sqrt();
+    }
+    let (mut low, mut high) = (1.0, 2.0);
+    while pitot_ratio(high) < ratio {
+        high *= 2.0;
+    }
+    for _ in 0..100 {
+        let mid = 0.5 * (low + high);
+        if pitot_ratio(mid) < ratio {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+    0.5 * (low + high)
+}
+
+/// A model of the atmosphere, altitudes are geometric in ft
+pub trait Atmosphere: Send + Sync {
+    fn properties(&self, altitude: f64) -> AirProperties;
+
+    /// Air data at `altitude` for a true airspeed `velocity` in ft/s
+    fn air_data(&self, altitude: f64, velocity: f64) -> AirData {
+        let air = self.properties(altitude);
+        let mach = velocity / air.speed_of_sound;
+        let qbar = 0.5 * air.density * velocity.powi(2);
+
+        let impact = air.pressure * (pitot_ratio(mach) - 1.0);
+        let cas = SEA_LEVEL_SPEED_OF_SOUND * pitot_mach(impact / SEA_LEVEL_PRESSURE + 1.0);
+        let eas = velocity * (air.density / SEA_LEVEL_DENSITY).sqrt();
+
+        AirData {
+            air,
+            mach,
+            qbar,
+            tas: velocity,
+            cas,
+            eas,
+        }
+    }
+}
+
+/// The two-piece approximation of Stevens and Lewis,
+/// a linear temperature below 35,000 ft and 390 °R above it, the default of `MechanicalModel`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SimpleAtmosphere;
+
+impl Atmosphere for SimpleAtmosphere {
+    fn properties(&self, altitude: f64) -> AirProperties {
+        let rho0 = 2.377e-3;
+        let tfac = 1.0 - 0.703e-5 * altitude;
+
+        let mut temp = 519.0 * tfac;
+        if altitude >= 35000.0 {
+            temp = 390.0;
+        }
+
+        let rho = rho0 * tfac.powf(4.14);
+        let mut ps = 1715.0 * rho * temp;
+
+        if ps.abs() < 1.0e-6 {
+            ps = 1715.0;
+        }
+
+        AirProperties {
+            temperature: temp,
+            pressure: ps,
+            density: rho,
+            speed_of_sound: (1.4 * 1716.3 * temp).sqrt(),
+            viscosity: sutherland(temp),
+        }
+    }
+}
+
+/// effective earth radius of the geopotential altitude in m
+const GEOPOTENTIAL_RADIUS: f64 = 6356766.0;
+/// base geopotential altitude (m) and lapse rate (K/m) of the layers
+const LAYERS: [(f64, f64); 7] = [
+    (0.0, -0.0065),
+    (11000.0, 0.0),
+    (20000.0, 0.001),
+    (32000.0, 0.0028),
+    (47000.0, 0.0),
+    (51000.0, -0.0028),
+    (71000.0, -0.002),
+];
+/// the top of the model, 86 km geometric
+const TOP: f64 = 84852.0;
+
+/// US Standard Atmosphere 1976 from below sea level to 86 km,
+/// isothermal above it, optionally shifted by a temperature offset
+/// temperature_offset: in K (or °C), hot days are positive,
+/// the pressure stays standard and the density follows the temperature
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StandardAtmosphere {
+    temperature_offset: f64,
+}
+
+impl StandardAtmosphere {
+    pub fn new(temperature_offset: f64) -> Self {
+        Self { temperature_offset }
+    }
+
+    pub fn temperature_offset(&self) -> f64 {
+        self.temperature_offset
+    }
+
+    /// temperature (K) and pressure (Pa) of the standard day at geopotential `h` in m
+    fn standard(h: f64) -> (f64, f64) {
+        let g0_r = 9.80665 / 287.053;
+        let (mut t_base, mut p_base) = (288.15, 101325.0);
+        let h = h.min(TOP);
+        for (i, &(h_base, lapse)) in LAYERS.iter().enumerate() {
+            let h_top = LAYERS.get(i + 1).map_or(TOP, |l| l.0);
+            let dh = if i == 0 {
+                h.min(h_top) - h_base
+            } else {
+                h.clamp(h_base, h_top) - h_base
+            };
+            let t = t_base + lapse * dh;
+            let p = if lapse == 0.0 {
+                p_base * (-g0_r * dh / t_base).exp()
+            } else {
+                p_base * (t / t_base).powf(-g0_r / lapse)
+            };
+            if h <= h_top {
+                return (t, p);
+            }
+            t_base = t;
+            p_base = p;
+        }
+        (t_base, p_base)
+    }
+}
+
+impl Atmosphere for StandardAtmosphere {
+    fn properties(&self, altitude: f64) -> AirProperties {
+        let z = altitude * FT;
+        let h = GEOPOTENTIAL_RADIUS * z / (GEOPOTENTIAL_RADIUS + z);
+        let (t, mut p) = Self::standard(h);
+        if h > TOP {
+            // isothermal above 86 km
+            p *= (-9.80665 / 287.053 * (h - TOP) / t).exp();
+        }
+        AirProperties::new((t + self.temperature_offset) * RANKINE, p * PASCAL)
+    }
+}
+
+/// An atmosphere tabulated against altitude, the temperature is interpolated linearly
+/// and the pressure exponentially, both are held beyond the ends of the table
+/// altitude: ft, strictly increasing, temperature: °R, pressure: lbf/ft^2
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TabulatedAtmosphere {
+    altitude: Vec<f64>,
+    temperature: Vec<f64>,
+    pressure: Vec<f64>,
+}
+
+impl TabulatedAtmosphere {
+    pub fn new(
+        altitude: Vec<f64>,
+        temperature: Vec<f64>,
+        pressure: Vec<f64>,
+    ) -> Result<Self, FatalCoreError> {
+        if altitude.is_empty()
+            || altitude.len() != temperature.len()
+            || altitude.len() != pressure.len()
+        {
+            return Err(FatalCoreError::InvalidInput(format!(
+                "atmosphere table needs as many temperatures ({}) and pressures ({}) as altitudes ({}), at least one",
+                temperature.len(),
+                pressure.len(),
+                altitude.len()
+            )));
+        }
+        if altitude.windows(2).any(|w| w[1] <= w[0]) {
+            return Err(FatalCoreError::InvalidInput(
+                "atmosphere table altitudes must be strictly increasing".to_string(),
+            ));
+        }
+        if temperature.iter().chain(pressure.iter()).any(|x| *x <= 0.0) {
+            return Err(FatalCoreError::InvalidInput(
+                "atmosphere table temperatures and pressures must be positive".to_string(),
+            ));
+        }
+        Ok(Self {
+            altitude,
+            temperature,
+            pressure,
+        })
+    }
+}
+
+impl Atmosphere for TabulatedAtmosphere {
+    fn properties(&self, altitude: f64) -> AirProperties {
+        let n = self.altitude.len();
+        if altitude <= self.altitude[0] || n == 1 {
+            return AirProperties::new(self.temperature[0], self.pressure[0]);
+        }
+        if altitude >= self.altitude[n - 1] {
+            return AirProperties::new(self.temperature[n - 1], self.pressure[n - 1]);
+        }
+        let i = self.altitude.partition_point(|a| *a <= altitude) - 1;
+        let s = (altitude - self.altitude[i]) / (self.altitude[i + 1] - self.altitude[i]);
+        let t = self.temperature[i] + s * (self.temperature[i + 1] - self.temperature[i]);
+        let p = self.pressure[i] * (self.pressure[i + 1] / self.pressure[i]).powf(s);
+        AirProperties::new(t, p)
+    }
+}
+
+#[cfg(test)]
+mod atmosphere_tests {
+    use super::*;
+
+    fn close(a: f64, b: f64, tol: f64) -> bool {
+        (a - b).abs() <= tol * b.abs()
+    }
+
+    #[test]
+    fn test_standard() {
+        let atmosphere = StandardAtmosphere::default();
+        let sea_level = atmosphere.properties(0.0);
+        assert!(close(sea_level.temperature, 518.67, 1e-6));
+        assert!(close(sea_level.pressure, 2116.22, 1e-5));
+        assert!(close(sea_level.density, 0.0023769, 1e-4));
+        assert!(close(sea_level.speed_of_sound, 1116.45, 1e-4));
+        assert!(close(sea_level.viscosity, 3.737e-7, 1e-3));
+
+        // tabulated values of the 1976 standard, geometric altitudes in m
+        for (z, t, p, rho) in [
+            (11000.0, 216.774, 22699.9, 0.364801),
+            (20000.0, 216.65, 5529.3, 0.0889097),
+            (32000.0, 228.49, 889.06, 0.0135551),
+            (50000.0, 270.65, 79.779, 0.00102688),
+            (80000.0, 198.639, 1.0525, 1.8458e-5),
+        ] {
+            let air = atmosphere.properties(z / FT);
+            assert!(close(air.temperature / RANKINE, t, 1e-4), "{z}");
+            assert!(close(air.pressure / PASCAL, p, 1e-3), "{z}");
+            assert!(close(air.density / KG_PER_M3, rho, 2e-3), "{z}");
+        }
+
+        let hot = StandardAtmosphere::new(20.0).properties(5000.0);
+        let standard = atmosphere.properties(5000.0);
+        assert_eq!(hot.pressure, standard.pressure);
+        assert!(close(hot.temperature - standard.temperature, 36.0, 1e-9));
+        assert!(hot.density < standard.density);
+    }
+
+    #[test]
+    fn test_air_data() {
+        let atmosphere = StandardAtmosphere::default();
+        // at sea level the calibrated and equivalent airspeeds are the true airspeed
+        let data = atmosphere.air_data(0.0, 500.0);
+        assert!(close(data.cas, 500.0, 1e-4));
+        assert!(close(data.eas, 500.0, 1e-4));
+        assert!(close(data.mach, 500.0 / 1116.45, 1e-4));
+
+        // at altitude CAS is a bit above EAS, both well below TAS
+        let data = atmosphere.air_data(30000.0, 800.0);
+        assert!(data.cas > data.eas);
+        assert!(data.cas < 0.7 * data.tas);
+
+        // supersonic at sea level
+        let data = atmosphere.air_data(0.0, 1500.0);
+        assert!(close(data.cas, 1500.0, 1e-4));
+    }
+
+    #[test]
+    fn test_tabulated() {
+        let res = TabulatedAtmosphere::new(vec![0.0, 0.0], vec![500.0, 490.0], vec![2000.0, 1.0]);
+        assert!(matches!(res, Err(FatalCoreError::InvalidInput(_))));
+
+        let standard = StandardAtmosphere::default();
+        let altitude: Vec<f64> = (0..=10).map(|i| 5000.0 * i as f64).collect();
+        let temperature = altitude
+            .iter()
+            .map(|h| standard.properties(*h).temperature)
+            .collect();
+        let pressure = altitude
+            .iter()
+            .map(|h| standard.properties(*h).pressure)
+            .collect();
+        let table = TabulatedAtmosphere::new(altitude, temperature, pressure).unwrap();
+        // the 5000 ft table only cuts the corner at the tropopause
+        for h in [0.0, 12345.0, 36000.0, 49999.0] {
+            let a = table.properties(h);
+            let b = standard.properties(h);
+            assert!(close(a.density, b.density, 1e-2), "{h}");
+        }
+        assert_eq!(table.properties(-100.0), table.properties(0.0));
+    }
+}
@@ -0,0 +1,313 @@
+use crate::components::flight::G;
+use crate::model::{wind_to_body, Control, CoreOutput, State};
+use crate::runner::DiscreteController;
+use crate::utils::error::FatalCoreError;
+use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
+
+/// What the pitch channel holds, ft, ft/s and rad
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum VerticalMode {
+    Altitude(f64),
+    VerticalSpeed(f64),
+    FlightPathAngle(f64),
+}
+
+/// What the roll channel holds, rad from north, the track is the course over the ground
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum LateralMode {
+    Heading(f64),
+    Track(f64),
+}
+
+/// What the autothrottle holds, true airspeed in ft/s or Mach
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum SpeedMode {
+    Airspeed(f64),
+    Mach(f64),
+}
+
+/// Gains and limits of the autopilot, surfaces in deg, angles in deg, rates in deg/s
+/// k_h: climb rate (ft/s) per ft of altitude error, max_vertical_speed: ft/s
+/// k_gamma, k_gamma_i: proportional (deg/deg) and integral (deg/deg/s) elevator on the
+/// flight path angle error, k_q: pitch damper (deg/(deg/s))
+/// k_psi: bank per deg of heading error, bank_limit: deg, k_phi: aileron per deg of bank
+/// error, k_p: roll damper, k_beta: rudder per deg of sideslip, k_r: yaw damper on the yaw
+/// rate beyond the one of a coordinated turn
+/// k_v, k_v_i: proportional (1/(ft/s)) and integral (1/ft) thrust on the airspeed error,
+/// as fractions of the thrust range
+/// sample_time: the frame of the autopilot in s
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct AutopilotGains {
+    pub k_h: f64,
+    pub max_vertical_speed: f64,
+    pub k_gamma: f64,
+    pub k_gamma_i: f64,
+    pub k_q: f64,
+    pub k_psi: f64,
+    pub bank_limit: f64,
+    pub k_phi: f64,
+    pub k_p: f64,
+    pub k_beta: f64,
+    pub k_r: f64,
+    pub k_v: f64,
+    pub k_v_i: f64,
+    pub sample_time: f64,
+}
+
+impl Default for AutopilotGains {
+    fn default() -> Self {
+        Self::f16()
+    }
+}
+
+impl AutopilotGains {
+    /// Gentle outer loops for the bare F-16, 30 deg of bank, 3000 ft/min and a 50 Hz frame
+    pub fn f16() -> Self {
+        Self {
+            k_h: 0.1,
+            max_vertical_speed: 50.0,
+            k_gamma: 2.0,
+            k_gamma_i: 0.5,
+            k_q: 1.0,
+            k_psi: 3.0,
+            bank_limit: 30.0,
+            k_phi: 0.3,
+            k_p: 0.1,
+            k_beta: 1.0,
+            k_r: 1.0,
+            k_v: 0.005,
+            k_v_i: 0.0002,
+            sample_time: 0.02,
+        }
+    }
+}
+
+/// Angle in (-pi, pi]
+fn wrap(angle: f64) -> f64 {
+    let angle = (angle + PI).rem_euclid(2.0 * PI) - PI;
+    if angle == -PI {
+        PI
+    } else {
+        angle
+    }
+}
+
+/// Velocity [north, east, down] in ft/s of `velocity` at `alpha` and `beta`
+/// in the attitude of `state`
+fn earth_velocity(state: &State, velocity: f64, alpha: f64, beta: f64) -> [f64; 3] {
+    let [u, v, w] = wind_to_body(velocity, alpha, beta);
+    let (sphi, cphi) = state.phi.sin_cos();
+    let (stheta, ctheta) = state.theta.sin_cos();
+    let (spsi, cpsi) = state.psi.sin_cos();
+    [
+        u * ctheta * cpsi
+            + v * (sphi * stheta * cpsi - cphi * spsi)
+            + w * (cphi * stheta * cpsi + sphi * spsi),
+        u * ctheta * spsi
+            + v * (sphi * stheta * spsi + cphi * cpsi)
+            + w * (cphi * stheta * spsi - sphi * cpsi),
+        -u * stheta + v * sphi * ctheta + w * cphi * ctheta,
+    ]
+}
+
+/// Outer loops flying the plane through its surfaces and thrust
+/// each channel is engaged on its own, a channel without a mode passes the reference
+/// through, so the reference should be the trim the loops work around
+/// pitch: altitude, vertical speed or flight path angle through the elevator,
+/// roll: heading or track through a bank command with the rudder coordinating the turn,
+/// speed: airspeed or Mach through the thrust
+/// the thrust command is kept in the thrust range, lbf by default, [0, 1] for a plane
+/// with an engine
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Autopilot {
+    pub gains: AutopilotGains,
+    pub vertical: Option<VerticalMode>,
+    pub lateral: Option<LateralMode>,
+    pub speed: Option<SpeedMode>,
+    thrust_range: [f64; 2],
+    /// integrals of the flight path angle (deg of elevator) and airspeed errors
+    /// (fraction of the thrust range)
+    integrators: [f64; 2],
+    last_time: Option<f64>,
+}
+
+impl Default for Autopilot {
+    fn default() -> Self {
+        Self::new(AutopilotGains::f16())
+    }
+}
+
+impl Autopilot {
+    pub fn new(gains: AutopilotGains) -> Self {
+        Self {
+            gains,
+            vertical: None,
+            lateral: None,
+            speed: None,
+            thrust_range: [1000.0, 19000.0],
+            integrators: [0.0; 2],
+            last_time: None,
+        }
+    }
+
+    pub fn f16() -> Self {
+        Self::default()
+    }
+
+    pub fn with_vertical(mut self, vertical: VerticalMode) -> Self {
+        self.vertical = Some(vertical);
+        self
+    }
+
+    pub fn with_lateral(mut self, lateral: LateralMode) -> Self {
+        self.lateral = Some(lateral);
+        self
+    }
+
+    pub fn with_speed(mut self, speed: SpeedMode) -> Self {
+        self.speed = Some(speed);
+        self
+    }
+
+    /// The thrust the autothrottle may command, [0, 1] for the throttle of an engine
+    pub fn with_thrust_range(mut self, bottom: f64, top: f64) -> Result<Self, FatalCoreError> {
+        if bottom.is_nan() || top.is_nan() || top <= bottom {
+            return Err(FatalCoreError::InvalidInput(format!(
+                "thrust range needs bottom < top, got [{bottom}, {top}]"
+            )));
+        }
+        self.thrust_range = [bottom, top];
+        Ok(self)
+    }
+
+    pub fn thrust_range(&self) -> [f64; 2] {
+        self.thrust_range
+    }
+
+    /// Empty the integrators, the modes stay engaged
+    pub fn reset(&mut self) {
+        self.integrators = [0.0; 2];
+        self.last_time = None;
+    }
+
+    /// The command at `t` for a plane at `output` around `reference`
+    pub fn step(&mut self, t: f64, output: &CoreOutput, reference: &Control) -> Control {
+        let g = self.gains;
+        let dt = self.last_time.map_or(0.0, |last| (t - last).max(0.0));
+        self.last_time = Some(t);
+
+        let state = &output.state;
+        // the loops fly the plane through the air, only the track is over the ground
+        let air = output.air_relative();
+        let velocity = air.velocity.max(1.0);
+        let [_, _, down] = earth_velocity(state, air.velocity, air.alpha, air.beta);
+        let [north, east, _] = earth_velocity(state, state.velocity, state.alpha, state.beta);
+        let mut command = *reference;
+
+        if let Some(vertical) = self.vertical {
+            let climb = |vs: f64| (vs / velocity).clamp(-1.0, 1.0).asin().to_degrees();
+            let max_vs = g.max_vertical_speed;
+            let gamma_command = match vertical {
+                VerticalMode::Altitude(h) => {
+                    climb((g.k_h * (h - state.altitude)).clamp(-max_vs, max_vs))
+                }
+                VerticalMode::VerticalSpeed(vs) => climb(vs),
+                VerticalMode::FlightPathAngle(gamma) => gamma.to_degrees(),
+            };
+            let gamma = (-down / velocity).clamp(-1.0, 1.0).asin().to_degrees();
+            let error = gamma_command - gamma;
+            self.integrators[0] =
+                (self.integrators[0] + g.k_gamma_i * error * dt).clamp(-10.0, 10.0);
+            // positive elevator pitches the nose down
+            command.elevator = reference.elevator - g.k_gamma * error - self.integrators[0]
+                + g.k_q * state.q.to_degrees();
+        }
+
+        if let Some(lateral) = self.lateral {
+            let error = match lateral {
+                LateralMode::Heading(psi) => wrap(psi - state.psi),
+                LateralMode::Track(track) => wrap(track - east.atan2(north)),
+            };
+            let bank = (g.k_psi * error.to_degrees()).clamp(-g.bank_limit, g.bank_limit);
+            // positive aileron rolls left, positive rudder yaws left
+            command.aileron = reference.aileron
+                + g.k_phi * (state.phi.to_degrees() - bank)
+                + g.k_p * state.p.to_degrees();
+            let turn_rate = G / velocity * state.phi.sin() * state.theta.cos();
+            command.rudder = reference.rudder - g.k_beta * air.beta.to_degrees()
+                + g.k_r * (state.r - turn_rate).to_degrees();
+        }
+
+        if let Some(speed) = self.speed {
+            let error = match speed {
+                SpeedMode::Airspeed(v) => v - air.velocity,
+                SpeedMode::Mach(mach) => {
+                    let speed_of_sound = air.velocity / output.state_extend.mach.max(1e-3);
+                    (mach - output.state_extend.mach) * speed_of_sound
+                }
+            };
+            let [bottom, top] = self.thrust_range;
+            let span = top - bottom;
+            self.integrators[1] = (self.integrators[1] + g.k_v_i * error * dt).clamp(-1.0, 1.0);
+            command.thrust = (reference.thrust + span * (g.k_v * error + self.integrators[1]))
+                .clamp(bottom, top);
+        }
+        command
+    }
+}
+
+impl DiscreteController for Autopilot {
+    fn sample_time(&self) -> f64 {
+        self.gains.sample_time
+    }
+
+    fn sample(
+        &mut self,
+        t: f64,
+        output: &CoreOutput,
+        reference: &Control,
+    ) -> Result<Control, FatalCoreError> {
+        Ok(self.step(t, output, reference))
+    }
+}
+
+#[cfg(test)]
+mod autopilot_tests {
+    use super::*;
+
+    #[test]
+    fn test_autopilot() {
+        assert!((wrap(1.5 * PI) + 0.5 * PI).abs() < 1e-12);
+        assert_eq!(wrap(-PI), PI);
+        let state = State {
+            psi: 0.5,
+            velocity: 500.0,
+            ..Default::default()
+        };
+        let [north, east, down] = earth_velocity(&state, state.velocity, state.alpha, state.beta);
+        assert!((east.atan2(north) - 0.5).abs() < 1e-12);
+        assert!(down.abs() < 1e-12);
+
+        // nothing engaged, the reference passes through
+        let trim = Control::from([3000.0, -2.0, 0.1, 0.2]);
+        let output = CoreOutput::new(state, trim, Default::default());
+        let mut autopilot = Autopilot::f16();
+        let command: [f64; 4] = autopilot.step(0.0, &output, &trim).into();
+        assert_eq!(command, [3000.0, -2.0, 0.1, 0.2]);
+
+        // below the altitude, right of the heading, slow: nose up, roll left, more thrust
+        let mut state = state;
+        state.altitude = 15000.0;
+        let output = CoreOutput::new(state, trim, Default::default());
+        let mut autopilot = autopilot
+            .with_vertical(VerticalMode::Altitude(16000.0))
+            .with_lateral(LateralMode::Heading(0.0))
+            .with_speed(SpeedMode::Airspeed(550.0));
+        let command = autopilot.step(0.0, &output, &trim);
+        assert!(command.elevator < -2.0);
+        assert!(command.aileron > 0.1);
+        assert!(command.thrust > 3000.0);
+        assert!(command.thrust <= 19000.0);
+    }
+}
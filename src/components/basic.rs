@@ -1,7 +1,12 @@
 use crate::{
-    solver::ODESolver,
-    utils::{Matrix, Vector},
+    components::control::{mul, Block, StateSpace},
+    linearize::{Linearization, ACTUATORS, EXTEND, INPUTS, STATES},
+    model::{Control, CoreOutput, State, StateExtend},
+    solver::{ODESolver, VectorODESolver},
+    utils::{error::FatalCoreError, Matrix, Vector},
 };
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 
 pub fn step(init: f64, end: f64, step_time: f64, t: f64) -> f64 {
     if t < step_time {
@@ -11,38 +16,235 @@ pub fn step(init: f64, end: f64, step_time: f64, t: f64) -> f64 {
     }
 }
 
-// pub struct LinearModel<S: ODESolver> {
-//     solver: S,
-//     a: Matrix,
-//     b: Matrix,
-//     c: Matrix,
-//     d: Matrix,
-// }
-
-// impl<S> LinearModel<S>
-// where
-//     S: ODESolver,
-// {
-//     pub fn new(a: Matrix, b: Matrix, c: Matrix, d: Matrix) -> Self {
-//         Self { a, b, c, d }
-//     }
-
-//     pub fn a(&self) -> &Matrix {
-//         &self.a
-//     }
-
-//     pub fn b(&self) -> &Matrix {
-//         &self.b
-//     }
-
-//     pub fn c(&self) -> &Matrix {
-//         &self.c
-//     }
-
-//     pub fn d(&self) -> &Matrix {
-//         &self.d
-//     }
-// }
+/// Everything a `LinearModel` needs to resume from where it was
+/// state: the deviation of the states from the operating point,
+/// control: the command of the last update
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LinearModelSnapshot {
+    pub state: Vec<f64>,
+    pub control: Control,
+    pub last_time: Option<f64>,
+}
+
+/// A linear time-invariant plane x' = A x + B u, y = C x + D u in deviations from the
+/// operating point of a `Linearization`, driven and read like a `PlaneBlock`
+/// the inputs are taken from the `Control` by name, the states and outputs fill the
+/// `State`, the actuator positions and the `StateExtend` of the output by name,
+/// the control is the command unless the actuators are states,
+/// what the model carries neither as output nor as state stays zero
+#[derive(Debug, Clone)]
+pub struct LinearModel<S: ODESolver + VectorODESolver> {
+    model: Linearization,
+    system: StateSpace<S>,
+    /// the channel of the `Control` behind each input
+    channels: Vec<usize>,
+    control: Control,
+    output: CoreOutput,
+    state_dot: State,
+}
+
+impl<S> LinearModel<S>
+where
+    S: ODESolver + VectorODESolver,
+{
+    /// At rest at the operating point
+    pub fn new(solver: Arc<S>, model: Linearization) -> Result<Self, FatalCoreError> {
+        let channels = model
+            .inputs
+            .iter()
+            .map(|name| {
+                INPUTS.iter().position(|c| c == name).ok_or_else(|| {
+                    FatalCoreError::InvalidInput(format!(
+                        "linear model input {name} is none of {INPUTS:?}"
+                    ))
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if model.state.len() != model.states.len()
+            || model.input.len() != model.inputs.len()
+            || model.output.len() != model.outputs.len()
+        {
+            return Err(FatalCoreError::InvalidInput(
+                "linear model operating point does not match its names".to_string(),
+            ));
+        }
+        let system = StateSpace::new(
+            solver,
+            model.a.clone(),
+            model.b.clone(),
+            model.c.clone(),
+            model.d.clone(),
+        )?;
+        let mut control = Control::default();
+        let mut command: [f64; 4] = control.into();
+        for (i, channel) in channels.iter().enumerate() {
+            command[*channel] = model.input[i];
+        }
+        control = Control::from(command);
+
+        let mut linear = Self {
+            model,
+            system,
+            channels,
+            control,
+            output: CoreOutput::new(State::default(), control, StateExtend::default()),
+            state_dot: State::default(),
+        };
+        linear.refresh();
+        Ok(linear)
+    }
+
+    /// Start from `state`, in the order of the states of the linearization
+    pub fn with_state(mut self, state: &[f64]) -> Result<Self, FatalCoreError> {
+        if state.len() != self.model.states.len() {
+            return Err(FatalCoreError::InvalidInput(format!(
+                "linear model has {} states, got {}",
+                self.model.states.len(),
+                state.len()
+            )));
+        }
+        let deviation: Vec<f64> = state
+            .iter()
+            .zip(&self.model.state)
+            .map(|(x, x_0)| x - x_0)
+            .collect();
+        self.system = self.system.with_state(&deviation)?;
+        self.refresh();
+        Ok(self)
+    }
+
+    pub fn a(&self) -> &Matrix {
+        &self.model.a
+    }
+
+    pub fn b(&self) -> &Matrix {
+        &self.model.b
+    }
+
+    pub fn c(&self) -> &Matrix {
+        &self.model.c
+    }
+
+    pub fn d(&self) -> &Matrix {
+        &self.model.d
+    }
+
+    pub fn linearization(&self) -> &Linearization {
+        &self.model
+    }
+
+    /// Advance the model to `t` with `control` held since the last update
+    pub fn update(
+        &mut self,
+        control: impl Into<Control>,
+        t: f64,
+    ) -> Result<CoreOutput, FatalCoreError> {
+        let control = control.into();
+        let input = self.deviation(&control);
+        self.system.update_vector(&input, t)?;
+        self.control = control;
+        self.refresh();
+        Ok(self.output)
+    }
+
+    /// Back at the initial state, the next update starts the clock again
+    pub fn reset(&mut self) {
+        Block::reset(&mut self.system);
+        self.refresh();
+    }
+
+    /// The states of the linearization, not their deviations
+    pub fn states(&self) -> Vec<f64> {
+        self.system
+            .state()
+            .iter()
+            .zip(&self.model.state)
+            .map(|(x, x_0)| x + x_0)
+            .collect()
+    }
+
+    pub fn state(&self) -> CoreOutput {
+        self.output
+    }
+
+    pub fn state_dot(&self) -> State {
+        self.state_dot
+    }
+
+    /// The time of the last update, None before the first one
+    pub fn last_time(&self) -> Option<f64> {
+        self.system.last_time()
+    }
+
+    pub fn snapshot(&self) -> LinearModelSnapshot {
+        LinearModelSnapshot {
+            state: self.system.state().to_vec(),
+            control: self.control,
+            last_time: self.system.last_time(),
+        }
+    }
+
+    pub fn restore(&mut self, snapshot: &LinearModelSnapshot) -> Result<(), FatalCoreError> {
+        self.system.set_state(&snapshot.state, snapshot.last_time)?;
+        self.control = snapshot.control;
+        self.refresh();
+        Ok(())
+    }
+
+    /// u - u_0 for the inputs of the model
+    fn deviation(&self, control: &Control) -> Vec<f64> {
+        let command: [f64; 4] = (*control).into();
+        self.channels
+            .iter()
+            .zip(&self.model.input)
+            .map(|(channel, u_0)| command[*channel] - u_0)
+            .collect()
+    }
+
+    /// The output and the state derivative at the state and control of now
+    fn refresh(&mut self) {
+        let m = &self.model;
+        let x = self.system.state();
+        let u = self.deviation(&self.control);
+        let add = |a: Vec<f64>, b: Vec<f64>| -> Vec<f64> {
+            a.iter().zip(b).map(|(a, b)| a + b).collect()
+        };
+        let x_dot = add(mul(&m.a, x), mul(&m.b, &u));
+        let y = add(mul(&m.c, x), mul(&m.d, &u));
+
+        // an output first, a state then, zero else
+        let value = |name: &str| -> Option<f64> {
+            if let Some(i) = m.outputs.iter().position(|n| n == name) {
+                return Some(m.output[i] + y[i]);
+            }
+            m.states
+                .iter()
+                .position(|n| n == name)
+                .map(|i| m.state[i] + x[i])
+        };
+        let state: Vec<f64> = STATES.iter().map(|n| value(n).unwrap_or(0.0)).collect();
+        let extend: Vec<f64> = EXTEND.iter().map(|n| value(n).unwrap_or(0.0)).collect();
+        let command: [f64; 4] = self.control.into();
+        let control: Vec<f64> = ACTUATORS
+            .iter()
+            .zip(command)
+            .map(|(n, c)| value(n).unwrap_or(c))
+            .collect();
+        let rate = |name: &&str| {
+            m.states
+                .iter()
+                .position(|n| n == name)
+                .map_or(0.0, |i| x_dot[i])
+        };
+
+        self.output = CoreOutput::new(
+            State::from(&state[..]),
+            Control::from(&control[..]),
+            StateExtend::from(&extend[..]),
+        );
+        self.state_dot = State::from(&STATES.iter().map(rate).collect::<Vec<_>>()[..]);
+    }
+}
 
 #[deprecated]
 #[derive(Clone)]
@@ -159,11 +361,76 @@ impl VectorIntegrator {
 
 #[cfg(test)]
 mod components_tests {
-    use super::{Integrator, VectorIntegrator};
-    use crate::utils::test_logger_init;
+    use super::{Integrator, LinearModel, VectorIntegrator};
+    use crate::linearize::Linearization;
+    use crate::model::Control;
+    use crate::solver::rk::RK4Solver;
+    use crate::utils::{test_logger_init, Matrix};
     use log::{info, trace};
+    use std::sync::Arc;
     use std::time::{Duration, SystemTime};
 
+    #[test]
+    fn test_linear_model() {
+        let names = |n: &[&str]| n.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        let model = Linearization {
+            states: names(&["velocity", "q"]),
+            inputs: names(&["elevator"]),
+            outputs: names(&["q", "nz"]),
+            a: Matrix::from(vec![vec![-1.0, 0.0], vec![0.0, -2.0]]),
+            b: Matrix::from(vec![vec![0.0], vec![1.0]]),
+            c: Matrix::from(vec![vec![0.0, 1.0], vec![0.0, 3.0]]),
+            d: Matrix::from(vec![vec![0.0], vec![0.5]]),
+            state: vec![500.0, 0.0],
+            input: vec![-2.0],
+            output: vec![0.0, 1.0],
+        };
+        let solver = Arc::new(RK4Solver::new(0.01));
+        let mut linear = LinearModel::new(solver.clone(), model.clone()).unwrap();
+
+        // at rest at the operating point
+        let output = linear
+            .update(Control::from([3000.0, -2.0, 0.0, 0.0]), 0.0)
+            .unwrap();
+        assert_eq!(output.state.velocity, 500.0);
+        assert_eq!(output.state_extend.nz, 1.0);
+        assert_eq!(output.control.thrust, 3000.0);
+        assert_eq!(linear.state_dot().q, 0.0);
+
+        // one degree more elevator, q settles at 0.5
+        let step = Control::from([3000.0, -1.0, 0.0, 0.0]);
+        linear.update(step, 1.0).unwrap();
+        let snapshot = linear.snapshot();
+        let output = linear.update(step, 10.0).unwrap();
+        assert!((output.state.q - 0.5).abs() < 1e-6);
+        assert!((output.state_extend.nz - 3.0).abs() < 1e-6);
+        assert_eq!(output.control.elevator, -1.0);
+        assert!(linear.state_dot().q.abs() < 1e-6);
+        assert!((linear.states()[1] - 0.5).abs() < 1e-6);
+
+        linear.restore(&snapshot).unwrap();
+        let again = linear.update(step, 10.0).unwrap();
+        assert_eq!(again.state.q, output.state.q);
+        assert!(linear.update(step, 5.0).is_err());
+        linear.reset();
+        assert_eq!(linear.state().state.q, 0.0);
+
+        let start = LinearModel::new(solver.clone(), model.clone())
+            .unwrap()
+            .with_state(&[510.0, 0.0])
+            .unwrap();
+        assert_eq!(start.state().state.velocity, 510.0);
+        assert!((start.state_dot().velocity + 10.0).abs() < 1e-12);
+        let short = LinearModel::new(solver.clone(), model.clone()).unwrap();
+        assert!(short.with_state(&[510.0]).is_err());
+        let long = LinearModel::new(solver.clone(), model.clone()).unwrap();
+        assert!(long.with_state(&[510.0, 0.0, 1.0]).is_err());
+
+        let mut flap = model;
+        flap.inputs = names(&["flap"]);
+        assert!(LinearModel::new(solver, flap).is_err());
+    }
+
     #[test]
     fn test_integrator() {
         test_logger_init();
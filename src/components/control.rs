@@ -0,0 +1,848 @@
+use crate::{
+    solver::{substeps, ODESolver, VectorODESolver},
+    utils::{error::FatalCoreError, Matrix, Vector},
+};
+use std::{collections::VecDeque, sync::Arc};
+
+/// A single-input single-output block advanced in time, the input given to `update`
+/// is held from the last update to `t`
+pub trait Block {
+    /// Advance the block to `t` and return its output at `t`
+    fn update(&mut self, input: f64, t: f64) -> Result<f64, FatalCoreError>;
+
+    /// The output of the last update
+    fn output(&self) -> f64;
+
+    /// Back to the initial condition, the next update starts the clock again
+    fn reset(&mut self);
+}
+
+/// The time elapsed since `last_time`, which becomes `t`
+fn elapsed(last_time: &mut Option<f64>, t: f64) -> Result<f64, FatalCoreError> {
+    let last = last_time.unwrap_or(t);
+    if t < last {
+        return Err(FatalCoreError::NonMonotonicTime(last, t));
+    }
+    *last_time = Some(t);
+    Ok(t - last)
+}
+
+/// `m * x` for the rows of `m`
+pub(crate) fn mul(m: &Matrix, x: &[f64]) -> Vec<f64> {
+    m.data
+        .iter()
+        .map(|row| row.data.iter().zip(x).map(|(a, b)| a * b).sum())
+        .collect()
+}
+
+/// The shape of `m` checked against `rows` and `cols`
+fn check_shape(name: &str, m: &Matrix, rows: usize, cols: usize) -> Result<(), FatalCoreError> {
+    if m.data.len() != rows || m.data.iter().any(|row| row.data.len() != cols) {
+        return Err(FatalCoreError::InvalidInput(format!(
+            "state space matrix {name} must be {rows} x {cols}"
+        )));
+    }
+    Ok(())
+}
+
+/// A continuous linear system x' = A x + B u, y = C x + D u
+/// with n states, m inputs and p outputs, D gives the number of inputs and outputs
+#[derive(Debug, Clone)]
+pub struct StateSpace<S: ODESolver + VectorODESolver> {
+    solver: Arc<S>,
+    a: Matrix,
+    b: Matrix,
+    c: Matrix,
+    d: Matrix,
+    init: Vec<f64>,
+    state: Vec<f64>,
+    output: Vec<f64>,
+    last_time: Option<f64>,
+}
+
+impl<S> StateSpace<S>
+where
+    S: ODESolver + VectorODESolver,
+{
+    pub fn new(
+        solver: Arc<S>,
+        a: Matrix,
+        b: Matrix,
+        c: Matrix,
+        d: Matrix,
+    ) -> Result<Self, FatalCoreError> {
+        let n = a.data.len();
+        let p = d.data.len();
+        let m = d.data.first().map_or(0, |row| row.data.len());
+        if p == 0 || m == 0 {
+            return Err(FatalCoreError::InvalidInput(
+                "state space needs at least one input and one output".to_string(),
+            ));
+        }
+        check_shape("A", &a, n, n)?;
+        check_shape("B", &b, n, m)?;
+        check_shape("C", &c, p, n)?;
+        check_shape("D", &d, p, m)?;
+        Ok(Self {
+            solver,
+            a,
+            b,
+            c,
+            d,
+            init: vec![0.0; n],
+            state: vec![0.0; n],
+            output: vec![0.0; p],
+            last_time: None,
+        })
+    }
+
+    /// Start from the state `init` instead of rest
+    pub fn with_state(mut self, init: &[f64]) -> Result<Self, FatalCoreError> {
+        if init.len() != self.state.len() {
+            return Err(FatalCoreError::InvalidInput(format!(
+                "state space has {} states, got {}",
+                self.state.len(),
+                init.len()
+            )));
+        }
+        self.init = init.to_vec();
+        self.state = init.to_vec();
+        self.output = mul(&self.c, &self.state);
+        Ok(self)
+    }
+
+    /// Number of states, inputs and outputs
+    pub fn shape(&self) -> (usize, usize, usize) {
+        (
+            self.state.len(),
+            self.d.data[0].data.len(),
+            self.output.len(),
+        )
+    }
+
+    pub fn state(&self) -> &[f64] {
+        &self.state
+    }
+
+    pub fn outputs(&self) -> &[f64] {
+        &self.output
+    }
+
+    /// The time of the last update, None before the first
+    pub fn last_time(&self) -> Option<f64> {
+        self.last_time
+    }
+
+    /// Put the system back at `state` as it was after the update at `last_time`,
+    /// the outputs follow at the next update
+    pub fn set_state(
+        &mut self,
+        state: &[f64],
+        last_time: Option<f64>,
+    ) -> Result<(), FatalCoreError> {
+        if state.len() != self.state.len() {
+            return Err(FatalCoreError::InvalidInput(format!(
+                "state space has {} states, got {}",
+                self.state.len(),
+                state.len()
+            )));
+        }
+        self.state = state.to_vec();
+        self.last_time = last_time;
+        Ok(())
+    }
+
+    /// Advance the system to `t` with `input` held since the last update, the outputs at `t`
+    pub fn update_vector(&mut self, input: &[f64], t: f64) -> Result<&[f64], FatalCoreError> {
+        let (n, m, _) = self.shape();
+        if input.len() != m {
+            return Err(FatalCoreError::InvalidInput(format!(
+                "state space has {m} inputs, got {}",
+                input.len()
+            )));
+        }
+        let last_time = self.last_time.unwrap_or(t);
+        elapsed(&mut self.last_time, t)?;
+        if n > 0 && t > last_time {
+            let (a, b) = (self.a.clone(), self.b.clone());
+            let dynamics = move |_t: f64, x: &Vector, u: &Vector| -> Vector {
+                let ax = mul(&a, &x.data);
+                let bu = mul(&b, &u.data);
+                Vector::from(ax.iter().zip(bu).map(|(a, b)| a + b).collect::<Vec<_>>())
+            };
+            let x = VectorODESolver::integrate(
+                self.solver.as_ref(),
+                &dynamics,
+                last_time,
+                t,
+                &Vector::from(self.state.clone()),
+                &Vector::from(input.to_vec()),
+            );
+            self.state = x.data;
+        }
+        let cx = mul(&self.c, &self.state);
+        let du = mul(&self.d, input);
+        self.output = cx.iter().zip(du).map(|(a, b)| a + b).collect();
+        Ok(&self.output)
+    }
+}
+
+impl<S> Block for StateSpace<S>
+where
+    S: ODESolver + VectorODESolver,
+{
+    /// Only for a system of one input and one output
+    fn update(&mut self, input: f64, t: f64) -> Result<f64, FatalCoreError> {
+        let (_, m, p) = self.shape();
+        if m != 1 || p != 1 {
+            return Err(FatalCoreError::InvalidInput(format!(
+                "a block needs one input and one output, the state space has {m} and {p}"
+            )));
+        }
+        Ok(self.update_vector(&[input], t)?[0])
+    }
+
+    fn output(&self) -> f64 {
+        self.output[0]
+    }
+
+    fn reset(&mut self) {
+        self.state = self.init.clone();
+        self.output = mul(&self.c, &self.state);
+        self.last_time = None;
+    }
+}
+
+/// A continuous transfer function num(s) / den(s), coefficients from the highest power
+/// of s down, realized in controllable canonical form
+/// the numerator may not be of higher degree than the denominator
+#[derive(Debug, Clone)]
+pub struct TransferFunction<S: ODESolver + VectorODESolver> {
+    num: Vec<f64>,
+    den: Vec<f64>,
+    system: StateSpace<S>,
+}
+
+impl<S> TransferFunction<S>
+where
+    S: ODESolver + VectorODESolver,
+{
+    pub fn new(solver: Arc<S>, num: &[f64], den: &[f64]) -> Result<Self, FatalCoreError> {
+        // leading zeros do not change the degree
+        let trim = |p: &[f64]| -> Vec<f64> {
+            let first = p.iter().position(|c| *c != 0.0).unwrap_or(p.len());
+            p[first..].to_vec()
+        };
+        let (num, den) = (trim(num), trim(den));
+        if den.is_empty() || den.iter().chain(num.iter()).any(|c| !c.is_finite()) {
+            return Err(FatalCoreError::InvalidInput(
+                "transfer function needs a nonzero finite denominator".to_string(),
+            ));
+        }
+        if num.len() > den.len() {
+            return Err(FatalCoreError::InvalidInput(format!(
+                "transfer function is improper, numerator of degree {} over denominator of degree {}",
+                num.len() - 1,
+                den.len() - 1
+            )));
+        }
+
+        // monic denominator s^n + a_1 s^(n-1) + ... + a_n and the numerator padded to n + 1
+        let n = den.len() - 1;
+        let lead = den[0];
+        let a: Vec<f64> = den[1..].iter().map(|c| c / lead).collect();
+        let mut b = vec![0.0; n + 1 - num.len()];
+        b.extend(num.iter().map(|c| c / lead));
+
+        let mut a_matrix = vec![vec![0.0; n]; n];
+        for i in 0..n.saturating_sub(1) {
+            a_matrix[i][i + 1] = 1.0;
+        }
+        if n > 0 {
+            a_matrix[n - 1] = a.iter().rev().map(|c| -c).collect();
+        }
+        let mut b_matrix = vec![vec![0.0]; n];
+        if n > 0 {
+            b_matrix[n - 1][0] = 1.0;
+        }
+        // y = (b_i - b_0 a_i) x for the powers of s from low to high, plus b_0 u
+        let c_row: Vec<f64> = (0..n).map(|i| b[n - i] - b[0] * a[n - 1 - i]).collect();
+        let system = StateSpace::new(
+            solver,
+            Matrix::from(a_matrix),
+            Matrix::from(b_matrix),
+            Matrix::from(vec![c_row]),
+            Matrix::from(vec![vec![b[0]]]),
+        )?;
+        Ok(Self { num, den, system })
+    }
+
+    /// 1 / (tau s + 1)
+    pub fn first_order(solver: Arc<S>, tau: f64) -> Result<Self, FatalCoreError> {
+        Self::new(solver, &[1.0], &[tau, 1.0])
+    }
+
+    /// w^2 / (s^2 + 2 zeta w s + w^2), w in rad/s
+    pub fn second_order(solver: Arc<S>, w: f64, zeta: f64) -> Result<Self, FatalCoreError> {
+        Self::new(solver, &[w * w], &[1.0, 2.0 * zeta * w, w * w])
+    }
+
+    /// (s^2 + 2 zeta_zero w s + w^2) / (s^2 + 2 zeta_pole w s + w^2), which takes
+    /// zeta_zero / zeta_pole of the gain at w, w in rad/s
+    pub fn notch(
+        solver: Arc<S>,
+        w: f64,
+        zeta_zero: f64,
+        zeta_pole: f64,
+    ) -> Result<Self, FatalCoreError> {
+        Self::new(
+            solver,
+            &[1.0, 2.0 * zeta_zero * w, w * w],
+            &[1.0, 2.0 * zeta_pole * w, w * w],
+        )
+    }
+
+    /// (t_lead s + 1) / (t_lag s + 1), a lead for t_lead > t_lag
+    pub fn lead_lag(solver: Arc<S>, t_lead: f64, t_lag: f64) -> Result<Self, FatalCoreError> {
+        Self::new(solver, &[t_lead, 1.0], &[t_lag, 1.0])
+    }
+
+    /// tau s / (tau s + 1), passes changes and washes out steady inputs
+    pub fn washout(solver: Arc<S>, tau: f64) -> Result<Self, FatalCoreError> {
+        Self::new(solver, &[tau, 0.0], &[tau, 1.0])
+    }
+
+    pub fn num(&self) -> &[f64] {
+        &self.num
+    }
+
+    pub fn den(&self) -> &[f64] {
+        &self.den
+    }
+
+    /// The controllable canonical realization
+    pub fn state_space(&self) -> &StateSpace<S> {
+        &self.system
+    }
+}
+
+impl<S> Block for TransferFunction<S>
+where
+    S: ODESolver + VectorODESolver,
+{
+    fn update(&mut self, input: f64, t: f64) -> Result<f64, FatalCoreError> {
+        self.system.update(input, t)
+    }
+
+    fn output(&self) -> f64 {
+        self.system.output()
+    }
+
+    fn reset(&mut self) {
+        self.system.reset();
+    }
+}
+
+/// A PID controller kp e + ki / s e + kd s / (tau s + 1) e on the error given as input
+/// tau: time constant of the derivative filter, zero for a raw derivative taken between
+/// updates
+/// limits: the output is saturated to [bottom, top], the integrator is kept from winding
+/// up by feeding back the saturation excess with the tracking gain `k_aw` (1/s)
+#[derive(Debug, Clone)]
+pub struct Pid<S: ODESolver> {
+    solver: Arc<S>,
+    pub kp: f64,
+    pub ki: f64,
+    pub kd: f64,
+    pub tau: f64,
+    limits: [f64; 2],
+    pub k_aw: f64,
+    integral: f64,
+    /// the filtered error of the derivative, the last error with a raw derivative
+    filtered: f64,
+    /// the saturation excess of the last output, fed back over the next update
+    excess: f64,
+    output: f64,
+    last_time: Option<f64>,
+}
+
+impl<S> Pid<S>
+where
+    S: ODESolver,
+{
+    pub fn new(solver: Arc<S>, kp: f64, ki: f64, kd: f64) -> Self {
+        Self {
+            solver,
+            kp,
+            ki,
+            kd,
+            tau: 0.0,
+            limits: [f64::NEG_INFINITY, f64::INFINITY],
+            k_aw: 0.0,
+            integral: 0.0,
+            filtered: 0.0,
+            excess: 0.0,
+            output: 0.0,
+            last_time: None,
+        }
+    }
+
+    pub fn with_filter(mut self, tau: f64) -> Self {
+        self.tau = tau;
+        self
+    }
+
+    /// Saturate the output to [bottom, top] with the anti-windup tracking gain `k_aw`
+    pub fn with_limits(mut self, bottom: f64, top: f64, k_aw: f64) -> Result<Self, FatalCoreError> {
+        if bottom.is_nan() || top.is_nan() || top < bottom {
+            return Err(FatalCoreError::InvalidInput(format!(
+                "PID limits need bottom <= top, got [{bottom}, {top}]"
+            )));
+        }
+        self.limits = [bottom, top];
+        self.k_aw = k_aw;
+        Ok(self)
+    }
+
+    pub fn limits(&self) -> [f64; 2] {
+        self.limits
+    }
+
+    /// The state of the integrator
+    pub fn integral(&self) -> f64 {
+        self.integral
+    }
+}
+
+impl<S> Block for Pid<S>
+where
+    S: ODESolver,
+{
+    fn update(&mut self, input: f64, t: f64) -> Result<f64, FatalCoreError> {
+        let first = self.last_time.is_none();
+        let last_time = self.last_time.unwrap_or(t);
+        let dt = elapsed(&mut self.last_time, t)?;
+
+        let (ki, k_aw, excess) = (self.ki, self.k_aw, self.excess);
+        let integrator =
+            move |_t: f64, _state: f64, error: f64| -> f64 { ki * error - k_aw * excess };
+        self.integral = ODESolver::integrate(
+            self.solver.as_ref(),
+            &integrator,
+            last_time,
+            t,
+            self.integral,
+            input,
+        );
+
+        let derivative = if self.tau > 0.0 {
+            if first {
+                self.filtered = input;
+            }
+            let tau = self.tau;
+            let filter = move |_t: f64, state: f64, error: f64| -> f64 { (error - state) / tau };
+            self.filtered = ODESolver::integrate(
+                self.solver.as_ref(),
+                &filter,
+                last_time,
+                t,
+                self.filtered,
+                input,
+            );
+            self.kd * (input - self.filtered) / tau
+        } else {
+            let rate = if dt > 0.0 {
+                (input - self.filtered) / dt
+            } else {
+                0.0
+            };
+            self.filtered = input;
+            self.kd * rate
+        };
+
+        let raw = self.kp * input + self.integral + derivative;
+        self.output = raw.clamp(self.limits[0], self.limits[1]);
+        self.excess = raw - self.output;
+        Ok(self.output)
+    }
+
+    fn output(&self) -> f64 {
+        self.output
+    }
+
+    fn reset(&mut self) {
+        self.integral = 0.0;
+        self.filtered = 0.0;
+        self.excess = 0.0;
+        self.output = 0.0;
+        self.last_time = None;
+    }
+}
+
+/// Limit the rate of change of the input to `rising` up and `falling` down, both per s
+/// and positive, the output starts at the first input
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+    pub rising: f64,
+    pub falling: f64,
+    output: Option<f64>,
+    last_time: Option<f64>,
+}
+
+impl RateLimiter {
+    pub fn new(rising: f64, falling: f64) -> Result<Self, FatalCoreError> {
+        if !(rising > 0.0 && falling > 0.0) {
+            return Err(FatalCoreError::InvalidInput(format!(
+                "rate limiter needs positive rates, got rising {rising} and falling {falling}"
+            )));
+        }
+        Ok(Self {
+            rising,
+            falling,
+            output: None,
+            last_time: None,
+        })
+    }
+
+    /// The same rate up and down
+    pub fn symmetric(rate: f64) -> Result<Self, FatalCoreError> {
+        Self::new(rate, rate)
+    }
+}
+
+impl Block for RateLimiter {
+    fn update(&mut self, input: f64, t: f64) -> Result<f64, FatalCoreError> {
+        let dt = elapsed(&mut self.last_time, t)?;
+        let output = match self.output {
+            Some(last) => input.clamp(last - self.falling * dt, last + self.rising * dt),
+            None => input,
+        };
+        self.output = Some(output);
+        Ok(output)
+    }
+
+    fn output(&self) -> f64 {
+        self.output.unwrap_or_default()
+    }
+
+    fn reset(&mut self) {
+        self.output = None;
+        self.last_time = None;
+    }
+}
+
+/// Clamp the input to [bottom, top]
+#[derive(Debug, Clone)]
+pub struct Saturation {
+    pub bottom: f64,
+    pub top: f64,
+    output: f64,
+}
+
+impl Saturation {
+    pub fn new(bottom: f64, top: f64) -> Result<Self, FatalCoreError> {
+        if bottom.is_nan() || top.is_nan() || top < bottom {
+            return Err(FatalCoreError::InvalidInput(format!(
+                "saturation needs bottom <= top, got [{bottom}, {top}]"
+            )));
+        }
+        Ok(Self {
+            bottom,
+            top,
+            output: 0.0_f64.clamp(bottom, top),
+        })
+    }
+}
+
+impl Block for Saturation {
+    fn update(&mut self, input: f64, _t: f64) -> Result<f64, FatalCoreError> {
+        self.output = input.clamp(self.bottom, self.top);
+        Ok(self.output)
+    }
+
+    fn output(&self) -> f64 {
+        self.output
+    }
+
+    fn reset(&mut self) {
+        self.output = 0.0_f64.clamp(self.bottom, self.top);
+    }
+}
+
+/// The input `delay` seconds ago, interpolated between updates, `init` until then
+#[derive(Debug, Clone)]
+pub struct TransportDelay {
+    pub delay: f64,
+    pub init: f64,
+    /// inputs as [time, input], the oldest still needed first
+    history: VecDeque<[f64; 2]>,
+    output: f64,
+    last_time: Option<f64>,
+}
+
+impl TransportDelay {
+    pub fn new(delay: f64, init: f64) -> Result<Self, FatalCoreError> {
+        if delay.is_nan() || delay < 0.0 {
+            return Err(FatalCoreError::InvalidInput(format!(
+                "delay must not be negative, got {delay}"
+            )));
+        }
+        Ok(Self {
+            delay,
+            init,
+            history: VecDeque::new(),
+            output: init,
+            last_time: None,
+        })
+    }
+}
+
+impl Block for TransportDelay {
+    fn update(&mut self, input: f64, t: f64) -> Result<f64, FatalCoreError> {
+        elapsed(&mut self.last_time, t)?;
+        self.history.push_back([t, input]);
+        let due = t - self.delay;
+        // keep the last input before the due time to interpolate from
+        while self.history.len() > 1 && self.history[1][0] <= due {
+            self.history.pop_front();
+        }
+        let [t_0, u_0] = self.history[0];
+        self.output = if due < t_0 {
+            self.init
+        } else if let Some([t_1, u_1]) = self.history.get(1).copied() {
+            u_0 + (u_1 - u_0) * (due - t_0) / (t_1 - t_0)
+        } else {
+            u_0
+        };
+        Ok(self.output)
+    }
+
+    fn output(&self) -> f64 {
+        self.output
+    }
+
+    fn reset(&mut self) {
+        self.history.clear();
+        self.output = self.init;
+        self.last_time = None;
+    }
+}
+
+/// Sample the input every `sample_time` seconds from the first update and hold it
+#[derive(Debug, Clone)]
+pub struct ZeroOrderHold {
+    pub sample_time: f64,
+    output: f64,
+    next_time: Option<f64>,
+}
+
+impl ZeroOrderHold {
+    pub fn new(sample_time: f64) -> Result<Self, FatalCoreError> {
+        if sample_time.is_nan() || sample_time <= 0.0 {
+            return Err(FatalCoreError::InvalidInput(format!(
+                "sample time must be positive, got {sample_time}"
+            )));
+        }
+        Ok(Self {
+            sample_time,
+            output: 0.0,
+            next_time: None,
+        })
+    }
+}
+
+impl Block for ZeroOrderHold {
+    fn update(&mut self, input: f64, t: f64) -> Result<f64, FatalCoreError> {
+        let tol = 1e-9 * self.sample_time;
+        let next = self.next_time.unwrap_or(t);
+        if t >= next - tol {
+            self.output = input;
+            let (n, _) = substeps(t - next + tol, self.sample_time);
+            self.next_time = Some(next + self.sample_time * (n.max(1)) as f64);
+        }
+        Ok(self.output)
+    }
+
+    fn output(&self) -> f64 {
+        self.output
+    }
+
+    fn reset(&mut self) {
+        self.output = 0.0;
+        self.next_time = None;
+    }
+}
+
+#[cfg(test)]
+mod control_tests {
+    use super::*;
+    use crate::solver::rk::RK4Solver;
+    use std::f64::consts::PI;
+
+    fn run(block: &mut impl Block, input: impl Fn(f64) -> f64, duration: f64) -> Vec<f64> {
+        let n = (duration / 0.01).round() as usize;
+        (0..=n)
+            .map(|i| {
+                let t = 0.01 * i as f64;
+                block.update(input(t), t).unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_transfer_function() {
+        let solver = Arc::new(RK4Solver::new(0.01));
+
+        let mut lag = TransferFunction::first_order(solver.clone(), 0.5).unwrap();
+        let y = run(&mut lag, |_| 1.0, 1.0);
+        assert!((y[50] - (1.0 - (-1.0_f64).exp())).abs() < 1e-6);
+        assert!((y[100] - (1.0 - (-2.0_f64).exp())).abs() < 1e-6);
+        lag.reset();
+        assert_eq!(lag.output(), 0.0);
+
+        let mut second = TransferFunction::second_order(solver.clone(), 10.0, 0.7).unwrap();
+        let y = run(&mut second, |_| 2.0, 3.0);
+        assert!((y.last().unwrap() - 2.0).abs() < 1e-3);
+        // about 5 % of overshoot
+        let peak = y.iter().cloned().fold(0.0, f64::max);
+        assert!((peak / 2.0 - 1.046).abs() < 5e-3);
+
+        // a direct feedthrough jumps with the input, a washout decays back to zero
+        let mut lead = TransferFunction::lead_lag(solver.clone(), 0.4, 0.1).unwrap();
+        let y = run(&mut lead, |_| 1.0, 2.0);
+        assert!((y[0] - 4.0).abs() < 1e-12);
+        assert!((y[200] - 1.0).abs() < 1e-6);
+        let mut washout = TransferFunction::washout(solver.clone(), 0.5).unwrap();
+        let y = run(&mut washout, |_| 1.0, 2.0);
+        assert!((y[0] - 1.0).abs() < 1e-12);
+        assert!((y[50] - (-1.0_f64).exp()).abs() < 1e-6);
+
+        // the notch takes out its frequency and passes others
+        let w = 2.0 * PI * 2.0;
+        let mut notch = TransferFunction::notch(solver.clone(), w, 0.0, 0.5).unwrap();
+        let y = run(&mut notch, |t| (w * t).sin(), 10.0);
+        let tail = y[800..].iter().map(|y| y.abs()).fold(0.0, f64::max);
+        // what is left comes from holding the input between updates
+        assert!(tail < 0.1);
+        let mut notch = TransferFunction::notch(solver.clone(), w, 0.0, 0.5).unwrap();
+        let y = run(&mut notch, |t| (0.1 * w * t).sin(), 20.0);
+        let tail = y[1500..].iter().map(|y| y.abs()).fold(0.0, f64::max);
+        assert!(tail > 0.98);
+
+        // leading zeros are dropped, improper systems are refused
+        let tf = TransferFunction::new(solver.clone(), &[0.0, 2.0], &[0.0, 1.0, 3.0]).unwrap();
+        assert_eq!(tf.num(), &[2.0]);
+        assert_eq!(tf.den(), &[1.0, 3.0]);
+        assert!(TransferFunction::new(solver.clone(), &[1.0, 0.0], &[1.0]).is_err());
+        assert!(TransferFunction::new(solver.clone(), &[1.0], &[0.0]).is_err());
+        let mut gain = TransferFunction::new(solver, &[3.0], &[2.0]).unwrap();
+        assert_eq!(gain.update(2.0, 0.0).unwrap(), 3.0);
+    }
+
+    #[test]
+    fn test_state_space() {
+        let solver = Arc::new(RK4Solver::new(0.01));
+        // two decoupled lags, the second output sums both states
+        let a = Matrix::from(vec![vec![-1.0, 0.0], vec![0.0, -2.0]]);
+        let b = Matrix::from(vec![vec![1.0, 0.0], vec![0.0, 2.0]]);
+        let c = Matrix::from(vec![vec![1.0, 0.0], vec![1.0, 1.0]]);
+        let d = Matrix::from(vec![vec![0.0, 0.0], vec![0.0, 0.0]]);
+        let mut system = StateSpace::new(solver.clone(), a.clone(), b.clone(), c.clone(), d)
+            .unwrap()
+            .with_state(&[1.0, 0.0])
+            .unwrap();
+        assert_eq!(system.shape(), (2, 2, 2));
+        for i in 0..=100 {
+            system.update_vector(&[0.0, 1.0], 0.01 * i as f64).unwrap();
+        }
+        let x = system.state();
+        assert!((x[0] - (-1.0_f64).exp()).abs() < 1e-6);
+        assert!((x[1] - (1.0 - (-2.0_f64).exp())).abs() < 1e-6);
+        assert!((system.outputs()[1] - x[0] - x[1]).abs() < 1e-12);
+        // two inputs do not make a block
+        assert!(system.update(1.0, 2.0).is_err());
+        assert!(system.update_vector(&[1.0], 2.0).is_err());
+        assert!(system.update_vector(&[0.0, 0.0], 0.5).is_err());
+        system.reset();
+        assert_eq!(system.state(), &[1.0, 0.0]);
+
+        let bad = Matrix::from(vec![vec![0.0]]);
+        assert!(StateSpace::new(solver, a, b, c, bad).is_err());
+    }
+
+    #[test]
+    fn test_pid() {
+        let solver = Arc::new(RK4Solver::new(0.01));
+
+        let mut pi = Pid::new(solver.clone(), 2.0, 1.0, 0.0);
+        let y = run(&mut pi, |_| 1.0, 2.0);
+        assert!((y[0] - 2.0).abs() < 1e-12);
+        assert!((y[200] - 4.0).abs() < 1e-9);
+
+        // a filtered derivative of a ramp settles at kd times its slope, less the half
+        // update the held input lags by
+        let mut pd = Pid::new(solver.clone(), 0.0, 0.0, 0.5).with_filter(0.2);
+        let y = run(&mut pd, |t| 3.0 * t, 2.0);
+        assert!((y[200] - 1.5 * (1.0 - 0.005 / 0.2)).abs() < 5e-3);
+        let mut raw = Pid::new(solver.clone(), 0.0, 0.0, 0.5);
+        let y = run(&mut raw, |t| 3.0 * t, 1.0);
+        assert!((y[100] - 1.5).abs() < 1e-9);
+
+        // without anti-windup the integrator runs away behind the limit
+        let mut wound = Pid::new(solver.clone(), 1.0, 2.0, 0.0)
+            .with_limits(-1.0, 1.0, 0.0)
+            .unwrap();
+        run(&mut wound, |_| 1.0, 5.0);
+        assert!((wound.integral() - 10.0).abs() < 1e-9);
+        let mut tracked = Pid::new(solver.clone(), 1.0, 2.0, 0.0)
+            .with_limits(-1.0, 1.0, 5.0)
+            .unwrap();
+        run(&mut tracked, |_| 1.0, 5.0);
+        assert!(tracked.integral() < 0.5);
+        assert_eq!(tracked.output(), 1.0);
+        // so it comes off the limit as soon as the error turns
+        assert!(tracked.update(-0.2, 5.1).unwrap() < 1.0);
+
+        assert!(Pid::new(solver, 1.0, 0.0, 0.0)
+            .with_limits(1.0, -1.0, 0.0)
+            .is_err());
+    }
+
+    #[test]
+    fn test_nonlinear_blocks() {
+        let mut limiter = RateLimiter::new(2.0, 1.0).unwrap();
+        let y = run(&mut limiter, |t| if t < 0.005 { 0.0 } else { 10.0 }, 1.0);
+        assert!((y[50] - 1.0).abs() < 1e-9);
+        let y = run(
+            &mut RateLimiter::symmetric(1.0).unwrap(),
+            |t| -t * 10.0,
+            1.0,
+        );
+        assert!((y[100] + 1.0).abs() < 1e-9);
+        assert!(limiter.update(0.0, 0.5).is_err());
+        assert!(RateLimiter::new(1.0, -1.0).is_err());
+        assert!(RateLimiter::symmetric(f64::NAN).is_err());
+
+        let mut saturation = Saturation::new(-1.0, 2.0).unwrap();
+        assert_eq!(saturation.update(3.0, 0.0).unwrap(), 2.0);
+        assert_eq!(saturation.update(-3.0, 0.0).unwrap(), -1.0);
+        assert!(Saturation::new(1.0, 0.0).is_err());
+
+        let mut delay = TransportDelay::new(0.105, -1.0).unwrap();
+        let y = run(&mut delay, |t| t, 1.0);
+        assert_eq!(y[10], -1.0);
+        assert!((y[11] - 0.005).abs() < 1e-12);
+        assert!((y[100] - 0.895).abs() < 1e-12);
+        assert!(TransportDelay::new(-1.0, 0.0).is_err());
+
+        let mut hold = ZeroOrderHold::new(0.025).unwrap();
+        let y = run(&mut hold, |t| t, 0.1);
+        assert_eq!(y[1], 0.0);
+        assert_eq!(y[2], 0.0);
+        assert!((y[3] - 0.03).abs() < 1e-12);
+        assert!((y[5] - 0.05).abs() < 1e-12);
+        hold.reset();
+        assert_eq!(hold.update(7.0, 3.0).unwrap(), 7.0);
+        assert!(ZeroOrderHold::new(0.0).is_err());
+    }
+}
@@ -0,0 +1,184 @@
+use serde::{Deserialize, Serialize};
+
+/// WGS-84 semi-major axis in ft
+pub const SEMI_MAJOR_AXIS: f64 = 6378137.0 / 0.3048;
+/// WGS-84 flattening
+pub const FLATTENING: f64 = 1.0 / 298.257223563;
+/// WGS-84 first eccentricity squared
+pub const ECCENTRICITY_2: f64 = FLATTENING * (2.0 - FLATTENING);
+/// Earth rotation rate in rad/s
+pub const EARTH_RATE: f64 = 7.292115e-5;
+
+/// normal gravity on the equator in ft/s^2
+const GRAVITY_EQUATOR: f64 = 9.7803253359 / 0.3048;
+/// Somigliana's constant
+const SOMIGLIANA: f64 = 0.00193185265241;
+/// omega^2 a^2 b / GM
+const GRAVITY_RATIO: f64 = 0.00344978650684;
+
+/// A point on or above the WGS-84 ellipsoid
+/// latitude and longitude in rad, altitude above the ellipsoid in ft
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Geodetic {
+    pub latitude: f64,
+    pub longitude: f64,
+    pub altitude: f64,
+}
+
+impl Geodetic {
+    pub fn new(latitude: f64, longitude: f64, altitude: f64) -> Self {
+        Self {
+            latitude,
+            longitude,
+            altitude,
+        }
+    }
+
+    /// Earth-centred Earth-fixed coordinates in ft
+    pub fn to_ecef(&self) -> [f64; 3] {
+        let (slat, clat) = self.latitude.sin_cos();
+        let (slon, clon) = self.longitude.sin_cos();
+        let (_, n) = radii(self.latitude);
+        let h = self.altitude;
+        [
+            (n + h) * clat * clon,
+            (n + h) * clat * slon,
+            (n * (1.0 - ECCENTRICITY_2) + h) * slat,
+        ]
+    }
+
+    /// The geodetic coordinates of a point in Earth-centred Earth-fixed coordinates in ft
+    pub fn from_ecef(ecef: &[f64; 3]) -> Self {
+        let [x, y, z] = *ecef;
+        let longitude = y.atan2(x);
+        let p = (x * x + y * y).sqrt();
+        let mut latitude = z.atan2(p * (1.0 - ECCENTRICITY_2));
+        let mut altitude = 0.0;
+        for _ in 0..10 {
+            let clat = latitude.cos();
+            let (_, n) = radii(latitude);
+            altitude = if clat.abs() > 1e-8 {
+                p / clat - n
+            } else {
+                z.abs() - n * (1.0 - ECCENTRICITY_2)
+            };
+            let next = z.atan2(p * (1.0 - ECCENTRICITY_2 * n / (n + altitude)));
+            let converged = (next - latitude).abs() < 1e-15;
+            latitude = next;
+            if converged {
+                break;
+            }
+        }
+        Self::new(latitude, longitude, altitude)
+    }
+}
+
+/// Meridian and prime vertical radii of curvature in ft at `latitude`
+pub fn radii(latitude: f64) -> (f64, f64) {
+    let s = 1.0 - ECCENTRICITY_2 * latitude.sin().powi(2);
+    let n = SEMI_MAJOR_AXIS / s.sqrt();
+    let m = SEMI_MAJOR_AXIS * (1.0 - ECCENTRICITY_2) / (s * s.sqrt());
+    (m, n)
+}
+
+/// WGS-84 normal gravity in ft/s^2 at `latitude` (rad) and `altitude` (ft),
+/// Somigliana's formula with the second order free-air correction
+pub fn gravity(latitude: f64, altitude: f64) -> f64 {
+    let s2 = latitude.sin().powi(2);
+    let surface = GRAVITY_EQUATOR * (1.0 + SOMIGLIANA * s2) / (1.0 - ECCENTRICITY_2 * s2).sqrt();
+    let a = SEMI_MAJOR_AXIS;
+    surface
+        * (1.0 - 2.0 / a * (1.0 + FLATTENING + GRAVITY_RATIO - 2.0 * FLATTENING * s2) * altitude
+            + 3.0 / (a * a) * altitude * altitude)
+}
+
+/// The plane tangent to the ellipsoid at an origin, with north and east axes,
+/// the flat-earth npos and epos are the coordinates of a point projected on it
+#[derive(Debug, Clone, Copy)]
+pub struct TangentPlane {
+    origin: Geodetic,
+    origin_ecef: [f64; 3],
+}
+
+impl TangentPlane {
+    /// `latitude` and `longitude` of the origin in rad, the origin lies on the ellipsoid
+    pub fn new(latitude: f64, longitude: f64) -> Self {
+        let origin = Geodetic::new(latitude, longitude, 0.0);
+        Self {
+            origin,
+            origin_ecef: origin.to_ecef(),
+        }
+    }
+
+    /// north and east coordinates in ft of `point` on the plane
+    pub fn to_local(&self, point: &Geodetic) -> [f64; 2] {
+        let ecef = point.to_ecef();
+        let d = [
+            ecef[0] - self.origin_ecef[0],
+            ecef[1] - self.origin_ecef[1],
+            ecef[2] - self.origin_ecef[2],
+        ];
+        let (slat, clat) = self.origin.latitude.sin_cos();
+        let (slon, clon) = self.origin.longitude.sin_cos();
+        [
+            -slat * clon * d[0] - slat * slon * d[1] + clat * d[2],
+            -slon * d[0] + clon * d[1],
+        ]
+    }
+
+    /// The point at `altitude` whose projection on the plane is `npos`, `epos`
+    pub fn to_geodetic(&self, npos: f64, epos: f64, altitude: f64) -> Geodetic {
+        let mut point = self.origin;
+        point.altitude = altitude;
+        for _ in 0..10 {
+            let [n, e] = self.to_local(&point);
+            let (m, r) = radii(point.latitude);
+            let d_lat = (npos - n) / (m + altitude);
+            let d_lon = (epos - e) / ((r + altitude) * point.latitude.cos());
+            point.latitude += d_lat;
+            point.longitude += d_lon;
+            if d_lat.abs() < 1e-15 && d_lon.abs() < 1e-15 {
+                break;
+            }
+        }
+        point
+    }
+}
+
+#[cfg(test)]
+mod earth_tests {
+    use super::*;
+
+    #[test]
+    fn test_gravity() {
+        // normal gravity on the equator and at the poles
+        assert!((gravity(0.0, 0.0) * 0.3048 - 9.7803253).abs() < 1e-6);
+        assert!((gravity(90.0_f64.to_radians(), 0.0) * 0.3048 - 9.8321849).abs() < 1e-6);
+        // about 3.086e-6 m/s^2 per m above the surface
+        let lapse = (gravity(0.5, 0.0) - gravity(0.5, 1000.0)) / 1000.0;
+        assert!((lapse - 3.086e-6).abs() < 1e-8);
+    }
+
+    #[test]
+    fn test_ecef() {
+        let point = Geodetic::new(0.7, -1.9, 15000.0);
+        let back = Geodetic::from_ecef(&point.to_ecef());
+        assert!((back.latitude - point.latitude).abs() < 1e-12);
+        assert!((back.longitude - point.longitude).abs() < 1e-12);
+        assert!((back.altitude - point.altitude).abs() < 1e-6);
+
+        let equator = Geodetic::new(0.0, 0.0, 0.0).to_ecef();
+        assert!((equator[0] - SEMI_MAJOR_AXIS).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_tangent_plane() {
+        let plane = TangentPlane::new(0.6, 2.0);
+        let point = plane.to_geodetic(30000.0, -12000.0, 15000.0);
+        let [npos, epos] = plane.to_local(&point);
+        assert!((npos - 30000.0).abs() < 1e-6);
+        assert!((epos + 12000.0).abs() < 1e-6);
+        assert!(point.latitude > 0.6);
+        assert!(point.longitude < 2.0);
+    }
+}
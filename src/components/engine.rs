@@ -0,0 +1,258 @@
+use crate::utils::error::FatalCoreError;
+use serde::{Deserialize, Serialize};
+
+/// Breakpoints of the F-16 thrust tables, Mach and ft
+const MACH: [f64; 6] = [0.0, 0.2, 0.4, 0.6, 0.8, 1.0];
+const ALTITUDE: [f64; 6] = [0.0, 10000.0, 20000.0, 30000.0, 40000.0, 50000.0];
+
+/// Thrust of the F-16 in lbf, rows by Mach, columns by altitude (Stevens & Lewis)
+const IDLE: [[f64; 6]; 6] = [
+    [1060.0, 670.0, 880.0, 1140.0, 1500.0, 1860.0],
+    [635.0, 425.0, 690.0, 1010.0, 1330.0, 1700.0],
+    [60.0, 25.0, 345.0, 755.0, 1130.0, 1525.0],
+    [-1020.0, -710.0, -300.0, 350.0, 910.0, 1360.0],
+    [-2700.0, -1900.0, -1300.0, -247.0, 600.0, 1100.0],
+    [-3600.0, -1400.0, -595.0, -342.0, -200.0, 700.0],
+];
+const MILITARY: [[f64; 6]; 6] = [
+    [12680.0, 9150.0, 6200.0, 3950.0, 2450.0, 1400.0],
+    [12680.0, 9150.0, 6313.0, 4040.0, 2470.0, 1400.0],
+    [12610.0, 9312.0, 6610.0, 4290.0, 2600.0, 1560.0],
+    [12640.0, 9839.0, 7090.0, 4660.0, 2840.0, 1660.0],
+    [12390.0, 10176.0, 7750.0, 5320.0, 3250.0, 1930.0],
+    [11680.0, 9848.0, 8050.0, 6100.0, 3800.0, 2310.0],
+];
+const MAXIMUM: [[f64; 6]; 6] = [
+    [20000.0, 15000.0, 10800.0, 7000.0, 4000.0, 2500.0],
+    [21420.0, 15700.0, 11225.0, 7323.0, 4435.0, 2600.0],
+    [22700.0, 16860.0, 12250.0, 8154.0, 5000.0, 2835.0],
+    [24240.0, 18910.0, 13760.0, 9285.0, 5700.0, 3215.0],
+    [26070.0, 21075.0, 15975.0, 11115.0, 6860.0, 3950.0],
+    [28886.0, 23319.0, 18300.0, 13484.0, 8642.0, 5057.0],
+];
+
+/// Commanded power in percent for a throttle position in [0, 1],
+/// military power (50 %) at 0.77, maximum afterburner (100 %) at 1
+pub fn tgear(throttle: f64) -> f64 {
+    if throttle <= 0.77 {
+        64.94 * throttle
+    } else {
+        217.38 * throttle - 117.38
+    }
+}
+
+/// The throttle position commanding `power`, the inverse of `tgear`
+pub fn throttle_for_power(power: f64) -> f64 {
+    if power <= 50.0 {
+        power / 64.94
+    } else {
+        (power + 117.38) / 217.38
+    }
+}
+
+/// Reciprocal time constant of the core engine in 1/s for a power error `dp`
+fn rtau(dp: f64) -> f64 {
+    if dp <= 25.0 {
+        1.0
+    } else if dp >= 50.0 {
+        0.1
+    } else {
+        1.9 - 0.036 * dp
+    }
+}
+
+/// Rate of change of the power `power` towards the commanded power `command`, both in percent,
+/// lighting or cutting the afterburner goes through military power
+pub fn power_rate(power: f64, command: f64) -> f64 {
+    let (target, rate) = match (command >= 50.0, power >= 50.0) {
+        (true, true) => (command, 5.0),
+        (true, false) => (60.0, rtau(60.0 - power)),
+        (false, true) => (40.0, 5.0),
+        (false, false) => (command, rtau(command - power)),
+    };
+    rate * (target - power)
+}
+
+/// Linear interpolation on the cell of `x` in `breakpoints`, extrapolating beyond the ends
+fn cell(breakpoints: &[f64], x: f64) -> (usize, f64) {
+    let n = breakpoints.len();
+    let i = breakpoints
+        .partition_point(|b| *b <= x)
+        .saturating_sub(1)
+        .min(n - 2);
+    (
+        i,
+        (x - breakpoints[i]) / (breakpoints[i + 1] - breakpoints[i]),
+    )
+}
+
+/// The thrust of one power setting in lbf
+fn lookup(mach: &[f64], altitude: &[f64], table: &[Vec<f64>], m: f64, h: f64) -> f64 {
+    let (i, dm) = cell(mach, m);
+    let (j, dh) = cell(altitude, h);
+    let s = table[i][j] * (1.0 - dh) + table[i][j + 1] * dh;
+    let t = table[i + 1][j] * (1.0 - dh) + table[i + 1][j + 1] * dh;
+    s + (t - s) * dm
+}
+
+/// A turbofan engine after Stevens & Lewis
+/// the throttle commands a power in percent through `tgear`, the power follows with the
+/// lag of `power_rate`, the thrust is interpolated between the idle and military tables
+/// below 50 % and between the military and maximum tables above
+/// mach, altitude: breakpoints of the tables, strictly increasing, at least two
+/// idle, military, maximum: thrust in lbf, rows by Mach, columns by altitude
+/// angular_momentum: of the rotor along the roll axis in slug ft^2/s, `h_eng` of the plane
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EngineModel {
+    mach: Vec<f64>,
+    altitude: Vec<f64>,
+    idle: Vec<Vec<f64>>,
+    military: Vec<Vec<f64>>,
+    maximum: Vec<Vec<f64>>,
+    pub angular_momentum: f64,
+}
+
+impl Default for EngineModel {
+    fn default() -> Self {
+        Self::f16()
+    }
+}
+
+impl EngineModel {
+    pub fn new(
+        mach: Vec<f64>,
+        altitude: Vec<f64>,
+        idle: Vec<Vec<f64>>,
+        military: Vec<Vec<f64>>,
+        maximum: Vec<Vec<f64>>,
+        angular_momentum: f64,
+    ) -> Result<Self, FatalCoreError> {
+        for breakpoints in [&mach, &altitude] {
+            if breakpoints.len() < 2 || breakpoints.windows(2).any(|w| w[1] <= w[0]) {
+                return Err(FatalCoreError::InvalidInput(
+                    "engine table breakpoints must be strictly increasing, at least two"
+                        .to_string(),
+                ));
+            }
+        }
+        for table in [&idle, &military, &maximum] {
+            if table.len() != mach.len() || table.iter().any(|row| row.len() != altitude.len()) {
+                return Err(FatalCoreError::InvalidInput(format!(
+                    "engine tables need {} rows of {} thrusts, one per Mach and altitude",
+                    mach.len(),
+                    altitude.len()
+                )));
+            }
+        }
+        Ok(Self {
+            mach,
+            altitude,
+            idle,
+            military,
+            maximum,
+            angular_momentum,
+        })
+    }
+
+    /// The F100 engine of the F-16 with a rotor of 160 slug ft^2/s
+    pub fn f16() -> Self {
+        let table = |t: &[[f64; 6]; 6]| t.iter().map(|row| row.to_vec()).collect();
+        Self {
+            mach: MACH.to_vec(),
+            altitude: ALTITUDE.to_vec(),
+            idle: table(&IDLE),
+            military: table(&MILITARY),
+            maximum: table(&MAXIMUM),
+            angular_momentum: 160.0,
+        }
+    }
+
+    /// Thrust in lbf at `power` (percent), `altitude` (ft) and `mach`
+    pub fn thrust(&self, power: f64, altitude: f64, mach: f64) -> f64 {
+        let military = lookup(&self.mach, &self.altitude, &self.military, mach, altitude);
+        if power < 50.0 {
+            let idle = lookup(&self.mach, &self.altitude, &self.idle, mach, altitude);
+            idle + (military - idle) * power * 0.02
+        } else {
+            let maximum = lookup(&self.mach, &self.altitude, &self.maximum, mach, altitude);
+            military + (maximum - military) * (power - 50.0) * 0.02
+        }
+    }
+
+    /// The steady power (percent) giving `thrust` (lbf) at `altitude` (ft) and `mach`,
+    /// clamped to idle and maximum
+    pub fn power_for_thrust(&self, thrust: f64, altitude: f64, mach: f64) -> f64 {
+        let idle = self.thrust(0.0, altitude, mach);
+        let military = self.thrust(50.0, altitude, mach);
+        let maximum = self.thrust(100.0, altitude, mach);
+        let power = if thrust < military {
+            50.0 * (thrust - idle) / (military - idle)
+        } else {
+            50.0 + 50.0 * (thrust - military) / (maximum - military)
+        };
+        power.clamp(0.0, 100.0)
+    }
+}
+
+/// The engine of a plane at one instant
+/// power: percent, thrust: lbf
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct EngineState {
+    pub power: f64,
+    pub thrust: f64,
+}
+
+#[cfg(test)]
+mod engine_tests {
+    use super::*;
+
+    #[test]
+    fn test_engine() {
+        assert_eq!(tgear(0.0), 0.0);
+        assert!((tgear(0.77) - 50.0).abs() < 1e-2);
+        assert!((tgear(1.0) - 100.0).abs() < 1e-9);
+        for throttle in [0.1, 0.5, 0.77, 0.9, 1.0] {
+            assert!((throttle_for_power(tgear(throttle)) - throttle).abs() < 1e-3);
+        }
+
+        // the breakpoints reproduce the tables
+        let engine = EngineModel::f16();
+        assert_eq!(engine.thrust(0.0, 0.0, 0.0), 1060.0);
+        assert_eq!(engine.thrust(50.0, 20000.0, 0.4), 6610.0);
+        assert_eq!(engine.thrust(100.0, 50000.0, 1.0), 5057.0);
+        let mid = engine.thrust(25.0, 15000.0, 0.5);
+        let (idle, military) = (
+            engine.thrust(0.0, 15000.0, 0.5),
+            engine.thrust(50.0, 15000.0, 0.5),
+        );
+        assert!((mid - (idle + military) / 2.0).abs() < 1e-9);
+        let thrust = engine.thrust(73.0, 12345.0, 0.63);
+        assert!((engine.power_for_thrust(thrust, 12345.0, 0.63) - 73.0).abs() < 1e-9);
+
+        // the power lag: slow spool up from idle, afterburner through military power
+        assert_eq!(power_rate(10.0, 20.0), 10.0);
+        assert!((power_rate(0.0, 100.0) - 0.1 * 60.0).abs() < 1e-12);
+        assert_eq!(power_rate(60.0, 100.0), 5.0 * 40.0);
+        assert_eq!(power_rate(80.0, 0.0), 5.0 * (40.0 - 80.0));
+
+        let table = vec![vec![0.0; 2]; 2];
+        let bad = EngineModel::new(
+            vec![0.0, 1.0],
+            vec![0.0],
+            table.clone(),
+            table.clone(),
+            table.clone(),
+            0.0,
+        );
+        assert!(bad.is_err());
+        let bad = EngineModel::new(
+            vec![0.0, 1.0, 2.0],
+            vec![0.0, 1.0],
+            table.clone(),
+            table.clone(),
+            table,
+            0.0,
+        );
+        assert!(bad.is_err());
+    }
+}
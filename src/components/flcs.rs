@@ -0,0 +1,357 @@
+use crate::model::{Control, ControlLimit, CoreOutput};
+use crate::runner::DiscreteController;
+use crate::utils::error::FatalCoreError;
+use serde::{Deserialize, Serialize};
+
+/// Gains and limits of the flight control system, surfaces in deg, rates in deg/s
+/// breakout: stick travel ignored around the center, the rest is shaped to
+/// 0.5 * s + 0.5 * s * |s| for a finer command near neutral
+/// nz_max, nz_min: load factor increment in g at full aft and full forward stick
+/// roll_rate_max: roll rate at full lateral stick, rudder_max: rudder at full pedal
+/// pitch_prefilter, roll_prefilter: break frequencies in rad/s of the command lags
+/// k_nz, k_i: proportional (deg/g) and integral (deg/g/s) gains on the load factor error
+/// k_q: pitch damper (deg/(deg/s)), alpha_limit: where the angle-of-attack limiter starts,
+/// k_alpha: g taken off the command per deg beyond it
+/// k_p: roll rate gain (deg/(deg/s)), k_r: yaw damper on the washed out stability-axis
+/// yaw rate (deg/(deg/s)), washout: its time constant in s, k_ari: rudder per deg of
+/// aileron per rad of alpha of the aileron-rudder interconnect
+/// qbar_ref: dynamic pressure (lb/ft^2) the feedback gains are set for, they are scaled
+/// by qbar_ref / qbar within [0.25, 4]
+/// sample_time: the frame of the flight control computer in s
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct FlcsGains {
+    pub breakout: f64,
+    pub nz_max: f64,
+    pub nz_min: f64,
+    pub roll_rate_max: f64,
+    pub rudder_max: f64,
+    pub pitch_prefilter: f64,
+    pub roll_prefilter: f64,
+    pub k_nz: f64,
+    pub k_i: f64,
+    pub k_q: f64,
+    pub alpha_limit: f64,
+    pub k_alpha: f64,
+    pub k_p: f64,
+    pub k_r: f64,
+    pub washout: f64,
+    pub k_ari: f64,
+    pub qbar_ref: f64,
+    pub sample_time: f64,
+}
+
+impl Default for FlcsGains {
+    fn default() -> Self {
+        Self::f16()
+    }
+}
+
+impl FlcsGains {
+    /// After the published block diagrams of the F-16 FLCS, 9 g and -3 g total,
+    /// 308 deg/s of roll rate, a 64 Hz frame and the limiter from 15 deg of alpha
+    pub fn f16() -> Self {
+        Self {
+            breakout: 0.02,
+            nz_max: 8.0,
+            nz_min: -4.0,
+            roll_rate_max: 308.0,
+            rudder_max: 30.0,
+            pitch_prefilter: 8.3,
+            roll_prefilter: 10.0,
+            k_nz: 2.0,
+            k_i: 5.0,
+            k_q: 0.5,
+            alpha_limit: 15.0,
+            k_alpha: 0.8,
+            k_p: 0.3,
+            k_r: 0.5,
+            washout: 1.0,
+            k_ari: 2.0,
+            qbar_ref: 300.0,
+            sample_time: 1.0 / 64.0,
+        }
+    }
+}
+
+/// Stick travel in [-1, 1] past the breakout, shaped
+fn shape(stick: f64, breakout: f64) -> f64 {
+    let s = stick.clamp(-1.0, 1.0);
+    if s.abs() <= breakout {
+        return 0.0;
+    }
+    let s = (s.abs() - breakout) / (1.0 - breakout) * s.signum();
+    0.5 * s + 0.5 * s * s.abs()
+}
+
+/// Move `y` towards `u` through a first-order lag of time constant `tau` over `dt`
+fn lag(y: f64, u: f64, tau: f64, dt: f64) -> f64 {
+    if tau <= 0.0 {
+        return u;
+    }
+    y + (u - y) * (1.0 - (-dt / tau).exp())
+}
+
+/// A flight control system in front of the actuators, stick and pedals in, surfaces out
+/// pitch: load factor command blended with a pitch damper, integral action on the load
+/// factor error and an angle-of-attack limiter, roll: roll rate command falling off at
+/// high alpha, yaw: damper on the stability-axis yaw rate, aileron-rudder interconnect
+/// and pedal command, alpha is the one through the air, see `CoreOutput::air_relative`
+/// the pilot input is a `Control`, thrust passes through, elevator is the longitudinal
+/// stick (positive aft), aileron the lateral stick (positive right), rudder the pedals
+/// (positive right), all in [-1, 1]
+/// with the stick centered the surfaces hold their trim
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Flcs {
+    pub gains: FlcsGains,
+    /// [bottom, top] travel of elevator, aileron and rudder in deg, commands are clamped to it
+    #[serde(default = "f16_limits")]
+    limits: [[f64; 2]; 3],
+    /// elevator, aileron and rudder with the stick centered,
+    /// None to take them from the plane at the first frame
+    trim: Option<[f64; 3]>,
+    /// lagged load factor and roll rate commands
+    commands: [f64; 2],
+    /// integral of the load factor error in deg of elevator
+    integrator: f64,
+    /// low-passed stability-axis yaw rate of the washout
+    yaw_rate: f64,
+    /// elevator, aileron and rudder of the last frame
+    command: [f64; 3],
+    last_time: Option<f64>,
+    next_time: Option<f64>,
+}
+
+fn f16_limits() -> [[f64; 2]; 3] {
+    [[-25.0, 25.0], [-21.5, 21.5], [-30.0, 30.0]]
+}
+
+impl Default for Flcs {
+    fn default() -> Self {
+        Self::new(FlcsGains::f16())
+    }
+}
+
+impl Flcs {
+    pub fn new(gains: FlcsGains) -> Self {
+        Self {
+            gains,
+            limits: f16_limits(),
+            trim: None,
+            commands: [0.0; 2],
+            integrator: 0.0,
+            yaw_rate: 0.0,
+            command: [0.0; 3],
+            last_time: None,
+            next_time: None,
+        }
+    }
+
+    pub fn f16() -> Self {
+        Self::default()
+    }
+
+    /// Clamp the commands to the [bottom, top] travel of elevator, aileron and rudder
+    pub fn set_limits(&mut self, limits: [[f64; 2]; 3]) {
+        self.limits = limits;
+    }
+
+    /// Take the travel from the command limits of `limit`
+    pub fn set_control_limit(&mut self, limit: &ControlLimit) {
+        self.limits = [
+            [limit.ele_cmd_limit_bottom, limit.ele_cmd_limit_top],
+            [limit.ail_cmd_limit_bottom, limit.ail_cmd_limit_top],
+            [limit.rud_cmd_limit_bottom, limit.rud_cmd_limit_top],
+        ];
+    }
+
+    pub fn limits(&self) -> [[f64; 2]; 3] {
+        self.limits
+    }
+
+    /// The surfaces of `trim` are held with the stick centered, None takes the surfaces
+    /// of the plane at the first frame, the filters start still
+    pub fn reset(&mut self, trim: Option<Control>) {
+        self.trim = trim.map(|c| [c.elevator, c.aileron, c.rudder]);
+        self.commands = [0.0; 2];
+        self.integrator = 0.0;
+        self.yaw_rate = 0.0;
+        self.command = self.trim.unwrap_or_default();
+        self.last_time = None;
+        self.next_time = None;
+    }
+
+    pub fn trim(&self) -> Option<Control> {
+        self.trim.map(|[e, a, r]| Control::from([0.0, e, a, r]))
+    }
+
+    /// Whether a frame starts at `t`
+    pub fn is_due(&self, t: f64) -> bool {
+        self.next_time
+            .is_none_or(|next| t >= next - 1e-9 * self.gains.sample_time)
+    }
+
+    /// The surface command of the last frame with the thrust of `pilot`
+    pub fn command(&self, pilot: &Control) -> Control {
+        let [elevator, aileron, rudder] = self.command;
+        Control::from([pilot.thrust, elevator, aileron, rudder])
+    }
+
+    /// Run one frame at `t` for a plane at `output` and the stick and pedals of `pilot`
+    pub fn step(&mut self, t: f64, output: &CoreOutput, pilot: &Control) -> Control {
+        let g = self.gains;
+        let dt = self.last_time.map_or(0.0, |last| (t - last).max(0.0));
+        self.last_time = Some(t);
+        let mut next = self.next_time.unwrap_or(t);
+        while next <= t + 1e-9 * g.sample_time {
+            next += g.sample_time;
+        }
+        self.next_time = Some(next);
+
+        let c = &output.control;
+        let trim = *self.trim.get_or_insert([c.elevator, c.aileron, c.rudder]);
+        let state = &output.state;
+        // the limiter, the stability axes and the interconnect see alpha through the air
+        let air_alpha = output.air_relative().alpha;
+        let alpha = air_alpha.to_degrees();
+        let (p, q, r) = (
+            state.p.to_degrees(),
+            state.q.to_degrees(),
+            (state.r - state.p * air_alpha).to_degrees(),
+        );
+        let scale = (g.qbar_ref / output.state_extend.qbar.max(1.0)).clamp(0.25, 4.0);
+
+        // pitch, stick to load factor command, limited in angle of attack
+        let stick = shape(pilot.elevator, g.breakout);
+        let nz_command = if stick >= 0.0 {
+            stick * g.nz_max
+        } else {
+            -stick * g.nz_min
+        };
+        self.commands[0] = lag(self.commands[0], nz_command, 1.0 / g.pitch_prefilter, dt);
+        let limiter = g.k_alpha * (alpha - g.alpha_limit).max(0.0);
+        let error = self.commands[0] - limiter - (output.state_extend.nz - 1.0);
+        self.integrator = (self.integrator + scale * g.k_i * error * dt).clamp(-25.0, 25.0);
+        let elevator = trim[0] - scale * g.k_nz * error - self.integrator + scale * g.k_q * q;
+
+        // roll, stick to roll rate command, reduced beyond the alpha limit
+        let fade = (1.0 - 0.8 * (alpha - g.alpha_limit) / 15.0).clamp(0.2, 1.0);
+        let roll_command = shape(pilot.aileron, g.breakout) * g.roll_rate_max * fade;
+        self.commands[1] = lag(self.commands[1], roll_command, 1.0 / g.roll_prefilter, dt);
+        let aileron = trim[1] - scale * g.k_p * (self.commands[1] - p);
+
+        // yaw, washed out damper, interconnect and pedals, positive rudder yaws left
+        self.yaw_rate = lag(self.yaw_rate, r, g.washout, dt);
+        let interconnect = g.k_ari * air_alpha.clamp(0.0, 0.6) * (aileron - trim[1]);
+        let rudder = trim[2] + scale * g.k_r * (r - self.yaw_rate) + interconnect
+            - shape(pilot.rudder, g.breakout) * g.rudder_max;
+
+        let [e, a, r] = self.limits;
+        self.command = [
+            elevator.clamp(e[0], e[1]),
+            aileron.clamp(a[0], a[1]),
+            rudder.clamp(r[0], r[1]),
+        ];
+        self.command(pilot)
+    }
+}
+
+impl DiscreteController for Flcs {
+    fn sample_time(&self) -> f64 {
+        self.gains.sample_time
+    }
+
+    fn sample(
+        &mut self,
+        t: f64,
+        output: &CoreOutput,
+        reference: &Control,
+    ) -> Result<Control, FatalCoreError> {
+        Ok(self.step(t, output, reference))
+    }
+}
+
+#[cfg(test)]
+mod flcs_tests {
+    use super::*;
+    use crate::components::wind::AirRelative;
+
+    #[test]
+    fn test_flcs() {
+        assert_eq!(shape(0.01, 0.02), 0.0);
+        assert_eq!(shape(1.0, 0.02), 1.0);
+        assert_eq!(shape(-2.0, 0.02), -1.0);
+        assert!((shape(0.51, 0.02) - 0.375).abs() < 1e-12);
+        assert!((lag(0.0, 1.0, 1.0, 1e3) - 1.0).abs() < 1e-12);
+        assert_eq!(lag(0.0, 1.0, 0.0, 0.01), 1.0);
+
+        // one frame every 1 / 64 s, the trim is held with the stick centered
+        let mut flcs = Flcs::f16();
+        let mut output = CoreOutput::new(
+            Default::default(),
+            Control::from([5000.0, -2.0, 0.5, 0.1]),
+            Default::default(),
+        );
+        output.state.velocity = 500.0;
+        output.state_extend.nz = 1.0;
+        output.state_extend.qbar = 300.0;
+        assert!(flcs.is_due(0.0));
+        let pilot = Control::from([6000.0, 0.0, 0.0, 0.0]);
+        let command = flcs.step(0.0, &output, &pilot);
+        let command: [f64; 4] = command.into();
+        assert_eq!(command, [6000.0, -2.0, 0.5, 0.1]);
+        assert!(!flcs.is_due(0.01));
+        assert!(flcs.is_due(1.0 / 64.0));
+        assert_eq!(flcs.trim().unwrap().elevator, -2.0);
+
+        // aft stick, trailing edge up
+        let pull = Control::from([6000.0, 1.0, 0.0, 0.0]);
+        let command = flcs.step(1.0 / 64.0, &output, &pull);
+        assert!(command.elevator < -2.0);
+
+        // the limiter acts on alpha through the air, a gust pushes the nose down
+        let mut calm = Flcs::f16();
+        let mut gust = Flcs::f16();
+        let mut gusty = output;
+        gusty.air = Some(AirRelative {
+            velocity: 500.0,
+            alpha: 20.0_f64.to_radians(),
+            beta: 0.0,
+        });
+        for i in 0..=10 {
+            let t = i as f64 / 64.0;
+            assert_eq!(calm.step(t, &output, &pilot).elevator, -2.0);
+            assert!(gust.step(t, &gusty, &pilot).elevator > -2.0);
+        }
+
+        // right stick, positive aileron rolls left so the aileron goes negative
+        let mut roll = Flcs::f16();
+        let right = Control::from([6000.0, 0.0, 0.5, 0.0]);
+        roll.step(0.0, &output, &pilot);
+        assert!(roll.step(1.0 / 64.0, &output, &right).aileron < 0.5);
+
+        // a yaw rate to the right, positive rudder yaws left so the damper gives more of it
+        let mut damper = Flcs::f16();
+        let mut yawing = output;
+        yawing.state.r = 5.0_f64.to_radians();
+        damper.step(0.0, &output, &pilot);
+        assert!(damper.step(1.0 / 64.0, &yawing, &pilot).rudder > 0.1);
+
+        // the commands stay within the travel given
+        let mut limited = Flcs::f16();
+        limited.set_limits([[-3.0, 3.0], [-1.0, 1.0], [-2.0, 2.0]]);
+        limited.step(0.0, &output, &pilot);
+        let full = Control::from([6000.0, 1.0, 1.0, 1.0]);
+        for i in 1..=64 {
+            let command = limited.step(i as f64 / 64.0, &output, &full);
+            assert!(command.elevator >= -3.0);
+            assert!(command.aileron >= -1.0);
+            assert!(command.rudder >= -2.0);
+        }
+        assert_eq!(limited.command(&full).elevator, -3.0);
+        assert_eq!(limited.command(&full).rudder, -2.0);
+
+        flcs.reset(None);
+        assert!(flcs.trim().is_none());
+        assert!(flcs.is_due(0.5));
+    }
+}
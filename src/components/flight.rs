@@ -1,5 +1,9 @@
+use super::atmosphere::{AirData, Atmosphere, SimpleAtmosphere};
+use super::earth::{gravity, radii, EARTH_RATE};
+use super::wind::{AirRelative, WindSample};
 use crate::model::{
-    MechanicalModelInput, MechanicalModelOutput, PlaneConstants, State, StateExtend, C,
+    Attitude, Control, Formulation, MechanicalModelInput, MechanicalModelOutput, Navigation,
+    PlaneConstants, State, StateExtend, Velocity, C,
 };
 use crate::plugin::{
     delete_handler_constructor, init_handler_constructor, step_handler_constructor,
@@ -8,21 +12,11 @@ use crate::plugin::{
 };
 use crate::utils::{error::FatalCoreError, Vector};
 use log::warn;
+use std::sync::Arc;
 
 /// gravity ft/s^2
 pub const G: f64 = 32.17;
 
-/// Disturbance on the rudder surface
-pub fn disturbance(deflection: f64, t: f64) -> f64 {
-    if t >= 1.0 && t <= 3.0 {
-        deflection
-    } else if t >= 3.0 && t <= 5.0 {
-        -deflection
-    } else {
-        0.0
-    }
-}
-
 pub fn multi_to_deg(input: &Vector) -> Vector {
     assert!(input.dim() >= 12);
     let mut input = input.clone();
@@ -46,26 +40,15 @@ impl Atmos {
         Self { mach, qbar, ps }
     }
 
-    /// Function for mach and qbar
+    /// Function for mach and qbar in the `SimpleAtmosphere`
     pub fn atmos(altitude: f64, velocity: f64) -> Self {
-        let rho0 = 2.377e-3;
-        let tfac = 1.0 - 0.703e-5 * altitude;
-
-        let mut temp = 519.0 * tfac;
-        if altitude >= 35000.0 {
-            temp = 390.0;
-        }
-
-        let mach = velocity / (1.4 * 1716.3 * temp).sqrt();
-        let rho = rho0 * tfac.powf(4.14);
-        let qbar = 0.5 * rho * velocity.powi(2);
-        let mut ps = 1715.0 * rho * temp;
-
-        if ps.abs() < 1.0e-6 {
-            ps = 1715.0;
-        }
+        Self::from(&SimpleAtmosphere.air_data(altitude, velocity))
+    }
+}
 
-        Atmos::new(mach, qbar, ps)
+impl From<&AirData> for Atmos {
+    fn from(value: &AirData) -> Self {
+        Atmos::new(value.mach, value.qbar, value.air.pressure)
     }
 }
 
@@ -282,7 +265,109 @@ fn kinematics(orientation: &Orientation, angle_rates: &AngleRates) -> Vector3 {
     Vector3::new(phi_dot, theta_dot, psi_dot)
 }
 
+/// return dot of the attitude quaternion [q0, q1, q2, q3]
+fn quaternion_kinematics(quaternion: &[f64], angle_rates: &AngleRates) -> [f64; 4] {
+    let (p, q, r) = (angle_rates.p, angle_rates.q, angle_rates.r);
+    let [q0, q1, q2, q3] = [quaternion[0], quaternion[1], quaternion[2], quaternion[3]];
+    [
+        0.5 * (-p * q1 - q * q2 - r * q3),
+        0.5 * (p * q0 + r * q2 - q * q3),
+        0.5 * (q * q0 - r * q1 + p * q3),
+        0.5 * (r * q0 + q * q1 - p * q2),
+    ]
+}
+
+/// Rotate a vector from the north-east-down frame into the body frame
+pub(crate) fn ned_to_body(orientation: &Orientation, ned: &Vector3) -> Vector3 {
+    let ctheta = orientation.trigonal_theta[1];
+    let cphi = orientation.trigonal_phi[1];
+    let cpsi = orientation.trigonal_psi[1];
+    let stheta = orientation.trigonal_theta[0];
+    let sphi = orientation.trigonal_phi[0];
+    let spsi = orientation.trigonal_psi[0];
+
+    let x = ctheta * cpsi * ned.x + ctheta * spsi * ned.y - stheta * ned.z;
+    let y = (sphi * stheta * cpsi - cphi * spsi) * ned.x
+        + (sphi * stheta * spsi + cphi * cpsi) * ned.y
+        + sphi * ctheta * ned.z;
+    let z = (cphi * stheta * cpsi + sphi * spsi) * ned.x
+        + (cphi * stheta * spsi - sphi * cpsi) * ned.y
+        + cphi * ctheta * ned.z;
+    Vector3::new(x, y, z)
+}
+
+/// The earth the equations of motion are written over
+/// Round: the rotating WGS-84 ellipsoid, `latitude` in rad
+#[derive(Debug, Clone, Copy)]
+enum Earth {
+    Flat,
+    Round { latitude: f64 },
+}
+
+/// The effect of the earth on the equations of motion
+/// gravity: in ft/s^2
+/// attitude_rates: the body rates relative to the north-east-down frame, which drive the attitude
+/// frame_rates: the body rates the velocity is transported with, inertial plus earth rate
+struct EarthTerms {
+    gravity: f64,
+    attitude_rates: AngleRates,
+    frame_rates: AngleRates,
+}
+
+impl Earth {
+    fn terms(
+        &self,
+        altitude: f64,
+        ned_velocity: &Vector3,
+        orientation: &Orientation,
+        angle_rates: &AngleRates,
+    ) -> EarthTerms {
+        match self {
+            Self::Flat => EarthTerms {
+                gravity: G,
+                attitude_rates: *angle_rates,
+                frame_rates: *angle_rates,
+            },
+            Self::Round { latitude } => {
+                let (slat, clat) = latitude.sin_cos();
+                let (m, n) = radii(*latitude);
+                // earth rate and transport rate in north-east-down
+                let earth_rate = Vector3::new(EARTH_RATE * clat, 0.0, -EARTH_RATE * slat);
+                let transport_rate = Vector3::new(
+                    ned_velocity.y / (n + altitude),
+                    -ned_velocity.x / (m + altitude),
+                    -ned_velocity.y * latitude.tan() / (n + altitude),
+                );
+                let ie = ned_to_body(orientation, &earth_rate);
+                let ie_en = ned_to_body(
+                    orientation,
+                    &Vector3::new(
+                        earth_rate.x + transport_rate.x,
+                        earth_rate.y + transport_rate.y,
+                        earth_rate.z + transport_rate.z,
+                    ),
+                );
+                EarthTerms {
+                    gravity: gravity(*latitude, altitude),
+                    attitude_rates: AngleRates::new(
+                        angle_rates.p - ie_en.x,
+                        angle_rates.q - ie_en.y,
+                        angle_rates.r - ie_en.z,
+                    ),
+                    frame_rates: AngleRates::new(
+                        angle_rates.p + ie.x,
+                        angle_rates.q + ie.y,
+                        angle_rates.r + ie.z,
+                    ),
+                }
+            }
+        }
+    }
+}
+
 /// return dot of velocity and it's sub value
+/// angle_rates: the rates the body velocities are transported with
+#[allow(clippy::too_many_arguments)]
 fn velocity_derivation(
     c: &C,
     constants: &PlaneConstants,
@@ -292,6 +377,7 @@ fn velocity_derivation(
     angle_rates: &AngleRates,
     qbar: f64,
     thrust: &f64,
+    gravity: f64,
 ) -> (f64, Vector3) {
     let m = constants.m;
     let s = constants.s;
@@ -309,20 +395,22 @@ fn velocity_derivation(
     let sphi = orientation.trigonal_phi[0];
     let cphi = orientation.trigonal_phi[1];
 
-    let u_dot = r * v - q * w - G * stheta + qbar * s * c.c_x / m + thrust / m;
-    let v_dot = p * w - r * u + G * ctheta * sphi + qbar * s * c.c_y / m;
-    let w_dot = q * u - p * v + G * ctheta * cphi + qbar * s * c.c_z / m;
+    let u_dot = r * v - q * w - gravity * stheta + qbar * s * c.c_x / m + thrust / m;
+    let v_dot = p * w - r * u + gravity * ctheta * sphi + qbar * s * c.c_y / m;
+    let w_dot = q * u - p * v + gravity * ctheta * cphi + qbar * s * c.c_z / m;
     (
         (u * u_dot + v * v_dot + w * w_dot) / velocity,
         Vector3::new(u_dot, v_dot, w_dot),
     )
 }
 
+/// load factors in g, `gravity` is the local gravity in ft/s^2
 fn accels(
     sub_velocity: Vector3,
     sub_velocity_dot: Vector3,
     orientation: &Orientation,
     angle_rates: &AngleRates,
+    gravity: f64,
 ) -> Vector3 {
     // const GRAV: f64 = 32.174;
     let vel_u = sub_velocity.x;
@@ -331,25 +419,36 @@ fn accels(
     let u_dot = sub_velocity_dot.x;
     let v_dot = sub_velocity_dot.y;
     let w_dot = sub_velocity_dot.z;
+    let ratio = gravity / G;
     let nx_cg = 1.0 / G * (u_dot + angle_rates.q * vel_w - angle_rates.r * vel_v)
-        + orientation.trigonal_theta[0];
+        + ratio * orientation.trigonal_theta[0];
     let ny_cg = 1.0 / G * (v_dot + angle_rates.r * vel_u - angle_rates.p * vel_w)
-        - orientation.trigonal_theta[1] * orientation.trigonal_phi[0];
+        - ratio * orientation.trigonal_theta[1] * orientation.trigonal_phi[0];
     let nz_cg = -1.0 / G * (w_dot + angle_rates.p * vel_v - angle_rates.q * vel_u)
-        + orientation.trigonal_theta[1] * orientation.trigonal_phi[1];
+        + ratio * orientation.trigonal_theta[1] * orientation.trigonal_phi[1];
 
     Vector3::new(nx_cg, ny_cg, nz_cg)
 }
 
-pub fn get_lef(altitude: f64, velocity: f64, alpha: f64) -> f64 {
-    let atmos = Atmos::atmos(altitude, velocity);
+pub fn get_lef(atmosphere: &dyn Atmosphere, altitude: f64, velocity: f64, alpha: f64) -> f64 {
+    let atmos = Atmos::from(&atmosphere.air_data(altitude, velocity));
     let mut lef = 1.38 * alpha.to_degrees() - 9.05 * atmos.qbar / atmos.ps + 1.45;
     lef = lef.clamp(0.0, 25.0);
     lef
 }
 
+/// Everything `MechanicalModel::derivatives` finds beyond the `State` derivative
+/// sub_velocity_dot: the dot of the body-axis velocities u, v and w
+/// attitude_rates: the body rates relative to the north-east-down frame
+struct Derivatives {
+    output: MechanicalModelOutput,
+    sub_velocity_dot: Vector3,
+    attitude_rates: AngleRates,
+}
+
 pub struct MechanicalModel {
     constants: PlaneConstants,
+    atmosphere: Arc<dyn Atmosphere>,
     model_trim_func: Box<AerodynamicModelTrimFn>,
     model_init_func: Box<AerodynamicModelInitFn>,
     model_step_func: Box<AerodynamicModelStepFn>,
@@ -380,6 +479,7 @@ impl MechanicalModel {
             delete_handler_constructor(delete_handler, model.info().name.clone());
         Ok(Self {
             constants,
+            atmosphere: Arc::new(SimpleAtmosphere),
             model_trim_func,
             model_init_func,
             model_step_func,
@@ -387,6 +487,25 @@ impl MechanicalModel {
         })
     }
 
+    /// The constants loaded from the aerodynamic model
+    pub fn constants(&self) -> PlaneConstants {
+        self.constants
+    }
+
+    /// The atmosphere the plane flies through, `SimpleAtmosphere` unless set
+    pub fn atmosphere(&self) -> Arc<dyn Atmosphere> {
+        self.atmosphere.clone()
+    }
+
+    pub fn set_atmosphere(&mut self, atmosphere: Arc<dyn Atmosphere>) {
+        self.atmosphere = atmosphere;
+    }
+
+    /// Air data at `altitude` (ft) and true airspeed `velocity` (ft/s)
+    pub fn air_data(&self, altitude: f64, velocity: f64) -> AirData {
+        self.atmosphere.air_data(altitude, velocity)
+    }
+
     pub fn init(&mut self) -> Result<(), FatalCoreError> {
         (self.model_init_func)().map_err(|e| FatalCoreError::from(e))
     }
@@ -395,60 +514,50 @@ impl MechanicalModel {
         &self,
         model_input: &MechanicalModelInput,
     ) -> Result<MechanicalModelOutput, FatalCoreError> {
-        let state = &model_input.state;
-        let control = &model_input.control;
-
-        let orientation = Orientation::from(state);
-        let air_angles = AirAngles::from(state);
-        let angle_rates = AngleRates::from(state);
-        let velocity = state.velocity.max(0.01);
-        let altitude = state.altitude;
-
-        let (mach, qbar, ps) = Atmos::atmos(altitude, velocity).into();
-        let (position_dot, sub_velocity) = navgation(velocity, &orientation, &air_angles);
-        let orientation_dot = kinematics(&orientation, &angle_rates);
-
         let c = (self.model_trim_func)(model_input).map_err(|e| FatalCoreError::from(e))?;
-
-        let (velocity_dot, sub_velocity_dot) = velocity_derivation(
-            &c,
-            &self.constants,
-            velocity,
-            &sub_velocity,
-            &orientation,
-            &angle_rates,
-            qbar,
-            &control.thrust,
-        );
-        let (alpha_dot, beta_dot) =
-            air_angles.derivation(velocity, velocity_dot, &sub_velocity, &sub_velocity_dot);
-        let angle_rate_dot = angle_rates.derivation(&c, &self.constants, qbar);
-
-        let n = accels(sub_velocity, sub_velocity_dot, &orientation, &angle_rates);
-
-        let state_dot = State::from([
-            position_dot.x,
-            position_dot.y,
-            position_dot.z,
-            orientation_dot.x,
-            orientation_dot.y,
-            orientation_dot.z,
-            velocity_dot,
-            alpha_dot,
-            beta_dot,
-            angle_rate_dot.p,
-            angle_rate_dot.q,
-            angle_rate_dot.r,
-        ]);
-        let state_extend = StateExtend::from([n.x, n.y, n.z, mach, qbar, ps]);
-
-        Ok(MechanicalModelOutput::new(state_dot, state_extend))
+        let velocity = model_input.state.velocity;
+        Ok(self
+            .derivatives(model_input, &c, &self.constants, Earth::Flat, velocity)
+            .output)
     }
 
     pub fn step(
         &self,
         model_input: &MechanicalModelInput,
     ) -> Result<MechanicalModelOutput, FatalCoreError> {
+        let c = (self.model_step_func)(model_input).map_err(|e| FatalCoreError::from(e))?;
+        let velocity = model_input.state.velocity;
+        Ok(self
+            .derivatives(model_input, &c, &self.constants, Earth::Flat, velocity)
+            .output)
+    }
+
+    /// The coefficients `c` of the aerodynamic model, given about the center of gravity
+    /// of the loaded constants, moved to the center of gravity of `constants`
+    fn transfer(&self, c: &C, constants: &PlaneConstants) -> C {
+        let shift = self.constants.x_cg - constants.x_cg;
+        if shift == 0.0 {
+            return *c;
+        }
+        C {
+            c_m: c.c_m + c.c_z * shift,
+            c_n: c.c_n - c.c_y * shift * constants.c_bar / constants.b,
+            ..*c
+        }
+    }
+
+    /// The equations of motion for the coefficients `c` over `earth`,
+    /// the state holds the velocity relative to the ground, `air_velocity` is the airspeed
+    /// constants: the mass properties of the plane at this instant
+    fn derivatives(
+        &self,
+        model_input: &MechanicalModelInput,
+        c: &C,
+        constants: &PlaneConstants,
+        earth: Earth,
+        air_velocity: f64,
+    ) -> Derivatives {
+        let c = &self.transfer(c, constants);
         let state = &model_input.state;
         let control = &model_input.control;
 
@@ -458,26 +567,35 @@ impl MechanicalModel {
         let velocity = state.velocity.max(0.01);
         let altitude = state.altitude;
 
-        let (mach, qbar, ps) = Atmos::atmos(altitude, velocity).into();
+        let (mach, qbar, ps) =
+            Atmos::from(&self.atmosphere.air_data(altitude, air_velocity)).into();
         let (position_dot, sub_velocity) = navgation(velocity, &orientation, &air_angles);
-        let orientation_dot = kinematics(&orientation, &angle_rates);
+        let ned_velocity = Vector3::new(position_dot.x, position_dot.y, -position_dot.z);
+        let earth = earth.terms(altitude, &ned_velocity, &orientation, &angle_rates);
+        let orientation_dot = kinematics(&orientation, &earth.attitude_rates);
 
-        let c = (self.model_step_func)(model_input).map_err(|e| FatalCoreError::from(e))?;
         let (velocity_dot, sub_velocity_dot) = velocity_derivation(
-            &c,
-            &self.constants,
+            c,
+            constants,
             velocity,
             &sub_velocity,
             &orientation,
-            &angle_rates,
+            &earth.frame_rates,
             qbar,
             &control.thrust,
+            earth.gravity,
         );
         let (alpha_dot, beta_dot) =
             air_angles.derivation(velocity, velocity_dot, &sub_velocity, &sub_velocity_dot);
-        let angle_rate_dot = angle_rates.derivation(&c, &self.constants, qbar);
+        let angle_rate_dot = angle_rates.derivation(c, constants, qbar);
 
-        let n = accels(sub_velocity, sub_velocity_dot, &orientation, &angle_rates);
+        let n = accels(
+            sub_velocity,
+            sub_velocity_dot,
+            &orientation,
+            &earth.frame_rates,
+            earth.gravity,
+        );
 
         let state_dot = State::from([
             position_dot.x,
@@ -495,7 +613,86 @@ impl MechanicalModel {
         ]);
         let state_extend = StateExtend::from([n.x, n.y, n.z, mach, qbar, ps]);
 
-        Ok(MechanicalModelOutput::new(state_dot, state_extend))
+        Derivatives {
+            output: MechanicalModelOutput::new(state_dot, state_extend),
+            sub_velocity_dot,
+            attitude_rates: earth.attitude_rates,
+        }
+    }
+
+    /// Derivative of a vector laid out by `formulation`,
+    /// the output holds the same derivative as a `State` and the extended state
+    /// wind: the aerodynamic model and qbar see the velocity relative to the air,
+    /// the equations of motion and the navigation integrate the velocity relative to the ground
+    /// alpha_limit, beta_limit: [bottom, top] in degrees, the aerodynamic model
+    /// sees alpha and beta within them while the equations of motion use the true values
+    /// constants: the mass properties of the plane at this instant, see `MassModel`
+    #[allow(clippy::too_many_arguments)]
+    pub fn step_formulation(
+        &self,
+        formulation: &Formulation,
+        constants: &PlaneConstants,
+        x: &[f64],
+        control: Control,
+        d_lef: f64,
+        wind: &WindSample,
+        alpha_limit: [f64; 2],
+        beta_limit: [f64; 2],
+    ) -> Result<(Vector, MechanicalModelOutput), FatalCoreError> {
+        let state = formulation.to_state(x);
+        let air = AirRelative::new(&state, wind);
+        let mut aero_state = state;
+        aero_state.velocity = air.velocity;
+        aero_state.alpha = air
+            .alpha
+            .clamp(alpha_limit[0].to_radians(), alpha_limit[1].to_radians());
+        aero_state.beta = air
+            .beta
+            .clamp(beta_limit[0].to_radians(), beta_limit[1].to_radians());
+        let aero_input = MechanicalModelInput::new(aero_state, control, d_lef);
+        let c = (self.model_step_func)(&aero_input).map_err(FatalCoreError::from)?;
+
+        let model_input = MechanicalModelInput::new(state, control, d_lef);
+        let earth = match formulation.navigation {
+            Navigation::Flat => Earth::Flat,
+            Navigation::Geodetic { .. } => Earth::Round { latitude: x[0] },
+        };
+        let Derivatives {
+            output,
+            sub_velocity_dot,
+            attitude_rates,
+        } = self.derivatives(&model_input, &c, constants, earth, air.velocity);
+        let state_dot: Vec<f64> = output.state_dot.into();
+
+        let mut x_dot = Vec::with_capacity(formulation.dim());
+        match earth {
+            Earth::Flat => x_dot.extend_from_slice(&state_dot[0..3]),
+            Earth::Round { latitude } => {
+                let (m, n) = radii(latitude);
+                let altitude = x[2];
+                x_dot.extend_from_slice(&[
+                    state_dot[0] / (m + altitude),
+                    state_dot[1] / ((n + altitude) * latitude.cos()),
+                    state_dot[2],
+                ])
+            }
+        }
+        match formulation.attitude {
+            Attitude::Euler => x_dot.extend_from_slice(&state_dot[3..6]),
+            Attitude::Quaternion => {
+                x_dot.extend_from_slice(&quaternion_kinematics(&x[3..7], &attitude_rates))
+            }
+        }
+        match formulation.velocity {
+            Velocity::Wind => x_dot.extend_from_slice(&state_dot[6..9]),
+            Velocity::Body => x_dot.extend_from_slice(&[
+                sub_velocity_dot.x,
+                sub_velocity_dot.y,
+                sub_velocity_dot.z,
+            ]),
+        }
+        x_dot.extend_from_slice(&state_dot[9..12]);
+        Ok((Vector::from(x_dot), output))
     }
 
     pub fn delete(&self) {
@@ -1,23 +1,198 @@
-use crate::solver::ODESolver;
+use crate::solver::{substeps, ODESolver, VectorODESolver};
+use crate::utils::{error::FatalCoreError, Vector};
+use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
 use std::sync::Arc;
 // use super::basic::Integrator;
 
+/// How an actuator follows its command
+/// FirstOrder: rate = gain * (command - position)
+/// SecondOrder: acceleration = natural_frequency^2 * (command - position)
+/// - 2 * damping * natural_frequency * rate, natural_frequency in rad/s
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ActuatorDynamics {
+    FirstOrder {
+        gain: f64,
+    },
+    SecondOrder {
+        natural_frequency: f64,
+        damping: f64,
+    },
+}
+
+/// Everything that shapes the response of an actuator
+/// command_limit_top, command_limit_bottom: the travel, commands are clamped to it
+/// rate_limit: the largest rate without load
+/// deadband: command errors smaller than half of it are ignored
+/// backlash: free play between actuator and surface, the surface only moves
+/// once the actuator has taken it up
+/// delay: transport delay on the command in s
+/// stall_deflection: where the hinge moment stalls the actuator, moving away from
+/// neutral the rate limit falls as sqrt(1 - |position| / stall_deflection), None without load
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ActuatorModel {
+    pub dynamics: ActuatorDynamics,
+    pub command_limit_top: f64,
+    pub command_limit_bottom: f64,
+    pub rate_limit: f64,
+    pub deadband: f64,
+    pub backlash: f64,
+    pub delay: f64,
+    pub stall_deflection: Option<f64>,
+}
+
+impl ActuatorModel {
+    pub fn first_order(
+        command_limit_top: f64,
+        command_limit_bottom: f64,
+        rate_limit: f64,
+        gain: f64,
+    ) -> Self {
+        Self {
+            dynamics: ActuatorDynamics::FirstOrder { gain },
+            command_limit_top,
+            command_limit_bottom,
+            rate_limit,
+            deadband: 0.0,
+            backlash: 0.0,
+            delay: 0.0,
+            stall_deflection: None,
+        }
+    }
+
+    pub fn second_order(
+        command_limit_top: f64,
+        command_limit_bottom: f64,
+        rate_limit: f64,
+        natural_frequency: f64,
+        damping: f64,
+    ) -> Self {
+        Self {
+            dynamics: ActuatorDynamics::SecondOrder {
+                natural_frequency,
+                damping,
+            },
+            ..Self::first_order(command_limit_top, command_limit_bottom, rate_limit, 1.0)
+        }
+    }
+
+    pub fn with_deadband(mut self, deadband: f64) -> Self {
+        self.deadband = deadband;
+        self
+    }
+
+    pub fn with_backlash(mut self, backlash: f64) -> Self {
+        self.backlash = backlash;
+        self
+    }
+
+    pub fn with_delay(mut self, delay: f64) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    pub fn with_stall_deflection(mut self, stall_deflection: f64) -> Self {
+        self.stall_deflection = Some(stall_deflection);
+        self
+    }
+
+    /// Without deadband, backlash, delay or hinge moment the first-order model
+    /// is integrated as a scalar, as it always was
+    fn is_plain(&self) -> bool {
+        matches!(self.dynamics, ActuatorDynamics::FirstOrder { .. })
+            && self.deadband == 0.0
+            && self.backlash == 0.0
+            && self.delay == 0.0
+            && self.stall_deflection.is_none()
+    }
+
+    /// The command error with the deadband taken out
+    fn error(&self, command: f64, position: f64) -> f64 {
+        let e = command - position;
+        let half = self.deadband / 2.0;
+        if e.abs() <= half {
+            0.0
+        } else {
+            e - half * e.signum()
+        }
+    }
+
+    /// [bottom, top] of the rate at `position`
+    fn rate_limits(&self, position: f64) -> (f64, f64) {
+        let r = self.rate_limit;
+        let Some(stall) = self.stall_deflection else {
+            return (-r, r);
+        };
+        let loaded = r * (1.0 - position.abs() / stall).max(0.0).sqrt();
+        if position >= 0.0 {
+            (-r, loaded)
+        } else {
+            (-loaded, r)
+        }
+    }
+}
+
+/// How an actuator fails
+/// Stuck: holds `position` within the command limits, or where it was when it failed if None
+/// Hardover: runs to the top or the bottom of its travel
+/// Floating: the surface is disconnected and trails to neutral with `time_constant` (s)
+/// LossOfEffectiveness: the surface moves `effectiveness` (0 to 1) of the actuator
+/// Oscillatory: a sine of `amplitude` and `frequency` (Hz) is added to the surface
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ActuatorFailure {
+    Stuck { position: Option<f64> },
+    Hardover { top: bool },
+    Floating { time_constant: f64 },
+    LossOfEffectiveness { effectiveness: f64 },
+    Oscillatory { amplitude: f64, frequency: f64 },
+}
+
+/// Everything an `Actuator` needs to resume from where it was
+/// state: position of the actuator, rate: its rate for second-order dynamics,
+/// surface: position of the surface behind the backlash, output: the last output,
+/// commands: the delayed commands as [time, command],
+/// failed_at: the surface position latched when a failure struck
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ActuatorSnapshot {
+    pub state: f64,
+    pub last_time: Option<f64>,
+    #[serde(default)]
+    pub rate: f64,
+    #[serde(default)]
+    pub surface: f64,
+    #[serde(default)]
+    pub output: f64,
+    #[serde(default)]
+    pub commands: Vec<[f64; 2]>,
+    pub failed_at: Option<f64>,
+}
+
 #[derive(Clone)]
-pub struct Actuator<S: ODESolver> {
+pub struct Actuator<S: ODESolver + VectorODESolver> {
     // integrator: Integrator,
     solver: Arc<S>,
     // feedback: f64,
-    command_saturation_top: f64,
-    command_saturation_bottom: f64,
-    rate_saturation: f64,
-    gain: f64,
-    state: f64,
+    model: ActuatorModel,
+    /// the failure and the time it strikes
+    failure: Option<(f64, ActuatorFailure)>,
+    /// position of the actuator
+    position: f64,
+    rate: f64,
+    /// position of the surface behind the backlash
+    surface: f64,
+    /// the surface as the failure leaves it, the last output
+    output: f64,
+    /// commands waiting out the delay as [time, command]
+    commands: Vec<[f64; 2]>,
+    failed_at: Option<f64>,
+    last_time: Option<f64>,
 }
 
 impl<S> Actuator<S>
 where
-    S: ODESolver,
+    S: ODESolver + VectorODESolver,
 {
+    /// A first-order actuator
     pub fn new(
         solver: Arc<S>,
         init: f64,
@@ -26,28 +201,130 @@ where
         rate_saturation: f64,
         gain: f64,
     ) -> Self {
-        Self {
-            // integrator: Integrator::new(init),
-            solver,
-            // feedback: 0.0,
+        let model = ActuatorModel::first_order(
             command_saturation_top,
             command_saturation_bottom,
             rate_saturation,
             gain,
-            state: init,
+        );
+        Self::with_model(solver, init, model)
+    }
+
+    pub fn with_model(solver: Arc<S>, init: f64, model: ActuatorModel) -> Self {
+        Self {
+            // integrator: Integrator::new(init),
+            solver,
+            // feedback: 0.0,
+            model,
+            failure: None,
+            position: init,
+            rate: 0.0,
+            surface: init,
+            output: init,
+            commands: Vec::new(),
+            failed_at: None,
+            last_time: None,
+        }
+    }
+
+    pub fn model(&self) -> ActuatorModel {
+        self.model
+    }
+
+    /// Change the model, the actuator carries on from where it is
+    pub fn set_model(&mut self, model: ActuatorModel) {
+        self.model = model;
+    }
+
+    /// Fail as `failure` from time `t` on, None repairs the actuator
+    pub fn set_failure(&mut self, failure: Option<(f64, ActuatorFailure)>) {
+        self.failure = failure;
+        self.failed_at = None;
+    }
+
+    pub fn failure(&self) -> Option<(f64, ActuatorFailure)> {
+        self.failure
+    }
+
+    /// Integrate the actuator from the last update time to `t`
+    pub fn update(&mut self, input: f64, t: f64) -> Result<f64, FatalCoreError> {
+        let last_time = self.last_time.unwrap_or(t);
+        if t < last_time {
+            return Err(FatalCoreError::NonMonotonicTime(last_time, t));
+        }
+        let failure = self.failure.filter(|(time, _)| t >= *time);
+        if self.model.is_plain() && failure.is_none() {
+            return Ok(self.update_plain(input, last_time, t));
         }
+
+        let model = self.model;
+        let mut command = self.delayed(input, t);
+        match failure {
+            Some((_, ActuatorFailure::Hardover { top })) => {
+                command = if top {
+                    model.command_limit_top
+                } else {
+                    model.command_limit_bottom
+                };
+            }
+            Some((_, ActuatorFailure::Stuck { position })) => {
+                let latched = *self.failed_at.get_or_insert(self.output);
+                self.output = position
+                    .map(|p| p.clamp(model.command_limit_bottom, model.command_limit_top))
+                    .unwrap_or(latched);
+                self.last_time = Some(t);
+                return Ok(self.output);
+            }
+            Some((_, ActuatorFailure::Floating { time_constant })) => {
+                self.failed_at.get_or_insert(self.output);
+                let decay = (-(t - last_time) / time_constant).exp();
+                self.position *= decay;
+                self.surface *= decay;
+                self.rate = 0.0;
+                self.output = self.surface;
+                self.last_time = Some(t);
+                return Ok(self.output);
+            }
+            _ => {}
+        }
+        self.integrate(command, last_time, t);
+        self.take_up_backlash();
+        self.last_time = Some(t);
+
+        let output = match failure {
+            Some((time, failure)) => {
+                self.failed_at.get_or_insert(self.surface);
+                match failure {
+                    ActuatorFailure::LossOfEffectiveness { effectiveness } => {
+                        effectiveness * self.surface
+                    }
+                    ActuatorFailure::Oscillatory {
+                        amplitude,
+                        frequency,
+                    } => self.surface + amplitude * (2.0 * PI * frequency * (t - time)).sin(),
+                    _ => self.surface,
+                }
+            }
+            None => self.surface,
+        };
+        self.output = output.clamp(model.command_limit_bottom, model.command_limit_top);
+        Ok(self.output)
     }
 
-    pub fn update(&mut self, input: f64, t: f64) -> f64 {
+    /// The first-order lag with command and rate saturation
+    fn update_plain(&mut self, input: f64, last_time: f64, t: f64) -> f64 {
         // let r_1 = input.clamp(self.command_saturation_bottom, self.command_saturation_top);
         // let r_2 = r_1 - self.feedback;
         // let r_3 = self.gain * r_2;
         // let r_4 = r_3.clamp(-self.rate_saturation, self.rate_saturation);
 
-        let csb = self.command_saturation_bottom;
-        let cst = self.command_saturation_top;
-        let rs = self.rate_saturation;
-        let g = self.gain;
+        let csb = self.model.command_limit_bottom;
+        let cst = self.model.command_limit_top;
+        let rs = self.model.rate_limit;
+        let g = match self.model.dynamics {
+            ActuatorDynamics::FirstOrder { gain } => gain,
+            ActuatorDynamics::SecondOrder { .. } => unreachable!(),
+        };
 
         let dynamics = move |_t: f64, state: f64, input: f64| -> f64 {
             let r_1 = input.clamp(csb, cst);
@@ -57,25 +334,140 @@ where
             r_4
         };
 
-        let r_5 = self.solver.solve(&dynamics, t, self.state, input);
-        self.state = r_5;
+        let r_5 = ODESolver::integrate(
+            self.solver.as_ref(),
+            &dynamics,
+            last_time,
+            t,
+            self.position,
+            input,
+        );
+        self.position = r_5;
+        self.surface = r_5;
+        self.output = r_5;
+        self.last_time = Some(t);
 
         // let r_5 = self.integrator.integrate(r_4, t);
         // self.feedback = r_5;
-        let r_6 = r_5.clamp(self.command_saturation_bottom, self.command_saturation_top);
-        r_6
+        r_5.clamp(csb, cst)
+    }
+
+    /// The command `delay` seconds ago, the position the actuator started from before that
+    fn delayed(&mut self, input: f64, t: f64) -> f64 {
+        let delay = self.model.delay;
+        if delay <= 0.0 {
+            return input;
+        }
+        self.commands.push([t, input]);
+        let due = self.commands.partition_point(|c| c[0] <= t - delay);
+        if due == 0 {
+            return self.position;
+        }
+        // keep the last command due, it holds until the next one is
+        self.commands.drain(..due - 1);
+        self.commands[0][1]
+    }
+
+    /// Integrate the position and rate under `command` with the rate limit and the travel
+    /// enforced after every sub-step
+    fn integrate(&mut self, command: f64, t_0: f64, t_1: f64) {
+        let model = self.model;
+        let command = command.clamp(model.command_limit_bottom, model.command_limit_top);
+        let (n, h) = substeps(t_1 - t_0, self.solver.delta_t());
+        for i in 0..n {
+            let (low, high) = model.rate_limits(self.position);
+            match model.dynamics {
+                ActuatorDynamics::FirstOrder { gain } => {
+                    let dynamics = move |_t: f64, state: f64, command: f64| -> f64 {
+                        (gain * model.error(command, state)).clamp(low, high)
+                    };
+                    self.position = ODESolver::solve_step(
+                        &*self.solver,
+                        &dynamics,
+                        t_0 + h * i as f64,
+                        h,
+                        self.position,
+                        command,
+                    );
+                }
+                ActuatorDynamics::SecondOrder {
+                    natural_frequency: w,
+                    damping: z,
+                } => {
+                    let dynamics = move |_t: f64, x: &Vector, u: &Vector| -> Vector {
+                        let rate = x[1].clamp(low, high);
+                        let acceleration = w * w * model.error(u[0], x[0]) - 2.0 * z * w * rate;
+                        Vector::from(vec![rate, acceleration])
+                    };
+                    let x = VectorODESolver::solve_step(
+                        &*self.solver,
+                        &dynamics,
+                        t_0 + h * i as f64,
+                        h,
+                        &Vector::from(vec![self.position, self.rate]),
+                        &Vector::from(vec![command]),
+                    );
+                    self.position = x[0];
+                    self.rate = x[1].clamp(low, high);
+                }
+            }
+            let stopped = self
+                .position
+                .clamp(model.command_limit_bottom, model.command_limit_top);
+            if stopped != self.position {
+                self.position = stopped;
+                self.rate = 0.0;
+            }
+        }
     }
 
+    /// Move the surface once the actuator has taken up the free play
+    fn take_up_backlash(&mut self) {
+        let half = self.model.backlash / 2.0;
+        self.surface = self
+            .surface
+            .clamp(self.position - half, self.position + half);
+    }
+
+    /// The position of the surface as last put out
     pub fn state(&self) -> f64 {
-        self.state
+        self.output
     }
 
     pub fn reset(&mut self, state: f64) {
         // self.feedback = 0.0;
-        self.state = state;
+        self.position = state;
+        self.rate = 0.0;
+        self.surface = state;
+        self.output = state;
+        self.commands.clear();
+        self.failed_at = None;
+        self.last_time = None;
         // self.integrator.reset();
     }
 
+    pub fn snapshot(&self) -> ActuatorSnapshot {
+        ActuatorSnapshot {
+            state: self.position,
+            last_time: self.last_time,
+            rate: self.rate,
+            surface: self.surface,
+            output: self.output,
+            commands: self.commands.clone(),
+            failed_at: self.failed_at,
+        }
+    }
+
+    pub fn restore(&mut self, snapshot: &ActuatorSnapshot) {
+        self.position = snapshot.state;
+        self.last_time = snapshot.last_time;
+        self.rate = snapshot.rate;
+        self.surface = snapshot.surface;
+        self.output = snapshot.output;
+        self.commands = snapshot.commands.clone();
+        self.failed_at = snapshot.failed_at;
+    }
+
     // pub fn last(&self) -> f64 {
     //     self.last
     // }
@@ -84,7 +476,10 @@ where
 #[cfg(test)]
 mod components_tests {
     use crate::{
-        components::{basic::step, group::Actuator},
+        components::{
+            basic::step,
+            group::{Actuator, ActuatorFailure, ActuatorModel},
+        },
         solver,
         utils::test_logger_init,
     };
@@ -103,14 +498,140 @@ mod components_tests {
         loop {
             let current_time = SystemTime::now();
             let delta_time = current_time.duration_since(start_time).unwrap();
-            r = i.update(
-                step(0.0, 25.0, 3.0, delta_time.as_secs_f64()),
-                delta_time.as_secs_f64(),
-            );
+            r = i
+                .update(
+                    step(0.0, 25.0, 3.0, delta_time.as_secs_f64()),
+                    delta_time.as_secs_f64(),
+                )
+                .unwrap();
             trace!("time: {:?} \n{:?}\n", delta_time, r);
             if delta_time > Duration::from_secs_f32(3.0) {
                 break;
             }
         }
     }
+
+    /// Run `actuator` under `command` from `t_0` to `t_1` in steps of 0.01 s
+    fn run(
+        actuator: &mut Actuator<solver::rk::RK4Solver>,
+        command: impl Fn(f64) -> f64,
+        t_0: f64,
+        t_1: f64,
+    ) -> Vec<f64> {
+        let n = ((t_1 - t_0) / 0.01).round() as usize;
+        (0..=n)
+            .map(|i| {
+                let t = t_0 + i as f64 * 0.01;
+                actuator.update(command(t), t).unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_actuator_model() {
+        let solver = Arc::new(solver::rk::RK4Solver::new(0.01));
+        let actuator = |model| Actuator::with_model(solver.clone(), 0.0, model);
+
+        // the plain first-order model is untouched by the general path
+        let mut plain = Actuator::new(solver.clone(), 0.0, 25.0, -25.0, 60.0, 20.2);
+        let mut general = actuator(ActuatorModel::first_order(25.0, -25.0, 60.0, 20.2));
+        general.set_failure(Some((100.0, ActuatorFailure::Stuck { position: None })));
+        let a = run(&mut plain, |_| 10.0, 0.0, 1.0);
+        let b = run(&mut general, |_| 10.0, 0.0, 1.0);
+        assert!(a.iter().zip(&b).all(|(a, b)| (a - b).abs() < 1e-6));
+
+        // a lightly damped second-order actuator overshoots, a well damped one does not
+        let mut light = actuator(ActuatorModel::second_order(25.0, -25.0, 600.0, 40.0, 0.3));
+        let mut heavy = actuator(ActuatorModel::second_order(25.0, -25.0, 600.0, 40.0, 1.0));
+        let light = run(&mut light, |_| 10.0, 0.0, 1.0);
+        let heavy = run(&mut heavy, |_| 10.0, 0.0, 1.0);
+        assert!(light.iter().cloned().fold(f64::MIN, f64::max) > 12.0);
+        assert!(heavy.iter().all(|x| *x <= 10.0 + 1e-6));
+        assert!((light.last().unwrap() - 10.0).abs() < 0.1);
+
+        // the rate limit holds for second-order dynamics too
+        let mut slow = actuator(ActuatorModel::second_order(25.0, -25.0, 20.0, 40.0, 0.7));
+        let slow = run(&mut slow, |_| 20.0, 0.0, 0.5);
+        assert!(slow
+            .windows(2)
+            .all(|w| (w[1] - w[0]).abs() <= 20.0 * 0.01 + 1e-9));
+
+        // commands inside the deadband are ignored
+        let model = ActuatorModel::first_order(25.0, -25.0, 60.0, 20.2).with_deadband(1.0);
+        let mut dead = actuator(model);
+        assert!(run(&mut dead, |_| 0.4, 0.0, 1.0).iter().all(|x| *x == 0.0));
+        let x = *run(&mut dead, |_| 5.0, 1.01, 2.0).last().unwrap();
+        assert!((x - 4.5).abs() < 1e-3);
+
+        // the surface lags the actuator by half the backlash after a reversal
+        let model = ActuatorModel::first_order(25.0, -25.0, 60.0, 20.2).with_backlash(2.0);
+        let mut loose = actuator(model);
+        assert!((run(&mut loose, |_| 5.0, 0.0, 1.0).last().unwrap() - 4.0).abs() < 1e-3);
+        assert!((run(&mut loose, |_| -5.0, 1.01, 2.0).last().unwrap() + 4.0).abs() < 1e-3);
+
+        // a delayed actuator holds until the command comes through
+        let model = ActuatorModel::first_order(25.0, -25.0, 60.0, 20.2).with_delay(0.1);
+        let mut late = actuator(model);
+        let late = run(&mut late, |_| 5.0, 0.0, 0.5);
+        assert!(late[..10].iter().all(|x| *x == 0.0));
+        assert!(late[12] > 0.0);
+
+        // the hinge moment slows the actuator away from neutral, not back to it
+        let model = ActuatorModel::first_order(25.0, -25.0, 60.0, 20.2).with_stall_deflection(30.0);
+        let mut loaded = actuator(model);
+        let out = run(&mut loaded, |_| 25.0, 0.0, 2.0);
+        assert!(*out.last().unwrap() < 25.0 && *out.last().unwrap() > 20.0);
+        let back = run(&mut loaded, |_| -25.0, 2.01, 2.02);
+        assert!((out.last().unwrap() - back[1]) > 0.5);
+
+        // every failure mode
+        let model = ActuatorModel::first_order(25.0, -25.0, 60.0, 20.2);
+        let fail = |failure| {
+            let mut a = actuator(model);
+            a.set_failure(Some((0.5, failure)));
+            run(&mut a, |_| 10.0, 0.0, 2.0)
+        };
+        let stuck = fail(ActuatorFailure::Stuck { position: None });
+        assert!(stuck[50] < 10.0 && stuck[50..].iter().all(|x| *x == stuck[50]));
+        let stuck = fail(ActuatorFailure::Stuck {
+            position: Some(-3.0),
+        });
+        assert_eq!(*stuck.last().unwrap(), -3.0);
+        let stuck = fail(ActuatorFailure::Stuck {
+            position: Some(40.0),
+        });
+        assert_eq!(*stuck.last().unwrap(), 25.0);
+        let hard = fail(ActuatorFailure::Hardover { top: false });
+        assert!((hard.last().unwrap() + 25.0).abs() < 1e-3);
+        let float = fail(ActuatorFailure::Floating { time_constant: 0.1 });
+        assert!(float[50] > 5.0 && float.last().unwrap().abs() < 1e-3);
+        let weak = fail(ActuatorFailure::LossOfEffectiveness { effectiveness: 0.5 });
+        assert!((weak.last().unwrap() - 5.0).abs() < 1e-3);
+        let shaky = fail(ActuatorFailure::Oscillatory {
+            amplitude: 1.0,
+            frequency: 2.0,
+        });
+        let tail = &shaky[100..];
+        let (low, high) = tail
+            .iter()
+            .fold((f64::MAX, f64::MIN), |(l, h), x| (l.min(*x), h.max(*x)));
+        assert!(low < 9.1 && high > 10.9);
+
+        // a snapshot resumes mid-delay and mid-failure
+        let model = ActuatorModel::second_order(25.0, -25.0, 60.0, 30.0, 0.5)
+            .with_delay(0.05)
+            .with_backlash(0.5);
+        let mut a = actuator(model);
+        a.set_failure(Some((
+            0.3,
+            ActuatorFailure::LossOfEffectiveness { effectiveness: 0.7 },
+        )));
+        let command = |t: f64| 10.0 * (3.0 * t).sin();
+        run(&mut a, command, 0.0, 0.2);
+        let snapshot = a.snapshot();
+        let text = toml::to_string(&snapshot).unwrap();
+        let expected = run(&mut a, command, 0.21, 1.0);
+        a.restore(&toml::from_str(&text).unwrap());
+        assert_eq!(run(&mut a, command, 0.21, 1.0), expected);
+    }
 }
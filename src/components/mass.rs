@@ -0,0 +1,400 @@
+use super::atmosphere::{AirData, SEA_LEVEL_TEMPERATURE};
+use super::flight::G;
+use crate::model::{MassState, PlaneConstants};
+use crate::utils::error::FatalCoreError;
+use serde::{Deserialize, Serialize};
+
+/// A fuel tank treated as a point mass
+/// capacity, fuel: lb
+/// position: ft along the body axes (x forward, y right, z down)
+/// from the center of gravity the plane constants are given for
+/// stage: tanks of the lowest stage holding fuel feed the engine,
+/// in proportion to their fuel so they run dry together
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Tank {
+    pub capacity: f64,
+    pub fuel: f64,
+    pub position: [f64; 3],
+    pub stage: usize,
+}
+
+impl Tank {
+    /// A full tank in the first stage
+    pub fn new(capacity: f64, position: [f64; 3]) -> Self {
+        Self {
+            capacity,
+            fuel: capacity,
+            position,
+            stage: 0,
+        }
+    }
+
+    pub fn with_fuel(mut self, fuel: f64) -> Self {
+        self.fuel = fuel;
+        self
+    }
+
+    pub fn with_stage(mut self, stage: usize) -> Self {
+        self.stage = stage;
+        self
+    }
+}
+
+/// Thrust-specific fuel consumption in lb of fuel per lbf of thrust and hour,
+/// (tsfc + mach_slope * mach) * sqrt(theta) with theta the temperature over
+/// the sea level standard temperature
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct FuelConsumption {
+    pub tsfc: f64,
+    pub mach_slope: f64,
+}
+
+impl FuelConsumption {
+    pub fn new(tsfc: f64, mach_slope: f64) -> Self {
+        Self { tsfc, mach_slope }
+    }
+
+    /// Fuel flow in lb/s at `thrust` (lbf), no fuel is burned at negative thrust
+    pub fn fuel_flow(&self, thrust: f64, air: &AirData) -> f64 {
+        let theta = air.air.temperature / SEA_LEVEL_TEMPERATURE;
+        (self.tsfc + self.mach_slope * air.mach) * theta.sqrt() * thrust.max(0.0) / 3600.0
+    }
+}
+
+/// j_x, j_y, j_z and j_xz of a point mass `m` (slugs) at `r` (ft)
+fn point_inertia(m: f64, r: [f64; 3]) -> [f64; 4] {
+    let [x, y, z] = r;
+    [
+        m * (y * y + z * z),
+        m * (x * x + z * z),
+        m * (x * x + y * y),
+        m * x * z,
+    ]
+}
+
+fn add_inertia(a: &mut [f64; 4], b: [f64; 4], sign: f64) {
+    a.iter_mut().zip(b).for_each(|(a, b)| *a += sign * b);
+}
+
+/// Mass, center of gravity and inertia of a plane burning fuel from its tanks
+/// the plane constants describe the plane with the tanks as given,
+/// the empty plane is found by taking the tanks out again
+/// only the shift of the center of gravity along x reaches the constants, as `x_cg`
+#[derive(Debug, Clone, PartialEq)]
+pub struct MassModel {
+    base: PlaneConstants,
+    consumption: FuelConsumption,
+    tanks: Vec<Tank>,
+    initial_fuel: Vec<f64>,
+    empty_mass: f64,
+    /// ft from the center of gravity of `base`
+    empty_cg: [f64; 3],
+    /// j_x, j_y, j_z and j_xz about `empty_cg`
+    empty_inertia: [f64; 4],
+    constants: PlaneConstants,
+    cg: [f64; 3],
+    fuel_flow: f64,
+}
+
+impl MassModel {
+    pub fn new(
+        constants: PlaneConstants,
+        tanks: Vec<Tank>,
+        consumption: FuelConsumption,
+    ) -> Result<Self, FatalCoreError> {
+        if let Some(tank) = tanks
+            .iter()
+            .find(|t| !(t.capacity >= 0.0 && (0.0..=t.capacity).contains(&t.fuel)))
+        {
+            return Err(FatalCoreError::InvalidInput(format!(
+                "a tank needs a capacity of at least 0 and fuel within it, got {} lb in {} lb",
+                tank.fuel, tank.capacity
+            )));
+        }
+        let fuel_mass: f64 = tanks.iter().map(|t| t.fuel / G).sum();
+        let empty_mass = constants.m - fuel_mass;
+        if empty_mass <= 0.0 {
+            return Err(FatalCoreError::InvalidInput(format!(
+                "the tanks hold {} slugs of fuel, more than the plane of {} slugs",
+                fuel_mass, constants.m
+            )));
+        }
+
+        let mut moment = [0.0; 3];
+        for tank in &tanks {
+            (0..3).for_each(|i| moment[i] += tank.fuel / G * tank.position[i]);
+        }
+        let empty_cg = moment.map(|x| -x / empty_mass);
+
+        let mut empty_inertia = [constants.j_x, constants.j_y, constants.j_z, constants.j_xz];
+        add_inertia(
+            &mut empty_inertia,
+            point_inertia(empty_mass, empty_cg),
+            -1.0,
+        );
+        for tank in &tanks {
+            add_inertia(
+                &mut empty_inertia,
+                point_inertia(tank.fuel / G, tank.position),
+                -1.0,
+            );
+        }
+
+        let initial_fuel = tanks.iter().map(|t| t.fuel).collect();
+        let mut mass = Self {
+            base: constants,
+            consumption,
+            tanks,
+            initial_fuel,
+            empty_mass,
+            empty_cg,
+            empty_inertia,
+            constants,
+            cg: [0.0; 3],
+            fuel_flow: 0.0,
+        };
+        mass.update();
+        Ok(mass)
+    }
+
+    /// Recompute the constants from the fuel in the tanks
+    fn update(&mut self) {
+        let mass = self.empty_mass + self.fuel() / G;
+        let mut moment = self.empty_cg.map(|x| x * self.empty_mass);
+        for tank in &self.tanks {
+            (0..3).for_each(|i| moment[i] += tank.fuel / G * tank.position[i]);
+        }
+        let cg = moment.map(|x| x / mass);
+
+        let offset = |r: [f64; 3]| [r[0] - cg[0], r[1] - cg[1], r[2] - cg[2]];
+        let mut inertia = self.empty_inertia;
+        add_inertia(
+            &mut inertia,
+            point_inertia(self.empty_mass, offset(self.empty_cg)),
+            1.0,
+        );
+        for tank in &self.tanks {
+            add_inertia(
+                &mut inertia,
+                point_inertia(tank.fuel / G, offset(tank.position)),
+                1.0,
+            );
+        }
+
+        let [j_x, j_y, j_z, j_xz] = inertia;
+        self.constants = PlaneConstants {
+            m: mass,
+            x_cg: self.base.x_cg - cg[0] / self.base.c_bar,
+            j_x,
+            j_y,
+            j_z,
+            j_xz,
+            ..self.base
+        };
+        self.cg = cg;
+    }
+
+    /// Burn the fuel for `thrust` (lbf) over `dt` seconds in the air `air`,
+    /// return the fuel burned in lb, less than asked for when the tanks run dry
+    pub fn burn(&mut self, dt: f64, thrust: f64, air: &AirData) -> f64 {
+        self.fuel_flow = if self.is_empty() {
+            0.0
+        } else {
+            self.consumption.fuel_flow(thrust, air)
+        };
+        let mut demand = self.fuel_flow * dt;
+        let mut burned = 0.0;
+        while demand > 0.0 {
+            let Some(stage) = self
+                .tanks
+                .iter()
+                .filter(|t| t.fuel > 0.0)
+                .map(|t| t.stage)
+                .min()
+            else {
+                break;
+            };
+            let available: f64 = self
+                .tanks
+                .iter()
+                .filter(|t| t.stage == stage)
+                .map(|t| t.fuel)
+                .sum();
+            let take = demand.min(available);
+            for tank in self.tanks.iter_mut().filter(|t| t.stage == stage) {
+                tank.fuel = if take == available {
+                    0.0
+                } else {
+                    tank.fuel * (1.0 - take / available)
+                };
+            }
+            demand -= take;
+            burned += take;
+        }
+        self.update();
+        burned
+    }
+
+    /// Fuel remaining in lb
+    pub fn fuel(&self) -> f64 {
+        self.tanks.iter().map(|t| t.fuel).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tanks.iter().all(|t| t.fuel <= 0.0)
+    }
+
+    pub fn tanks(&self) -> &[Tank] {
+        &self.tanks
+    }
+
+    /// Check that `fuel` (lb) fits the tanks, one per tank
+    pub fn check_fuel(&self, fuel: &[f64]) -> Result<(), FatalCoreError> {
+        if fuel.len() != self.tanks.len()
+            || self
+                .tanks
+                .iter()
+                .zip(fuel)
+                .any(|(t, f)| !(0.0..=t.capacity).contains(f))
+        {
+            return Err(FatalCoreError::InvalidInput(format!(
+                "{:?} lb do not fit {} tanks of {:?} lb",
+                fuel,
+                self.tanks.len(),
+                self.tanks.iter().map(|t| t.capacity).collect::<Vec<_>>()
+            )));
+        }
+        Ok(())
+    }
+
+    /// Fill the tanks with `fuel` (lb), one per tank
+    pub fn set_fuel(&mut self, fuel: &[f64]) -> Result<(), FatalCoreError> {
+        self.check_fuel(fuel)?;
+        self.tanks
+            .iter_mut()
+            .zip(fuel)
+            .for_each(|(t, f)| t.fuel = *f);
+        self.update();
+        Ok(())
+    }
+
+    /// Refill the tanks to the fuel they were created with
+    pub fn reset(&mut self) {
+        self.tanks
+            .iter_mut()
+            .zip(&self.initial_fuel)
+            .for_each(|(t, f)| t.fuel = *f);
+        self.fuel_flow = 0.0;
+        self.update();
+    }
+
+    /// The plane constants with the fuel remaining
+    pub fn constants(&self) -> PlaneConstants {
+        self.constants
+    }
+
+    /// The center of gravity in ft from that of the constants the model was created with
+    pub fn cg(&self) -> [f64; 3] {
+        self.cg
+    }
+
+    pub fn consumption(&self) -> FuelConsumption {
+        self.consumption
+    }
+
+    pub fn state(&self) -> MassState {
+        MassState {
+            mass: self.constants.m,
+            fuel: self.fuel(),
+            fuel_flow: self.fuel_flow,
+            x_cg: self.constants.x_cg,
+        }
+    }
+}
+
+#[cfg(test)]
+mod mass_tests {
+    use super::*;
+    use crate::components::atmosphere::{Atmosphere, StandardAtmosphere};
+
+    fn f16() -> PlaneConstants {
+        PlaneConstants::new(
+            636.94, 30.0, 300.0, 11.32, 0.35, 0.35, 160.0, 55814.0, 982.0, 63100.0, 9496.0,
+        )
+    }
+
+    fn tanks() -> Vec<Tank> {
+        vec![
+            Tank::new(3000.0, [4.0, 0.0, 0.5]),
+            Tank::new(2800.0, [-5.0, 0.0, 0.5]).with_stage(1),
+            Tank::new(600.0, [-2.0, -6.0, 0.0]).with_stage(2),
+            Tank::new(600.0, [-2.0, 6.0, 0.0]).with_stage(2),
+        ]
+    }
+
+    #[test]
+    fn test_mass() {
+        let base = f16();
+        let mut mass = MassModel::new(base, tanks(), FuelConsumption::new(0.8, 0.2)).unwrap();
+        let c = mass.constants();
+        assert!((c.m - base.m).abs() < 1e-9);
+        assert!((c.x_cg - base.x_cg).abs() < 1e-12);
+        assert!((c.j_x - base.j_x).abs() < 1e-6 && (c.j_xz - base.j_xz).abs() < 1e-6);
+        assert!(mass.cg().iter().all(|x| x.abs() < 1e-12));
+
+        // the forward tank goes first, so the center of gravity moves aft
+        let air = StandardAtmosphere::new(0.0).air_data(0.0, 0.0);
+        let flow = mass.consumption().fuel_flow(10000.0, &air);
+        assert!((flow - 0.8 * 10000.0 / 3600.0).abs() < 1e-9);
+        let burned = mass.burn(100.0, 10000.0, &air);
+        assert!((burned - 100.0 * flow).abs() < 1e-9);
+        assert!((mass.tanks()[0].fuel - (3000.0 - burned)).abs() < 1e-9);
+        assert_eq!(mass.tanks()[1].fuel, 2800.0);
+        assert!(mass.constants().x_cg > base.x_cg);
+        assert!((mass.constants().m - (base.m - burned / G)).abs() < 1e-9);
+        assert!(mass.constants().j_y < base.j_y);
+
+        // the wing tanks run dry together and keep the center of gravity on the centerline
+        mass.set_fuel(&[0.0, 0.0, 600.0, 600.0]).unwrap();
+        mass.burn(1.0, 3600.0 * 300.0 / 0.8, &air);
+        assert!((mass.tanks()[2].fuel - 450.0).abs() < 1e-9);
+        assert!((mass.tanks()[3].fuel - 450.0).abs() < 1e-9);
+        assert!(mass.cg()[1].abs() < 1e-12);
+        let burned = mass.burn(10.0, 3600.0 * 300.0 / 0.8, &air);
+        assert!((burned - 900.0).abs() < 1e-9);
+        assert!(mass.is_empty());
+        assert_eq!(mass.burn(10.0, 10000.0, &air), 0.0);
+        assert_eq!(mass.state().fuel_flow, 0.0);
+
+        // the empty plane is the same whichever fuel it was created with,
+        // the tanks are then placed from its center of gravity
+        let cg = mass.cg();
+        let partial: Vec<Tank> = tanks()
+            .into_iter()
+            .map(|t| {
+                let [x, y, z] = t.position;
+                Tank::new(t.capacity, [x - cg[0], y - cg[1], z - cg[2]]).with_fuel(0.0)
+            })
+            .collect();
+        let empty_base = mass.constants();
+        let empty = MassModel::new(empty_base, partial, FuelConsumption::new(0.8, 0.0)).unwrap();
+        let mut refilled = empty.clone();
+        refilled.set_fuel(&[3000.0, 2800.0, 600.0, 600.0]).unwrap();
+        let c = refilled.constants();
+        assert!((c.m - base.m).abs() < 1e-9);
+        assert!((c.j_y - base.j_y).abs() < 1e-6);
+        assert!((c.j_xz - base.j_xz).abs() < 1e-6);
+        assert!((c.x_cg - base.x_cg).abs() < 1e-12);
+
+        mass.reset();
+        assert_eq!(mass.fuel(), 7000.0);
+        assert!(mass.set_fuel(&[0.0]).is_err());
+        assert!(MassModel::new(base, vec![Tank::new(1e6, [0.0; 3])], mass.consumption()).is_err());
+        assert!(MassModel::new(
+            base,
+            vec![Tank::new(10.0, [0.0; 3]).with_fuel(20.0)],
+            mass.consumption()
+        )
+        .is_err());
+    }
+}
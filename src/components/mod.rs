@@ -1,3 +1,13 @@
+pub mod atmosphere;
+pub mod autopilot;
 pub mod basic;
+pub mod control;
+pub mod earth;
+pub mod engine;
+pub mod flcs;
 pub mod flight;
 pub mod group;
+pub mod mass;
+pub mod sensor;
+pub mod signal;
+pub mod wind;
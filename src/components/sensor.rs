@@ -0,0 +1,637 @@
+use super::atmosphere::pitot_ratio;
+use super::flight::G;
+use crate::model::{
+    body_to_wind, wind_to_body, AhrsMeasurement, AirDataMeasurement, ImuMeasurement, Measurements,
+    State, StateExtend,
+};
+use crate::utils::Rng;
+use serde::{Deserialize, Serialize};
+
+/// The errors of one sensor channel, the default is a perfect sensor
+/// noise: standard deviation of the white noise on every sample
+/// bias: constant offset
+/// random_walk: the bias drifts with a standard deviation of random_walk * sqrt(t)
+/// scale_factor: relative error of the gain, 0.01 reads 1 % high
+/// quantization: the resolution, 0 for a continuous reading
+/// limits: [bottom, top] the reading saturates at
+/// lag: time constant of a first-order lag in s, 0 for none
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct SensorModel {
+    pub noise: f64,
+    pub bias: f64,
+    pub random_walk: f64,
+    pub scale_factor: f64,
+    pub quantization: f64,
+    pub limits: Option<[f64; 2]>,
+    pub lag: f64,
+}
+
+impl SensorModel {
+    pub fn with_noise(mut self, noise: f64) -> Self {
+        self.noise = noise;
+        self
+    }
+
+    pub fn with_bias(mut self, bias: f64) -> Self {
+        self.bias = bias;
+        self
+    }
+
+    pub fn with_random_walk(mut self, random_walk: f64) -> Self {
+        self.random_walk = random_walk;
+        self
+    }
+
+    pub fn with_scale_factor(mut self, scale_factor: f64) -> Self {
+        self.scale_factor = scale_factor;
+        self
+    }
+
+    pub fn with_quantization(mut self, quantization: f64) -> Self {
+        self.quantization = quantization;
+        self
+    }
+
+    pub fn with_limits(mut self, bottom: f64, top: f64) -> Self {
+        self.limits = Some([bottom, top]);
+        self
+    }
+
+    pub fn with_lag(mut self, lag: f64) -> Self {
+        self.lag = lag;
+        self
+    }
+
+    /// Read `truth` `dt` seconds after the last sample of `channel`
+    fn read(&self, channel: &mut Channel, truth: f64, dt: f64, rng: &mut Rng) -> f64 {
+        let lagged = match channel.lagged {
+            Some(lagged) if self.lag > 0.0 => {
+                lagged + (truth - lagged) * (1.0 - (-dt / self.lag).exp())
+            }
+            _ => truth,
+        };
+        channel.lagged = Some(lagged);
+        if self.random_walk > 0.0 && dt > 0.0 {
+            channel.drift += self.random_walk * dt.sqrt() * rng.normal();
+        }
+        let mut reading = (1.0 + self.scale_factor) * lagged + self.bias + channel.drift;
+        if self.noise > 0.0 {
+            reading += self.noise * rng.normal();
+        }
+        if self.quantization > 0.0 {
+            reading = (reading / self.quantization).round() * self.quantization;
+        }
+        match self.limits {
+            Some([bottom, top]) => reading.clamp(bottom, top),
+            None => reading,
+        }
+    }
+}
+
+/// The lag and the drifted bias of one channel
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+struct Channel {
+    lagged: Option<f64>,
+    drift: f64,
+}
+
+/// The channels of one sensor sampled together at `sample_rate`, from one random generator
+/// readings are held between samples, a sample rate of 0 samples on every update
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Sampler {
+    sample_rate: f64,
+    seed: u32,
+    rng: Rng,
+    channels: Vec<Channel>,
+    last_time: Option<f64>,
+    readings: Vec<f64>,
+}
+
+impl Sampler {
+    fn new(sample_rate: f64, seed: u32, n: usize) -> Self {
+        Self {
+            sample_rate,
+            seed,
+            rng: Rng::new(seed as u64),
+            channels: vec![Channel::default(); n],
+            last_time: None,
+            readings: Vec::new(),
+        }
+    }
+
+    /// Whether a sample falls in (last sample, `t`], the samples are at multiples of the period
+    fn is_due(&self, t: f64) -> bool {
+        match self.last_time {
+            None => true,
+            Some(last) if self.sample_rate <= 0.0 => t > last,
+            Some(last) => {
+                let tick = |t: f64| (t * self.sample_rate + 1e-9).floor();
+                tick(t) > tick(last)
+            }
+        }
+    }
+
+    /// Sample `truth` through `models` at `t` if a sample is due, otherwise hold
+    fn update(&mut self, t: f64, truth: &[f64], models: &[SensorModel]) -> &[f64] {
+        if self.is_due(t) {
+            let dt = self.last_time.map_or(0.0, |last| t - last);
+            self.readings = models
+                .iter()
+                .zip(truth)
+                .zip(&mut self.channels)
+                .map(|((model, truth), channel)| model.read(channel, *truth, dt, &mut self.rng))
+                .collect();
+            self.last_time = Some(t);
+        }
+        &self.readings
+    }
+
+    fn reset(&mut self) {
+        self.rng = Rng::new(self.seed as u64);
+        self.channels.fill(Channel::default());
+        self.last_time = None;
+        self.readings.clear();
+    }
+}
+
+/// Rate gyros and accelerometers at `position`, ft from the center of gravity
+/// along the body axes, x forward, y right, z down
+/// the accelerometers feel the angular and centripetal accelerations of the lever arm
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Imu {
+    pub position: [f64; 3],
+    pub gyro: SensorModel,
+    pub accel: SensorModel,
+    sampler: Sampler,
+}
+
+impl Imu {
+    /// A perfect IMU at the center of gravity
+    pub fn new(sample_rate: f64, seed: u32) -> Self {
+        Self {
+            position: [0.0; 3],
+            gyro: SensorModel::default(),
+            accel: SensorModel::default(),
+            sampler: Sampler::new(sample_rate, seed, 6),
+        }
+    }
+
+    pub fn with_position(mut self, position: [f64; 3]) -> Self {
+        self.position = position;
+        self
+    }
+
+    pub fn with_gyro(mut self, gyro: SensorModel) -> Self {
+        self.gyro = gyro;
+        self
+    }
+
+    pub fn with_accel(mut self, accel: SensorModel) -> Self {
+        self.accel = accel;
+        self
+    }
+
+    pub fn sample_rate(&self) -> f64 {
+        self.sampler.sample_rate
+    }
+
+    pub fn seed(&self) -> u32 {
+        self.sampler.seed
+    }
+
+    /// The load factors in g at `position`
+    pub fn accels(&self, state: &State, state_dot: &State, extend: &StateExtend) -> [f64; 3] {
+        let [x, y, z] = self.position;
+        let (p, q, r) = (state.p, state.q, state.r);
+        let (p_dot, q_dot, r_dot) = (state_dot.p, state_dot.q, state_dot.r);
+        // angular acceleration cross r plus omega cross omega cross r
+        let omega_r = [q * z - r * y, r * x - p * z, p * y - q * x];
+        let lever = [
+            q_dot * z - r_dot * y + q * omega_r[2] - r * omega_r[1],
+            r_dot * x - p_dot * z + r * omega_r[0] - p * omega_r[2],
+            p_dot * y - q_dot * x + p * omega_r[1] - q * omega_r[0],
+        ];
+        // nz is positive up
+        [
+            extend.nx + lever[0] / G,
+            extend.ny + lever[1] / G,
+            extend.nz - lever[2] / G,
+        ]
+    }
+
+    pub fn update(
+        &mut self,
+        t: f64,
+        state: &State,
+        state_dot: &State,
+        extend: &StateExtend,
+    ) -> ImuMeasurement {
+        let [nx, ny, nz] = self.accels(state, state_dot, extend);
+        let truth = [state.p, state.q, state.r, nx, ny, nz];
+        let models = [
+            self.gyro, self.gyro, self.gyro, self.accel, self.accel, self.accel,
+        ];
+        let r = self.sampler.update(t, &truth, &models);
+        ImuMeasurement {
+            rates: [r[0], r[1], r[2]],
+            accels: [r[3], r[4], r[5]],
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.sampler.reset();
+    }
+}
+
+/// Total pressure in front of a pitot tube at `mach` for a static pressure `ps`,
+/// behind the normal shock above Mach 1
+pub fn total_pressure(ps: f64, mach: f64) -> f64 {
+    ps * pitot_ratio(mach)
+}
+
+/// Alpha and beta vanes at `position` (ft from the center of gravity, body axes)
+/// and a pitot-static probe for Mach, static and total pressure
+/// the vanes see the flow induced by the body rates at the boom
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AirDataSensor {
+    pub position: [f64; 3],
+    pub alpha: SensorModel,
+    pub beta: SensorModel,
+    pub mach: SensorModel,
+    pub static_pressure: SensorModel,
+    pub total_pressure: SensorModel,
+    sampler: Sampler,
+}
+
+impl AirDataSensor {
+    /// A perfect air data system at the center of gravity
+    pub fn new(sample_rate: f64, seed: u32) -> Self {
+        Self {
+            position: [0.0; 3],
+            alpha: SensorModel::default(),
+            beta: SensorModel::default(),
+            mach: SensorModel::default(),
+            static_pressure: SensorModel::default(),
+            total_pressure: SensorModel::default(),
+            sampler: Sampler::new(sample_rate, seed, 5),
+        }
+    }
+
+    pub fn with_position(mut self, position: [f64; 3]) -> Self {
+        self.position = position;
+        self
+    }
+
+    pub fn with_alpha(mut self, alpha: SensorModel) -> Self {
+        self.alpha = alpha;
+        self
+    }
+
+    pub fn with_beta(mut self, beta: SensorModel) -> Self {
+        self.beta = beta;
+        self
+    }
+
+    pub fn with_mach(mut self, mach: SensorModel) -> Self {
+        self.mach = mach;
+        self
+    }
+
+    pub fn with_static_pressure(mut self, static_pressure: SensorModel) -> Self {
+        self.static_pressure = static_pressure;
+        self
+    }
+
+    pub fn with_total_pressure(mut self, total_pressure: SensorModel) -> Self {
+        self.total_pressure = total_pressure;
+        self
+    }
+
+    pub fn sample_rate(&self) -> f64 {
+        self.sampler.sample_rate
+    }
+
+    pub fn seed(&self) -> u32 {
+        self.sampler.seed
+    }
+
+    /// Alpha and beta in rad at the vanes for a plane flying at `velocity`, `alpha`
+    /// and `beta` through the air
+    pub fn vane_angles(&self, state: &State, velocity: f64, alpha: f64, beta: f64) -> [f64; 2] {
+        let [x, y, z] = self.position;
+        let (p, q, r) = (state.p, state.q, state.r);
+        let [u, v, w] = wind_to_body(velocity, alpha, beta);
+        let [_, alpha, beta] =
+            body_to_wind(u + q * z - r * y, v + r * x - p * z, w + p * y - q * x);
+        [alpha, beta]
+    }
+
+    /// `velocity`, `alpha` and `beta` are relative to the air
+    pub fn update(
+        &mut self,
+        t: f64,
+        state: &State,
+        extend: &StateExtend,
+        velocity: f64,
+        alpha: f64,
+        beta: f64,
+    ) -> AirDataMeasurement {
+        let [alpha, beta] = self.vane_angles(state, velocity, alpha, beta);
+        let pt = total_pressure(extend.ps, extend.mach);
+        let truth = [alpha, beta, extend.mach, extend.ps, pt];
+        let models = [
+            self.alpha,
+            self.beta,
+            self.mach,
+            self.static_pressure,
+            self.total_pressure,
+        ];
+        let r = self.sampler.update(t, &truth, &models);
+        AirDataMeasurement {
+            alpha: r[0],
+            beta: r[1],
+            mach: r[2],
+            static_pressure: r[3],
+            total_pressure: r[4],
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.sampler.reset();
+    }
+}
+
+/// An attitude and heading reference, `attitude` models phi and theta, `heading` psi
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Ahrs {
+    pub attitude: SensorModel,
+    pub heading: SensorModel,
+    sampler: Sampler,
+}
+
+impl Ahrs {
+    /// A perfect attitude and heading reference
+    pub fn new(sample_rate: f64, seed: u32) -> Self {
+        Self {
+            attitude: SensorModel::default(),
+            heading: SensorModel::default(),
+            sampler: Sampler::new(sample_rate, seed, 3),
+        }
+    }
+
+    pub fn with_attitude(mut self, attitude: SensorModel) -> Self {
+        self.attitude = attitude;
+        self
+    }
+
+    pub fn with_heading(mut self, heading: SensorModel) -> Self {
+        self.heading = heading;
+        self
+    }
+
+    pub fn sample_rate(&self) -> f64 {
+        self.sampler.sample_rate
+    }
+
+    pub fn seed(&self) -> u32 {
+        self.sampler.seed
+    }
+
+    pub fn update(&mut self, t: f64, state: &State) -> AhrsMeasurement {
+        let truth = [state.phi, state.theta, state.psi];
+        let models = [self.attitude, self.attitude, self.heading];
+        let r = self.sampler.update(t, &truth, &models);
+        AhrsMeasurement {
+            phi: r[0],
+            theta: r[1],
+            psi: r[2],
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.sampler.reset();
+    }
+}
+
+/// The sensors of a plane, each with its own sample rate and random generator
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Sensors {
+    pub imu: Option<Imu>,
+    pub air_data: Option<AirDataSensor>,
+    pub ahrs: Option<Ahrs>,
+    measurements: Measurements,
+}
+
+impl Sensors {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_imu(mut self, imu: Imu) -> Self {
+        self.imu = Some(imu);
+        self
+    }
+
+    pub fn with_air_data(mut self, air_data: AirDataSensor) -> Self {
+        self.air_data = Some(air_data);
+        self
+    }
+
+    pub fn with_ahrs(mut self, ahrs: Ahrs) -> Self {
+        self.ahrs = Some(ahrs);
+        self
+    }
+
+    /// The readings of the last update
+    pub fn measurements(&self) -> Measurements {
+        self.measurements
+    }
+
+    /// Sample the sensors that are due at `t` (s), `air` is the airspeed, alpha and beta
+    /// relative to the air
+    pub fn update(
+        &mut self,
+        t: f64,
+        state: &State,
+        state_dot: &State,
+        extend: &StateExtend,
+        air: [f64; 3],
+    ) -> Measurements {
+        let [velocity, alpha, beta] = air;
+        self.measurements = Measurements {
+            imu: self
+                .imu
+                .as_mut()
+                .map(|imu| imu.update(t, state, state_dot, extend)),
+            air_data: self
+                .air_data
+                .as_mut()
+                .map(|a| a.update(t, state, extend, velocity, alpha, beta)),
+            ahrs: self.ahrs.as_mut().map(|ahrs| ahrs.update(t, state)),
+        };
+        self.measurements
+    }
+
+    /// Back to the initial seeds, without lag, drift or held readings
+    pub fn reset(&mut self) {
+        if let Some(imu) = &mut self.imu {
+            imu.reset();
+        }
+        if let Some(air_data) = &mut self.air_data {
+            air_data.reset();
+        }
+        if let Some(ahrs) = &mut self.ahrs {
+            ahrs.reset();
+        }
+        self.measurements = Measurements::default();
+    }
+}
+
+#[cfg(test)]
+mod sensor_tests {
+    use super::*;
+
+    fn statistics(samples: &[f64]) -> (f64, f64) {
+        let n = samples.len() as f64;
+        let mean = samples.iter().sum::<f64>() / n;
+        let variance = samples.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / n;
+        (mean, variance.sqrt())
+    }
+
+    #[test]
+    fn test_sensor_model() {
+        let mut rng = Rng::new(7);
+        let read = |model: SensorModel, truth: &[f64], dt: f64, rng: &mut Rng| -> Vec<f64> {
+            let mut channel = Channel::default();
+            truth
+                .iter()
+                .map(|t| model.read(&mut channel, *t, dt, rng))
+                .collect()
+        };
+
+        // perfect by default
+        let perfect = read(SensorModel::default(), &[1.0, -2.0], 0.01, &mut rng);
+        assert_eq!(perfect, vec![1.0, -2.0]);
+
+        let model = SensorModel::default()
+            .with_noise(0.1)
+            .with_bias(0.5)
+            .with_scale_factor(0.02);
+        let readings = read(model, &[10.0; 20000], 0.01, &mut rng);
+        let (mean, sigma) = statistics(&readings);
+        assert!((mean - 10.7).abs() < 0.01, "{mean}");
+        assert!((sigma - 0.1).abs() < 0.005, "{sigma}");
+
+        // the drift grows with the square root of time
+        let model = SensorModel::default().with_random_walk(0.2);
+        let ends: Vec<f64> = (0..2000)
+            .map(|_| *read(model, &[0.0; 101], 0.04, &mut rng).last().unwrap())
+            .collect();
+        let (_, sigma) = statistics(&ends);
+        assert!((sigma - 0.4).abs() < 0.03, "{sigma}");
+
+        let model = SensorModel::default()
+            .with_quantization(0.25)
+            .with_limits(-1.0, 1.0);
+        let readings = read(model, &[0.3, 0.4, 5.0, -5.0], 0.01, &mut rng);
+        assert_eq!(readings, vec![0.25, 0.5, 1.0, -1.0]);
+
+        // a first-order lag reaches 63 % after one time constant
+        let model = SensorModel::default().with_lag(0.5);
+        let mut step = vec![0.0];
+        step.extend([1.0; 50]);
+        let readings = read(model, &step, 0.01, &mut rng);
+        assert!((readings[50] - (1.0 - (-1.0f64).exp())).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_sensors() {
+        let state = State {
+            velocity: 500.0,
+            alpha: 0.1,
+            theta: 0.1,
+            psi: 1.0,
+            p: 0.2,
+            q: 0.1,
+            r: -0.05,
+            ..Default::default()
+        };
+        let state_dot = State {
+            p: 1.0,
+            q: -0.5,
+            r: 0.2,
+            ..Default::default()
+        };
+        let extend = StateExtend {
+            nz: 1.0,
+            mach: 0.5,
+            ps: 1455.6,
+            ..Default::default()
+        };
+        let air = [500.0, 0.1, 0.0];
+
+        // perfect sensors at the center of gravity read the truth
+        let mut sensors = Sensors::new()
+            .with_imu(Imu::new(0.0, 1))
+            .with_air_data(AirDataSensor::new(0.0, 2))
+            .with_ahrs(Ahrs::new(0.0, 3));
+        let m = sensors.update(0.0, &state, &state_dot, &extend, air);
+        let imu = m.imu.unwrap();
+        assert_eq!(imu.rates, [0.2, 0.1, -0.05]);
+        assert_eq!(imu.accels, [0.0, 0.0, 1.0]);
+        let air_data = m.air_data.unwrap();
+        assert!((air_data.alpha - 0.1).abs() < 1e-12 && air_data.beta.abs() < 1e-12);
+        assert!((air_data.total_pressure / air_data.static_pressure - 1.186).abs() < 1e-3);
+        assert_eq!(m.ahrs.unwrap().psi, 1.0);
+
+        // an accelerometer ahead of the center of gravity feels the pitch acceleration,
+        // a vane ahead of it the pitch rate
+        let imu = Imu::new(0.0, 1).with_position([10.0, 0.0, 0.0]);
+        let [nx, _, nz] = imu.accels(&state, &state_dot, &extend);
+        // q_dot * x less the centripetal p * (r * x)
+        assert!((nz - (1.0 - 4.9 / G)).abs() < 1e-12, "{nz}");
+        assert!(nx < 0.0);
+        let vane = AirDataSensor::new(0.0, 2).with_position([15.0, 0.0, 0.0]);
+        let [alpha, beta] = vane.vane_angles(&state, 500.0, 0.1, 0.0);
+        assert!((alpha - (0.1 - 0.1 * 15.0 / 500.0)).abs() < 1e-3);
+        assert!(beta < 0.0);
+
+        // supersonic, the pitot reads behind the normal shock
+        assert!((total_pressure(1.0, 2.0) - 5.6404).abs() < 1e-3);
+        assert!((total_pressure(1.0, 1.0 + 1e-9) - total_pressure(1.0, 1.0)).abs() < 1e-6);
+
+        // readings are held between samples, the same seed repeats the same noise
+        let noisy = SensorModel::default().with_noise(0.01);
+        let imu = Imu::new(50.0, 42).with_gyro(noisy).with_accel(noisy);
+        let mut a = Sensors::new().with_imu(imu.clone());
+        let mut b = Sensors::new().with_imu(imu);
+        let run = |s: &mut Sensors| -> Vec<[f64; 3]> {
+            (0..=20)
+                .map(|i| {
+                    let t = 0.005 * i as f64;
+                    s.update(t, &state, &state_dot, &extend, air)
+                        .imu
+                        .unwrap()
+                        .rates
+                })
+                .collect()
+        };
+        let readings = run(&mut a);
+        assert_eq!(readings, run(&mut b));
+        assert_eq!(readings[1], readings[0]);
+        assert_eq!(readings[3], readings[0]);
+        assert_ne!(readings[4], readings[0]);
+        assert_eq!(readings[5], readings[4]);
+
+        let text = toml::to_string(&a).unwrap();
+        a.reset();
+        assert_eq!(run(&mut a), readings);
+        let mut c: Sensors = toml::from_str(&text).unwrap();
+        b.update(0.12, &state, &state_dot, &extend, air);
+        assert_eq!(
+            c.update(0.12, &state, &state_dot, &extend, air),
+            b.measurements()
+        );
+        assert_ne!(Imu::new(50.0, 43).seed(), a.imu.as_ref().unwrap().seed());
+    }
+}
@@ -0,0 +1,379 @@
+use crate::utils::error::FatalCoreError;
+use std::f64::consts::PI;
+
+/// How a chirp sweeps from its start to its end frequency
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Sweep {
+    #[default]
+    Linear,
+    Logarithmic,
+}
+
+/// maximal-length feedback taps of an LFSR of order 2 to 16
+const PRBS_TAPS: [&[u32]; 15] = [
+    &[2, 1],
+    &[3, 2],
+    &[4, 3],
+    &[5, 3],
+    &[6, 5],
+    &[7, 6],
+    &[8, 6, 5, 4],
+    &[9, 5],
+    &[10, 7],
+    &[11, 9],
+    &[12, 11, 10, 4],
+    &[13, 12, 11, 8],
+    &[14, 13, 12, 2],
+    &[15, 14],
+    &[16, 15, 13, 4],
+];
+
+/// The shape of a signal within its window
+/// Pulse: pulses of `width` s every `period` s
+/// Multistep3211: +3, -2, +1, -1 units, a unit is a seventh of the duration
+/// Chirp: a sine sweeping from `f_0` to `f_1` in Hz
+/// Multisine: cosines at `frequencies` in Hz with Schroeder phases,
+/// each with amplitude / sqrt(n) so the rms is that of a single sine
+/// Prbs: a maximal-length binary sequence of ±1 clocked every `clock` s
+/// Sum: the sum of other signals
+#[derive(Debug, Clone, PartialEq)]
+pub enum Waveform {
+    Step,
+    Ramp,
+    Pulse {
+        period: f64,
+        width: f64,
+    },
+    Doublet,
+    Multistep3211,
+    Chirp {
+        f_0: f64,
+        f_1: f64,
+        sweep: Sweep,
+    },
+    Multisine {
+        frequencies: Vec<f64>,
+        phases: Vec<f64>,
+    },
+    Prbs {
+        clock: f64,
+        sequence: Vec<bool>,
+    },
+    Sum(Vec<Signal>),
+}
+
+/// An excitation signal, zero before `start_time` and active for `duration` s,
+/// after the window a ramp holds its final value and other signals return to zero
+#[derive(Debug, Clone, PartialEq)]
+pub struct Signal {
+    pub waveform: Waveform,
+    pub start_time: f64,
+    pub amplitude: f64,
+    pub duration: f64,
+}
+
+impl Signal {
+    pub fn new(waveform: Waveform, start_time: f64, amplitude: f64, duration: f64) -> Self {
+        Self {
+            waveform,
+            start_time,
+            amplitude,
+            duration,
+        }
+    }
+
+    /// `amplitude` from `start_time` for `duration` s, use `f64::INFINITY` for a plain step
+    pub fn step(start_time: f64, amplitude: f64, duration: f64) -> Self {
+        Self::new(Waveform::Step, start_time, amplitude, duration)
+    }
+
+    /// Rise linearly to `amplitude` over `duration` s and hold it
+    pub fn ramp(start_time: f64, amplitude: f64, duration: f64) -> Self {
+        Self::new(Waveform::Ramp, start_time, amplitude, duration)
+    }
+
+    pub fn pulse(start_time: f64, amplitude: f64, duration: f64, period: f64, width: f64) -> Self {
+        Self::new(
+            Waveform::Pulse { period, width },
+            start_time,
+            amplitude,
+            duration,
+        )
+    }
+
+    /// `amplitude` for half of `duration`, then `-amplitude` for the other half
+    pub fn doublet(start_time: f64, amplitude: f64, duration: f64) -> Self {
+        Self::new(Waveform::Doublet, start_time, amplitude, duration)
+    }
+
+    pub fn multistep_3211(start_time: f64, amplitude: f64, duration: f64) -> Self {
+        Self::new(Waveform::Multistep3211, start_time, amplitude, duration)
+    }
+
+    pub fn chirp(
+        start_time: f64,
+        amplitude: f64,
+        duration: f64,
+        f_0: f64,
+        f_1: f64,
+        sweep: Sweep,
+    ) -> Result<Self, FatalCoreError> {
+        if sweep == Sweep::Logarithmic && (f_0 <= 0.0 || f_1 <= 0.0) {
+            return Err(FatalCoreError::InvalidInput(
+                "a logarithmic chirp needs positive frequencies".to_string(),
+            ));
+        }
+        if !duration.is_finite() || duration <= 0.0 {
+            return Err(FatalCoreError::InvalidInput(
+                "a chirp needs a finite positive duration".to_string(),
+            ));
+        }
+        Ok(Self::new(
+            Waveform::Chirp { f_0, f_1, sweep },
+            start_time,
+            amplitude,
+            duration,
+        ))
+    }
+
+    pub fn multisine(
+        start_time: f64,
+        amplitude: f64,
+        duration: f64,
+        frequencies: Vec<f64>,
+    ) -> Result<Self, FatalCoreError> {
+        if frequencies.is_empty() {
+            return Err(FatalCoreError::InvalidInput(
+                "a multisine needs at least one frequency".to_string(),
+            ));
+        }
+        let n = frequencies.len() as f64;
+        let phases = (1..=frequencies.len())
+            .map(|k| -PI * (k * (k - 1)) as f64 / n)
+            .collect();
+        Ok(Self::new(
+            Waveform::Multisine {
+                frequencies,
+                phases,
+            },
+            start_time,
+            amplitude,
+            duration,
+        ))
+    }
+
+    /// A sequence of `2^order - 1` bits from a linear feedback shift register, `order` in 2..=16
+    pub fn prbs(
+        start_time: f64,
+        amplitude: f64,
+        duration: f64,
+        clock: f64,
+        order: u32,
+    ) -> Result<Self, FatalCoreError> {
+        if !(2..=16).contains(&order) || clock <= 0.0 {
+            return Err(FatalCoreError::InvalidInput(format!(
+                "a PRBS needs an order in 2..=16 and a positive clock, got {} and {}",
+                order, clock
+            )));
+        }
+        let taps = PRBS_TAPS[order as usize - 2];
+        let mut register: u32 = 1;
+        let sequence = (0..(1u32 << order) - 1)
+            .map(|_| {
+                let bit = taps
+                    .iter()
+                    .fold(0, |acc, tap| acc ^ (register >> (tap - 1)) & 1);
+                register = ((register << 1) | bit) & ((1 << order) - 1);
+                bit == 1
+            })
+            .collect();
+        Ok(Self::new(
+            Waveform::Prbs { clock, sequence },
+            start_time,
+            amplitude,
+            duration,
+        ))
+    }
+
+    /// The sum of `signals`, active from 0 with unit amplitude
+    pub fn sum(signals: Vec<Signal>) -> Self {
+        Self::new(Waveform::Sum(signals), 0.0, 1.0, f64::INFINITY)
+    }
+
+    pub fn value(&self, t: f64) -> f64 {
+        let tau = t - self.start_time;
+        if tau < 0.0 {
+            return 0.0;
+        }
+        let d = self.duration;
+        let a = self.amplitude;
+        if tau > d {
+            return match self.waveform {
+                Waveform::Ramp => a,
+                _ => 0.0,
+            };
+        }
+        match &self.waveform {
+            Waveform::Step => a,
+            Waveform::Ramp if d > 0.0 => a * tau / d,
+            Waveform::Ramp => a,
+            Waveform::Pulse { period, width } => {
+                if tau % period < *width {
+                    a
+                } else {
+                    0.0
+                }
+            }
+            Waveform::Doublet => {
+                if tau < 0.5 * d {
+                    a
+                } else {
+                    -a
+                }
+            }
+            Waveform::Multistep3211 => {
+                let unit = tau / d * 7.0;
+                if unit < 3.0 {
+                    a
+                } else if unit < 5.0 {
+                    -a
+                } else if unit < 6.0 {
+                    a
+                } else {
+                    -a
+                }
+            }
+            Waveform::Chirp { f_0, f_1, sweep } => {
+                let phase = match sweep {
+                    Sweep::Linear => 2.0 * PI * (f_0 * tau + (f_1 - f_0) * tau * tau / (2.0 * d)),
+                    Sweep::Logarithmic => {
+                        let k = f_1 / f_0;
+                        if (k - 1.0).abs() < 1e-12 {
+                            2.0 * PI * f_0 * tau
+                        } else {
+                            2.0 * PI * f_0 * d / k.ln() * (k.powf(tau / d) - 1.0)
+                        }
+                    }
+                };
+                a * phase.sin()
+            }
+            Waveform::Multisine {
+                frequencies,
+                phases,
+            } => {
+                let sum: f64 = frequencies
+                    .iter()
+                    .zip(phases)
+                    .map(|(f, phi)| (2.0 * PI * f * tau + phi).cos())
+                    .sum();
+                a * sum / (frequencies.len() as f64).sqrt()
+            }
+            Waveform::Prbs { clock, sequence } => {
+                let index = (tau / clock) as usize % sequence.len();
+                if sequence[index] {
+                    a
+                } else {
+                    -a
+                }
+            }
+            Waveform::Sum(signals) => a * signals.iter().map(|s| s.value(tau)).sum::<f64>(),
+        }
+    }
+}
+
+impl std::ops::Add for Signal {
+    type Output = Signal;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        match self.waveform {
+            Waveform::Sum(mut signals)
+                if self.start_time == 0.0
+                    && self.amplitude == 1.0
+                    && self.duration.is_infinite() =>
+            {
+                signals.push(rhs);
+                Signal::sum(signals)
+            }
+            _ => Signal::sum(vec![self, rhs]),
+        }
+    }
+}
+
+#[cfg(test)]
+mod signal_tests {
+    use super::*;
+
+    #[test]
+    fn test_shapes() {
+        let step = Signal::step(1.0, 2.0, f64::INFINITY);
+        assert_eq!(step.value(0.5), 0.0);
+        assert_eq!(step.value(100.0), 2.0);
+
+        let ramp = Signal::ramp(1.0, 2.0, 4.0);
+        assert_eq!(ramp.value(3.0), 1.0);
+        assert_eq!(ramp.value(10.0), 2.0);
+
+        let pulse = Signal::pulse(0.0, 1.0, 10.0, 2.0, 0.5);
+        assert_eq!(pulse.value(4.25), 1.0);
+        assert_eq!(pulse.value(4.75), 0.0);
+        assert_eq!(pulse.value(10.25), 0.0);
+
+        let doublet = Signal::doublet(1.0, 3.0, 4.0);
+        assert_eq!(doublet.value(2.0), 3.0);
+        assert_eq!(doublet.value(4.0), -3.0);
+        assert_eq!(doublet.value(5.5), 0.0);
+
+        let multistep = Signal::multistep_3211(0.0, 1.0, 7.0);
+        let values: Vec<f64> = (0..7).map(|i| multistep.value(i as f64 + 0.5)).collect();
+        assert_eq!(values, [1.0, 1.0, 1.0, -1.0, -1.0, 1.0, -1.0]);
+
+        // signals compose by addition
+        let sum = Signal::step(0.0, 1.0, f64::INFINITY) + doublet.clone() + ramp;
+        assert_eq!(sum.value(2.0), 1.0 + 3.0 + 0.5);
+        assert!(matches!(&sum.waveform, Waveform::Sum(s) if s.len() == 3));
+    }
+
+    #[test]
+    fn test_spectral() {
+        // the number of zero crossings follows the phase swept over the window
+        let crossings = |signal: &Signal| {
+            let samples: Vec<f64> = (1..100000).map(|i| signal.value(i as f64 * 1e-4)).collect();
+            samples.windows(2).filter(|w| w[0] * w[1] < 0.0).count() as f64
+        };
+        let linear = Signal::chirp(0.0, 1.0, 10.0, 0.1, 2.0, Sweep::Linear).unwrap();
+        assert!((crossings(&linear) - 2.0 * 10.0 * 1.05).abs() <= 1.0);
+        let log = Signal::chirp(0.0, 1.0, 10.0, 0.1, 2.0, Sweep::Logarithmic).unwrap();
+        let cycles = 0.1 * 10.0 * 19.0 / 20.0_f64.ln();
+        assert!((crossings(&log) - 2.0 * cycles).abs() <= 1.0);
+        assert_eq!(log.value(0.0), 0.0);
+        assert!(Signal::chirp(0.0, 1.0, 10.0, 0.0, 2.0, Sweep::Logarithmic).is_err());
+
+        // Schroeder phases keep the peak of many sines low
+        let frequencies: Vec<f64> = (1..=20).map(|k| 0.1 * k as f64).collect();
+        let multisine = Signal::multisine(0.0, 1.0, 10.0, frequencies).unwrap();
+        let samples: Vec<f64> = (0..10000)
+            .map(|i| multisine.value(i as f64 * 1e-3))
+            .collect();
+        let peak = samples.iter().fold(0.0_f64, |p, x| p.max(x.abs()));
+        let rms = (samples.iter().map(|x| x * x).sum::<f64>() / samples.len() as f64).sqrt();
+        assert!((rms - 0.5_f64.sqrt()).abs() < 0.05, "{rms}");
+        assert!(peak < 2.0 * rms, "{peak}");
+
+        // maximal length sequences repeat after 2^n - 1 bits and are nearly balanced
+        for order in 2..=16 {
+            let prbs = Signal::prbs(0.0, 1.0, f64::INFINITY, 1.0, order).unwrap();
+            let Waveform::Prbs { sequence, .. } = &prbs.waveform else {
+                unreachable!()
+            };
+            let ones = sequence.iter().filter(|b| **b).count();
+            assert_eq!(ones, 1 << (order - 1), "{order}");
+            // no shorter period divides the sequence
+            let n = sequence.len();
+            let periodic = (1..n)
+                .filter(|p| n % p == 0)
+                .any(|p| (0..n).all(|i| sequence[i] == sequence[(i + p) % n]));
+            assert!(!periodic, "{order}");
+        }
+        assert!(Signal::prbs(0.0, 1.0, 1.0, 1.0, 17).is_err());
+    }
+}
@@ -0,0 +1,434 @@
+use super::flight::{ned_to_body, Orientation, Vector3};
+use crate::model::{body_to_wind, wind_to_body, State};
+use crate::utils::Rng;
+use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
+
+/// The velocity of the air around the plane in ft/s
+/// ned: steady wind and shear in north-east-down
+/// body: gusts and turbulence along the body axes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct WindSample {
+    pub ned: [f64; 3],
+    pub body: [f64; 3],
+}
+
+impl WindSample {
+    pub fn is_calm(&self) -> bool {
+        self.ned == [0.0; 3] && self.body == [0.0; 3]
+    }
+
+    /// The whole wind along the body axes of a plane in `state`
+    pub fn body_velocity(&self, state: &State) -> [f64; 3] {
+        let [n, e, d] = self.ned;
+        let orientation = Orientation::from(state);
+        let ned = ned_to_body(&orientation, &Vector3::new(n, e, d));
+        [
+            ned.x + self.body[0],
+            ned.y + self.body[1],
+            ned.z + self.body[2],
+        ]
+    }
+}
+
+/// Airspeed (ft/s), alpha and beta (rad) of the plane relative to the air,
+/// the state holds the velocity relative to the ground
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct AirRelative {
+    pub velocity: f64,
+    pub alpha: f64,
+    pub beta: f64,
+}
+
+impl AirRelative {
+    pub fn new(state: &State, wind: &WindSample) -> Self {
+        if wind.is_calm() {
+            return Self {
+                velocity: state.velocity,
+                alpha: state.alpha,
+                beta: state.beta,
+            };
+        }
+        let [u, v, w] = wind_to_body(state.velocity, state.alpha, state.beta);
+        let [u_w, v_w, w_w] = wind.body_velocity(state);
+        let [velocity, alpha, beta] = body_to_wind(u - u_w, v - v_w, w - w_w);
+        Self {
+            velocity,
+            alpha,
+            beta,
+        }
+    }
+}
+
+/// The logarithmic wind shear of MIL-F-8785C below 1000 ft, held above it
+/// wind_20: wind speed at 20 ft in ft/s
+/// direction: where the wind blows from in rad, clockwise from north
+/// roughness: 0.15 ft for a landing approach, 2 ft otherwise
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Shear {
+    pub wind_20: f64,
+    pub direction: f64,
+    pub roughness: f64,
+}
+
+impl Shear {
+    pub fn new(wind_20: f64, direction: f64, roughness: f64) -> Self {
+        Self {
+            wind_20,
+            direction,
+            roughness,
+        }
+    }
+
+    /// The wind in north-east-down at `altitude` above the ground in ft
+    pub fn velocity(&self, altitude: f64) -> [f64; 3] {
+        let h = altitude.clamp(self.roughness, 1000.0);
+        let speed = self.wind_20 * (h / self.roughness).ln() / (20.0 / self.roughness).ln();
+        let (s, c) = self.direction.sin_cos();
+        [-speed * c, -speed * s, 0.0]
+    }
+}
+
+/// A discrete 1-cosine gust along the body axes, penetrated at the current airspeed
+/// start_time: in s, relative to the start of the run
+/// gradient: the distance to the peak in ft, the gust is twice as long
+/// amplitude: peak gust velocity along the body axes in ft/s, an updraft has a negative w
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Gust {
+    pub start_time: f64,
+    pub gradient: f64,
+    pub amplitude: [f64; 3],
+}
+
+impl Gust {
+    pub fn new(start_time: f64, gradient: f64, amplitude: [f64; 3]) -> Self {
+        Self {
+            start_time,
+            gradient,
+            amplitude,
+        }
+    }
+
+    /// The gust velocity at `t` for a true airspeed `tas` in ft/s
+    pub fn velocity(&self, t: f64, tas: f64) -> [f64; 3] {
+        let s = tas * (t - self.start_time);
+        if s <= 0.0 || s >= 2.0 * self.gradient {
+            return [0.0; 3];
+        }
+        let shape = 0.5 * (1.0 - (PI * s / self.gradient).cos());
+        self.amplitude.map(|a| a * shape)
+    }
+}
+
+/// The power spectrum of continuous turbulence
+/// VonKarman: the rational approximation of MIL-HDBK-1797
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Spectrum {
+    #[default]
+    Dryden,
+    VonKarman,
+}
+
+impl std::fmt::Display for Spectrum {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Dryden => write!(f, "dryden"),
+            Self::VonKarman => write!(f, "von karman"),
+        }
+    }
+}
+
+/// Continuous turbulence along the body axes, white noise through shaping filters
+/// below 1000 ft the intensities and scale lengths follow the low altitude model of
+/// MIL-F-8785C with `wind_20` the wind speed at 20 ft, above 2000 ft the turbulence is
+/// isotropic with `intensity` (ft/s), linearly blended in between
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Turbulence {
+    spectrum: Spectrum,
+    wind_20: f64,
+    intensity: f64,
+    seed: u32,
+    rng: Rng,
+    filters: [Vec<f64>; 3],
+}
+
+impl Turbulence {
+    /// The intensity above 2000 ft is that of the low altitude model at 1000 ft
+    pub fn new(spectrum: Spectrum, wind_20: f64, seed: u32) -> Self {
+        let order = match spectrum {
+            Spectrum::Dryden => [1, 2, 2],
+            Spectrum::VonKarman => [2, 3, 3],
+        };
+        Self {
+            spectrum,
+            wind_20,
+            intensity: 0.1 * wind_20,
+            seed,
+            rng: Rng::new(seed as u64),
+            filters: order.map(|n| vec![0.0; n]),
+        }
+    }
+
+    pub fn with_intensity(mut self, intensity: f64) -> Self {
+        self.intensity = intensity;
+        self
+    }
+
+    pub fn spectrum(&self) -> Spectrum {
+        self.spectrum
+    }
+
+    pub fn seed(&self) -> u32 {
+        self.seed
+    }
+
+    /// Intensities and scale lengths in ft of u, v and w at `altitude` in ft
+    pub fn scales(&self, altitude: f64) -> ([f64; 3], [f64; 3]) {
+        let low = |h: f64| {
+            let h = h.clamp(10.0, 1000.0);
+            let k = 0.177 + 0.000823 * h;
+            let sigma_w = 0.1 * self.wind_20;
+            let sigma_u = sigma_w / k.powf(0.4);
+            let length_u = h / k.powf(1.2);
+            ([sigma_u, sigma_u, sigma_w], [length_u, length_u, h])
+        };
+        let length = match self.spectrum {
+            Spectrum::Dryden => 1750.0,
+            Spectrum::VonKarman => 2500.0,
+        };
+        let high = ([self.intensity; 3], [length; 3]);
+        if altitude <= 1000.0 {
+            return low(altitude);
+        }
+        if altitude >= 2000.0 {
+            return high;
+        }
+        let f = (altitude - 1000.0) / 1000.0;
+        let (sigma, length) = low(1000.0);
+        let blend = |a: [f64; 3], b: [f64; 3]| [0, 1, 2].map(|i| a[i] + f * (b[i] - a[i]));
+        (blend(sigma, high.0), blend(length, high.1))
+    }
+
+    /// gain, numerator and denominator in ascending powers of s of the filter of `axis`
+    fn filter(&self, axis: usize, sigma: f64, length: f64, tas: f64) -> (f64, Vec<f64>, Vec<f64>) {
+        let tau = length / tas;
+        match (self.spectrum, axis) {
+            (Spectrum::Dryden, 0) => (sigma * (2.0 * tau / PI).sqrt(), vec![1.0], vec![1.0, tau]),
+            (Spectrum::Dryden, _) => (
+                sigma * (tau / PI).sqrt(),
+                vec![1.0, 3.0_f64.sqrt() * tau],
+                vec![1.0, 2.0 * tau, tau * tau],
+            ),
+            (Spectrum::VonKarman, 0) => (
+                sigma * (2.0 * tau / PI).sqrt(),
+                vec![1.0, 0.25 * tau],
+                vec![1.0, 1.357 * tau, 0.1987 * tau * tau],
+            ),
+            (Spectrum::VonKarman, _) => (
+                sigma * (tau / PI).sqrt(),
+                vec![1.0, 2.7478 * tau, 0.3398 * tau * tau],
+                vec![1.0, 2.9958 * tau, 1.9754 * tau * tau, 0.1539 * tau.powi(3)],
+            ),
+        }
+    }
+
+    /// Advance the filters by `dt` and return the turbulence along the body axes
+    /// the white noise is held over `dt`, the filters are sub-stepped well below their time constants
+    pub fn step(&mut self, dt: f64, altitude: f64, tas: f64) -> [f64; 3] {
+        let tas = tas.max(1.0);
+        let (sigma, length) = self.scales(altitude);
+        let mut output = [0.0; 3];
+        for axis in 0..3 {
+            let (gain, num, den) = self.filter(axis, sigma[axis], length[axis], tas);
+            let n = den.len() - 1;
+            let a: Vec<f64> = den[..n].iter().map(|d| d / den[n]).collect();
+            if dt > 0.0 {
+                // white noise with a one-sided spectrum of 1 per rad/s
+                let noise = (PI / dt).sqrt() * self.rng.normal();
+                let dynamics = |x: &[f64]| -> Vec<f64> {
+                    let mut x_dot: Vec<f64> = x[1..].to_vec();
+                    x_dot.push(noise - a.iter().zip(x).map(|(a, x)| a * x).sum::<f64>());
+                    x_dot
+                };
+                let tau = length[axis] / tas;
+                let substeps = (dt / (0.2 * tau)).ceil().max(1.0) as usize;
+                let h = dt / substeps as f64;
+                let x = &mut self.filters[axis];
+                for _ in 0..substeps {
+                    let k_1 = dynamics(x);
+                    let stage = |k: &[f64], c: f64| -> Vec<f64> {
+                        x.iter().zip(k).map(|(x, k)| x + c * h * k).collect()
+                    };
+                    let k_2 = dynamics(&stage(&k_1, 0.5));
+                    let k_3 = dynamics(&stage(&k_2, 0.5));
+                    let k_4 = dynamics(&stage(&k_3, 1.0));
+                    for i in 0..n {
+                        x[i] += h / 6.0 * (k_1[i] + 2.0 * k_2[i] + 2.0 * k_3[i] + k_4[i]);
+                    }
+                }
+            }
+            let x = &self.filters[axis];
+            output[axis] = gain * num.iter().zip(x).map(|(b, x)| b * x).sum::<f64>() / den[n];
+        }
+        output
+    }
+
+    /// Back to still filters and the initial seed
+    pub fn reset(&mut self) {
+        self.rng = Rng::new(self.seed as u64);
+        self.filters.iter_mut().for_each(|x| x.fill(0.0));
+    }
+}
+
+/// The air a plane flies through, steady wind, shear, discrete gusts and turbulence
+/// steady: the velocity of the air in north-east-down in ft/s
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WindModel {
+    pub steady: [f64; 3],
+    pub shear: Option<Shear>,
+    pub gusts: Vec<Gust>,
+    pub turbulence: Option<Turbulence>,
+    sample: WindSample,
+}
+
+impl WindModel {
+    pub fn new(steady: [f64; 3]) -> Self {
+        Self {
+            steady,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_shear(mut self, shear: Shear) -> Self {
+        self.shear = Some(shear);
+        self
+    }
+
+    pub fn with_gust(mut self, gust: Gust) -> Self {
+        self.gusts.push(gust);
+        self
+    }
+
+    pub fn with_turbulence(mut self, turbulence: Turbulence) -> Self {
+        self.turbulence = Some(turbulence);
+        self
+    }
+
+    /// The wind of the last update
+    pub fn sample(&self) -> WindSample {
+        self.sample
+    }
+
+    /// The wind at `t` (s) for a plane at `altitude` (ft) and true airspeed `tas` (ft/s),
+    /// the turbulence is first advanced by `dt`
+    pub fn update(&mut self, t: f64, dt: f64, altitude: f64, tas: f64) -> WindSample {
+        let mut ned = self.steady;
+        if let Some(shear) = &self.shear {
+            let s = shear.velocity(altitude);
+            (0..3).for_each(|i| ned[i] += s[i]);
+        }
+        let mut body = match &mut self.turbulence {
+            Some(turbulence) => turbulence.step(dt, altitude, tas),
+            None => [0.0; 3],
+        };
+        for gust in &self.gusts {
+            let g = gust.velocity(t, tas);
+            (0..3).for_each(|i| body[i] += g[i]);
+        }
+        self.sample = WindSample { ned, body };
+        self.sample
+    }
+
+    pub fn reset(&mut self) {
+        if let Some(turbulence) = &mut self.turbulence {
+            turbulence.reset();
+        }
+        self.sample = WindSample::default();
+    }
+}
+
+#[cfg(test)]
+mod wind_tests {
+    use super::*;
+
+    #[test]
+    fn test_shear_and_gust() {
+        // from the west, blowing east
+        let shear = Shear::new(30.0, -PI / 2.0, 2.0);
+        let at_20 = shear.velocity(20.0);
+        assert!(at_20[0].abs() < 1e-12);
+        assert!((at_20[1] - 30.0).abs() < 1e-12);
+        assert!(shear.velocity(500.0)[1] > shear.velocity(100.0)[1]);
+        assert_eq!(shear.velocity(5000.0), shear.velocity(1000.0));
+        assert_eq!(shear.velocity(1.0), [0.0, 0.0, 0.0]);
+
+        let gust = Gust::new(1.0, 100.0, [0.0, 0.0, -20.0]);
+        assert_eq!(gust.velocity(0.5, 500.0), [0.0; 3]);
+        assert!((gust.velocity(1.2, 500.0)[2] + 20.0).abs() < 1e-9);
+        assert_eq!(gust.velocity(1.5, 500.0), [0.0; 3]);
+
+        let mut wind = WindModel::new([10.0, 0.0, 0.0]).with_gust(gust);
+        let sample = wind.update(1.1, 0.01, 1000.0, 500.0);
+        assert_eq!(sample.ned, [10.0, 0.0, 0.0]);
+        assert!((sample.body[2] + 10.0).abs() < 1e-9);
+
+        // the plane flies north into a 10 ft/s tailwind
+        let state = State {
+            velocity: 500.0,
+            ..Default::default()
+        };
+        let calm = WindSample::default();
+        assert_eq!(AirRelative::new(&state, &calm).velocity, 500.0);
+        let tail = WindSample {
+            ned: [10.0, 0.0, 0.0],
+            body: [0.0; 3],
+        };
+        let air = AirRelative::new(&state, &tail);
+        assert!((air.velocity - 490.0).abs() < 1e-9);
+        assert!(air.alpha.abs() < 1e-12);
+    }
+
+    fn standard_deviation(turbulence: &mut Turbulence, altitude: f64) -> [f64; 3] {
+        let n = 100000;
+        let mut sum = [0.0; 3];
+        for _ in 0..n {
+            let x = turbulence.step(0.01, altitude, 500.0);
+            (0..3).for_each(|i| sum[i] += x[i] * x[i]);
+        }
+        sum.map(|s| (s / n as f64).sqrt())
+    }
+
+    #[test]
+    fn test_turbulence() {
+        for spectrum in [Spectrum::Dryden, Spectrum::VonKarman] {
+            let mut turbulence = Turbulence::new(spectrum, 30.0, 7);
+            // the scale lengths are short at 50 ft, 1000 s covers many of them
+            let (sigma, _) = turbulence.scales(50.0);
+            let sd = standard_deviation(&mut turbulence, 50.0);
+            assert!(
+                (sd[2] - sigma[2]).abs() < 0.1 * sigma[2],
+                "{spectrum} {sd:?}"
+            );
+            assert!(
+                (sd[1] - sigma[1]).abs() < 0.2 * sigma[1],
+                "{spectrum} {sd:?}"
+            );
+
+            // the same seed repeats the same turbulence
+            turbulence.reset();
+            let a: Vec<_> = (0..10)
+                .map(|_| turbulence.step(0.01, 500.0, 500.0))
+                .collect();
+            let mut other = Turbulence::new(spectrum, 30.0, 7);
+            let b: Vec<_> = (0..10).map(|_| other.step(0.01, 500.0, 500.0)).collect();
+            assert_eq!(a, b);
+        }
+
+        let turbulence = Turbulence::new(Spectrum::Dryden, 30.0, 0).with_intensity(5.0);
+        let (sigma, length) = turbulence.scales(1000.0);
+        assert!((sigma[0] - 3.0).abs() < 1e-9 && (length[0] - 1000.0).abs() < 1e-9);
+        assert_eq!(turbulence.scales(3000.0), ([5.0; 3], [1750.0; 3]));
+        let (sigma, _) = turbulence.scales(1500.0);
+        assert!((sigma[2] - 4.0).abs() < 1e-9);
+    }
+}
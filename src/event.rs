@@ -0,0 +1,225 @@
+use crate::model::{State, StateExtend};
+use crate::utils::error::FatalCoreError;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// Which sign changes of an event function trigger the event
+/// Rising: from negative to non-negative
+/// Falling: from positive to non-positive
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Direction {
+    Rising,
+    Falling,
+    #[default]
+    Both,
+}
+
+impl std::fmt::Display for Direction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Rising => write!(f, "rising"),
+            Self::Falling => write!(f, "falling"),
+            Self::Both => write!(f, "both"),
+        }
+    }
+}
+
+impl Direction {
+    /// The direction of the crossing between two values, None if the sign does not change
+    pub fn crossing(value_0: f64, value_1: f64) -> Option<Direction> {
+        if value_0 < 0.0 && value_1 >= 0.0 {
+            Some(Direction::Rising)
+        } else if value_0 > 0.0 && value_1 <= 0.0 {
+            Some(Direction::Falling)
+        } else {
+            None
+        }
+    }
+
+    /// Whether an event watching `self` is triggered by a crossing in `direction`
+    pub fn accepts(&self, direction: Direction) -> bool {
+        *self == Direction::Both || *self == direction
+    }
+}
+
+pub type EventFunction = dyn Fn(&State, &StateExtend) -> Result<f64, FatalCoreError> + Send + Sync;
+
+/// A zero crossing of a function of the plane's state
+/// terminal: stop the simulation at the crossing
+#[derive(Clone)]
+pub struct Event {
+    name: String,
+    function: Arc<EventFunction>,
+    direction: Direction,
+    terminal: bool,
+}
+
+impl std::fmt::Debug for Event {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Event")
+            .field("name", &self.name)
+            .field("direction", &self.direction)
+            .field("terminal", &self.terminal)
+            .finish()
+    }
+}
+
+impl Event {
+    pub fn new(
+        name: &str,
+        function: impl Fn(&State, &StateExtend) -> Result<f64, FatalCoreError> + Send + Sync + 'static,
+        direction: Direction,
+        terminal: bool,
+    ) -> Self {
+        Self {
+            name: name.to_string(),
+            function: Arc::new(function),
+            direction,
+            terminal,
+        }
+    }
+
+    /// The altitude drops to zero, stops the simulation
+    pub fn ground() -> Self {
+        Self::altitude(0.0, Direction::Falling, true).rename("ground")
+    }
+
+    /// The altitude (ft) crosses `value`
+    pub fn altitude(value: f64, direction: Direction, terminal: bool) -> Self {
+        let function = move |state: &State, _: &StateExtend| Ok(state.altitude - value);
+        Self::new("altitude", function, direction, terminal)
+    }
+
+    /// The angle of attack crosses `value` in degrees
+    pub fn alpha(value: f64, direction: Direction, terminal: bool) -> Self {
+        let function = move |state: &State, _: &StateExtend| Ok(state.alpha.to_degrees() - value);
+        Self::new("alpha", function, direction, terminal)
+    }
+
+    /// The Mach number crosses `value`
+    pub fn mach(value: f64, direction: Direction, terminal: bool) -> Self {
+        let function = move |_: &State, extend: &StateExtend| Ok(extend.mach - value);
+        Self::new("mach", function, direction, terminal)
+    }
+
+    pub fn rename(mut self, name: &str) -> Self {
+        self.name = name.to_string();
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn direction(&self) -> Direction {
+        self.direction
+    }
+
+    pub fn terminal(&self) -> bool {
+        self.terminal
+    }
+
+    pub fn evaluate(&self, state: &State, extend: &StateExtend) -> Result<f64, FatalCoreError> {
+        (self.function)(state, extend)
+    }
+}
+
+/// An event which happened during a simulation
+/// index: the position of the event in the list passed to the runner
+/// direction: the direction of the crossing, never `Both`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EventRecord {
+    pub name: String,
+    pub index: usize,
+    pub time: f64,
+    pub direction: Direction,
+    pub terminal: bool,
+    pub state: State,
+    pub state_extend: StateExtend,
+}
+
+/// Locate the root of `f` in `[t_0, t_1]` by the Illinois variant of regula falsi,
+/// `value_0` and `value_1` are the values at the ends and must not have the same sign
+/// return the end of the final bracket on the side of `t_1`, so the crossing has happened there
+pub fn locate_crossing(
+    mut f: impl FnMut(f64) -> Result<f64, FatalCoreError>,
+    t_0: f64,
+    value_0: f64,
+    t_1: f64,
+    value_1: f64,
+    tol: f64,
+) -> Result<f64, FatalCoreError> {
+    let (mut a, mut f_a) = (t_0, value_0);
+    let (mut b, mut f_b) = (t_1, value_1);
+    if f_b == 0.0 {
+        return Ok(b);
+    }
+    // which end was kept in the last iteration
+    let mut kept = 0;
+    for _ in 0..100 {
+        if (b - a).abs() <= tol {
+            break;
+        }
+        let c = (a * f_b - b * f_a) / (f_b - f_a);
+        // keep the iterate strictly inside the bracket
+        let c = c.clamp(a + 0.01 * tol, b - 0.01 * tol);
+        let f_c = f(c)?;
+        if f_c == 0.0 {
+            return Ok(c);
+        }
+        if f_c.signum() == f_b.signum() {
+            b = c;
+            f_b = f_c;
+            if kept == -1 {
+                f_a /= 2.0;
+            }
+            kept = -1;
+        } else {
+            a = c;
+            f_a = f_c;
+            if kept == 1 {
+                f_b /= 2.0;
+            }
+            kept = 1;
+        }
+    }
+    Ok(b)
+}
+
+#[cfg(test)]
+mod event_tests {
+    use super::*;
+
+    #[test]
+    fn test_direction() {
+        assert_eq!(Direction::crossing(-1.0, 0.0), Some(Direction::Rising));
+        assert_eq!(Direction::crossing(1.0, -1.0), Some(Direction::Falling));
+        assert_eq!(Direction::crossing(0.0, 1.0), None);
+        assert_eq!(Direction::crossing(1.0, 2.0), None);
+        assert!(Direction::Both.accepts(Direction::Falling));
+        assert!(!Direction::Rising.accepts(Direction::Falling));
+    }
+
+    #[test]
+    fn test_locate_crossing() {
+        let mut evals = 0;
+        let f = |t: f64| {
+            evals += 1;
+            Ok(t.powi(3) - 2.0)
+        };
+        let t = locate_crossing(f, 0.0, -2.0, 2.0, 6.0, 1e-12).unwrap();
+        assert!((t - 2.0_f64.cbrt()).abs() < 1e-10);
+        assert!(t >= 2.0_f64.cbrt());
+        assert!(evals < 30);
+
+        let altitude = Event::ground();
+        let state = State {
+            altitude: 10.0,
+            ..Default::default()
+        };
+        let value = altitude.evaluate(&state, &StateExtend::default()).unwrap();
+        assert_eq!(value, 10.0);
+        assert_eq!(altitude.name(), "ground");
+        assert!(altitude.terminal());
+    }
+}
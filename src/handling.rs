@@ -0,0 +1,515 @@
+use crate::linearize::Linearization;
+use crate::modal::{modal_analysis, Mode, ModeKind};
+use crate::utils::{error::FatalCoreError, Matrix, Vector};
+use serde::{Deserialize, Serialize};
+
+/// Flight phase category of MIL-F-8785C
+/// A: rapid maneuvering and precision tracking, B: gradual maneuvering (climb, cruise),
+/// C: terminal phases with precision control (takeoff, approach, landing)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Category {
+    A,
+    B,
+    C,
+}
+
+/// Airplane class of MIL-F-8785C
+/// I: small and light, II: medium weight and moderate maneuverability,
+/// III: large and heavy, IV: high maneuverability, class II takes the land based limits
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AircraftClass {
+    I,
+    II,
+    III,
+    IV,
+}
+
+/// Level of flying qualities, `Unacceptable` fails even the Level 3 limits
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Level {
+    One,
+    Two,
+    Three,
+    Unacceptable,
+}
+
+impl Level {
+    /// 1, 2, 3 or 4 for unacceptable
+    pub fn number(&self) -> u8 {
+        match self {
+            Self::One => 1,
+            Self::Two => 2,
+            Self::Three => 3,
+            Self::Unacceptable => 4,
+        }
+    }
+}
+
+impl std::fmt::Display for Level {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Unacceptable => write!(f, "unacceptable"),
+            _ => write!(f, "Level {}", self.number()),
+        }
+    }
+}
+
+/// category: defaults to A, class: defaults to IV as for the F-16
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct HandlingOptions {
+    pub category: Category,
+    pub class: AircraftClass,
+}
+
+impl Default for HandlingOptions {
+    fn default() -> Self {
+        Self {
+            category: Category::A,
+            class: AircraftClass::IV,
+        }
+    }
+}
+
+/// One requirement, limits: the (min, max) range of Level 1, 2 and 3 in the unit of value
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Criterion {
+    pub name: String,
+    pub value: f64,
+    pub limits: [(f64, f64); 3],
+    pub level: Level,
+}
+
+impl Criterion {
+    fn new(name: &str, value: f64, limits: [(f64, f64); 3]) -> Self {
+        let level = match limits
+            .iter()
+            .position(|(min, max)| value >= *min && value <= *max)
+        {
+            Some(0) => Level::One,
+            Some(1) => Level::Two,
+            Some(_) => Level::Three,
+            None => Level::Unacceptable,
+        };
+        Self {
+            name: name.to_string(),
+            value,
+            limits,
+            level,
+        }
+    }
+
+    /// Levels by lower bounds only
+    fn at_least(name: &str, value: f64, min: [f64; 3]) -> Self {
+        Self::new(name, value, min.map(|m| (m, f64::INFINITY)))
+    }
+
+    /// Levels by upper bounds only
+    fn at_most(name: &str, value: f64, max: [f64; 3]) -> Self {
+        Self::new(name, value, max.map(|m| (f64::NEG_INFINITY, m)))
+    }
+}
+
+/// The metrics of a linearization and the requirements they are graded against
+/// short_period_frequency (rad/s), n_alpha: load factor per angle of attack (g/rad),
+/// cap: control anticipation parameter (1/(g s^2)), dutch_roll_frequency (rad/s),
+/// roll_time_constant (s), spiral_time_to_double (s), infinite when stable,
+/// bandwidth (rad/s) and phase_delay (s) of the pitch attitude by the elevator,
+/// None for category B which they are not defined for
+/// level: the worst level of all criteria
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HandlingQualities {
+    pub options: HandlingOptions,
+    pub short_period_frequency: f64,
+    pub short_period_damping: f64,
+    pub n_alpha: f64,
+    pub cap: f64,
+    pub dutch_roll_frequency: f64,
+    pub dutch_roll_damping: f64,
+    pub roll_time_constant: f64,
+    pub spiral_time_to_double: f64,
+    pub bandwidth: Option<f64>,
+    pub phase_delay: Option<f64>,
+    pub criteria: Vec<Criterion>,
+    pub level: Level,
+}
+
+impl HandlingQualities {
+    pub fn criterion(&self, name: &str) -> Option<&Criterion> {
+        self.criteria.iter().find(|c| c.name == name)
+    }
+}
+
+/// Natural frequency and damping of a second order mode, from the oscillatory pair or
+/// from two real roots which it has split into
+fn second_order(modes: &[&Mode], name: &str) -> Result<(f64, f64), FatalCoreError> {
+    match modes {
+        [mode, ..] if mode.is_oscillatory() => Ok((mode.natural_frequency, mode.damping)),
+        [a, b, ..] => {
+            // (s - a)(s - b) = s^2 + 2 zeta wn s + wn^2
+            let product = a.eigenvalue.re * b.eigenvalue.re;
+            let sum = a.eigenvalue.re + b.eigenvalue.re;
+            if product > 0.0 {
+                Ok((product.sqrt(), -sum / (2.0 * product.sqrt())))
+            } else {
+                // a root in the right half plane, or at the origin
+                Ok((product.abs().sqrt(), f64::NEG_INFINITY))
+            }
+        }
+        _ => Err(FatalCoreError::InvalidInput(format!(
+            "the linearization has no {name} mode"
+        ))),
+    }
+}
+
+fn first_order<'a>(modes: &[&'a Mode], name: &str) -> Result<&'a Mode, FatalCoreError> {
+    modes.first().copied().ok_or_else(|| {
+        FatalCoreError::InvalidInput(format!("the linearization has no {name} mode"))
+    })
+}
+
+fn index(names: &[String], name: &str) -> Result<usize, FatalCoreError> {
+    names.iter().position(|n| n == name).ok_or_else(|| {
+        FatalCoreError::InvalidInput(format!("handling qualities need {name} in {names:?}"))
+    })
+}
+
+/// Gain (dB) and continuous phase (deg) of the pitch attitude by trailing edge up elevator,
+/// the short period dynamics of alpha and q (with the elevator actuator and the flap when
+/// augmented) integrated once, this leaves out the phugoid
+fn pitch_response(
+    linear: &Linearization,
+    frequencies: &[f64],
+) -> Result<Vec<(f64, f64)>, FatalCoreError> {
+    let states: Vec<&str> = ["alpha", "q", "elevator_actuator", "lef_filter", "lef"]
+        .into_iter()
+        .filter(|s| linear.states.iter().any(|n| n == s))
+        .collect();
+    let model = linear.subset(&states, &["elevator"], &["q"])?;
+    let n = states.len();
+
+    let mut response = Vec::with_capacity(frequencies.len());
+    let mut last_phase: Option<f64> = None;
+    for w in frequencies {
+        // (jw I - A) (x_re + j x_im) = b as a real system of twice the size
+        let mut m = Matrix::new((2 * n, 2 * n));
+        let mut rhs = Vector::new(2 * n);
+        for i in 0..n {
+            for j in 0..n {
+                m[(i, j)] = -model.a[(i, j)];
+                m[(n + i, n + j)] = -model.a[(i, j)];
+            }
+            m[(i, n + i)] = -w;
+            m[(n + i, i)] = *w;
+            rhs[i] = model.b[(i, 0)];
+        }
+        let x = m.solve(&rhs)?;
+        // q / (-elevator), then theta = q / (jw)
+        let re = -(0..n).map(|j| model.c[(0, j)] * x[j]).sum::<f64>() - model.d[(0, 0)];
+        let im = -(0..n).map(|j| model.c[(0, j)] * x[n + j]).sum::<f64>();
+        let (re, im) = (im / w, -re / w);
+        let gain = 20.0 * re.hypot(im).log10();
+        let mut phase = im.atan2(re).to_degrees();
+        if let Some(last) = last_phase {
+            phase -= 360.0 * ((phase - last) / 360.0).round();
+        }
+        last_phase = Some(phase);
+        response.push((gain, phase));
+    }
+    Ok(response)
+}
+
+/// The frequency where `f` of the response first falls to `level`, interpolated in log w
+fn crossing(
+    frequencies: &[f64],
+    response: &[(f64, f64)],
+    f: impl Fn(&(f64, f64)) -> f64,
+    level: f64,
+) -> Option<f64> {
+    (1..frequencies.len()).find_map(|i| {
+        let (a, b) = (f(&response[i - 1]), f(&response[i]));
+        (a > level && b <= level).then(|| {
+            let t = (a - level) / (a - b);
+            let (wa, wb) = (frequencies[i - 1].ln(), frequencies[i].ln());
+            (wa + t * (wb - wa)).exp()
+        })
+    })
+}
+
+/// Bandwidth and phase delay after MIL-STD-1797A, the bandwidth is the lower of the
+/// frequencies with 45 deg phase margin and with 6 dB gain margin, the phase delay
+/// follows from the phase at twice the frequency of -180 deg, 0 if it is not crossed
+fn bandwidth(linear: &Linearization) -> Result<(f64, f64), FatalCoreError> {
+    let frequencies: Vec<f64> = (0..=5000)
+        .map(|i| 10f64.powf(-2.0 + 5.0 * i as f64 / 5000.0))
+        .collect();
+    let response = pitch_response(linear, &frequencies)?;
+    let phase_at = |w: f64| {
+        let i = frequencies
+            .partition_point(|f| *f < w)
+            .min(frequencies.len() - 1);
+        response[i].1
+    };
+    let gain_at = |w: f64| {
+        let i = frequencies
+            .partition_point(|f| *f < w)
+            .min(frequencies.len() - 1);
+        response[i].0
+    };
+
+    let phase_bandwidth = crossing(&frequencies, &response, |r| r.1, -135.0);
+    let w_180 = crossing(&frequencies, &response, |r| r.1, -180.0);
+    let gain_bandwidth =
+        w_180.and_then(|w| crossing(&frequencies, &response, |r| r.0, gain_at(w) + 6.0));
+    let bandwidth = match (phase_bandwidth, gain_bandwidth) {
+        (Some(p), Some(g)) => p.min(g),
+        (Some(p), None) => p,
+        (None, Some(g)) => g,
+        (None, None) => 0.0,
+    };
+    let phase_delay = match w_180 {
+        Some(w) => -(phase_at(2.0 * w) + 180.0).to_radians() / (2.0 * w),
+        None => 0.0,
+    };
+    Ok((bandwidth, phase_delay))
+}
+
+/// Grade `linear` against MIL-F-8785C and the bandwidth criterion of MIL-STD-1797A
+/// `linear` needs both axes of the plane with nz among the outputs, as `linearize` gives
+/// it, the actuators and the flap belong in it for a meaningful bandwidth
+/// the limits:
+/// short period damping (3.2.2.1.2), CAP and frequency (3.2.2.1.1, figures 1 to 3),
+/// Dutch roll damping, damping times frequency and frequency (3.3.1.1, table VI),
+/// roll mode time constant (3.3.1.2, table VIII), spiral time to double (3.3.1.3, table IX),
+/// pitch attitude bandwidth and phase delay (MIL-STD-1797A 4.2.1.2) for categories A and C
+pub fn handling_qualities(
+    linear: &Linearization,
+    options: Option<HandlingOptions>,
+) -> Result<HandlingQualities, FatalCoreError> {
+    let options = options.unwrap_or_default();
+    let analysis = modal_analysis(linear)?;
+    let (w_sp, zeta_sp) = second_order(
+        &analysis.modes_of(ModeKind::ShortPeriod),
+        &ModeKind::ShortPeriod.to_string(),
+    )?;
+    let (w_dr, zeta_dr) = second_order(
+        &analysis.modes_of(ModeKind::DutchRoll),
+        &ModeKind::DutchRoll.to_string(),
+    )?;
+    let roll = first_order(&analysis.modes_of(ModeKind::Roll), "roll")?;
+    let spiral = first_order(&analysis.modes_of(ModeKind::Spiral), "spiral")?;
+
+    let n_alpha = linear.c[(
+        index(&linear.outputs, "nz")?,
+        index(&linear.states, "alpha")?,
+    )];
+    let cap = w_sp * w_sp / n_alpha;
+    let roll_time_constant = match roll.is_stable() {
+        true => roll.time_constant.unwrap_or(f64::INFINITY),
+        false => f64::INFINITY,
+    };
+    let spiral_time_to_double = spiral.time_to_double.unwrap_or(f64::INFINITY);
+
+    use AircraftClass::*;
+    use Category::*;
+    let (category, class) = (options.category, options.class);
+    let fast = matches!(class, I | IV);
+    let mut criteria = vec![];
+
+    criteria.push(match category {
+        A | C => Criterion::new(
+            "short_period_damping",
+            zeta_sp,
+            [(0.35, 1.3), (0.25, 2.0), (0.15, f64::INFINITY)],
+        ),
+        B => Criterion::new(
+            "short_period_damping",
+            zeta_sp,
+            [(0.3, 2.0), (0.2, 2.0), (0.15, f64::INFINITY)],
+        ),
+    });
+    let cap_limits = match category {
+        A => [(0.28, 3.6), (0.16, 10.0), (0.0, f64::INFINITY)],
+        B => [(0.085, 3.6), (0.038, 10.0), (0.0, f64::INFINITY)],
+        C => [(0.16, 3.6), (0.096, 10.0), (0.0, f64::INFINITY)],
+    };
+    criteria.push(Criterion::new("cap", cap, cap_limits));
+    match (category, fast) {
+        (A, _) => criteria.push(Criterion::at_least(
+            "short_period_frequency",
+            w_sp,
+            [1.0, 0.6, 0.0],
+        )),
+        (C, true) => criteria.push(Criterion::at_least(
+            "short_period_frequency",
+            w_sp,
+            [0.87, 0.6, 0.0],
+        )),
+        (C, false) => criteria.push(Criterion::at_least(
+            "short_period_frequency",
+            w_sp,
+            [0.7, 0.4, 0.0],
+        )),
+        (B, _) => {}
+    }
+
+    let (zeta_min, zeta_w_min, w_min) = match (category, fast) {
+        (A, true) => (0.19, 0.35, 1.0),
+        (A, false) => (0.19, 0.35, 0.4),
+        (B, _) => (0.08, 0.15, 0.4),
+        (C, true) => (0.08, 0.15, 1.0),
+        (C, false) => (0.08, 0.15, 0.4),
+    };
+    criteria.push(Criterion::at_least(
+        "dutch_roll_damping",
+        zeta_dr,
+        [zeta_min, 0.02, 0.0],
+    ));
+    criteria.push(Criterion::at_least(
+        "dutch_roll_damping_frequency",
+        zeta_dr * w_dr,
+        [zeta_w_min, 0.05, f64::NEG_INFINITY],
+    ));
+    criteria.push(Criterion::at_least(
+        "dutch_roll_frequency",
+        w_dr,
+        [w_min, 0.4, 0.4],
+    ));
+
+    let roll_max = match (category, fast) {
+        (A | C, true) => [1.0, 1.4, 10.0],
+        _ => [1.4, 3.0, 10.0],
+    };
+    criteria.push(Criterion::at_most(
+        "roll_time_constant",
+        roll_time_constant,
+        roll_max,
+    ));
+    let spiral_min = match category {
+        A | C => [12.0, 8.0, 4.0],
+        B => [20.0, 8.0, 4.0],
+    };
+    criteria.push(Criterion::at_least(
+        "spiral_time_to_double",
+        spiral_time_to_double,
+        spiral_min,
+    ));
+
+    let (bandwidth, phase_delay) = match category {
+        A | C => {
+            let (w_bw, tau_p) = bandwidth(linear)?;
+            let w_min = if category == A {
+                [6.5, 5.0, 0.0]
+            } else {
+                [2.5, 1.5, 0.0]
+            };
+            criteria.push(Criterion::at_least("bandwidth", w_bw, w_min));
+            criteria.push(Criterion::at_most(
+                "phase_delay",
+                tau_p,
+                [0.12, 0.2, f64::INFINITY],
+            ));
+            (Some(w_bw), Some(tau_p))
+        }
+        B => (None, None),
+    };
+
+    let level = criteria.iter().map(|c| c.level).max().unwrap_or(Level::One);
+    Ok(HandlingQualities {
+        options,
+        short_period_frequency: w_sp,
+        short_period_damping: zeta_sp,
+        n_alpha,
+        cap,
+        dutch_roll_frequency: w_dr,
+        dutch_roll_damping: zeta_dr,
+        roll_time_constant,
+        spiral_time_to_double,
+        bandwidth,
+        phase_delay,
+        criteria,
+        level,
+    })
+}
+
+#[cfg(test)]
+mod handling_tests {
+    use super::*;
+    use crate::components::flight::MechanicalModel;
+    use crate::linearize::{linearize, LinearizeOptions};
+    use crate::utils::{test_model_fin, test_model_init};
+
+    #[test]
+    fn test_criterion() {
+        let c = Criterion::new("damping", 0.3, [(0.35, 1.3), (0.25, 2.0), (0.15, 10.0)]);
+        assert_eq!(c.level, Level::Two);
+        let c = Criterion::at_most("time_constant", 1.0, [1.0, 1.4, 10.0]);
+        assert_eq!(c.level, Level::One);
+        let c = Criterion::at_least("frequency", 0.1, [1.0, 0.6, 0.4]);
+        assert_eq!(c.level, Level::Unacceptable);
+        assert_eq!(c.level.number(), 4);
+        assert!(Level::Two < Level::Three);
+    }
+
+    #[test]
+    fn test_handling_qualities() {
+        let (model, trimmed) = test_model_init();
+        let plane = MechanicalModel::new(&model).unwrap();
+        let options = LinearizeOptions::default().with_actuators().with_lef();
+        let linear = linearize(&plane, &trimmed, Some(options)).unwrap();
+
+        // the bare airframe at 15000 ft and 500 ft/s
+        let hq = handling_qualities(&linear, None).unwrap();
+        assert!(hq.short_period_frequency > 1.0 && hq.short_period_frequency < 3.0);
+        assert!((hq.cap - hq.short_period_frequency.powi(2) / hq.n_alpha).abs() < 1e-12);
+        assert!(hq.n_alpha > 5.0 && hq.n_alpha < 20.0);
+        assert!(hq.roll_time_constant < 1.0);
+        // a stable spiral never doubles
+        assert_eq!(hq.spiral_time_to_double, f64::INFINITY);
+        assert_eq!(
+            hq.criterion("spiral_time_to_double").unwrap().level,
+            Level::One
+        );
+        assert_eq!(
+            hq.criterion("short_period_damping").unwrap().level,
+            Level::One
+        );
+        assert_eq!(
+            hq.criterion("roll_time_constant").unwrap().level,
+            Level::One
+        );
+        assert_eq!(hq.criteria.len(), 10);
+        // without a flight control system the pitch bandwidth falls short of category A
+        let bandwidth = hq.bandwidth.unwrap();
+        assert!(bandwidth > 0.5 && bandwidth < hq.short_period_frequency * 2.0);
+        assert_eq!(hq.criterion("bandwidth").unwrap().level, Level::Three);
+        assert!(hq.phase_delay.unwrap() > 0.0 && hq.phase_delay.unwrap() < 0.12);
+        assert_eq!(hq.level, Level::Three);
+        assert_eq!(hq.level, hq.criteria.iter().map(|c| c.level).max().unwrap());
+
+        // the attitude lags a quarter period at low frequency and half a period at high,
+        // the actuator adds the rest
+        let response = pitch_response(&linear, &[0.01, 1000.0]).unwrap();
+        assert!((response[0].1 + 90.0).abs() < 5.0, "{response:?}");
+        assert!(response[1].1 < -180.0 - 45.0, "{response:?}");
+
+        // category B has no bandwidth criterion, without the actuator the phase delay is gone
+        let cruise = HandlingOptions {
+            category: Category::B,
+            class: AircraftClass::IV,
+        };
+        let hq = handling_qualities(&linear, Some(cruise)).unwrap();
+        assert!(hq.bandwidth.is_none() && hq.criterion("phase_delay").is_none());
+        assert_eq!(
+            hq.criterion("dutch_roll_damping").unwrap().level,
+            Level::One
+        );
+        let bare = linearize(&plane, &trimmed, None).unwrap();
+        let hq = handling_qualities(&bare, None).unwrap();
+        assert_eq!(hq.phase_delay, Some(0.0));
+
+        // both axes are needed
+        assert!(handling_qualities(&linear.longitudinal(), None).is_err());
+
+        test_model_fin(model);
+    }
+}
@@ -1,9 +1,14 @@
 pub mod binding;
 pub mod block;
 pub mod components;
+pub mod event;
+pub mod handling;
+pub mod linearize;
+pub mod modal;
 pub mod model;
 pub mod optimizer;
 pub mod plugin;
+pub mod runner;
 pub mod solver;
 pub mod trim;
 pub mod utils;
@@ -0,0 +1,453 @@
+use crate::block::{LEF_GAIN, SURFACE_GAIN};
+use crate::components::flight::{get_lef, Atmos, MechanicalModel};
+use crate::model::{Control, MechanicalModelInput, State};
+use crate::trim::TrimOutput;
+use crate::utils::{error::FatalCoreError, Matrix};
+use log::trace;
+use serde::{Deserialize, Serialize};
+
+/// Names of the rows of `State`, of `Control` and of `StateExtend`
+pub(crate) const STATES: [&str; 12] = [
+    "npos", "epos", "altitude", "phi", "theta", "psi", "velocity", "alpha", "beta", "p", "q", "r",
+];
+pub(crate) const INPUTS: [&str; 4] = ["thrust", "elevator", "aileron", "rudder"];
+pub(crate) const EXTEND: [&str; 6] = ["nx", "ny", "nz", "mach", "qbar", "ps"];
+pub(crate) const ACTUATORS: [&str; 4] = [
+    "thrust_actuator",
+    "elevator_actuator",
+    "aileron_actuator",
+    "rudder_actuator",
+];
+const LEF: [&str; 2] = ["lef_filter", "lef"];
+
+/// How the model is perturbed and what it is augmented with
+/// step: perturbation relative to the magnitude of each variable, min_step: its floor
+/// max_halvings: how often the step may be halved until two estimates of a column agree
+/// within `tolerance`, relative to the largest derivative of the column
+/// actuators: the first-order gains (1/s) of the thrust, elevator, aileron and rudder
+/// actuators, their positions become states and the inputs become their commands
+/// lef: the leading edge flap becomes two states, the lead-lag on alpha and the actuator,
+/// without it the flap follows its schedule at once
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LinearizeOptions {
+    pub step: f64,
+    pub min_step: f64,
+    pub max_halvings: usize,
+    pub tolerance: f64,
+    pub actuators: Option<[f64; 4]>,
+    pub lef: bool,
+}
+
+impl Default for LinearizeOptions {
+    fn default() -> Self {
+        Self {
+            step: 1e-3,
+            min_step: 1e-4,
+            max_halvings: 6,
+            tolerance: 1e-6,
+            actuators: None,
+            lef: false,
+        }
+    }
+}
+
+impl LinearizeOptions {
+    /// Augmented with the actuators of `PlaneBlock`
+    pub fn with_actuators(mut self) -> Self {
+        self.actuators = Some([1.0, SURFACE_GAIN, SURFACE_GAIN, SURFACE_GAIN]);
+        self
+    }
+
+    pub fn with_lef(mut self) -> Self {
+        self.lef = true;
+        self
+    }
+}
+
+/// x_dot = a x + b u, y = c x + d u about an operating point, all deviations from it
+/// the rows and columns are named by `states`, `inputs` and `outputs`,
+/// `state`, `input` and `output` hold the operating point
+/// states: `State` (ft, rad, ft/s, rad/s) followed by the actuators (lbf, deg) and the
+/// leading edge flap (deg) when augmented, inputs: `Control` (lbf, deg),
+/// outputs: `State` followed by `StateExtend` (g, Mach, lb/ft^2)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Linearization {
+    pub states: Vec<String>,
+    pub inputs: Vec<String>,
+    pub outputs: Vec<String>,
+    pub a: Matrix,
+    pub b: Matrix,
+    pub c: Matrix,
+    pub d: Matrix,
+    pub state: Vec<f64>,
+    pub input: Vec<f64>,
+    pub output: Vec<f64>,
+}
+
+/// Indices of `names` in `all`
+fn indices(kind: &str, all: &[String], names: &[&str]) -> Result<Vec<usize>, FatalCoreError> {
+    names
+        .iter()
+        .map(|name| {
+            all.iter().position(|n| n == name).ok_or_else(|| {
+                FatalCoreError::InvalidInput(format!("no {kind} named {name} in {all:?}"))
+            })
+        })
+        .collect()
+}
+
+/// The rows `rows` and columns `cols` of `m`
+fn pick(m: &Matrix, rows: &[usize], cols: &[usize]) -> Matrix {
+    Matrix::from(
+        rows.iter()
+            .map(|i| cols.iter().map(|j| m[(*i, *j)]).collect::<Vec<_>>())
+            .collect::<Vec<_>>(),
+    )
+}
+
+impl Linearization {
+    /// The model restricted to `states`, `inputs` and `outputs`, the other states are
+    /// held at the operating point
+    pub fn subset(
+        &self,
+        states: &[&str],
+        inputs: &[&str],
+        outputs: &[&str],
+    ) -> Result<Self, FatalCoreError> {
+        let x = indices("state", &self.states, states)?;
+        let u = indices("input", &self.inputs, inputs)?;
+        let y = indices("output", &self.outputs, outputs)?;
+        if x.is_empty() || u.is_empty() || y.is_empty() {
+            return Err(FatalCoreError::InvalidInput(
+                "a subset needs at least one state, input and output".to_string(),
+            ));
+        }
+        let names = |all: &[String], idx: &[usize]| idx.iter().map(|i| all[*i].clone()).collect();
+        let values = |all: &[f64], idx: &[usize]| idx.iter().map(|i| all[*i]).collect();
+        Ok(Self {
+            states: names(&self.states, &x),
+            inputs: names(&self.inputs, &u),
+            outputs: names(&self.outputs, &y),
+            a: pick(&self.a, &x, &x),
+            b: pick(&self.b, &x, &u),
+            c: pick(&self.c, &y, &x),
+            d: pick(&self.d, &y, &u),
+            state: values(&self.state, &x),
+            input: values(&self.input, &u),
+            output: values(&self.output, &y),
+        })
+    }
+
+    /// velocity, alpha, q, theta and altitude driven by thrust and elevator,
+    /// with their actuators and the leading edge flap when augmented
+    pub fn longitudinal(&self) -> Self {
+        let mut states = vec!["velocity", "alpha", "q", "theta", "altitude"];
+        states.extend(self.present(&[ACTUATORS[0], ACTUATORS[1], LEF[0], LEF[1]]));
+        self.subset(
+            &states,
+            &["thrust", "elevator"],
+            &[
+                "velocity", "alpha", "q", "theta", "altitude", "nx", "nz", "mach", "qbar",
+            ],
+        )
+        .expect("the longitudinal states are always there")
+    }
+
+    /// beta, phi, p, r and psi driven by aileron and rudder, with their actuators when
+    /// augmented
+    pub fn lateral(&self) -> Self {
+        let mut states = vec!["beta", "phi", "p", "r", "psi"];
+        states.extend(self.present(&[ACTUATORS[2], ACTUATORS[3]]));
+        self.subset(
+            &states,
+            &["aileron", "rudder"],
+            &["beta", "phi", "p", "r", "psi", "ny"],
+        )
+        .expect("the lateral-directional states are always there")
+    }
+
+    fn present<'a>(&self, names: &[&'a str]) -> Vec<&'a str> {
+        names
+            .iter()
+            .filter(|name| self.states.iter().any(|s| s == *name))
+            .copied()
+            .collect()
+    }
+}
+
+/// The plane with its augmenting states as x_dot = f(x, u), y = g(x, u)
+struct Augmented<'a> {
+    plane: &'a MechanicalModel,
+    options: LinearizeOptions,
+}
+
+impl Augmented<'_> {
+    fn evaluate(&self, x: &[f64], u: &[f64]) -> Result<(Vec<f64>, Vec<f64>), FatalCoreError> {
+        let state = State::from(&x[..12]);
+        let mut n = 12;
+        let control = match self.options.actuators {
+            Some(_) => {
+                n += 4;
+                Control::from(&x[12..16])
+            }
+            None => Control::from(u),
+        };
+        let alpha = state.alpha.to_degrees();
+        let d_lef = if self.options.lef {
+            x[n + 1]
+        } else {
+            get_lef(
+                &*self.plane.atmosphere(),
+                state.altitude,
+                state.velocity,
+                state.alpha,
+            )
+        };
+
+        let output = self
+            .plane
+            .step(&MechanicalModelInput::new(state, control, d_lef))?;
+        let mut x_dot: Vec<f64> = output.state_dot.into();
+        if let Some(gains) = self.options.actuators {
+            x_dot.extend((0..4).map(|i| gains[i] * (u[i] - x[12 + i])));
+        }
+        if self.options.lef {
+            // the lead-lag and the actuator of `LeadingEdgeFlapBlock`
+            let atmos = Atmos::from(&self.plane.air_data(state.altitude, state.velocity));
+            let filter = x[n];
+            let command = (1.45 + 1.38 * (filter + 2.0 * alpha) - 9.05 * atmos.qbar / atmos.ps)
+                .clamp(0.0, 25.0);
+            x_dot.push(-7.25 * (filter + alpha));
+            x_dot.push(LEF_GAIN * (command - x[n + 1]));
+        }
+
+        let mut y: Vec<f64> = state.into();
+        y.extend(Vec::<f64>::from(output.state_extend));
+        Ok((x_dot, y))
+    }
+
+    /// Central differences of f and g along x[index], or u[index] for an input,
+    /// halving the step until two estimates agree
+    fn column(
+        &self,
+        x: &[f64],
+        u: &[f64],
+        index: usize,
+        of_input: bool,
+    ) -> Result<Vec<f64>, FatalCoreError> {
+        let o = self.options;
+        let value = if of_input { u[index] } else { x[index] };
+        let difference = |h: f64| -> Result<Vec<f64>, FatalCoreError> {
+            let (mut x_p, mut u_p) = (x.to_vec(), u.to_vec());
+            let (mut x_m, mut u_m) = (x.to_vec(), u.to_vec());
+            if of_input {
+                u_p[index] += h;
+                u_m[index] -= h;
+            } else {
+                x_p[index] += h;
+                x_m[index] -= h;
+            }
+            let (f_p, g_p) = self.evaluate(&x_p, &u_p)?;
+            let (f_m, g_m) = self.evaluate(&x_m, &u_m)?;
+            Ok(f_p
+                .iter()
+                .chain(&g_p)
+                .zip(f_m.iter().chain(&g_m))
+                .map(|(p, m)| (p - m) / (2.0 * h))
+                .collect())
+        };
+
+        let mut h = (o.step * value.abs()).max(o.min_step);
+        let mut previous = difference(h)?;
+        let mut best = previous.clone();
+        let mut best_error = f64::INFINITY;
+        for _ in 0..o.max_halvings {
+            h /= 2.0;
+            let next = difference(h)?;
+            let scale = next.iter().fold(1.0_f64, |s, v| s.max(v.abs()));
+            let error = next
+                .iter()
+                .zip(&previous)
+                .fold(0.0_f64, |e, (a, b)| e.max((a - b).abs()))
+                / scale;
+            if error < best_error {
+                best_error = error;
+                best = next.clone();
+            }
+            if error <= o.tolerance {
+                break;
+            }
+            previous = next;
+        }
+        trace!("linearize: column {index} (input: {of_input}) settled to {best_error:e}");
+        Ok(best)
+    }
+}
+
+/// Linearize `plane` about the point `trim` has found
+pub fn linearize(
+    plane: &MechanicalModel,
+    trim: &TrimOutput,
+    options: Option<LinearizeOptions>,
+) -> Result<Linearization, FatalCoreError> {
+    linearize_at(plane, &trim.state, &trim.control, options)
+}
+
+/// Linearize `plane` about `state` and `control` by central differences on
+/// `MechanicalModel::step`, the point need not be an equilibrium
+/// the actuators start at `control` and the leading edge flap at its schedule
+pub fn linearize_at(
+    plane: &MechanicalModel,
+    state: &State,
+    control: &Control,
+    options: Option<LinearizeOptions>,
+) -> Result<Linearization, FatalCoreError> {
+    let options = options.unwrap_or_default();
+    if !(options.step > 0.0 && options.min_step > 0.0 && options.tolerance >= 0.0) {
+        return Err(FatalCoreError::InvalidInput(format!(
+            "linearize needs a positive step and min_step, got {} and {}",
+            options.step, options.min_step
+        )));
+    }
+
+    let u: Vec<f64> = (*control).into();
+    let mut x: Vec<f64> = (*state).into();
+    let mut states: Vec<String> = STATES.iter().map(|s| s.to_string()).collect();
+    if options.actuators.is_some() {
+        x.extend(&u);
+        states.extend(ACTUATORS.iter().map(|s| s.to_string()));
+    }
+    if options.lef {
+        let lef = get_lef(
+            &*plane.atmosphere(),
+            state.altitude,
+            state.velocity,
+            state.alpha,
+        );
+        x.extend([-state.alpha.to_degrees(), lef]);
+        states.extend(LEF.iter().map(|s| s.to_string()));
+    }
+    let (n, m) = (x.len(), u.len());
+
+    let plant = Augmented { plane, options };
+    let (_, output) = plant.evaluate(&x, &u)?;
+    let p = output.len();
+    let mut a = Matrix::new((n, n));
+    let mut b = Matrix::new((n, m));
+    let mut c = Matrix::new((p, n));
+    let mut d = Matrix::new((p, m));
+    for j in 0..n + m {
+        let of_input = j >= n;
+        let column = plant.column(&x, &u, if of_input { j - n } else { j }, of_input)?;
+        let (top, bottom) = column.split_at(n);
+        for (i, v) in top.iter().enumerate() {
+            match of_input {
+                false => a[(i, j)] = *v,
+                true => b[(i, j - n)] = *v,
+            }
+        }
+        for (i, v) in bottom.iter().enumerate() {
+            match of_input {
+                false => c[(i, j)] = *v,
+                true => d[(i, j - n)] = *v,
+            }
+        }
+    }
+
+    let mut outputs: Vec<String> = STATES.iter().map(|s| s.to_string()).collect();
+    outputs.extend(EXTEND.iter().map(|s| s.to_string()));
+    Ok(Linearization {
+        states,
+        inputs: INPUTS.iter().map(|s| s.to_string()).collect(),
+        outputs,
+        a,
+        b,
+        c,
+        d,
+        state: x,
+        input: u,
+        output,
+    })
+}
+
+#[cfg(test)]
+mod linearize_tests {
+    use super::*;
+    use crate::utils::{test_model_fin, test_model_init};
+
+    #[test]
+    fn test_linearize() {
+        let (model, trimmed) = test_model_init();
+        let plane = MechanicalModel::new(&model).unwrap();
+
+        let linear = linearize(&plane, &trimmed, None).unwrap();
+        assert_eq!(linear.a.shape(), (12, 12));
+        assert_eq!(linear.b.shape(), (12, 4));
+        assert_eq!(linear.c.shape(), (18, 12));
+        assert_eq!(linear.d.shape(), (18, 4));
+
+        // the states come out unchanged, the altitude rate is the climb rate
+        let at = |m: &Matrix, names: &[String], (r, c): (&str, &str), cols: &[String]| {
+            let i = names.iter().position(|n| n == r).unwrap();
+            let j = cols.iter().position(|n| n == c).unwrap();
+            m[(i, j)]
+        };
+        let a = |r, c| at(&linear.a, &linear.states, (r, c), &linear.states);
+        let b = |r, c| at(&linear.b, &linear.states, (r, c), &linear.inputs);
+        assert!((at(&linear.c, &linear.outputs, ("q", "q"), &linear.states) - 1.0).abs() < 1e-9);
+        assert!(
+            at(
+                &linear.d,
+                &linear.outputs,
+                ("alpha", "elevator"),
+                &linear.inputs
+            )
+            .abs()
+                < 1e-9
+        );
+        assert!((a("altitude", "theta") - 500.0).abs() < 1.0);
+
+        // the classical derivatives have their signs, and a stable short period
+        assert!(a("alpha", "alpha") < 0.0);
+        assert!(a("q", "q") < 0.0);
+        assert!(b("q", "elevator") < 0.0);
+        assert!(b("velocity", "thrust") > 0.0);
+        assert!(b("p", "aileron") < 0.0);
+        let trace = a("alpha", "alpha") + a("q", "q");
+        let det = a("alpha", "alpha") * a("q", "q") - a("alpha", "q") * a("q", "alpha");
+        assert!(trace < 0.0 && det > 0.0);
+
+        // wings level the rates and the bank leave the longitudinal motion alone,
+        // sideslip does not quite with the trim residue on aileron and rudder
+        for row in ["velocity", "alpha", "q"] {
+            for col in ["p", "r", "phi"] {
+                assert!(a(row, col).abs() < 1e-6, "{row} by {col}: {}", a(row, col));
+            }
+        }
+        let long = linear.longitudinal();
+        assert_eq!(long.a.shape(), (5, 5));
+        assert_eq!(long.inputs, ["thrust", "elevator"]);
+        let lat = linear.lateral();
+        assert_eq!(lat.b.shape(), (5, 2));
+        assert_eq!(lat.outputs.last().unwrap(), "ny");
+        assert!(linear.subset(&["alpha"], &["flap"], &["nz"]).is_err());
+
+        // the actuators carry the commands, the flap follows alpha
+        let options = LinearizeOptions::default().with_actuators().with_lef();
+        let augmented = linearize(&plane, &trimmed, Some(options)).unwrap();
+        assert_eq!(augmented.a.shape(), (18, 18));
+        let a = |r, c| at(&augmented.a, &augmented.states, (r, c), &augmented.states);
+        let b = |r, c| at(&augmented.b, &augmented.states, (r, c), &augmented.inputs);
+        assert!(b("q", "elevator").abs() < 1e-9);
+        assert!((b("elevator_actuator", "elevator") - SURFACE_GAIN).abs() < 1e-6);
+        assert!((a("elevator_actuator", "elevator_actuator") + SURFACE_GAIN).abs() < 1e-6);
+        assert!((a("q", "elevator_actuator") - linear.b[(10, 1)]).abs() < 1e-6);
+        assert!((a("lef", "lef") + LEF_GAIN).abs() < 1e-6);
+        assert!(a("lef_filter", "alpha") < 0.0);
+        let long = augmented.longitudinal();
+        assert_eq!(long.states.len(), 9);
+        assert_eq!(augmented.lateral().states.len(), 7);
+
+        test_model_fin(model);
+    }
+}
@@ -0,0 +1,279 @@
+use crate::linearize::{Linearization, ACTUATORS};
+use crate::utils::{error::FatalCoreError, Complex};
+use serde::{Deserialize, Serialize};
+
+/// States of the longitudinal and of the lateral-directional motion
+const LONGITUDINAL: [&str; 5] = ["velocity", "alpha", "q", "theta", "altitude"];
+const LATERAL: [&str; 5] = ["beta", "phi", "p", "r", "psi"];
+/// States which only integrate the others, a mode they dominate is not a flight mode
+const INTEGRATORS: [&str; 4] = ["npos", "epos", "altitude", "psi"];
+/// Real parts below this (1/s) are taken as neutrally stable
+const NEUTRAL: f64 = 1e-9;
+
+/// The classic modes of the rigid aircraft, `Actuator` is dominated by an actuator or the
+/// leading edge flap, `Other` by a navigation state (altitude, heading, position) or
+/// could not be told apart
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ModeKind {
+    ShortPeriod,
+    Phugoid,
+    DutchRoll,
+    Roll,
+    Spiral,
+    Actuator,
+    Other,
+}
+
+impl ModeKind {
+    pub const ALL: [ModeKind; 7] = [
+        Self::ShortPeriod,
+        Self::Phugoid,
+        Self::DutchRoll,
+        Self::Roll,
+        Self::Spiral,
+        Self::Actuator,
+        Self::Other,
+    ];
+}
+
+impl std::fmt::Display for ModeKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::ShortPeriod => write!(f, "short_period"),
+            Self::Phugoid => write!(f, "phugoid"),
+            Self::DutchRoll => write!(f, "dutch_roll"),
+            Self::Roll => write!(f, "roll"),
+            Self::Spiral => write!(f, "spiral"),
+            Self::Actuator => write!(f, "actuator"),
+            Self::Other => write!(f, "other"),
+        }
+    }
+}
+
+/// One real eigenvalue or one complex pair, given by its member with positive imaginary part
+/// natural_frequency: |eigenvalue| (rad/s), damping: -re / |eigenvalue|, 0 at the origin
+/// damped_frequency: im (rad/s), period: of the oscillation (s)
+/// time_constant: 1 / |re| (s), time_to_half / time_to_double: of the amplitude (s)
+/// shape: the right eigenvector, unit norm, participation: of every state, sums to 1
+/// dominant: the state with the largest participation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Mode {
+    pub kind: ModeKind,
+    pub eigenvalue: Complex,
+    pub natural_frequency: f64,
+    pub damping: f64,
+    pub damped_frequency: f64,
+    pub period: Option<f64>,
+    pub time_constant: Option<f64>,
+    pub time_to_half: Option<f64>,
+    pub time_to_double: Option<f64>,
+    pub shape: Vec<Complex>,
+    pub participation: Vec<f64>,
+    pub dominant: String,
+}
+
+impl Mode {
+    fn new(
+        eigenvalue: Complex,
+        shape: Vec<Complex>,
+        participation: Vec<f64>,
+        states: &[String],
+    ) -> Self {
+        let (re, im) = (eigenvalue.re, eigenvalue.im.abs());
+        let natural_frequency = eigenvalue.norm();
+        let dominant = (0..participation.len())
+            .max_by(|&i, &j| participation[i].total_cmp(&participation[j]))
+            .map_or(String::new(), |i| states[i].clone());
+        Self {
+            kind: ModeKind::Other,
+            eigenvalue: Complex::new(re, im),
+            natural_frequency,
+            damping: if natural_frequency > 0.0 {
+                -re / natural_frequency
+            } else {
+                0.0
+            },
+            damped_frequency: im,
+            period: (im > 0.0).then(|| 2.0 * std::f64::consts::PI / im),
+            time_constant: (re.abs() > NEUTRAL).then(|| 1.0 / re.abs()),
+            time_to_half: (re < -NEUTRAL).then(|| std::f64::consts::LN_2 / -re),
+            time_to_double: (re > NEUTRAL).then(|| std::f64::consts::LN_2 / re),
+            shape,
+            participation,
+            dominant,
+        }
+    }
+
+    pub fn is_oscillatory(&self) -> bool {
+        self.damped_frequency > 0.0
+    }
+
+    pub fn is_stable(&self) -> bool {
+        self.eigenvalue.re < -NEUTRAL
+    }
+
+    /// Participation of the state `name`, 0 if there is no such state
+    fn of(&self, states: &[String], name: &str) -> f64 {
+        states
+            .iter()
+            .position(|s| s == name)
+            .map_or(0.0, |i| self.participation[i])
+    }
+
+    fn classify(&self, states: &[String]) -> ModeKind {
+        let of = |name: &str| self.of(states, name);
+        let sum = |names: &[&str]| names.iter().map(|n| of(n)).sum::<f64>();
+        let dominant = self.dominant.as_str();
+        if ACTUATORS.contains(&dominant) || dominant.starts_with("lef") {
+            ModeKind::Actuator
+        } else if INTEGRATORS.contains(&dominant) {
+            ModeKind::Other
+        } else if sum(&LONGITUDINAL) >= sum(&LATERAL) {
+            if of("alpha") + of("q") >= of("velocity") + of("theta") {
+                ModeKind::ShortPeriod
+            } else {
+                ModeKind::Phugoid
+            }
+        } else if self.is_oscillatory() {
+            ModeKind::DutchRoll
+        } else if of("p") >= of("phi") {
+            ModeKind::Roll
+        } else {
+            ModeKind::Spiral
+        }
+    }
+}
+
+/// The modes of a linearization in the order of descending real part,
+/// `states` names the rows of the shapes and participations
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModalAnalysis {
+    pub states: Vec<String>,
+    pub modes: Vec<Mode>,
+}
+
+impl ModalAnalysis {
+    /// The least stable mode of `kind`
+    pub fn mode(&self, kind: ModeKind) -> Option<&Mode> {
+        self.modes.iter().find(|m| m.kind == kind)
+    }
+
+    pub fn modes_of(&self, kind: ModeKind) -> Vec<&Mode> {
+        self.modes.iter().filter(|m| m.kind == kind).collect()
+    }
+}
+
+/// Eigen-decomposition of `linear.a` with the modes classified by their participation
+/// factors |w_i v_i|, w and v the left and right eigenvectors, which do not depend
+/// on the units of the states
+/// the short period and the phugoid split by alpha and q against velocity and theta,
+/// the lateral-directional modes are the Dutch roll when oscillatory and otherwise the
+/// roll by p or the spiral by phi
+pub fn modal_analysis(linear: &Linearization) -> Result<ModalAnalysis, FatalCoreError> {
+    let eigen = linear.a.eigen()?;
+    let mut left = linear.a.transpose().eigen()?;
+    let mut modes = Vec::new();
+    for (lambda, right) in eigen.values.iter().zip(eigen.vectors) {
+        // pair with the closest left eigenvalue, a repeated one has independent vectors
+        let j = (0..left.values.len())
+            .min_by(|&i, &j| {
+                let d = |k: usize| (left.values[k] - *lambda).norm();
+                d(i).total_cmp(&d(j))
+            })
+            .unwrap();
+        left.values.remove(j);
+        let left = left.vectors.remove(j);
+        if lambda.im < 0.0 {
+            // the conjugate of the previous mode
+            continue;
+        }
+        let mut participation: Vec<f64> = left
+            .iter()
+            .zip(right.iter())
+            .map(|(w, v)| (*w * *v).norm())
+            .collect();
+        let total: f64 = participation.iter().sum();
+        if total > f64::EPSILON {
+            participation.iter_mut().for_each(|p| *p /= total);
+        } else {
+            // left and right are orthogonal for a defective eigenvalue, fall back to the shape
+            participation = right.iter().map(|v| v.norm_sq()).collect();
+        }
+        let mut mode = Mode::new(*lambda, right, participation, &linear.states);
+        mode.kind = mode.classify(&linear.states);
+        modes.push(mode);
+    }
+    Ok(ModalAnalysis {
+        states: linear.states.clone(),
+        modes,
+    })
+}
+
+#[cfg(test)]
+mod modal_tests {
+    use super::*;
+    use crate::components::flight::MechanicalModel;
+    use crate::linearize::{linearize, LinearizeOptions};
+    use crate::utils::{test_model_fin, test_model_init};
+
+    #[test]
+    fn test_modal_analysis() {
+        let (model, trimmed) = test_model_init();
+        let plane = MechanicalModel::new(&model).unwrap();
+        let linear = linearize(&plane, &trimmed, None).unwrap();
+
+        let analysis = modal_analysis(&linear).unwrap();
+        assert_eq!(analysis.states, linear.states);
+        for kind in [
+            ModeKind::ShortPeriod,
+            ModeKind::Phugoid,
+            ModeKind::DutchRoll,
+            ModeKind::Roll,
+            ModeKind::Spiral,
+        ] {
+            assert_eq!(analysis.modes_of(kind).len(), 1, "{kind}: {analysis:?}");
+        }
+        for mode in analysis.modes.iter() {
+            assert!((mode.participation.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+            assert!(mode.eigenvalue.im >= 0.0);
+        }
+
+        let sp = analysis.mode(ModeKind::ShortPeriod).unwrap();
+        assert!(sp.is_oscillatory() && sp.is_stable());
+        assert!(sp.natural_frequency > 1.0 && sp.natural_frequency < 3.0);
+        assert!(sp.damping > 0.3 && sp.damping < 0.9);
+        assert!(sp.dominant == "alpha" || sp.dominant == "q");
+        let ph = analysis.mode(ModeKind::Phugoid).unwrap();
+        assert!(ph.natural_frequency < 0.2 && ph.damping < 0.2);
+        let period = 2.0 * std::f64::consts::PI / ph.damped_frequency;
+        assert!((ph.period.unwrap() - period).abs() < 1e-9);
+        assert!(ph.time_to_half.unwrap() > period);
+        assert!(ph.time_to_double.is_none());
+        let dr = analysis.mode(ModeKind::DutchRoll).unwrap();
+        assert!(dr.is_oscillatory() && dr.is_stable());
+        let roll = analysis.mode(ModeKind::Roll).unwrap();
+        assert!(!roll.is_oscillatory() && roll.time_constant.unwrap() < 1.0);
+        let spiral = analysis.mode(ModeKind::Spiral).unwrap();
+        assert!(!spiral.is_oscillatory() && spiral.time_constant.unwrap() > 10.0);
+
+        // the decoupled models keep the modes of their axis
+        let long = modal_analysis(&linear.longitudinal()).unwrap();
+        let eigenvalue = long.mode(ModeKind::ShortPeriod).unwrap().eigenvalue;
+        assert!((eigenvalue - sp.eigenvalue).norm() < 1e-2 * sp.natural_frequency);
+        assert!(long.mode(ModeKind::DutchRoll).is_none());
+        let lat = modal_analysis(&linear.lateral()).unwrap();
+        let eigenvalue = lat.mode(ModeKind::DutchRoll).unwrap().eigenvalue;
+        assert!((eigenvalue - dr.eigenvalue).norm() < 1e-2 * dr.natural_frequency);
+        assert!(lat.mode(ModeKind::Phugoid).is_none());
+
+        // the actuators and the flap bring their own modes
+        let options = LinearizeOptions::default().with_actuators().with_lef();
+        let augmented =
+            modal_analysis(&linearize(&plane, &trimmed, Some(options)).unwrap()).unwrap();
+        assert_eq!(augmented.modes_of(ModeKind::Actuator).len(), 6);
+        assert_eq!(augmented.modes_of(ModeKind::ShortPeriod).len(), 1);
+        assert_eq!(augmented.modes_of(ModeKind::Roll).len(), 1);
+
+        test_model_fin(model);
+    }
+}
@@ -1,4 +1,10 @@
-use super::{control::Control, state::State, state_extend::StateExtend};
+use super::{
+    control::Control,
+    other::{MassState, Measurements},
+    state::State,
+    state_extend::StateExtend,
+};
+use crate::components::wind::AirRelative;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
@@ -33,6 +39,15 @@ pub struct CoreOutput {
     pub state: State,
     pub control: Control,
     pub state_extend: StateExtend,
+    /// fuel and center of gravity, None without a mass model
+    #[serde(default)]
+    pub mass: Option<MassState>,
+    /// the readings of the sensors, None without sensors
+    #[serde(default)]
+    pub sensors: Option<Measurements>,
+    /// airspeed, alpha and beta relative to the air, None if they are those of the state
+    #[serde(default)]
+    pub air: Option<AirRelative>,
 }
 
 impl CoreOutput {
@@ -41,8 +56,35 @@ impl CoreOutput {
             state,
             control,
             state_extend,
+            mass: None,
+            sensors: None,
+            air: None,
         }
     }
+
+    pub fn with_mass(mut self, mass: Option<MassState>) -> Self {
+        self.mass = mass;
+        self
+    }
+
+    pub fn with_sensors(mut self, sensors: Option<Measurements>) -> Self {
+        self.sensors = sensors;
+        self
+    }
+
+    pub fn with_air(mut self, air: Option<AirRelative>) -> Self {
+        self.air = air;
+        self
+    }
+
+    /// Airspeed, alpha and beta relative to the air, those of the state without `air`
+    pub fn air_relative(&self) -> AirRelative {
+        self.air.unwrap_or(AirRelative {
+            velocity: self.state.velocity,
+            alpha: self.state.alpha,
+            beta: self.state.beta,
+        })
+    }
 }
 
 impl Into<Vec<f64>> for CoreOutput {
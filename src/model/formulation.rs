@@ -0,0 +1,329 @@
+use super::state::State;
+use crate::components::earth::{Geodetic, TangentPlane};
+use crate::utils::Vector;
+use serde::{Deserialize, Serialize};
+
+/// How the attitude is propagated
+/// Euler: phi, theta and psi, singular at theta = ±90°
+/// Quaternion: a unit quaternion, converted to Euler angles only for output
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Attitude {
+    #[default]
+    Euler,
+    Quaternion,
+}
+
+impl std::fmt::Display for Attitude {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Euler => write!(f, "euler"),
+            Self::Quaternion => write!(f, "quaternion"),
+        }
+    }
+}
+
+/// How the velocity is propagated
+/// Wind: airspeed, alpha and beta, ill-conditioned at low speed and at beta = ±90°
+/// Body: the body-axis velocities u, v and w, airspeed, alpha and beta are derived from them
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Velocity {
+    #[default]
+    Wind,
+    Body,
+}
+
+impl std::fmt::Display for Velocity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Wind => write!(f, "wind"),
+            Self::Body => write!(f, "body"),
+        }
+    }
+}
+
+/// Where the plane navigates
+/// Flat: npos, epos and altitude over a flat, non-rotating earth with constant gravity
+/// Geodetic: latitude, longitude and altitude on the rotating WGS-84 ellipsoid
+/// with normal gravity, npos and epos are then the projection on the plane tangent
+/// to the ellipsoid at the origin `latitude`, `longitude` in rad
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum Navigation {
+    #[default]
+    Flat,
+    Geodetic {
+        latitude: f64,
+        longitude: f64,
+    },
+}
+
+impl std::fmt::Display for Navigation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Flat => write!(f, "flat"),
+            Self::Geodetic {
+                latitude,
+                longitude,
+            } => write!(
+                f,
+                "geodetic ({:.6}°, {:.6}°)",
+                latitude.to_degrees(),
+                longitude.to_degrees()
+            ),
+        }
+    }
+}
+
+impl Navigation {
+    /// The tangent plane of the flat-earth view, None for flat navigation
+    pub fn tangent_plane(&self) -> Option<TangentPlane> {
+        match self {
+            Self::Flat => None,
+            Self::Geodetic {
+                latitude,
+                longitude,
+            } => Some(TangentPlane::new(*latitude, *longitude)),
+        }
+    }
+}
+
+/// The layout of the vector a `PlaneBlock` integrates
+/// position (npos, epos, altitude or latitude, longitude, altitude),
+/// attitude (3 Euler angles or 4 quaternion elements),
+/// velocity (velocity, alpha, beta or u, v, w), p, q, r
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Formulation {
+    pub attitude: Attitude,
+    pub velocity: Velocity,
+    pub navigation: Navigation,
+}
+
+impl std::fmt::Display for Formulation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} attitude, {} velocity, {} navigation",
+            self.attitude, self.velocity, self.navigation
+        )
+    }
+}
+
+impl Formulation {
+    pub fn new(attitude: Attitude, velocity: Velocity, navigation: Navigation) -> Self {
+        Self {
+            attitude,
+            velocity,
+            navigation,
+        }
+    }
+
+    /// The length of the integrated vector
+    pub fn dim(&self) -> usize {
+        match self.attitude {
+            Attitude::Euler => 12,
+            Attitude::Quaternion => 13,
+        }
+    }
+
+    /// Index of the first element after the attitude
+    pub fn velocity_index(&self) -> usize {
+        3 + self.attitude_dim()
+    }
+
+    fn attitude_dim(&self) -> usize {
+        match self.attitude {
+            Attitude::Euler => 3,
+            Attitude::Quaternion => 4,
+        }
+    }
+
+    /// The integrated vector which represents `state`
+    pub fn from_state(&self, state: &State) -> Vector {
+        let s: Vec<f64> = (*state).into();
+        let mut x = Vec::with_capacity(self.dim());
+        match self.navigation.tangent_plane() {
+            None => x.extend_from_slice(&s[0..3]),
+            Some(plane) => {
+                let point = plane.to_geodetic(state.npos, state.epos, state.altitude);
+                x.extend_from_slice(&[point.latitude, point.longitude, point.altitude])
+            }
+        }
+        match self.attitude {
+            Attitude::Euler => x.extend_from_slice(&s[3..6]),
+            Attitude::Quaternion => {
+                x.extend_from_slice(&euler_to_quaternion(state.phi, state.theta, state.psi))
+            }
+        }
+        match self.velocity {
+            Velocity::Wind => x.extend_from_slice(&s[6..9]),
+            Velocity::Body => {
+                x.extend_from_slice(&wind_to_body(state.velocity, state.alpha, state.beta))
+            }
+        }
+        x.extend_from_slice(&s[9..12]);
+        Vector::from(x)
+    }
+
+    /// The `State` view of an integrated vector
+    pub fn to_state(&self, x: &[f64]) -> State {
+        let mut s = [0.0; 12];
+        match self.navigation.tangent_plane() {
+            None => s[0..3].copy_from_slice(&x[0..3]),
+            Some(plane) => {
+                let [npos, epos] = plane.to_local(&Geodetic::new(x[0], x[1], x[2]));
+                s[0..3].copy_from_slice(&[npos, epos, x[2]])
+            }
+        }
+        match self.attitude {
+            Attitude::Euler => s[3..6].copy_from_slice(&x[3..6]),
+            Attitude::Quaternion => {
+                s[3..6].copy_from_slice(&quaternion_to_euler(&[x[3], x[4], x[5], x[6]]))
+            }
+        }
+        let v = self.velocity_index();
+        match self.velocity {
+            Velocity::Wind => s[6..9].copy_from_slice(&x[v..v + 3]),
+            Velocity::Body => s[6..9].copy_from_slice(&body_to_wind(x[v], x[v + 1], x[v + 2])),
+        }
+        s[9..12].copy_from_slice(&x[v + 3..v + 6]);
+        State::from(s)
+    }
+
+    /// The geodetic position of an integrated vector, None for flat navigation
+    pub fn geodetic(&self, x: &[f64]) -> Option<Geodetic> {
+        match self.navigation {
+            Navigation::Flat => None,
+            Navigation::Geodetic { .. } => Some(Geodetic::new(x[0], x[1], x[2])),
+        }
+    }
+
+    /// Pull the integrated vector back onto its manifold, i.e. rescale the quaternion to unit length
+    pub fn normalize(&self, x: &mut Vector) {
+        if self.attitude == Attitude::Quaternion {
+            let norm = x.data[3..7].iter().map(|q| q * q).sum::<f64>().sqrt();
+            if norm > 0.0 {
+                x.data[3..7].iter_mut().for_each(|q| *q /= norm);
+            }
+        }
+    }
+}
+
+/// Unit quaternion [q0, q1, q2, q3] of the 3-2-1 Euler angles in rad
+pub fn euler_to_quaternion(phi: f64, theta: f64, psi: f64) -> [f64; 4] {
+    let (sphi, cphi) = (phi / 2.0).sin_cos();
+    let (stheta, ctheta) = (theta / 2.0).sin_cos();
+    let (spsi, cpsi) = (psi / 2.0).sin_cos();
+    [
+        cphi * ctheta * cpsi + sphi * stheta * spsi,
+        sphi * ctheta * cpsi - cphi * stheta * spsi,
+        cphi * stheta * cpsi + sphi * ctheta * spsi,
+        cphi * ctheta * spsi - sphi * stheta * cpsi,
+    ]
+}
+
+/// Body-axis velocities [u, v, w] of the airspeed, alpha and beta in rad
+pub fn wind_to_body(velocity: f64, alpha: f64, beta: f64) -> [f64; 3] {
+    [
+        velocity * alpha.cos() * beta.cos(),
+        velocity * beta.sin(),
+        velocity * alpha.sin() * beta.cos(),
+    ]
+}
+
+/// Airspeed, alpha and beta in rad [velocity, alpha, beta] of the body-axis velocities,
+/// alpha is in (-pi, pi] so a tail slide keeps its sign, both angles are zero at rest
+pub fn body_to_wind(u: f64, v: f64, w: f64) -> [f64; 3] {
+    let velocity = (u * u + v * v + w * w).sqrt();
+    if velocity == 0.0 {
+        return [0.0, 0.0, 0.0];
+    }
+    let alpha = w.atan2(u);
+    let beta = (v / velocity).clamp(-1.0, 1.0).asin();
+    [velocity, alpha, beta]
+}
+
+/// 3-2-1 Euler angles [phi, theta, psi] in rad of a unit quaternion,
+/// phi and psi are in (-pi, pi]
+pub fn quaternion_to_euler(q: &[f64; 4]) -> [f64; 3] {
+    let [q0, q1, q2, q3] = *q;
+    let phi = (2.0 * (q0 * q1 + q2 * q3)).atan2(1.0 - 2.0 * (q1 * q1 + q2 * q2));
+    let theta = (2.0 * (q0 * q2 - q3 * q1)).clamp(-1.0, 1.0).asin();
+    let psi = (2.0 * (q0 * q3 + q1 * q2)).atan2(1.0 - 2.0 * (q2 * q2 + q3 * q3));
+    [phi, theta, psi]
+}
+
+#[cfg(test)]
+mod formulation_tests {
+    use super::*;
+
+    #[test]
+    fn test_quaternion_round_trip() {
+        for (phi, theta, psi) in [(0.3, -0.2, 1.0), (-2.5, 1.2, -3.0), (0.1, 0.0, 3.1)] {
+            let q = euler_to_quaternion(phi, theta, psi);
+            let norm: f64 = q.iter().map(|q| q * q).sum();
+            assert!((norm - 1.0).abs() < 1e-12);
+            let [a, b, c] = quaternion_to_euler(&q);
+            assert!((a - phi).abs() < 1e-12);
+            assert!((b - theta).abs() < 1e-12);
+            assert!((c - psi).abs() < 1e-12);
+        }
+
+        let state = State {
+            altitude: 15000.0,
+            phi: 0.1,
+            theta: 0.2,
+            psi: 0.3,
+            velocity: 500.0,
+            alpha: 0.05,
+            q: 0.01,
+            ..Default::default()
+        };
+        let origin: Vec<f64> = state.into();
+        for velocity in [Velocity::Wind, Velocity::Body] {
+            let formulation = Formulation::new(Attitude::Quaternion, velocity, Navigation::Flat);
+            let x = formulation.from_state(&state);
+            assert_eq!(x.dim(), 13);
+            let back: Vec<f64> = formulation.to_state(&x.data).into();
+            for (a, b) in back.iter().zip(origin.iter()) {
+                assert!((a - b).abs() < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn test_geodetic() {
+        let state = State {
+            npos: 20000.0,
+            epos: -5000.0,
+            altitude: 15000.0,
+            velocity: 500.0,
+            ..Default::default()
+        };
+        let navigation = Navigation::Geodetic {
+            latitude: 0.6,
+            longitude: 2.0,
+        };
+        let formulation = Formulation::new(Attitude::Euler, Velocity::Wind, navigation);
+        let x = formulation.from_state(&state);
+        let point = formulation.geodetic(&x.data).unwrap();
+        assert!(point.latitude > 0.6);
+        assert_eq!(point.altitude, 15000.0);
+        let back = formulation.to_state(&x.data);
+        assert!((back.npos - state.npos).abs() < 1e-6);
+        assert!((back.epos - state.epos).abs() < 1e-6);
+        assert!(Formulation::default().geodetic(&x.data).is_none());
+    }
+
+    #[test]
+    fn test_body_velocity() {
+        let [u, v, w] = wind_to_body(500.0, 0.1, -0.05);
+        let [velocity, alpha, beta] = body_to_wind(u, v, w);
+        assert!((velocity - 500.0).abs() < 1e-10);
+        assert!((alpha - 0.1).abs() < 1e-12);
+        assert!((beta + 0.05).abs() < 1e-12);
+
+        // tail slide
+        let [_, alpha, _] = body_to_wind(-10.0, 0.0, 1.0);
+        assert!(alpha > 3.0);
+        assert_eq!(body_to_wind(0.0, 0.0, 0.0), [0.0, 0.0, 0.0]);
+    }
+}
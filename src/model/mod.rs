@@ -1,5 +1,6 @@
 pub(crate) mod control;
 pub(crate) mod core;
+pub(crate) mod formulation;
 pub(crate) mod model_input;
 pub(crate) mod model_output;
 pub(crate) mod other;
@@ -8,6 +9,7 @@ pub(crate) mod state_extend;
 
 pub use control::*;
 pub use core::*;
+pub use formulation::*;
 pub use model_input::*;
 pub use model_output::*;
 pub use other::*;
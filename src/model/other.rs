@@ -100,6 +100,58 @@ impl std::fmt::Display for PlaneConstants {
     }
 }
 
+/// Mass properties of a plane burning fuel
+/// mass: slugs, fuel: fuel remaining in lb, fuel_flow: lb/s,
+/// x_cg: center of gravity as a fraction of cbar
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct MassState {
+    pub mass: f64,
+    pub fuel: f64,
+    pub fuel_flow: f64,
+    pub x_cg: f64,
+}
+
+impl From<MassState> for Vec<f64> {
+    fn from(value: MassState) -> Self {
+        vec![value.mass, value.fuel, value.fuel_flow, value.x_cg]
+    }
+}
+
+/// What an inertial measurement unit reads
+/// rates: p, q, r in rad/s, accels: nx, ny, nz in g like `StateExtend`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct ImuMeasurement {
+    pub rates: [f64; 3],
+    pub accels: [f64; 3],
+}
+
+/// What the air data system reads
+/// alpha, beta: rad at the vanes, static_pressure, total_pressure: lb/ft^2
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct AirDataMeasurement {
+    pub alpha: f64,
+    pub beta: f64,
+    pub mach: f64,
+    pub static_pressure: f64,
+    pub total_pressure: f64,
+}
+
+/// What the attitude and heading reference reads, phi, theta, psi in rad
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct AhrsMeasurement {
+    pub phi: f64,
+    pub theta: f64,
+    pub psi: f64,
+}
+
+/// The readings of the sensors of a plane, held between samples, None for a missing sensor
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Measurements {
+    pub imu: Option<ImuMeasurement>,
+    pub air_data: Option<AirDataMeasurement>,
+    pub ahrs: Option<AhrsMeasurement>,
+}
+
 #[repr(C)]
 #[derive(Debug, Clone, Copy, Default, PartialEq)]
 pub struct ControlLimit {
@@ -10,7 +10,7 @@ use std::collections::HashMap;
 /// alpha (rad) beta (rad)
 /// p (rad/s) q (rad/s) r (rad/s)
 #[repr(C)]
-#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
 pub struct State {
     pub npos: f64,
     pub epos: f64,
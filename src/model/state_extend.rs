@@ -7,7 +7,7 @@ use std::collections::HashMap;
 /// mach
 /// qbar(lb/ft ft) ps(lb/ft ft)
 #[repr(C)]
-#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
 pub struct StateExtend {
     pub nx: f64,
     pub ny: f64,
@@ -1,24 +1,47 @@
 from enum import Enum
-from typing import List
+from typing import List, Optional, Tuple
 
 from pyf16._core import *
 from typing import Callable
 
 
-SolverType = Enum("SolverType", "RK1 RK2 RK3 RK4")
+SolverType = Enum("SolverType", "RK1 RK2 RK3 RK4 RK45 BackwardEuler Trapezoidal SDIRK2")
 Dynamics = Callable[[float, list, list], list]
+Controller = Callable[[float, CoreOutput, Control], Control]
 
 class SimpleSolver:
-    def __init__(self, solver: SolverType, delta_t: float) -> None:
-        self._solver = self._get_solver_class(solver)(delta_t)
+    def __init__(self, solver: SolverType, delta_t: float, **options) -> None:
+        # rtol, atol and min_step are accepted by RK45, tol and max_iter by the implicit solvers
+        self._solver = self._get_solver_class(solver)(delta_t, **options)
 
     def solve(self, dynamics: Dynamics, time: float, state: list, input_: list) -> list:
         return self._solver.solve(dynamics, time, state, input_)
 
+    def solve_step(
+        self, dynamics: Dynamics, time: float, h: float, state: list, input_: list
+    ) -> list:
+        return self._solver.solve_step(dynamics, time, h, state, input_)
+
+    def integrate(
+        self, dynamics: Dynamics, t_0: float, t_1: float, state: list, input_: list
+    ) -> list:
+        return self._solver.integrate(dynamics, t_0, t_1, state, input_)
+
     @property
     def delta_t(self) -> float:
         return self._solver.delta_t
 
+    @property
+    def stats(self) -> Optional[SolverStats]:
+        # only the adaptive solver keeps counters
+        if isinstance(self._solver, SimpleSolverRK45):
+            return self._solver.stats
+        return None
+
+    def reset_stats(self) -> None:
+        if isinstance(self._solver, SimpleSolverRK45):
+            self._solver.reset_stats()
+
     @staticmethod
     def _get_solver_class(solver: SolverType) -> type:
         if solver == SolverType.RK1:
@@ -29,7 +52,15 @@ class SimpleSolver:
             return SimpleSolverRK3
         elif solver == SolverType.RK4:
             return SimpleSolverRK4
-            
+        elif solver == SolverType.RK45:
+            return SimpleSolverRK45
+        elif solver == SolverType.BackwardEuler:
+            return SimpleSolverBackwardEuler
+        elif solver == SolverType.Trapezoidal:
+            return SimpleSolverTrapezoidal
+        elif solver == SolverType.SDIRK2:
+            return SimpleSolverSDIRK2
+
 
 class PlaneBlock:
     def __init__(
@@ -40,10 +71,14 @@ class PlaneBlock:
         init: CoreInit,
         deflection: List[float],
         ctrl_limit: ControlLimit,
+        formulation: Optional[Formulation] = None,
+        **options,
     ) -> None:
         core = self._get_core_class(solver)
         self._delta_t = delta_t
-        self._core = core(delta_t, model, init, deflection, ctrl_limit)
+        self._core = core(
+            delta_t, model, init, deflection, ctrl_limit, formulation, **options
+        )
 
     @staticmethod
     def _get_core_class(solver: SolverType) -> type:
@@ -55,6 +90,14 @@ class PlaneBlock:
             return PlaneBlockRK3
         elif solver == SolverType.RK4:
             return PlaneBlockRK4
+        elif solver == SolverType.RK45:
+            return PlaneBlockRK45
+        elif solver == SolverType.BackwardEuler:
+            return PlaneBlockBackwardEuler
+        elif solver == SolverType.Trapezoidal:
+            return PlaneBlockTrapezoidal
+        elif solver == SolverType.SDIRK2:
+            return PlaneBlockSDIRK2
 
     def update(self, control: Control, t: float) -> CoreOutput:
         return self._core.update(control, t)
@@ -62,6 +105,60 @@ class PlaneBlock:
     def reset(self, init: CoreInit) -> None:
         self._core.reset(init)
 
+    def snapshot(self) -> PlaneBlockSnapshot:
+        return self._core.snapshot()
+
+    def restore(self, snapshot: PlaneBlockSnapshot) -> None:
+        self._core.restore(snapshot)
+
+    def run(
+        self,
+        schedule: ControlSchedule,
+        duration: float,
+        decimation: int = 1,
+        events: Optional[List[Event]] = None,
+    ) -> Trajectory:
+        return self._core.run(schedule, duration, self._delta_t, decimation, events)
+
+    def run_closed_loop(
+        self,
+        controller: Controller,
+        sample_time: float,
+        schedule: ControlSchedule,
+        duration: float,
+        decimation: int = 1,
+        events: Optional[List[Event]] = None,
+    ) -> Trajectory:
+        return self._core.run_closed_loop(
+            controller,
+            sample_time,
+            schedule,
+            duration,
+            self._delta_t,
+            decimation,
+            events,
+        )
+
+    def run_autopilot(
+        self,
+        autopilot: Autopilot,
+        schedule: ControlSchedule,
+        duration: float,
+        decimation: int = 1,
+        events: Optional[List[Event]] = None,
+    ) -> Trajectory:
+        # the schedule is the trim the autopilot flies around, its integrators carry on
+        return self._core.run_autopilot(
+            autopilot, schedule, duration, self._delta_t, decimation, events
+        )
+
+    @property
+    def subsystem_substeps(self) -> int:
+        return self._core.subsystem_substeps
+
+    def set_subsystem_substeps(self, n: int) -> None:
+        self._core.set_subsystem_substeps(n)
+
     @property
     def state(self) -> CoreOutput:
         return self._core.state
@@ -70,9 +167,219 @@ class PlaneBlock:
     def state_dot(self) -> State:
         return self._core.state_dot
 
+    @property
+    def formulation(self) -> Formulation:
+        return self._core.formulation
+
+    @property
+    def geodetic(self) -> Optional[Geodetic]:
+        return self._core.geodetic
+
+    @property
+    def atmosphere(self) -> Atmosphere:
+        return self._core.atmosphere
+
+    def set_atmosphere(self, atmosphere: Atmosphere) -> None:
+        self._core.set_atmosphere(atmosphere)
+
+    @property
+    def air_data(self) -> AirData:
+        return self._core.air_data
+
+    def set_excitation(self, channel: int, signal: Optional[Signal] = None) -> None:
+        self._core.set_excitation(channel, signal)
+
+    def excitation(self, channel: int) -> Optional[Signal]:
+        return self._core.excitation(channel)
+
+    def set_wind(self, wind: Optional[Wind] = None) -> None:
+        self._core.set_wind(wind)
+
+    @property
+    def wind(self) -> WindSample:
+        return self._core.wind
+
+    @property
+    def air_relative(self) -> AirRelative:
+        return self._core.air_relative
+
+    def set_mass_model(self, mass: Optional[MassModel] = None) -> None:
+        self._core.set_mass_model(mass)
+
+    @property
+    def mass_model(self) -> Optional[MassModel]:
+        return self._core.mass_model
+
+    @property
+    def mass_state(self) -> Optional[MassState]:
+        return self._core.mass_state
+
+    @property
+    def constants(self) -> PlaneConstants:
+        return self._core.constants
+
+    def set_engine(self, engine: Optional[Engine] = None) -> None:
+        self._core.set_engine(engine)
+
+    @property
+    def engine(self) -> Optional[Engine]:
+        return self._core.engine
+
+    @property
+    def engine_state(self) -> Optional[EngineState]:
+        return self._core.engine_state
+
+    def set_sensors(self, sensors: Optional[Sensors] = None) -> None:
+        self._core.set_sensors(sensors)
+
+    @property
+    def sensors(self) -> Optional[Sensors]:
+        return self._core.sensors
+
+    @property
+    def measurements(self) -> Optional[Measurements]:
+        return self._core.measurements
+
+    def set_flcs(self, flcs: Optional[Flcs] = None) -> None:
+        # the pilot input becomes stick and pedals in [-1, 1], the surfaces are commanded
+        self._core.set_flcs(flcs)
+
+    @property
+    def flcs(self) -> Optional[Flcs]:
+        return self._core.flcs
+
+    def set_actuator(self, channel: int, model: ActuatorModel) -> None:
+        # channel: 0 thrust, 1 elevator, 2 aileron, 3 rudder, 4 leading edge flap
+        self._core.set_actuator(channel, model)
+
+    def actuator(self, channel: int) -> ActuatorModel:
+        return self._core.actuator(channel)
+
+    def set_actuator_failure(
+        self, channel: int, time: float, failure: Optional[ActuatorFailure] = None
+    ) -> None:
+        self._core.set_actuator_failure(channel, time, failure)
+
+    def actuator_failure(self, channel: int) -> Optional[Tuple[float, ActuatorFailure]]:
+        return self._core.actuator_failure(channel)
+
     def delete_model(self) -> None:
         self._core.delete_model()
 
     @property
     def delta_t(self) -> float:
         return self._delta_t
+
+    @property
+    def stats(self) -> Optional[SolverStats]:
+        # only the adaptive solver keeps counters
+        if isinstance(self._core, PlaneBlockRK45):
+            return self._core.stats
+        return None
+
+    def reset_stats(self) -> None:
+        if isinstance(self._core, PlaneBlockRK45):
+            self._core.reset_stats()
+
+
+class LinearModel:
+    """x' = A x + B u, y = C x + D u about the operating point of a `Linearization`,
+    driven and read like a `PlaneBlock` and run by the same runners"""
+
+    def __init__(
+        self,
+        solver: SolverType,
+        delta_t: float,
+        linearization: Linearization,
+        **options,
+    ) -> None:
+        core = self._get_core_class(solver)
+        self._delta_t = delta_t
+        self._core = core(delta_t, linearization, **options)
+
+    @staticmethod
+    def _get_core_class(solver: SolverType) -> type:
+        if solver == SolverType.RK1:
+            return LinearModelRK1
+        elif solver == SolverType.RK2:
+            return LinearModelRK2
+        elif solver == SolverType.RK3:
+            return LinearModelRK3
+        elif solver == SolverType.RK4:
+            return LinearModelRK4
+        elif solver == SolverType.RK45:
+            return LinearModelRK45
+        elif solver == SolverType.BackwardEuler:
+            return LinearModelBackwardEuler
+        elif solver == SolverType.Trapezoidal:
+            return LinearModelTrapezoidal
+        elif solver == SolverType.SDIRK2:
+            return LinearModelSDIRK2
+
+    def update(self, control: Control, t: float) -> CoreOutput:
+        return self._core.update(control, t)
+
+    def reset(self) -> None:
+        self._core.reset()
+
+    def snapshot(self) -> LinearModelSnapshot:
+        return self._core.snapshot()
+
+    def restore(self, snapshot: LinearModelSnapshot) -> None:
+        self._core.restore(snapshot)
+
+    def run(
+        self,
+        schedule: ControlSchedule,
+        duration: float,
+        decimation: int = 1,
+        events: Optional[List[Event]] = None,
+    ) -> Trajectory:
+        return self._core.run(schedule, duration, self._delta_t, decimation, events)
+
+    def run_closed_loop(
+        self,
+        controller: Controller,
+        sample_time: float,
+        schedule: ControlSchedule,
+        duration: float,
+        decimation: int = 1,
+        events: Optional[List[Event]] = None,
+    ) -> Trajectory:
+        return self._core.run_closed_loop(
+            controller,
+            sample_time,
+            schedule,
+            duration,
+            self._delta_t,
+            decimation,
+            events,
+        )
+
+    def run_autopilot(
+        self,
+        autopilot: Autopilot,
+        schedule: ControlSchedule,
+        duration: float,
+        decimation: int = 1,
+        events: Optional[List[Event]] = None,
+    ) -> Trajectory:
+        return self._core.run_autopilot(
+            autopilot, schedule, duration, self._delta_t, decimation, events
+        )
+
+    @property
+    def state(self) -> CoreOutput:
+        return self._core.state
+
+    @property
+    def state_dot(self) -> State:
+        return self._core.state_dot
+
+    @property
+    def states(self) -> List[float]:
+        return self._core.states
+
+    @property
+    def linearization(self) -> Linearization:
+        return self._core.linearization
@@ -1,5 +1,5 @@
 from __future__ import annotations
-from typing import Optional, List
+from typing import Callable, Dict, Optional, List, Tuple, Union
 
 class PlaneConstants:
     def __init__(
@@ -295,8 +295,143 @@ def trim(
     trim_init: Optional[TrimInit] = None,
     flight_condition: Optional[FlightCondition] = None,
     optim_options: Optional[NelderMeadOptions] = None,
+    atmosphere: Optional["Atmosphere"] = None,
 ) -> TrimOutput: ...
 
+class Linearization:
+    """x_dot = a x + b u, y = c x + d u about a trim point, deviations from it"""
+
+    @property
+    def a(self) -> List[List[float]]: ...
+    @property
+    def b(self) -> List[List[float]]: ...
+    @property
+    def c(self) -> List[List[float]]: ...
+    @property
+    def d(self) -> List[List[float]]: ...
+    @property
+    def states(self) -> List[str]: ...
+    @property
+    def inputs(self) -> List[str]: ...
+    @property
+    def outputs(self) -> List[str]: ...
+    @property
+    def state(self) -> List[float]: ...
+    @property
+    def input(self) -> List[float]: ...
+    @property
+    def output(self) -> List[float]: ...
+    def subset(
+        self, states: List[str], inputs: List[str], outputs: List[str]
+    ) -> "Linearization": ...
+    def longitudinal(self) -> "Linearization": ...
+    def lateral(self) -> "Linearization": ...
+
+def linearize(
+    model: AerodynamicModel,
+    trim_output: TrimOutput,
+    actuators: Union[bool, Tuple[float, float, float, float], None] = None,
+    lef: bool = False,
+    step: Optional[float] = None,
+    min_step: Optional[float] = None,
+    atmosphere: Optional["Atmosphere"] = None,
+) -> Linearization: ...
+
+class Mode:
+    """One real eigenvalue or complex pair, the pair by its member with positive imaginary part"""
+
+    @property
+    def kind(self) -> str:
+        """'short_period', 'phugoid', 'dutch_roll', 'roll', 'spiral', 'actuator' or 'other'"""
+        ...
+    @property
+    def eigenvalue(self) -> complex: ...
+    @property
+    def natural_frequency(self) -> float: ...
+    @property
+    def damping(self) -> float: ...
+    @property
+    def damped_frequency(self) -> float: ...
+    @property
+    def period(self) -> Optional[float]: ...
+    @property
+    def time_constant(self) -> Optional[float]: ...
+    @property
+    def time_to_half(self) -> Optional[float]: ...
+    @property
+    def time_to_double(self) -> Optional[float]: ...
+    @property
+    def shape(self) -> List[complex]: ...
+    @property
+    def participation(self) -> List[float]: ...
+    @property
+    def dominant(self) -> str: ...
+    def is_oscillatory(self) -> bool: ...
+    def is_stable(self) -> bool: ...
+
+class ModalAnalysis:
+    """The modes of a linearization in the order of descending real part"""
+
+    @property
+    def states(self) -> List[str]: ...
+    @property
+    def modes(self) -> List[Mode]: ...
+    def mode(self, kind: str) -> Optional[Mode]: ...
+    def modes_of(self, kind: str) -> List[Mode]: ...
+
+def modal_analysis(linearization: Linearization) -> ModalAnalysis: ...
+
+class Criterion:
+    """One handling-qualities requirement and the level it is met at"""
+
+    @property
+    def name(self) -> str: ...
+    @property
+    def value(self) -> float: ...
+    @property
+    def limits(self) -> List[Tuple[float, float]]:
+        """The (min, max) range of Level 1, 2 and 3"""
+        ...
+    @property
+    def level(self) -> int:
+        """1, 2, 3 or 4 when even the Level 3 limits are not met"""
+        ...
+
+class HandlingQualities:
+    @property
+    def category(self) -> str: ...
+    @property
+    def aircraft_class(self) -> str: ...
+    @property
+    def short_period_frequency(self) -> float: ...
+    @property
+    def short_period_damping(self) -> float: ...
+    @property
+    def n_alpha(self) -> float: ...
+    @property
+    def cap(self) -> float: ...
+    @property
+    def dutch_roll_frequency(self) -> float: ...
+    @property
+    def dutch_roll_damping(self) -> float: ...
+    @property
+    def roll_time_constant(self) -> float: ...
+    @property
+    def spiral_time_to_double(self) -> float: ...
+    @property
+    def bandwidth(self) -> Optional[float]: ...
+    @property
+    def phase_delay(self) -> Optional[float]: ...
+    @property
+    def criteria(self) -> List[Criterion]: ...
+    @property
+    def level(self) -> int: ...
+    def criterion(self, name: str) -> Optional[Criterion]: ...
+
+def handling_qualities(
+    linearization: Linearization, category: str = "A", aircraft_class: str = "IV"
+) -> HandlingQualities: ...
+
 class CoreOutput:
     @property
     def state(self) -> State: ...
@@ -304,6 +439,12 @@ class CoreOutput:
     def control(self) -> Control: ...
     @property
     def state_extend(self) -> StateExtend: ...
+    @property
+    def mass(self) -> Optional["MassState"]: ...
+    @property
+    def sensors(self) -> Optional["Measurements"]: ...
+    @property
+    def air(self) -> "AirRelative": ...
 
 class CoreInit:
     def __init__(self, state: State, control: Control) -> None: ...
@@ -316,12 +457,870 @@ class CoreInit:
     @control.setter
     def control(self, value: Control) -> None: ...
 
+class Interpolation:
+    def __init__(self, value: int) -> None: ...
+    @property
+    def value(self) -> int: ...
+    @staticmethod
+    def step() -> "Interpolation": ...
+    @staticmethod
+    def linear() -> "Interpolation": ...
+
+class ControlSchedule:
+    def __init__(
+        self,
+        time: List[float],
+        controls: List[Control],
+        interpolation: Optional[Interpolation] = None,
+    ) -> None: ...
+    @staticmethod
+    def from_list(
+        time: List[float],
+        controls: List[List[float]],
+        interpolation: Optional[Interpolation] = None,
+    ) -> "ControlSchedule": ...
+    @staticmethod
+    def constant(control: Control) -> "ControlSchedule": ...
+    def at(self, t: float) -> Control: ...
+    @property
+    def time(self) -> List[float]: ...
+    @property
+    def controls(self) -> List[List[float]]: ...
+    @property
+    def interpolation(self) -> Interpolation: ...
+
+class Trajectory:
+    def __len__(self) -> int: ...
+    @property
+    def time(self) -> List[float]: ...
+    @property
+    def state(self) -> List[List[float]]: ...
+    @property
+    def control(self) -> List[List[float]]: ...
+    @property
+    def state_extend(self) -> List[List[float]]: ...
+    @property
+    def state_dot(self) -> List[List[float]]: ...
+    @property
+    def command(self) -> List[List[float]]: ...
+    @property
+    def mass(self) -> List[Optional[List[float]]]: ...
+    @property
+    def sensors(self) -> List[Optional["Measurements"]]: ...
+    @property
+    def events(self) -> List["EventRecord"]: ...
+
+class Direction:
+    def __init__(self, value: int) -> None: ...
+    @property
+    def value(self) -> int: ...
+    @staticmethod
+    def rising() -> "Direction": ...
+    @staticmethod
+    def falling() -> "Direction": ...
+    @staticmethod
+    def both() -> "Direction": ...
+
+class Event:
+    def __init__(
+        self,
+        name: str,
+        function: Callable[[State, StateExtend], float],
+        direction: Optional[Direction] = None,
+        terminal: bool = False,
+    ) -> None: ...
+    @staticmethod
+    def ground() -> "Event": ...
+    @staticmethod
+    def altitude(
+        value: float, direction: Optional[Direction] = None, terminal: bool = False
+    ) -> "Event": ...
+    @staticmethod
+    def alpha(
+        value: float, direction: Optional[Direction] = None, terminal: bool = False
+    ) -> "Event": ...
+    @staticmethod
+    def mach(
+        value: float, direction: Optional[Direction] = None, terminal: bool = False
+    ) -> "Event": ...
+    @property
+    def name(self) -> str: ...
+    @property
+    def direction(self) -> Direction: ...
+    @property
+    def terminal(self) -> bool: ...
+
+class EventRecord:
+    @property
+    def name(self) -> str: ...
+    @property
+    def index(self) -> int: ...
+    @property
+    def time(self) -> float: ...
+    @property
+    def direction(self) -> Direction: ...
+    @property
+    def terminal(self) -> bool: ...
+    @property
+    def state(self) -> State: ...
+    @property
+    def state_extend(self) -> StateExtend: ...
+
+class Attitude:
+    def __init__(self, value: int) -> None: ...
+    @property
+    def value(self) -> int: ...
+    @staticmethod
+    def euler() -> "Attitude": ...
+    @staticmethod
+    def quaternion() -> "Attitude": ...
+
+class Velocity:
+    def __init__(self, value: int) -> None: ...
+    @property
+    def value(self) -> int: ...
+    @staticmethod
+    def wind() -> "Velocity": ...
+    @staticmethod
+    def body() -> "Velocity": ...
+
+class Navigation:
+    @staticmethod
+    def flat() -> "Navigation": ...
+    @staticmethod
+    def geodetic(latitude: float, longitude: float) -> "Navigation": ...
+    @property
+    def is_geodetic(self) -> bool: ...
+    @property
+    def latitude(self) -> Optional[float]: ...
+    @property
+    def longitude(self) -> Optional[float]: ...
+
+class Geodetic:
+    def __init__(self, latitude: float, longitude: float, altitude: float) -> None: ...
+    @property
+    def latitude(self) -> float: ...
+    @property
+    def longitude(self) -> float: ...
+    @property
+    def altitude(self) -> float: ...
+
+class Formulation:
+    def __init__(
+        self,
+        attitude: Optional[Attitude] = None,
+        velocity: Optional[Velocity] = None,
+        navigation: Optional[Navigation] = None,
+    ) -> None: ...
+    @property
+    def attitude(self) -> Attitude: ...
+    @property
+    def velocity(self) -> Velocity: ...
+    @property
+    def navigation(self) -> Navigation: ...
+    @property
+    def dim(self) -> int: ...
+
+class AirProperties:
+    @property
+    def temperature(self) -> float: ...
+    @property
+    def pressure(self) -> float: ...
+    @property
+    def density(self) -> float: ...
+    @property
+    def speed_of_sound(self) -> float: ...
+    @property
+    def viscosity(self) -> float: ...
+
+class AirData:
+    @property
+    def air(self) -> AirProperties: ...
+    @property
+    def mach(self) -> float: ...
+    @property
+    def qbar(self) -> float: ...
+    @property
+    def tas(self) -> float: ...
+    @property
+    def cas(self) -> float: ...
+    @property
+    def eas(self) -> float: ...
+
+class Atmosphere:
+    @staticmethod
+    def simple() -> "Atmosphere": ...
+    @staticmethod
+    def standard(temperature_offset: float = 0.0) -> "Atmosphere": ...
+    @staticmethod
+    def tabulated(
+        altitude: List[float], temperature: List[float], pressure: List[float]
+    ) -> "Atmosphere": ...
+    def properties(self, altitude: float) -> AirProperties: ...
+    def air_data(self, altitude: float, velocity: float) -> AirData: ...
+
+class Tank:
+    def __init__(
+        self,
+        capacity: float,
+        position: List[float],
+        fuel: Optional[float] = None,
+        stage: int = 0,
+    ) -> None: ...
+    @property
+    def capacity(self) -> float: ...
+    @property
+    def fuel(self) -> float: ...
+    @property
+    def position(self) -> List[float]: ...
+    @property
+    def stage(self) -> int: ...
+
+class FuelConsumption:
+    def __init__(self, tsfc: float, mach_slope: float = 0.0) -> None: ...
+    def fuel_flow(self, thrust: float, air: AirData) -> float: ...
+    @property
+    def tsfc(self) -> float: ...
+    @property
+    def mach_slope(self) -> float: ...
+
+class MassModel:
+    def __init__(
+        self, constants: PlaneConstants, tanks: List[Tank], consumption: FuelConsumption
+    ) -> None: ...
+    def burn(self, dt: float, thrust: float, air: AirData) -> float: ...
+    def set_fuel(self, fuel: List[float]) -> None: ...
+    def reset(self) -> None: ...
+    @property
+    def fuel(self) -> float: ...
+    @property
+    def tanks(self) -> List[Tank]: ...
+    @property
+    def constants(self) -> PlaneConstants: ...
+    @property
+    def cg(self) -> List[float]: ...
+    @property
+    def consumption(self) -> FuelConsumption: ...
+    @property
+    def state(self) -> "MassState": ...
+
+class MassState:
+    @property
+    def mass(self) -> float: ...
+    @property
+    def fuel(self) -> float: ...
+    @property
+    def fuel_flow(self) -> float: ...
+    @property
+    def x_cg(self) -> float: ...
+
+class Engine:
+    def __init__(
+        self,
+        mach: List[float],
+        altitude: List[float],
+        idle: List[List[float]],
+        military: List[List[float]],
+        maximum: List[List[float]],
+        angular_momentum: float,
+    ) -> None: ...
+    @staticmethod
+    def f16() -> "Engine": ...
+    @staticmethod
+    def tgear(throttle: float) -> float: ...
+    @staticmethod
+    def throttle_for_power(power: float) -> float: ...
+    @staticmethod
+    def power_rate(power: float, command: float) -> float: ...
+    def thrust(self, power: float, altitude: float, mach: float) -> float: ...
+    def power_for_thrust(self, thrust: float, altitude: float, mach: float) -> float: ...
+    @property
+    def angular_momentum(self) -> float: ...
+    @angular_momentum.setter
+    def angular_momentum(self, value: float) -> None: ...
+
+class EngineState:
+    @property
+    def power(self) -> float: ...
+    @property
+    def thrust(self) -> float: ...
+
+class SensorModel:
+    def __init__(
+        self,
+        noise: float = 0.0,
+        bias: float = 0.0,
+        random_walk: float = 0.0,
+        scale_factor: float = 0.0,
+        quantization: float = 0.0,
+        limits: Optional[List[float]] = None,
+        lag: float = 0.0,
+    ) -> None: ...
+    @property
+    def noise(self) -> float: ...
+    @property
+    def bias(self) -> float: ...
+    @property
+    def random_walk(self) -> float: ...
+    @property
+    def scale_factor(self) -> float: ...
+    @property
+    def quantization(self) -> float: ...
+    @property
+    def limits(self) -> Optional[List[float]]: ...
+    @property
+    def lag(self) -> float: ...
+
+class Imu:
+    def __init__(
+        self,
+        sample_rate: float = 0.0,
+        seed: int = 0,
+        position: Optional[List[float]] = None,
+        gyro: Optional[SensorModel] = None,
+        accel: Optional[SensorModel] = None,
+    ) -> None: ...
+    def accels(self, state: State, state_dot: State, extend: StateExtend) -> List[float]: ...
+    @property
+    def sample_rate(self) -> float: ...
+    @property
+    def seed(self) -> int: ...
+    @property
+    def position(self) -> List[float]: ...
+    @property
+    def gyro(self) -> SensorModel: ...
+    @property
+    def accel(self) -> SensorModel: ...
+
+class AirDataSensor:
+    def __init__(
+        self,
+        sample_rate: float = 0.0,
+        seed: int = 0,
+        position: Optional[List[float]] = None,
+        alpha: Optional[SensorModel] = None,
+        beta: Optional[SensorModel] = None,
+        mach: Optional[SensorModel] = None,
+        static_pressure: Optional[SensorModel] = None,
+        total_pressure: Optional[SensorModel] = None,
+    ) -> None: ...
+    @staticmethod
+    def pitot_pressure(static_pressure: float, mach: float) -> float: ...
+    @property
+    def sample_rate(self) -> float: ...
+    @property
+    def seed(self) -> int: ...
+    @property
+    def position(self) -> List[float]: ...
+    @property
+    def alpha(self) -> SensorModel: ...
+    @property
+    def beta(self) -> SensorModel: ...
+    @property
+    def mach(self) -> SensorModel: ...
+    @property
+    def static_pressure(self) -> SensorModel: ...
+    @property
+    def total_pressure(self) -> SensorModel: ...
+
+class Ahrs:
+    def __init__(
+        self,
+        sample_rate: float = 0.0,
+        seed: int = 0,
+        attitude: Optional[SensorModel] = None,
+        heading: Optional[SensorModel] = None,
+    ) -> None: ...
+    @property
+    def sample_rate(self) -> float: ...
+    @property
+    def seed(self) -> int: ...
+    @property
+    def attitude(self) -> SensorModel: ...
+    @property
+    def heading(self) -> SensorModel: ...
+
+class Sensors:
+    def __init__(
+        self,
+        imu: Optional[Imu] = None,
+        air_data: Optional[AirDataSensor] = None,
+        ahrs: Optional[Ahrs] = None,
+    ) -> None: ...
+    def reset(self) -> None: ...
+    @property
+    def imu(self) -> Optional[Imu]: ...
+    @property
+    def air_data(self) -> Optional[AirDataSensor]: ...
+    @property
+    def ahrs(self) -> Optional[Ahrs]: ...
+    @property
+    def measurements(self) -> "Measurements": ...
+
+class Measurements:
+    @property
+    def rates(self) -> Optional[List[float]]: ...
+    @property
+    def accels(self) -> Optional[List[float]]: ...
+    @property
+    def alpha(self) -> Optional[float]: ...
+    @property
+    def beta(self) -> Optional[float]: ...
+    @property
+    def mach(self) -> Optional[float]: ...
+    @property
+    def static_pressure(self) -> Optional[float]: ...
+    @property
+    def total_pressure(self) -> Optional[float]: ...
+    @property
+    def attitude(self) -> Optional[List[float]]: ...
+
+class TransferFunction:
+    def __init__(self, num: List[float], den: List[float], delta_t: float = 0.01) -> None: ...
+    @staticmethod
+    def first_order(tau: float, delta_t: float = 0.01) -> "TransferFunction": ...
+    @staticmethod
+    def second_order(w: float, zeta: float, delta_t: float = 0.01) -> "TransferFunction": ...
+    @staticmethod
+    def notch(
+        w: float, zeta_zero: float, zeta_pole: float, delta_t: float = 0.01
+    ) -> "TransferFunction": ...
+    @staticmethod
+    def lead_lag(t_lead: float, t_lag: float, delta_t: float = 0.01) -> "TransferFunction": ...
+    @staticmethod
+    def washout(tau: float, delta_t: float = 0.01) -> "TransferFunction": ...
+    @property
+    def num(self) -> List[float]: ...
+    @property
+    def den(self) -> List[float]: ...
+    def state_space(self) -> "StateSpace": ...
+    def update(self, input: float, t: float) -> float: ...
+    @property
+    def output(self) -> float: ...
+    def reset(self) -> None: ...
+
+class StateSpace:
+    def __init__(
+        self,
+        a: List[List[float]],
+        b: List[List[float]],
+        c: List[List[float]],
+        d: List[List[float]],
+        x0: Optional[List[float]] = None,
+        delta_t: float = 0.01,
+    ) -> None: ...
+    @property
+    def shape(self) -> Tuple[int, int, int]: ...
+    @property
+    def state(self) -> List[float]: ...
+    @property
+    def outputs(self) -> List[float]: ...
+    def update_vector(self, input: List[float], t: float) -> List[float]: ...
+    def update(self, input: float, t: float) -> float: ...
+    @property
+    def output(self) -> float: ...
+    def reset(self) -> None: ...
+
+class Pid:
+    def __init__(
+        self,
+        kp: float,
+        ki: float = 0.0,
+        kd: float = 0.0,
+        tau: float = 0.0,
+        limits: Optional[Tuple[float, float]] = None,
+        k_aw: float = 0.0,
+        delta_t: float = 0.01,
+    ) -> None: ...
+    @property
+    def kp(self) -> float: ...
+    @property
+    def ki(self) -> float: ...
+    @property
+    def kd(self) -> float: ...
+    @property
+    def tau(self) -> float: ...
+    @property
+    def limits(self) -> List[float]: ...
+    @property
+    def integral(self) -> float: ...
+    def update(self, input: float, t: float) -> float: ...
+    @property
+    def output(self) -> float: ...
+    def reset(self) -> None: ...
+
+class RateLimiter:
+    def __init__(self, rising: float, falling: Optional[float] = None) -> None: ...
+    def update(self, input: float, t: float) -> float: ...
+    @property
+    def output(self) -> float: ...
+    def reset(self) -> None: ...
+
+class Saturation:
+    def __init__(self, bottom: float, top: float) -> None: ...
+    def update(self, input: float, t: float) -> float: ...
+    @property
+    def output(self) -> float: ...
+    def reset(self) -> None: ...
+
+class TransportDelay:
+    def __init__(self, delay: float, init: float = 0.0) -> None: ...
+    def update(self, input: float, t: float) -> float: ...
+    @property
+    def output(self) -> float: ...
+    def reset(self) -> None: ...
+
+class ZeroOrderHold:
+    def __init__(self, sample_time: float) -> None: ...
+    def update(self, input: float, t: float) -> float: ...
+    @property
+    def output(self) -> float: ...
+    def reset(self) -> None: ...
+
+class Autopilot:
+    def __init__(
+        self,
+        k_h: Optional[float] = None,
+        max_vertical_speed: Optional[float] = None,
+        k_gamma: Optional[float] = None,
+        k_gamma_i: Optional[float] = None,
+        k_q: Optional[float] = None,
+        k_psi: Optional[float] = None,
+        bank_limit: Optional[float] = None,
+        k_phi: Optional[float] = None,
+        k_p: Optional[float] = None,
+        k_beta: Optional[float] = None,
+        k_r: Optional[float] = None,
+        k_v: Optional[float] = None,
+        k_v_i: Optional[float] = None,
+        sample_time: Optional[float] = None,
+    ) -> None: ...
+    @staticmethod
+    def f16() -> "Autopilot": ...
+    def set_altitude(self, altitude: float) -> None: ...
+    def set_vertical_speed(self, vertical_speed: float) -> None: ...
+    def set_flight_path_angle(self, gamma: float) -> None: ...
+    def set_heading(self, heading: float) -> None: ...
+    def set_track(self, track: float) -> None: ...
+    def set_airspeed(self, airspeed: float) -> None: ...
+    def set_mach(self, mach: float) -> None: ...
+    def disengage_vertical(self) -> None: ...
+    def disengage_lateral(self) -> None: ...
+    def disengage_speed(self) -> None: ...
+    @property
+    def vertical(self) -> Optional[Tuple[str, float]]: ...
+    @property
+    def lateral(self) -> Optional[Tuple[str, float]]: ...
+    @property
+    def speed(self) -> Optional[Tuple[str, float]]: ...
+    def set_thrust_range(self, bottom: float, top: float) -> None: ...
+    @property
+    def thrust_range(self) -> List[float]: ...
+    @property
+    def gains(self) -> Dict[str, float]: ...
+    @property
+    def sample_time(self) -> float: ...
+    def reset(self) -> None: ...
+    def step(self, t: float, output: CoreOutput, reference: Control) -> Control: ...
+
+class Flcs:
+    def __init__(
+        self,
+        breakout: Optional[float] = None,
+        nz_max: Optional[float] = None,
+        nz_min: Optional[float] = None,
+        roll_rate_max: Optional[float] = None,
+        rudder_max: Optional[float] = None,
+        pitch_prefilter: Optional[float] = None,
+        roll_prefilter: Optional[float] = None,
+        k_nz: Optional[float] = None,
+        k_i: Optional[float] = None,
+        k_q: Optional[float] = None,
+        alpha_limit: Optional[float] = None,
+        k_alpha: Optional[float] = None,
+        k_p: Optional[float] = None,
+        k_r: Optional[float] = None,
+        washout: Optional[float] = None,
+        k_ari: Optional[float] = None,
+        qbar_ref: Optional[float] = None,
+        sample_time: Optional[float] = None,
+    ) -> None: ...
+    @staticmethod
+    def f16() -> "Flcs": ...
+    @property
+    def gains(self) -> Dict[str, float]: ...
+    @property
+    def sample_time(self) -> float: ...
+    @property
+    def limits(self) -> List[List[float]]: ...
+    @limits.setter
+    def limits(self, value: List[List[float]]) -> None: ...
+    @property
+    def trim(self) -> Optional[Control]: ...
+    def reset(self, trim: Optional[Control] = None) -> None: ...
+    def step(self, t: float, output: CoreOutput, pilot: Control) -> Control: ...
+
+class ActuatorModel:
+    @staticmethod
+    def first_order(
+        command_limit_top: float,
+        command_limit_bottom: float,
+        rate_limit: float,
+        gain: float,
+        deadband: float = 0.0,
+        backlash: float = 0.0,
+        delay: float = 0.0,
+        stall_deflection: Optional[float] = None,
+    ) -> "ActuatorModel": ...
+    @staticmethod
+    def second_order(
+        command_limit_top: float,
+        command_limit_bottom: float,
+        rate_limit: float,
+        natural_frequency: float,
+        damping: float,
+        deadband: float = 0.0,
+        backlash: float = 0.0,
+        delay: float = 0.0,
+        stall_deflection: Optional[float] = None,
+    ) -> "ActuatorModel": ...
+    @property
+    def order(self) -> str: ...
+    @property
+    def gain(self) -> Optional[float]: ...
+    @property
+    def natural_frequency(self) -> Optional[float]: ...
+    @property
+    def damping(self) -> Optional[float]: ...
+    @property
+    def command_limit_top(self) -> float: ...
+    @property
+    def command_limit_bottom(self) -> float: ...
+    @property
+    def rate_limit(self) -> float: ...
+    @property
+    def deadband(self) -> float: ...
+    @property
+    def backlash(self) -> float: ...
+    @property
+    def delay(self) -> float: ...
+    @property
+    def stall_deflection(self) -> Optional[float]: ...
+
+class ActuatorFailure:
+    @staticmethod
+    def stuck(position: Optional[float] = None) -> "ActuatorFailure": ...
+    @staticmethod
+    def hardover(top: bool = True) -> "ActuatorFailure": ...
+    @staticmethod
+    def floating(time_constant: float = 0.1) -> "ActuatorFailure": ...
+    @staticmethod
+    def loss_of_effectiveness(effectiveness: float) -> "ActuatorFailure": ...
+    @staticmethod
+    def oscillatory(amplitude: float, frequency: float) -> "ActuatorFailure": ...
+    @property
+    def kind(self) -> str: ...
+
+class Sweep:
+    def __init__(self, value: int) -> None: ...
+    @property
+    def value(self) -> int: ...
+    @staticmethod
+    def linear() -> "Sweep": ...
+    @staticmethod
+    def logarithmic() -> "Sweep": ...
+
+class Signal:
+    @staticmethod
+    def step(start_time: float, amplitude: float, duration: float = ...) -> "Signal": ...
+    @staticmethod
+    def ramp(start_time: float, amplitude: float, duration: float) -> "Signal": ...
+    @staticmethod
+    def pulse(
+        start_time: float, amplitude: float, duration: float, period: float, width: float
+    ) -> "Signal": ...
+    @staticmethod
+    def doublet(start_time: float, amplitude: float, duration: float) -> "Signal": ...
+    @staticmethod
+    def multistep_3211(start_time: float, amplitude: float, duration: float) -> "Signal": ...
+    @staticmethod
+    def chirp(
+        start_time: float,
+        amplitude: float,
+        duration: float,
+        f_0: float,
+        f_1: float,
+        sweep: Optional[Sweep] = None,
+    ) -> "Signal": ...
+    @staticmethod
+    def multisine(
+        start_time: float, amplitude: float, duration: float, frequencies: List[float]
+    ) -> "Signal": ...
+    @staticmethod
+    def prbs(
+        start_time: float, amplitude: float, duration: float, clock: float, order: int = 7
+    ) -> "Signal": ...
+    @staticmethod
+    def sum(signals: List["Signal"]) -> "Signal": ...
+    def value(self, t: float) -> float: ...
+    def __call__(self, t: float) -> float: ...
+    def __add__(self, other: "Signal") -> "Signal": ...
+    @property
+    def start_time(self) -> float: ...
+    @property
+    def amplitude(self) -> float: ...
+    @property
+    def duration(self) -> float: ...
+
+class Shear:
+    def __init__(self, wind_20: float, direction: float, roughness: float = 2.0) -> None: ...
+    def velocity(self, altitude: float) -> List[float]: ...
+    @property
+    def wind_20(self) -> float: ...
+    @property
+    def direction(self) -> float: ...
+    @property
+    def roughness(self) -> float: ...
+
+class Gust:
+    def __init__(self, start_time: float, gradient: float, amplitude: List[float]) -> None: ...
+    def velocity(self, t: float, tas: float) -> List[float]: ...
+    @property
+    def start_time(self) -> float: ...
+    @property
+    def gradient(self) -> float: ...
+    @property
+    def amplitude(self) -> List[float]: ...
+
+class Spectrum:
+    def __init__(self, value: int) -> None: ...
+    @property
+    def value(self) -> int: ...
+    @staticmethod
+    def dryden() -> "Spectrum": ...
+    @staticmethod
+    def von_karman() -> "Spectrum": ...
+
+class Turbulence:
+    def __init__(
+        self,
+        spectrum: Spectrum,
+        wind_20: float,
+        seed: int = 0,
+        intensity: Optional[float] = None,
+    ) -> None: ...
+    def scales(self, altitude: float) -> tuple[List[float], List[float]]: ...
+    def step(self, dt: float, altitude: float, tas: float) -> List[float]: ...
+    def reset(self) -> None: ...
+    @property
+    def spectrum(self) -> Spectrum: ...
+    @property
+    def seed(self) -> int: ...
+
+class WindSample:
+    @property
+    def ned(self) -> List[float]: ...
+    @property
+    def body(self) -> List[float]: ...
+
+class AirRelative:
+    @property
+    def velocity(self) -> float: ...
+    @property
+    def alpha(self) -> float: ...
+    @property
+    def beta(self) -> float: ...
+
+class Wind:
+    def __init__(
+        self,
+        steady: Optional[List[float]] = None,
+        shear: Optional[Shear] = None,
+        gusts: Optional[List[Gust]] = None,
+        turbulence: Optional[Turbulence] = None,
+    ) -> None: ...
+    def update(self, t: float, dt: float, altitude: float, tas: float) -> WindSample: ...
+    def reset(self) -> None: ...
+    @property
+    def steady(self) -> List[float]: ...
+    @property
+    def shear(self) -> Optional[Shear]: ...
+    @property
+    def gusts(self) -> List[Gust]: ...
+    @property
+    def turbulence(self) -> Optional[Turbulence]: ...
+
+class PlaneBlockSnapshot:
+    def __init__(self, content: str) -> None: ...
+    def to_toml(self) -> str: ...
+    @property
+    def start_time(self) -> Optional[float]: ...
+    @property
+    def last_time(self) -> Optional[float]: ...
+    @property
+    def formulation(self) -> "Formulation": ...
+    @property
+    def vector(self) -> List[float]: ...
+    @property
+    def state(self) -> State: ...
+    @property
+    def state_dot(self) -> State: ...
+    @property
+    def state_extend(self) -> Optional[StateExtend]: ...
+    @property
+    def actuators(self) -> List[float]: ...
+    @property
+    def lef(self) -> List[float]: ...
+
 class PlaneBlockRK1: ...
 class PlaneBlockRK2: ...
 class PlaneBlockRK3: ...
 class PlaneBlockRK4: ...
+class PlaneBlockRK45:
+    @property
+    def stats(self) -> SolverStats: ...
+    def reset_stats(self) -> None: ...
+
+class PlaneBlockBackwardEuler: ...
+class PlaneBlockTrapezoidal: ...
+class PlaneBlockSDIRK2: ...
+
+class LinearModelSnapshot:
+    def __init__(self, content: str) -> None: ...
+    def to_toml(self) -> str: ...
+    @property
+    def state(self) -> List[float]: ...
+    @property
+    def control(self) -> Control: ...
+    @property
+    def last_time(self) -> Optional[float]: ...
+
+class LinearModelRK1: ...
+class LinearModelRK2: ...
+class LinearModelRK3: ...
+class LinearModelRK4: ...
+class LinearModelRK45: ...
+class LinearModelBackwardEuler: ...
+class LinearModelTrapezoidal: ...
+class LinearModelSDIRK2: ...
+
+class SolverStats:
+    @property
+    def accepted_steps(self) -> int: ...
+    @property
+    def rejected_steps(self) -> int: ...
+    @property
+    def fun_evals(self) -> int: ...
 
 class SimpleSolverRK1: ...
 class SimpleSolverRK2: ...
 class SimpleSolverRK3: ...
-class SimpleSolverRK4: ...
\ No newline at end of file
+class SimpleSolverRK4: ...
+class SimpleSolverRK45:
+    @property
+    def stats(self) -> SolverStats: ...
+    def reset_stats(self) -> None: ...
+
+class SimpleSolverBackwardEuler: ...
+class SimpleSolverTrapezoidal: ...
+class SimpleSolverSDIRK2: ...
\ No newline at end of file
@@ -0,0 +1,911 @@
+use crate::block::{PlaneBlock, PlaneBlockSnapshot};
+use crate::components::basic::{LinearModel, LinearModelSnapshot};
+use crate::event::{locate_crossing, Direction, Event, EventRecord};
+use crate::model::{Control, CoreOutput, MassState, Measurements, State, StateExtend};
+use crate::solver::{ODESolver, VectorODESolver};
+use crate::utils::error::FatalCoreError;
+use log::{debug, trace};
+use serde::{Deserialize, Serialize};
+
+/// How the control between two breakpoints of a `ControlSchedule` is computed
+/// Step: hold the value of the last breakpoint
+/// Linear: interpolate linearly between the two breakpoints
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Interpolation {
+    #[default]
+    Step,
+    Linear,
+}
+
+impl std::fmt::Display for Interpolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Step => write!(f, "step"),
+            Self::Linear => write!(f, "linear"),
+        }
+    }
+}
+
+/// A time-indexed table of `Control`
+/// before the first breakpoint the first control is used,
+/// after the last breakpoint the last control is held
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ControlSchedule {
+    time: Vec<f64>,
+    control: Vec<Control>,
+    interpolation: Interpolation,
+}
+
+impl ControlSchedule {
+    /// time must be strictly increasing and have the same length as control
+    pub fn new(
+        time: Vec<f64>,
+        control: Vec<Control>,
+        interpolation: Interpolation,
+    ) -> Result<Self, FatalCoreError> {
+        if time.is_empty() {
+            return Err(FatalCoreError::InvalidInput(
+                "control schedule must have at least one breakpoint".to_string(),
+            ));
+        }
+        if time.len() != control.len() {
+            return Err(FatalCoreError::InvalidInput(format!(
+                "control schedule has {} time points but {} controls",
+                time.len(),
+                control.len()
+            )));
+        }
+        if time.windows(2).any(|w| w[1] <= w[0]) {
+            return Err(FatalCoreError::InvalidInput(
+                "control schedule time must be strictly increasing".to_string(),
+            ));
+        }
+        Ok(Self {
+            time,
+            control,
+            interpolation,
+        })
+    }
+
+    /// A schedule which always returns the same control
+    pub fn constant(control: impl Into<Control>) -> Self {
+        Self {
+            time: vec![0.0],
+            control: vec![control.into()],
+            interpolation: Interpolation::Step,
+        }
+    }
+
+    pub fn time(&self) -> &[f64] {
+        &self.time
+    }
+
+    pub fn control(&self) -> &[Control] {
+        &self.control
+    }
+
+    pub fn interpolation(&self) -> Interpolation {
+        self.interpolation
+    }
+
+    /// Control at time t
+    pub fn at(&self, t: f64) -> Control {
+        // index of the first breakpoint later than t
+        let i = self.time.partition_point(|&x| x <= t);
+        if i == 0 {
+            return self.control[0];
+        }
+        if i == self.time.len() {
+            return self.control[i - 1];
+        }
+        match self.interpolation {
+            Interpolation::Step => self.control[i - 1],
+            Interpolation::Linear => {
+                let (t_0, t_1) = (self.time[i - 1], self.time[i]);
+                let c_0: [f64; 4] = self.control[i - 1].into();
+                let c_1: [f64; 4] = self.control[i].into();
+                let ratio = (t - t_0) / (t_1 - t_0);
+                let control: Vec<f64> = c_0
+                    .iter()
+                    .zip(c_1.iter())
+                    .map(|(a, b)| a + (b - a) * ratio)
+                    .collect();
+                Control::from(control)
+            }
+        }
+    }
+}
+
+/// Columnar record of a simulation
+/// every column has the same length, the i-th element of each column belongs to `time[i]`
+/// control: the actuator positions, command: the control given to the plane from `time[i]` on
+/// events: the events in the order they happened, a terminal event also ends the columns
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Trajectory {
+    pub time: Vec<f64>,
+    pub state: Vec<State>,
+    pub control: Vec<Control>,
+    pub state_extend: Vec<StateExtend>,
+    pub state_dot: Vec<State>,
+    pub command: Vec<Control>,
+    /// fuel and center of gravity, None without a mass model
+    #[serde(default)]
+    pub mass: Vec<Option<MassState>>,
+    /// the readings of the sensors, None without sensors
+    #[serde(default)]
+    pub sensors: Vec<Option<Measurements>>,
+    pub events: Vec<EventRecord>,
+}
+
+impl Trajectory {
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            time: Vec::with_capacity(capacity),
+            state: Vec::with_capacity(capacity),
+            control: Vec::with_capacity(capacity),
+            state_extend: Vec::with_capacity(capacity),
+            state_dot: Vec::with_capacity(capacity),
+            command: Vec::with_capacity(capacity),
+            mass: Vec::with_capacity(capacity),
+            sensors: Vec::with_capacity(capacity),
+            events: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, t: f64, output: &CoreOutput, state_dot: State, command: Control) {
+        self.time.push(t);
+        self.state.push(output.state);
+        self.control.push(output.control);
+        self.state_extend.push(output.state_extend);
+        self.state_dot.push(state_dot);
+        self.command.push(command);
+        self.mass.push(output.mass);
+        self.sensors.push(output.sensors);
+    }
+
+    pub fn len(&self) -> usize {
+        self.time.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.time.is_empty()
+    }
+}
+
+/// A plane the runners can drive, the nonlinear `PlaneBlock` or a `LinearModel`
+pub trait Plant {
+    /// Everything needed to go back to an earlier instant
+    type Snapshot;
+
+    /// Advance the plane to `t` with `control` held since the last update
+    fn update(&mut self, control: Control, t: f64) -> Result<CoreOutput, FatalCoreError>;
+
+    /// The state derivative at the last update
+    fn state_dot(&self) -> State;
+
+    /// The time of the last update as passed to `update`, None before the first one
+    fn last_time(&self) -> Option<f64>;
+
+    fn snapshot(&self) -> Self::Snapshot;
+
+    fn restore(&mut self, snapshot: &Self::Snapshot) -> Result<(), FatalCoreError>;
+}
+
+impl<S> Plant for PlaneBlock<S>
+where
+    S: ODESolver + VectorODESolver,
+{
+    type Snapshot = PlaneBlockSnapshot;
+
+    fn update(&mut self, control: Control, t: f64) -> Result<CoreOutput, FatalCoreError> {
+        PlaneBlock::update(self, control, t)
+    }
+
+    fn state_dot(&self) -> State {
+        PlaneBlock::state_dot(self)
+    }
+
+    fn last_time(&self) -> Option<f64> {
+        PlaneBlock::last_time(self)
+    }
+
+    fn snapshot(&self) -> PlaneBlockSnapshot {
+        PlaneBlock::snapshot(self)
+    }
+
+    fn restore(&mut self, snapshot: &PlaneBlockSnapshot) -> Result<(), FatalCoreError> {
+        PlaneBlock::restore(self, snapshot)
+    }
+}
+
+impl<S> Plant for LinearModel<S>
+where
+    S: ODESolver + VectorODESolver,
+{
+    type Snapshot = LinearModelSnapshot;
+
+    fn update(&mut self, control: Control, t: f64) -> Result<CoreOutput, FatalCoreError> {
+        LinearModel::update(self, control, t)
+    }
+
+    fn state_dot(&self) -> State {
+        LinearModel::state_dot(self)
+    }
+
+    fn last_time(&self) -> Option<f64> {
+        LinearModel::last_time(self)
+    }
+
+    fn snapshot(&self) -> LinearModelSnapshot {
+        LinearModel::snapshot(self)
+    }
+
+    fn restore(&mut self, snapshot: &LinearModelSnapshot) -> Result<(), FatalCoreError> {
+        LinearModel::restore(self, snapshot)
+    }
+}
+
+/// A controller sampled at a fixed period, its command is held until the next sample
+pub trait DiscreteController {
+    /// The sample period in seconds
+    fn sample_time(&self) -> f64;
+
+    /// Compute the command for the plane from its output at the sample time `t`,
+    /// reference: the pilot or guidance input at `t`
+    fn sample(
+        &mut self,
+        t: f64,
+        output: &CoreOutput,
+        reference: &Control,
+    ) -> Result<Control, FatalCoreError>;
+}
+
+fn check_run(duration: f64, delta_t: f64, decimation: usize) -> Result<usize, FatalCoreError> {
+    if delta_t.is_nan() || delta_t <= 0.0 {
+        return Err(FatalCoreError::InvalidInput(format!(
+            "delta_t must be positive, got {delta_t}"
+        )));
+    }
+    if duration.is_nan() || duration < 0.0 {
+        return Err(FatalCoreError::InvalidInput(format!(
+            "duration must not be negative, got {duration}"
+        )));
+    }
+    if decimation == 0 {
+        return Err(FatalCoreError::InvalidInput(
+            "decimation must be at least 1".to_string(),
+        ));
+    }
+    Ok((duration / delta_t).round() as usize)
+}
+
+/// Crossing times closer than this (s) to the root are not refined any further
+const EVENT_TIME_TOL: f64 = 1e-9;
+
+/// Watches the events over the updates of a block
+struct EventMonitor<'a> {
+    events: &'a [Event],
+    /// event function values after the last accepted update
+    values: Vec<f64>,
+}
+
+impl<'a> EventMonitor<'a> {
+    fn new(events: &'a [Event], output: &CoreOutput) -> Result<Self, FatalCoreError> {
+        let values = Self::evaluate(events, output)?;
+        Ok(Self { events, values })
+    }
+
+    fn evaluate(events: &[Event], output: &CoreOutput) -> Result<Vec<f64>, FatalCoreError> {
+        events
+            .iter()
+            .map(|e| e.evaluate(&output.state, &output.state_extend))
+            .collect()
+    }
+
+    /// The snapshot to go back to for root finding, only taken if there is an event
+    fn before<P>(&self, block: &P) -> Option<P::Snapshot>
+    where
+        P: Plant + ?Sized,
+    {
+        (!self.events.is_empty()).then(|| block.snapshot())
+    }
+
+    /// Check the update of `block` from `t_0` to `t_1` which ended with `output`
+    /// every crossing is located by integrating again from `before`, the events are added to
+    /// `records`, on a terminal event the block is left at the crossing
+    /// return the time and output of the terminal event, if any
+    #[allow(clippy::too_many_arguments)]
+    fn check<P>(
+        &mut self,
+        block: &mut P,
+        before: Option<P::Snapshot>,
+        control: Control,
+        t_0: f64,
+        t_1: f64,
+        output: &CoreOutput,
+        records: &mut Vec<EventRecord>,
+    ) -> Result<Option<(f64, CoreOutput)>, FatalCoreError>
+    where
+        P: Plant + ?Sized,
+    {
+        let Some(before) = before else {
+            return Ok(None);
+        };
+        let values = Self::evaluate(self.events, output)?;
+
+        let mut crossings = Vec::new();
+        for (i, event) in self.events.iter().enumerate() {
+            if let Some(direction) = Direction::crossing(self.values[i], values[i]) {
+                if event.direction().accepts(direction) {
+                    crossings.push((i, direction));
+                }
+            }
+        }
+        if crossings.is_empty() {
+            self.values = values;
+            return Ok(None);
+        }
+
+        let after = block.snapshot();
+        let mut located = Vec::with_capacity(crossings.len());
+        for (i, direction) in crossings {
+            let event = &self.events[i];
+            let f = |t: f64| {
+                block.restore(&before)?;
+                let output = block.update(control, t)?;
+                event.evaluate(&output.state, &output.state_extend)
+            };
+            let t = locate_crossing(f, t_0, self.values[i], t_1, values[i], EVENT_TIME_TOL)?;
+            located.push((t, i, direction));
+        }
+        located.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        // nothing after the first terminal event happens
+        let t_stop = located
+            .iter()
+            .find(|(_, i, _)| self.events[*i].terminal())
+            .map(|(t, _, _)| *t);
+        for (t, i, direction) in located {
+            if t_stop.is_some_and(|t_stop| t > t_stop) {
+                break;
+            }
+            let event = &self.events[i];
+            block.restore(&before)?;
+            let output = block.update(control, t)?;
+            debug!("event {} at time: {}", event.name(), t);
+            records.push(EventRecord {
+                name: event.name().to_string(),
+                index: i,
+                time: t,
+                direction,
+                terminal: event.terminal(),
+                state: output.state,
+                state_extend: output.state_extend,
+            });
+            if event.terminal() {
+                return Ok(Some((t, output)));
+            }
+        }
+
+        block.restore(&after)?;
+        self.values = values;
+        Ok(None)
+    }
+}
+
+/// Drive a `PlaneBlock` or a `LinearModel` with a control schedule for `duration` from its
+/// last update, or from 0 for a block never updated, the schedule starts with the run
+/// delta_t: the time between two updates of the block
+/// decimation: record one sample every `decimation` updates
+/// events: watched after every update, a terminal event ends the run early
+pub fn simulate<P>(
+    block: &mut P,
+    schedule: &ControlSchedule,
+    duration: f64,
+    delta_t: f64,
+    decimation: usize,
+    events: &[Event],
+) -> Result<Trajectory, FatalCoreError>
+where
+    P: Plant + ?Sized,
+{
+    let steps = check_run(duration, delta_t, decimation)?;
+    debug!(
+        "simulate {} steps with delta_t: {}, decimation: {}",
+        steps, delta_t, decimation
+    );
+
+    let t_start = block.last_time().unwrap_or(0.0);
+    let mut trajectory = Trajectory::with_capacity(steps / decimation + 1);
+    let control = schedule.at(0.0);
+    let output = block.update(control, t_start)?;
+    let mut monitor = EventMonitor::new(events, &output)?;
+    trajectory.push(t_start, &output, block.state_dot(), control);
+
+    for i in 1..=steps {
+        let (t_0, t) = (
+            t_start + delta_t * (i - 1) as f64,
+            t_start + delta_t * i as f64,
+        );
+        // the command at the start of the interval is held over it, as in the closed loop
+        let control = schedule.at(t_0 - t_start);
+        let before = monitor.before(block);
+        let output = block.update(control, t)?;
+        let records = &mut trajectory.events;
+        if let Some((t, output)) =
+            monitor.check(block, before, control, t_0, t, &output, records)?
+        {
+            trajectory.push(t, &output, block.state_dot(), control);
+            return Ok(trajectory);
+        }
+        if i % decimation == 0 {
+            trace!("record time: {}", t);
+            trajectory.push(t, &output, block.state_dot(), control);
+        }
+    }
+    Ok(trajectory)
+}
+
+/// Close the loop around a `PlaneBlock` or a `LinearModel` with a discrete controller
+/// for `duration` from its last update, or from 0 for a block never updated
+/// the block is updated every `delta_t` and additionally at every sample time of the controller,
+/// so a controller period which is not a multiple of `delta_t` is still sampled exactly
+/// schedule: the reference passed to the controller, it starts with the run
+/// decimation: record one sample every `decimation` updates of the `delta_t` grid
+/// events: watched after every update, a terminal event ends the run early
+pub fn simulate_closed_loop<P, C>(
+    block: &mut P,
+    controller: &mut C,
+    schedule: &ControlSchedule,
+    duration: f64,
+    delta_t: f64,
+    decimation: usize,
+    events: &[Event],
+) -> Result<Trajectory, FatalCoreError>
+where
+    P: Plant + ?Sized,
+    C: DiscreteController + ?Sized,
+{
+    let steps = check_run(duration, delta_t, decimation)?;
+    let sample_time = controller.sample_time();
+    if sample_time.is_nan() || sample_time <= 0.0 {
+        return Err(FatalCoreError::InvalidInput(format!(
+            "sample time must be positive, got {sample_time}"
+        )));
+    }
+    debug!(
+        "simulate {} steps with delta_t: {}, sample_time: {}, decimation: {}",
+        steps, delta_t, sample_time, decimation
+    );
+    // grid points closer than this are the same instant
+    let tol = 1e-9 * delta_t.min(sample_time);
+
+    let t_start = block.last_time().unwrap_or(0.0);
+    let mut trajectory = Trajectory::with_capacity(steps / decimation + 1);
+    let output = block.update(schedule.at(0.0), t_start)?;
+    let mut monitor = EventMonitor::new(events, &output)?;
+    let mut command = controller.sample(t_start, &output, &schedule.at(0.0))?;
+    trajectory.push(t_start, &output, block.state_dot(), command);
+
+    let (mut i, mut k) = (1, 1);
+    let mut t_0 = t_start;
+    while i <= steps {
+        let t_plant = t_start + delta_t * i as f64;
+        let t_sample = t_start + sample_time * k as f64;
+        let sample_only = t_sample < t_plant - tol;
+        let t = if sample_only { t_sample } else { t_plant };
+
+        let before = monitor.before(block);
+        let output = block.update(command, t)?;
+        let records = &mut trajectory.events;
+        if let Some((t, output)) =
+            monitor.check(block, before, command, t_0, t, &output, records)?
+        {
+            trajectory.push(t, &output, block.state_dot(), command);
+            return Ok(trajectory);
+        }
+        t_0 = t;
+
+        if sample_only {
+            command = controller.sample(t, &output, &schedule.at(t - t_start))?;
+            k += 1;
+            continue;
+        }
+        if (t_sample - t_plant).abs() <= tol {
+            command = controller.sample(t, &output, &schedule.at(t - t_start))?;
+            k += 1;
+        }
+        if i % decimation == 0 {
+            trace!("record time: {}", t);
+            trajectory.push(t, &output, block.state_dot(), command);
+        }
+        i += 1;
+    }
+    Ok(trajectory)
+}
+
+#[cfg(test)]
+mod runner_tests {
+    use super::*;
+    use crate::components::autopilot::{Autopilot, LateralMode, SpeedMode, VerticalMode};
+    use crate::components::wind::WindModel;
+    use crate::linearize::{linearize, LinearizeOptions};
+    use crate::utils::{test_model_fin, test_model_init, TEST_CONTROL_LIMIT as CL};
+    use crate::{components::flight::MechanicalModel, solver};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_schedule() {
+        let c_0 = Control::from([2000.0, 0.0, 0.0, 0.0]);
+        let c_1 = Control::from([4000.0, -2.0, 1.0, 0.0]);
+
+        let step = ControlSchedule::new(vec![1.0, 2.0], vec![c_0, c_1], Interpolation::Step);
+        let step = step.unwrap();
+        assert_eq!(step.at(0.0).thrust, 2000.0);
+        assert_eq!(step.at(1.5).thrust, 2000.0);
+        assert_eq!(step.at(2.0).elevator, -2.0);
+        assert_eq!(step.at(10.0).aileron, 1.0);
+
+        let linear = ControlSchedule::new(vec![1.0, 2.0], vec![c_0, c_1], Interpolation::Linear);
+        let linear = linear.unwrap();
+        assert_eq!(linear.at(0.5).thrust, 2000.0);
+        assert!((linear.at(1.5).thrust - 3000.0).abs() < 1e-9);
+        assert!((linear.at(1.25).elevator + 0.5).abs() < 1e-9);
+        assert_eq!(linear.at(3.0).thrust, 4000.0);
+
+        let unordered = ControlSchedule::new(vec![1.0, 1.0], vec![c_0, c_1], Interpolation::Step);
+        assert!(matches!(unordered, Err(FatalCoreError::InvalidInput(_))));
+        let mismatch = ControlSchedule::new(vec![1.0], vec![c_0, c_1], Interpolation::Step);
+        assert!(matches!(mismatch, Err(FatalCoreError::InvalidInput(_))));
+    }
+
+    #[test]
+    fn test_simulate() {
+        let (model, result) = test_model_init();
+
+        let solver = Arc::new(solver::rk::RK4Solver::new(0.01));
+        let mut block =
+            PlaneBlock::new(solver, &model, &result.clone().into(), &[0.0, 0.0, 0.0], CL).unwrap();
+
+        let schedule = ControlSchedule::constant(result.control);
+        let trajectory = simulate(&mut block, &schedule, 2.0, 0.01, 10, &[]).unwrap();
+
+        assert_eq!(trajectory.len(), 21);
+        assert_eq!(trajectory.state.len(), trajectory.state_dot.len());
+        assert!((trajectory.time[20] - 2.0).abs() < 1e-9);
+        // trimmed level flight keeps the altitude
+        let altitude = trajectory.state.last().unwrap().altitude;
+        assert!((altitude - result.state.altitude).abs() < 10.0);
+
+        let res = simulate(&mut block, &schedule, 1.0, 0.01, 0, &[]);
+        assert!(matches!(res, Err(FatalCoreError::InvalidInput(_))));
+
+        // a breakpoint takes effect on the interval starting at it
+        let mut pulled = result.control;
+        pulled.elevator -= 1.0;
+        let step = ControlSchedule::new(
+            vec![0.0, 0.5],
+            vec![result.control, pulled],
+            Interpolation::Step,
+        )
+        .unwrap();
+        let trajectory = simulate(&mut block, &step, 1.0, 0.01, 1, &[]).unwrap();
+        assert_eq!(trajectory.command[50].elevator, result.control.elevator);
+        assert_eq!(trajectory.command[51].elevator, pulled.elevator);
+
+        block.delete_model();
+        test_model_fin(model);
+    }
+
+    #[test]
+    fn test_resume() {
+        let (model, result) = test_model_init();
+
+        let solver = Arc::new(solver::rk::RK4Solver::new(0.01));
+        let init = result.clone().into();
+        let mut whole =
+            PlaneBlock::new(solver.clone(), &model, &init, &[0.0, 0.0, 0.0], CL).unwrap();
+        let mut split = PlaneBlock::new(solver, &model, &init, &[0.0, 0.0, 0.0], CL).unwrap();
+
+        let mut pulled = result.control;
+        pulled.elevator -= 1.0;
+        let schedule = ControlSchedule::constant(pulled);
+        let once = simulate(&mut whole, &schedule, 2.0, 0.01, 10, &[]).unwrap();
+
+        // a second run carries on from where the first one stopped
+        simulate(&mut split, &schedule, 1.0, 0.01, 10, &[]).unwrap();
+        let snapshot = split.snapshot();
+        let twice = simulate(&mut split, &schedule, 1.0, 0.01, 10, &[]).unwrap();
+        assert_eq!(twice.len(), 11);
+        assert!((twice.time[0] - 1.0).abs() < 1e-12);
+        assert!((twice.time[10] - 2.0).abs() < 1e-9);
+        let (a, b) = (once.state.last().unwrap(), twice.state.last().unwrap());
+        assert!((a.altitude - b.altitude).abs() < 1e-6);
+        assert!((a.theta - b.theta).abs() < 1e-9);
+
+        // so does a run from a snapshot taken in between
+        split.restore(&snapshot).unwrap();
+        let restored = simulate(&mut split, &schedule, 1.0, 0.01, 10, &[]).unwrap();
+        assert_eq!(restored.time, twice.time);
+        assert_eq!(restored.state, twice.state);
+
+        // and a closed loop run after the open loop ones
+        let mut controller = PitchDamper {
+            sample_time: 0.02,
+            samples: Vec::new(),
+        };
+        let closed =
+            simulate_closed_loop(&mut split, &mut controller, &schedule, 1.0, 0.01, 10, &[])
+                .unwrap();
+        assert!((closed.time[0] - 2.0).abs() < 1e-9);
+        assert!((controller.samples[0].0 - 2.0).abs() < 1e-9);
+
+        whole.delete_model();
+        test_model_fin(model);
+    }
+
+    #[test]
+    fn test_linear_model() {
+        let (model, result) = test_model_init();
+
+        let solver = Arc::new(solver::rk::RK4Solver::new(0.01));
+        let mut block = PlaneBlock::new(
+            solver.clone(),
+            &model,
+            &result.clone().into(),
+            &[0.0, 0.0, 0.0],
+            CL,
+        )
+        .unwrap();
+        let plane = MechanicalModel::new(&model).unwrap();
+        let options = LinearizeOptions::default().with_actuators().with_lef();
+        let linearization = linearize(&plane, &result, Some(options)).unwrap();
+        let mut linear = LinearModel::new(solver, linearization).unwrap();
+
+        // the same small elevator step through both planes
+        let mut pulled = result.control;
+        pulled.elevator -= 0.5;
+        let schedule = ControlSchedule::new(
+            vec![0.0, 1.0],
+            vec![result.control, pulled],
+            Interpolation::Step,
+        )
+        .unwrap();
+        let nonlinear = simulate(&mut block, &schedule, 4.0, 0.01, 10, &[]).unwrap();
+        let linear = simulate(&mut linear, &schedule, 4.0, 0.01, 10, &[]).unwrap();
+        assert_eq!(nonlinear.len(), linear.len());
+
+        // within a few percent while the motion stays small
+        let peak = nonlinear
+            .state
+            .iter()
+            .map(|s| s.q.abs())
+            .fold(0.0, f64::max);
+        assert!(peak > 1e-3);
+        for (n, l) in nonlinear.state.iter().zip(&linear.state) {
+            assert!((n.q - l.q).abs() < 0.15 * peak, "q: {} and {}", n.q, l.q);
+            assert!((n.alpha - l.alpha).abs() < 5e-3);
+            assert!((n.velocity - l.velocity).abs() < 1.0);
+        }
+        let (n, l) = (
+            nonlinear.control.last().unwrap(),
+            linear.control.last().unwrap(),
+        );
+        assert!((n.elevator - l.elevator).abs() < 1e-3);
+
+        block.delete_model();
+        test_model_fin(model);
+    }
+
+    #[test]
+    fn test_events() {
+        let (model, result) = test_model_init();
+
+        let solver = Arc::new(solver::rk::RK4Solver::new(0.01));
+        let mut block =
+            PlaneBlock::new(solver, &model, &result.clone().into(), &[0.0, 0.0, 0.0], CL).unwrap();
+
+        let pulled = Control::from([
+            result.control.thrust,
+            result.control.elevator - 3.0,
+            result.control.aileron,
+            result.control.rudder,
+        ]);
+        let schedule = ControlSchedule::constant(pulled);
+
+        let altitude = result.state.altitude + 1.0;
+        let theta = result.state.theta.to_degrees() + 5.0;
+        let events = [
+            Event::altitude(altitude, Direction::Rising, false),
+            Event::new(
+                "theta",
+                move |state: &State, _: &StateExtend| Ok(state.theta.to_degrees() - theta),
+                Direction::Rising,
+                true,
+            ),
+            // never reached while climbing
+            Event::altitude(result.state.altitude - 100.0, Direction::Both, true),
+        ];
+        let trajectory = simulate(&mut block, &schedule, 10.0, 0.01, 10, &events).unwrap();
+
+        assert_eq!(trajectory.events.len(), 2);
+        let (first, last) = (&trajectory.events[0], &trajectory.events[1]);
+        assert_eq!(first.name, "altitude");
+        assert_eq!(first.direction, Direction::Rising);
+        assert!(!first.terminal);
+        assert!((first.state.altitude - altitude).abs() < 1e-6);
+        assert_eq!(last.index, 1);
+        assert!(last.terminal && last.time > first.time);
+        assert!((last.state.theta.to_degrees() - theta).abs() < 1e-6);
+
+        // the run stops at the terminal event, which is also the last sample
+        assert_eq!(*trajectory.time.last().unwrap(), last.time);
+        assert!(last.time < 10.0);
+        assert_eq!(block.state().state, last.state);
+
+        block.delete_model();
+        test_model_fin(model);
+    }
+
+    /// pitch damper which remembers when it was sampled and what it commanded
+    struct PitchDamper {
+        sample_time: f64,
+        samples: Vec<(f64, f64)>,
+    }
+
+    impl DiscreteController for PitchDamper {
+        fn sample_time(&self) -> f64 {
+            self.sample_time
+        }
+
+        fn sample(
+            &mut self,
+            t: f64,
+            output: &CoreOutput,
+            reference: &Control,
+        ) -> Result<Control, FatalCoreError> {
+            let mut command = *reference;
+            command.elevator += 2.0 * output.state.q.to_degrees();
+            self.samples.push((t, command.elevator));
+            Ok(command)
+        }
+    }
+
+    #[test]
+    fn test_closed_loop() {
+        let (model, result) = test_model_init();
+
+        let solver = Arc::new(solver::rk::RK4Solver::new(0.01));
+        let mut block =
+            PlaneBlock::new(solver, &model, &result.clone().into(), &[0.0, 0.0, 0.0], CL).unwrap();
+        block.set_subsystem_substeps(4).unwrap();
+        assert!(block.set_subsystem_substeps(0).is_err());
+
+        let pulled = Control::from([
+            result.control.thrust,
+            result.control.elevator - 1.0,
+            result.control.aileron,
+            result.control.rudder,
+        ]);
+        let schedule = ControlSchedule::new(
+            vec![0.0, 0.5],
+            vec![pulled, result.control],
+            Interpolation::Step,
+        )
+        .unwrap();
+
+        // an 80 Hz flight computer on a 100 Hz airframe
+        let mut controller = PitchDamper {
+            sample_time: 0.0125,
+            samples: Vec::new(),
+        };
+        let trajectory =
+            simulate_closed_loop(&mut block, &mut controller, &schedule, 2.0, 0.01, 1, &[])
+                .unwrap();
+
+        assert_eq!(trajectory.len(), 201);
+        assert_eq!(controller.samples.len(), 161);
+        for (k, (t, _)) in controller.samples.iter().enumerate() {
+            assert!((t - 0.0125 * k as f64).abs() < 1e-12);
+        }
+        // every recorded command is the one of the latest sample
+        for (t, command) in trajectory.time.iter().zip(trajectory.command.iter()) {
+            let k = (t / 0.0125 + 1e-9).floor() as usize;
+            assert_eq!(command.elevator, controller.samples[k].1);
+        }
+
+        // the damper brings the pitch rate back after the pulse
+        let q = trajectory.state.last().unwrap().q;
+        assert!(q.abs() < 0.2_f64.to_radians());
+
+        block.delete_model();
+        test_model_fin(model);
+    }
+
+    #[test]
+    fn test_autopilot() {
+        let (model, result) = test_model_init();
+
+        let solver = Arc::new(solver::rk::RK4Solver::new(0.01));
+        let mut block = PlaneBlock::new(
+            solver.clone(),
+            &model,
+            &result.clone().into(),
+            &[0.0, 0.0, 0.0],
+            CL,
+        )
+        .unwrap();
+        let schedule = ControlSchedule::constant(result.control);
+
+        // fly heading 090 at 15,500 ft and Mach 0.5
+        let mut autopilot = Autopilot::f16()
+            .with_vertical(VerticalMode::Altitude(15500.0))
+            .with_lateral(LateralMode::Heading(90.0_f64.to_radians()))
+            .with_speed(SpeedMode::Mach(0.5));
+        let trajectory =
+            simulate_closed_loop(&mut block, &mut autopilot, &schedule, 90.0, 0.01, 100, &[])
+                .unwrap();
+        let bank = trajectory.state.iter().map(|s| s.phi.to_degrees().abs());
+        assert!(bank.fold(0.0, f64::max) < 31.0);
+        let state = trajectory.state.last().unwrap();
+        assert!((state.altitude - 15500.0).abs() < 30.0);
+        assert!((state.psi.to_degrees() - 90.0).abs() < 2.0);
+        assert!(state.phi.to_degrees().abs() < 3.0);
+        let mach = trajectory.state_extend.last().unwrap().mach;
+        assert!((mach - 0.5).abs() < 0.01);
+
+        // descend at 30 ft/s on a track of 330
+        let mut block = PlaneBlock::new(
+            solver.clone(),
+            &model,
+            &result.clone().into(),
+            &[0.0, 0.0, 0.0],
+            CL,
+        )
+        .unwrap();
+        let mut autopilot = Autopilot::f16()
+            .with_vertical(VerticalMode::VerticalSpeed(-30.0))
+            .with_lateral(LateralMode::Track(-30.0_f64.to_radians()))
+            .with_speed(SpeedMode::Airspeed(500.0));
+        let trajectory =
+            simulate_closed_loop(&mut block, &mut autopilot, &schedule, 60.0, 0.01, 100, &[])
+                .unwrap();
+        let descent = trajectory.state[30].altitude - trajectory.state[60].altitude;
+        assert!((descent - 900.0).abs() < 60.0);
+        let state = trajectory.state.last().unwrap();
+        assert!((state.psi.to_degrees() + 30.0).abs() < 2.0);
+        assert!((state.velocity - 500.0).abs() < 20.0);
+
+        // in a steady wind from ahead and the right the plane crabs to hold the track
+        // at the airspeed through the air
+        let mut block =
+            PlaneBlock::new(solver, &model, &result.clone().into(), &[0.0, 0.0, 0.0], CL).unwrap();
+        block.set_wind(Some(WindModel::new([-40.0, -60.0, 0.0])));
+        let mut autopilot = Autopilot::f16()
+            .with_vertical(VerticalMode::Altitude(15000.0))
+            .with_lateral(LateralMode::Track(0.0))
+            .with_speed(SpeedMode::Airspeed(500.0));
+        let trajectory =
+            simulate_closed_loop(&mut block, &mut autopilot, &schedule, 90.0, 0.01, 100, &[])
+                .unwrap();
+        let (a, b) = (&trajectory.state[80], trajectory.state.last().unwrap());
+        let track = (b.epos - a.epos).atan2(b.npos - a.npos);
+        assert!(track.to_degrees().abs() < 1.0, "{}", track.to_degrees());
+        let crab = (60.0_f64 / 500.0).asin().to_degrees();
+        assert!(
+            (b.psi.to_degrees() - crab).abs() < 1.0,
+            "{}",
+            b.psi.to_degrees()
+        );
+        let air = block.air_relative();
+        assert!((air.velocity - 500.0).abs() < 5.0, "{}", air.velocity);
+        assert!(air.beta.to_degrees().abs() < 1.0);
+        assert!((b.velocity - air.velocity).abs() > 30.0);
+        assert!((b.altitude - 15000.0).abs() < 30.0);
+
+        assert!(Autopilot::f16().with_thrust_range(1.0, 0.0).is_err());
+
+        block.delete_model();
+        test_model_fin(model);
+    }
+}
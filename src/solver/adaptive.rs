@@ -0,0 +1,387 @@
+use super::{Dynamics, ODESolver, Solver, VectorDynamics, VectorODESolver};
+use crate::utils::{error::FatalCoreError, Vector};
+use log::{trace, warn};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Counters of an adaptive solver since creation or the last `reset_stats`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SolverStats {
+    pub accepted_steps: usize,
+    pub rejected_steps: usize,
+    pub fun_evals: usize,
+}
+
+impl std::fmt::Display for SolverStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "accepted_steps: {}, rejected_steps: {}, fun_evals: {}",
+            self.accepted_steps, self.rejected_steps, self.fun_evals
+        )
+    }
+}
+
+#[derive(Debug, Default)]
+struct AtomicStats {
+    accepted_steps: AtomicUsize,
+    rejected_steps: AtomicUsize,
+    fun_evals: AtomicUsize,
+}
+
+impl AtomicStats {
+    fn load(&self) -> SolverStats {
+        SolverStats {
+            accepted_steps: self.accepted_steps.load(Ordering::Relaxed),
+            rejected_steps: self.rejected_steps.load(Ordering::Relaxed),
+            fun_evals: self.fun_evals.load(Ordering::Relaxed),
+        }
+    }
+
+    fn store(&self, stats: SolverStats) {
+        self.accepted_steps
+            .store(stats.accepted_steps, Ordering::Relaxed);
+        self.rejected_steps
+            .store(stats.rejected_steps, Ordering::Relaxed);
+        self.fun_evals.store(stats.fun_evals, Ordering::Relaxed);
+    }
+}
+
+// Dormand–Prince 5(4) tableau
+const C: [f64; 7] = [0.0, 1.0 / 5.0, 3.0 / 10.0, 4.0 / 5.0, 8.0 / 9.0, 1.0, 1.0];
+const A: [[f64; 6]; 7] = [
+    [0.0, 0.0, 0.0, 0.0, 0.0, 0.0],
+    [1.0 / 5.0, 0.0, 0.0, 0.0, 0.0, 0.0],
+    [3.0 / 40.0, 9.0 / 40.0, 0.0, 0.0, 0.0, 0.0],
+    [44.0 / 45.0, -56.0 / 15.0, 32.0 / 9.0, 0.0, 0.0, 0.0],
+    [
+        19372.0 / 6561.0,
+        -25360.0 / 2187.0,
+        64448.0 / 6561.0,
+        -212.0 / 729.0,
+        0.0,
+        0.0,
+    ],
+    [
+        9017.0 / 3168.0,
+        -355.0 / 33.0,
+        46732.0 / 5247.0,
+        49.0 / 176.0,
+        -5103.0 / 18656.0,
+        0.0,
+    ],
+    // the 5th order weights, the last stage is evaluated at the new point (FSAL)
+    [
+        35.0 / 384.0,
+        0.0,
+        500.0 / 1113.0,
+        125.0 / 192.0,
+        -2187.0 / 6784.0,
+        11.0 / 84.0,
+    ],
+];
+/// difference between the 5th and the embedded 4th order weights
+const E: [f64; 7] = [
+    71.0 / 57600.0,
+    0.0,
+    -71.0 / 16695.0,
+    71.0 / 1920.0,
+    -17253.0 / 339200.0,
+    22.0 / 525.0,
+    -1.0 / 40.0,
+];
+
+/// Embedded Runge–Kutta 4(5) solver of Dormand and Prince with step size control
+/// delta_t: the largest step, every call starts with the full requested step
+/// rtol, atol: relative and absolute tolerance of the local error
+/// min_step: steps are never shortened below it, even if the error is too large,
+/// 0 < min_step <= delta_t
+pub struct RK45Solver {
+    delta_t: f64,
+    rtol: f64,
+    atol: f64,
+    min_step: f64,
+    stats: AtomicStats,
+}
+
+impl Clone for RK45Solver {
+    fn clone(&self) -> Self {
+        let stats = AtomicStats::default();
+        stats.store(self.stats.load());
+        Self {
+            delta_t: self.delta_t,
+            rtol: self.rtol,
+            atol: self.atol,
+            min_step: self.min_step,
+            stats,
+        }
+    }
+}
+
+impl RK45Solver {
+    pub fn new(delta_t: f64, rtol: f64, atol: f64, min_step: f64) -> Result<Self, FatalCoreError> {
+        if !(rtol >= 0.0 && atol >= 0.0) || rtol + atol == 0.0 {
+            return Err(FatalCoreError::InvalidInput(format!(
+                "RK45 needs non-negative tolerances, not both zero, got rtol {rtol} and atol {atol}"
+            )));
+        }
+        if !(min_step > 0.0 && min_step <= delta_t) {
+            return Err(FatalCoreError::InvalidInput(format!(
+                "RK45 needs 0 < min_step <= delta_t, got min_step {min_step} and delta_t {delta_t}"
+            )));
+        }
+        Ok(RK45Solver {
+            delta_t,
+            rtol,
+            atol,
+            min_step,
+            stats: AtomicStats::default(),
+        })
+    }
+
+    pub fn delta_t(&self) -> f64 {
+        self.delta_t
+    }
+
+    pub fn rtol(&self) -> f64 {
+        self.rtol
+    }
+
+    pub fn atol(&self) -> f64 {
+        self.atol
+    }
+
+    pub fn min_step(&self) -> f64 {
+        self.min_step
+    }
+
+    pub fn stats(&self) -> SolverStats {
+        self.stats.load()
+    }
+
+    pub fn reset_stats(&self) {
+        self.stats.store(SolverStats::default());
+    }
+
+    /// RMS norm of the error weighted by the tolerances
+    fn error_norm(&self, error: &[f64], state: &[f64], state_next: &[f64]) -> f64 {
+        let sum: f64 = error
+            .iter()
+            .zip(state.iter().zip(state_next.iter()))
+            .map(|(e, (y, y_next))| {
+                let scale = self.atol + self.rtol * y.abs().max(y_next.abs());
+                (e / scale).powi(2)
+            })
+            .sum();
+        (sum / error.len().max(1) as f64).sqrt()
+    }
+
+    fn integrate_adaptive(
+        &self,
+        dynamics: impl Fn(f64, &Vector, &Vector) -> Vector,
+        t: f64,
+        h: f64,
+        state: &Vector,
+        input: &Vector,
+    ) -> Vector {
+        let t_end = t + h;
+        let mut t = t;
+        let mut state = state.clone();
+        let mut step = h.min(self.delta_t);
+        let mut k_first = dynamics(t, &state, input);
+        let mut stats = self.stats.load();
+        stats.fun_evals += 1;
+
+        while t_end - t > 1e-12 * t_end.abs().max(1.0) {
+            step = step.min(t_end - t);
+
+            let mut k: Vec<Vector> = Vec::with_capacity(7);
+            k.push(k_first.clone());
+            for i in 1..7 {
+                let mut stage = state.clone();
+                for (j, k_j) in k.iter().enumerate() {
+                    if A[i][j] != 0.0 {
+                        stage += k_j.clone() * (A[i][j] * step);
+                    }
+                }
+                k.push(dynamics(t + C[i] * step, &stage, input));
+            }
+            stats.fun_evals += 6;
+
+            // the 6th stage point is the 5th order solution
+            let mut state_next = state.clone();
+            for (j, k_j) in k.iter().take(6).enumerate() {
+                if A[6][j] != 0.0 {
+                    state_next += k_j.clone() * (A[6][j] * step);
+                }
+            }
+            let mut error = Vector::zero(state.dim());
+            for (j, k_j) in k.iter().enumerate() {
+                if E[j] != 0.0 {
+                    error += k_j.clone() * (E[j] * step);
+                }
+            }
+
+            let error_norm = self.error_norm(&error.data, &state.data, &state_next.data);
+            if !error_norm.is_finite() {
+                // a non-finite error can never be accepted, shrink hard until
+                // the minimum step and then hand the NaN back to the caller
+                if step <= self.min_step {
+                    warn!(
+                        "RK45 got a non-finite error norm at the minimum step {} at t = {}",
+                        step, t
+                    );
+                    self.stats.store(stats);
+                    return Vector::from(vec![f64::NAN; state.dim()]);
+                }
+                trace!("RK45 rejected step {} with non-finite error norm", step);
+                stats.rejected_steps += 1;
+                step = (step * 0.2).clamp(self.min_step, self.delta_t);
+                continue;
+            }
+            let accept = error_norm <= 1.0 || step <= self.min_step;
+            if accept {
+                if error_norm > 1.0 {
+                    warn!(
+                        "RK45 accepted step {} at the minimum with error norm {}",
+                        step, error_norm
+                    );
+                }
+                t += step;
+                state = state_next;
+                k_first = k.pop().unwrap();
+                stats.accepted_steps += 1;
+            } else {
+                trace!("RK45 rejected step {} with error norm {}", step, error_norm);
+                stats.rejected_steps += 1;
+            }
+
+            let factor = if error_norm == 0.0 {
+                5.0
+            } else {
+                (0.9 * error_norm.powf(-0.2)).clamp(0.2, 5.0)
+            };
+            // never grow the step right after a rejection
+            let factor = if accept { factor } else { factor.min(1.0) };
+            step = (step * factor).clamp(self.min_step, self.delta_t);
+        }
+
+        self.stats.store(stats);
+        state
+    }
+}
+
+impl Solver for RK45Solver {
+    fn delta_t(&self) -> f64 {
+        self.delta_t
+    }
+}
+
+impl ODESolver for RK45Solver {
+    fn solve_step(&self, dynamics: &Dynamics, t: f64, dt: f64, state: f64, input: f64) -> f64 {
+        let dynamics = |t: f64, state: &Vector, input: &Vector| -> Vector {
+            Vector::from(vec![dynamics(t, state[0], input[0])])
+        };
+        let state = Vector::from(vec![state]);
+        let input = Vector::from(vec![input]);
+        self.integrate_adaptive(dynamics, t, dt, &state, &input)[0]
+    }
+}
+
+impl VectorODESolver for RK45Solver {
+    fn solve_step(
+        &self,
+        dynamics: &VectorDynamics,
+        t: f64,
+        dt: f64,
+        state: &Vector,
+        input: &Vector,
+    ) -> Vector {
+        self.integrate_adaptive(dynamics, t, dt, state, input)
+    }
+}
+
+#[cfg(test)]
+mod solver_tests {
+    use super::*;
+    use crate::solver::rk::RK4Solver;
+
+    #[test]
+    fn test_rk45() {
+        // dx/dt = -x, x(0) = 1
+        let dynamics = |_t: f64, state: f64, _input: f64| -> f64 { -state };
+        let solver = RK45Solver::new(1.0, 1e-10, 1e-12, 1e-8).unwrap();
+        let x = ODESolver::integrate(&solver, &dynamics, 0.0, 5.0, 1.0, 0.0);
+        assert!((x - (-5.0_f64).exp()).abs() < 1e-9);
+
+        let stats = solver.stats();
+        assert!(stats.accepted_steps > 5);
+        assert_eq!(
+            stats.fun_evals,
+            5 + 6 * (stats.accepted_steps + stats.rejected_steps)
+        );
+
+        solver.reset_stats();
+        assert_eq!(solver.stats(), SolverStats::default());
+    }
+
+    #[test]
+    fn test_rk45_accuracy() {
+        // harmonic oscillator, x(t) = cos(t)
+        let dynamics = |_t: f64, state: &Vector, _input: &Vector| -> Vector {
+            Vector::from(vec![state[1], -state[0]])
+        };
+        let init = Vector::from(vec![1.0, 0.0]);
+        let input = Vector::zero(0);
+
+        let tight = RK45Solver::new(0.5, 1e-9, 1e-12, 1e-8).unwrap();
+        let x = VectorODESolver::integrate(&tight, &dynamics, 0.0, 20.0, &init, &input);
+        assert!((x[0] - 20.0_f64.cos()).abs() < 1e-7);
+
+        let loose = RK45Solver::new(0.5, 1e-3, 1e-6, 1e-8).unwrap();
+        let y = VectorODESolver::integrate(&loose, &dynamics, 0.0, 20.0, &init, &input);
+        assert!(loose.stats().accepted_steps < tight.stats().accepted_steps);
+
+        // as a fixed step solver RK4 needs a small step to be as accurate
+        let rk4 = RK4Solver::new(0.5);
+        let z = VectorODESolver::integrate(&rk4, &dynamics, 0.0, 20.0, &init, &input);
+        assert!((x[0] - 20.0_f64.cos()).abs() < (z[0] - 20.0_f64.cos()).abs());
+        assert!((y[0] - 20.0_f64.cos()).abs() < 1e-2);
+    }
+
+    #[test]
+    fn test_rk45_rejection() {
+        // a fast mode forces the solver to reject the initial step
+        let dynamics = |_t: f64, state: f64, _input: f64| -> f64 { -50.0 * (state - 1.0) };
+        let solver = RK45Solver::new(1.0, 1e-8, 1e-10, 1e-8).unwrap();
+        let x = ODESolver::solve_step(&solver, &dynamics, 0.0, 1.0, 0.0, 0.0);
+        assert!((x - 1.0).abs() < 1e-8);
+        assert!(solver.stats().rejected_steps > 0);
+    }
+
+    #[test]
+    fn test_rk45_nan() {
+        // the dynamics blow up after t = 0.05, the solver must give up instead of looping
+        let dynamics = |t: f64, state: f64, _input: f64| -> f64 {
+            if t > 0.05 {
+                f64::NAN
+            } else {
+                -state
+            }
+        };
+        let solver = RK45Solver::new(0.1, 1e-6, 1e-9, 1e-6).unwrap();
+        let x = ODESolver::integrate(&solver, &dynamics, 0.0, 1.0, 1.0, 0.0);
+        assert!(x.is_nan());
+        assert!(solver.stats().rejected_steps > 0);
+    }
+
+    #[test]
+    fn test_rk45_options() {
+        assert!(RK45Solver::new(0.1, 0.0, 0.0, 1e-6).is_err());
+        assert!(RK45Solver::new(0.1, -1e-6, 1e-8, 1e-6).is_err());
+        assert!(RK45Solver::new(0.1, f64::NAN, 1e-8, 1e-6).is_err());
+        assert!(RK45Solver::new(0.1, 1e-6, 1e-8, 0.0).is_err());
+        assert!(RK45Solver::new(0.1, 1e-6, 1e-8, 0.2).is_err());
+        assert!(RK45Solver::new(0.1, 0.0, 1e-8, 0.1).is_ok());
+    }
+}
@@ -2,14 +2,77 @@ use crate::utils::Vector;
 
 pub type Dynamics = dyn Fn(f64, f64, f64) -> f64;
 
-pub trait ODESolver: Clone {
-    fn solve(&self, dynamics: &Dynamics, t: f64, state: f64, input: f64) -> f64;
+/// The part shared by scalar and vector solvers
+pub trait Solver: Clone {
+    /// The configured step size, which is also the largest step taken by `integrate`
+    fn delta_t(&self) -> f64;
+}
+
+pub trait ODESolver: Solver {
+    /// Advance the state from `t` by exactly `h`
+    fn solve_step(&self, dynamics: &Dynamics, t: f64, h: f64, state: f64, input: f64) -> f64;
+
+    /// Advance the state from `t` by one configured step
+    fn solve(&self, dynamics: &Dynamics, t: f64, state: f64, input: f64) -> f64 {
+        self.solve_step(dynamics, t, self.delta_t(), state, input)
+    }
+
+    /// Advance the state from `t_0` to `t_1`, sub-stepping at no more than `delta_t`
+    fn integrate(&self, dynamics: &Dynamics, t_0: f64, t_1: f64, state: f64, input: f64) -> f64 {
+        let (n, h) = substeps(t_1 - t_0, self.delta_t());
+        let mut state = state;
+        for i in 0..n {
+            state = self.solve_step(dynamics, t_0 + h * i as f64, h, state, input);
+        }
+        state
+    }
 }
 
 pub type VectorDynamics = dyn Fn(f64, &Vector, &Vector) -> Vector;
 
 /// A trait for ordinary differential equation (ODE) solvers.
 /// An ODE solver is a type that can solve a system of ODEs given the current time, state, and input.
-pub trait VectorODESolver: Clone {
-    fn solve(&self, dynamics: &VectorDynamics, t: f64, state: &Vector, input: &Vector) -> Vector;
+pub trait VectorODESolver: Solver {
+    /// Advance the state from `t` by exactly `h`
+    fn solve_step(
+        &self,
+        dynamics: &VectorDynamics,
+        t: f64,
+        h: f64,
+        state: &Vector,
+        input: &Vector,
+    ) -> Vector;
+
+    /// Advance the state from `t` by one configured step
+    fn solve(&self, dynamics: &VectorDynamics, t: f64, state: &Vector, input: &Vector) -> Vector {
+        self.solve_step(dynamics, t, self.delta_t(), state, input)
+    }
+
+    /// Advance the state from `t_0` to `t_1`, sub-stepping at no more than `delta_t`
+    fn integrate(
+        &self,
+        dynamics: &VectorDynamics,
+        t_0: f64,
+        t_1: f64,
+        state: &Vector,
+        input: &Vector,
+    ) -> Vector {
+        let (n, h) = substeps(t_1 - t_0, self.delta_t());
+        let mut state = state.clone();
+        for i in 0..n {
+            state = self.solve_step(dynamics, t_0 + h * i as f64, h, &state, input);
+        }
+        state
+    }
+}
+
+/// Split `span` into the fewest equal steps which are not longer than `max_step`
+/// return the number of steps and the step size, a non-positive span needs no step
+pub fn substeps(span: f64, max_step: f64) -> (usize, f64) {
+    if span <= 0.0 {
+        return (0, 0.0);
+    }
+    // the tolerance keeps a span of exactly k steps from rounding up to k + 1
+    let n = ((span / max_step) * (1.0 - 1e-12)).ceil().max(1.0) as usize;
+    (n, span / n as f64)
 }
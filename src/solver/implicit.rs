@@ -0,0 +1,390 @@
+use super::{Dynamics, ODESolver, Solver, VectorDynamics, VectorODESolver};
+use crate::utils::{Matrix, Vector};
+use log::{trace, warn};
+use serde::{Deserialize, Serialize};
+
+/// Options of the Newton iteration which solves the implicit stages
+/// tol: the iteration stops once the update is below `tol * (1 + |z|)` in the max norm
+/// max_iter: the last iterate is used if the iteration has not converged by then
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct NewtonOptions {
+    pub tol: f64,
+    pub max_iter: usize,
+}
+
+impl Default for NewtonOptions {
+    fn default() -> Self {
+        Self {
+            tol: 1e-10,
+            max_iter: 20,
+        }
+    }
+}
+
+/// Solve `z = base + coeff * f(t, z, input)` for z with Newton's method,
+/// the Jacobian of f is approximated by forward differences at every iterate
+fn solve_stage(
+    dynamics: &impl Fn(f64, &Vector, &Vector) -> Vector,
+    t: f64,
+    base: &Vector,
+    coeff: f64,
+    guess: Vector,
+    input: &Vector,
+    options: &NewtonOptions,
+) -> Vector {
+    let n = base.dim();
+    let mut z = guess;
+
+    for iter in 0..options.max_iter {
+        let f = dynamics(t, &z, input);
+        // residual of g(z) = z - base - coeff * f(z)
+        let residual = Vector::from(
+            (0..n)
+                .map(|i| z[i] - base[i] - coeff * f[i])
+                .collect::<Vec<_>>(),
+        );
+
+        // dg/dz = I - coeff * df/dz
+        let mut jacobian = Matrix::new((n, n));
+        for j in 0..n {
+            let eps = f64::EPSILON.sqrt() * z[j].abs().max(1.0);
+            let mut z_eps = z.clone();
+            z_eps[j] += eps;
+            let f_eps = dynamics(t, &z_eps, input);
+            for i in 0..n {
+                jacobian[(i, j)] = -coeff * (f_eps[i] - f[i]) / eps;
+            }
+            jacobian[(j, j)] += 1.0;
+        }
+
+        let delta = match jacobian.solve(&residual) {
+            Ok(delta) => delta,
+            Err(_) => {
+                warn!("singular Newton matrix at t: {}, keep the last iterate", t);
+                return z;
+            }
+        };
+
+        let mut converged = true;
+        for i in 0..n {
+            z[i] -= delta[i];
+            if delta[i].abs() > options.tol * (1.0 + z[i].abs()) {
+                converged = false;
+            }
+        }
+        if converged {
+            trace!("Newton converged after {} iterations", iter + 1);
+            return z;
+        }
+    }
+
+    warn!(
+        "Newton did not converge in {} iterations at t: {}",
+        options.max_iter, t
+    );
+    z
+}
+
+/// Run a vector method on a scalar problem
+fn scalar_step(
+    step: impl Fn(&dyn Fn(f64, &Vector, &Vector) -> Vector, &Vector, &Vector) -> Vector,
+    dynamics: &Dynamics,
+    state: f64,
+    input: f64,
+) -> f64 {
+    let dynamics = |t: f64, state: &Vector, input: &Vector| -> Vector {
+        Vector::from(vec![dynamics(t, state[0], input[0])])
+    };
+    let state = Vector::from(vec![state]);
+    let input = Vector::from(vec![input]);
+    step(&dynamics, &state, &input)[0]
+}
+
+/// Implicit (backward) Euler, first order and L-stable
+#[derive(Clone)]
+pub struct BackwardEulerSolver {
+    delta_t: f64,
+    options: NewtonOptions,
+}
+
+impl BackwardEulerSolver {
+    pub fn new(delta_t: f64) -> Self {
+        Self::with_options(delta_t, NewtonOptions::default())
+    }
+
+    pub fn with_options(delta_t: f64, options: NewtonOptions) -> Self {
+        BackwardEulerSolver { delta_t, options }
+    }
+
+    pub fn delta_t(&self) -> f64 {
+        self.delta_t
+    }
+
+    fn step(
+        &self,
+        dynamics: &dyn Fn(f64, &Vector, &Vector) -> Vector,
+        t: f64,
+        dt: f64,
+        state: &Vector,
+        input: &Vector,
+    ) -> Vector {
+        // explicit Euler predictor
+        let guess = state.clone() + dynamics(t, state, input) * dt;
+        solve_stage(&dynamics, t + dt, state, dt, guess, input, &self.options)
+    }
+}
+
+impl Solver for BackwardEulerSolver {
+    fn delta_t(&self) -> f64 {
+        self.delta_t
+    }
+}
+
+impl ODESolver for BackwardEulerSolver {
+    fn solve_step(&self, dynamics: &Dynamics, t: f64, dt: f64, state: f64, input: f64) -> f64 {
+        let step = |f: &dyn Fn(f64, &Vector, &Vector) -> Vector, state: &Vector, input: &Vector| {
+            self.step(f, t, dt, state, input)
+        };
+        scalar_step(step, dynamics, state, input)
+    }
+}
+
+impl VectorODESolver for BackwardEulerSolver {
+    fn solve_step(
+        &self,
+        dynamics: &VectorDynamics,
+        t: f64,
+        dt: f64,
+        state: &Vector,
+        input: &Vector,
+    ) -> Vector {
+        self.step(dynamics, t, dt, state, input)
+    }
+}
+
+/// Trapezoidal rule (Crank–Nicolson), second order and A-stable,
+/// stiff modes are not damped but oscillate with a factor close to -1
+#[derive(Clone)]
+pub struct TrapezoidalSolver {
+    delta_t: f64,
+    options: NewtonOptions,
+}
+
+impl TrapezoidalSolver {
+    pub fn new(delta_t: f64) -> Self {
+        Self::with_options(delta_t, NewtonOptions::default())
+    }
+
+    pub fn with_options(delta_t: f64, options: NewtonOptions) -> Self {
+        TrapezoidalSolver { delta_t, options }
+    }
+
+    pub fn delta_t(&self) -> f64 {
+        self.delta_t
+    }
+
+    fn step(
+        &self,
+        dynamics: &dyn Fn(f64, &Vector, &Vector) -> Vector,
+        t: f64,
+        dt: f64,
+        state: &Vector,
+        input: &Vector,
+    ) -> Vector {
+        let k1 = dynamics(t, state, input);
+        let base = state.clone() + k1.clone() * (dt / 2.0);
+        let guess = state.clone() + k1 * dt;
+        solve_stage(
+            &dynamics,
+            t + dt,
+            &base,
+            dt / 2.0,
+            guess,
+            input,
+            &self.options,
+        )
+    }
+}
+
+impl Solver for TrapezoidalSolver {
+    fn delta_t(&self) -> f64 {
+        self.delta_t
+    }
+}
+
+impl ODESolver for TrapezoidalSolver {
+    fn solve_step(&self, dynamics: &Dynamics, t: f64, dt: f64, state: f64, input: f64) -> f64 {
+        let step = |f: &dyn Fn(f64, &Vector, &Vector) -> Vector, state: &Vector, input: &Vector| {
+            self.step(f, t, dt, state, input)
+        };
+        scalar_step(step, dynamics, state, input)
+    }
+}
+
+impl VectorODESolver for TrapezoidalSolver {
+    fn solve_step(
+        &self,
+        dynamics: &VectorDynamics,
+        t: f64,
+        dt: f64,
+        state: &Vector,
+        input: &Vector,
+    ) -> Vector {
+        self.step(dynamics, t, dt, state, input)
+    }
+}
+
+/// Two stage SDIRK of Alexander, second order, L-stable and stiffly accurate
+#[derive(Clone)]
+pub struct SDIRK2Solver {
+    delta_t: f64,
+    options: NewtonOptions,
+}
+
+impl SDIRK2Solver {
+    const GAMMA: f64 = 1.0 - std::f64::consts::FRAC_1_SQRT_2;
+
+    pub fn new(delta_t: f64) -> Self {
+        Self::with_options(delta_t, NewtonOptions::default())
+    }
+
+    pub fn with_options(delta_t: f64, options: NewtonOptions) -> Self {
+        SDIRK2Solver { delta_t, options }
+    }
+
+    pub fn delta_t(&self) -> f64 {
+        self.delta_t
+    }
+
+    fn step(
+        &self,
+        dynamics: &dyn Fn(f64, &Vector, &Vector) -> Vector,
+        t: f64,
+        dt: f64,
+        state: &Vector,
+        input: &Vector,
+    ) -> Vector {
+        let gamma = Self::GAMMA;
+        let k0 = dynamics(t, state, input);
+
+        let guess = state.clone() + k0 * (gamma * dt);
+        let z1 = solve_stage(
+            &dynamics,
+            t + gamma * dt,
+            state,
+            gamma * dt,
+            guess,
+            input,
+            &self.options,
+        );
+        // recover the stage derivative from the stage equation instead of evaluating f again
+        let k1 = (z1.clone() - state.clone()) * (1.0 / (gamma * dt));
+
+        let base = state.clone() + k1 * ((1.0 - gamma) * dt);
+        solve_stage(
+            &dynamics,
+            t + dt,
+            &base,
+            gamma * dt,
+            z1,
+            input,
+            &self.options,
+        )
+    }
+}
+
+impl Solver for SDIRK2Solver {
+    fn delta_t(&self) -> f64 {
+        self.delta_t
+    }
+}
+
+impl ODESolver for SDIRK2Solver {
+    fn solve_step(&self, dynamics: &Dynamics, t: f64, dt: f64, state: f64, input: f64) -> f64 {
+        let step = |f: &dyn Fn(f64, &Vector, &Vector) -> Vector, state: &Vector, input: &Vector| {
+            self.step(f, t, dt, state, input)
+        };
+        scalar_step(step, dynamics, state, input)
+    }
+}
+
+impl VectorODESolver for SDIRK2Solver {
+    fn solve_step(
+        &self,
+        dynamics: &VectorDynamics,
+        t: f64,
+        dt: f64,
+        state: &Vector,
+        input: &Vector,
+    ) -> Vector {
+        self.step(dynamics, t, dt, state, input)
+    }
+}
+
+#[cfg(test)]
+mod solver_tests {
+    use super::*;
+    use crate::solver::rk::RK2Solver;
+
+    #[test]
+    fn test_implicit_accuracy() {
+        // dx/dt = -x, x(0) = 1
+        let dynamics = |_t: f64, state: f64, _input: f64| -> f64 { -state };
+        let exact = (-1.0_f64).exp();
+
+        let error = |h: f64, solver: &dyn Fn(f64) -> f64| (solver(h) - exact).abs();
+        let be = |h: f64| {
+            ODESolver::integrate(&BackwardEulerSolver::new(h), &dynamics, 0.0, 1.0, 1.0, 0.0)
+        };
+        let tr = |h: f64| {
+            ODESolver::integrate(&TrapezoidalSolver::new(h), &dynamics, 0.0, 1.0, 1.0, 0.0)
+        };
+        let sd =
+            |h: f64| ODESolver::integrate(&SDIRK2Solver::new(h), &dynamics, 0.0, 1.0, 1.0, 0.0);
+
+        // halving the step halves the error of a first order method and quarters a second order one
+        let ratio = error(0.02, &be) / error(0.01, &be);
+        assert!((ratio - 2.0).abs() < 0.1, "{ratio}");
+        let ratio = error(0.02, &tr) / error(0.01, &tr);
+        assert!((ratio - 4.0).abs() < 0.1, "{ratio}");
+        let ratio = error(0.02, &sd) / error(0.01, &sd);
+        assert!((ratio - 4.0).abs() < 0.1, "{ratio}");
+    }
+
+    #[test]
+    fn test_stiff() {
+        // the elevator actuator: first order lag with a gain of 20.2 towards the command
+        let dynamics = |_t: f64, state: &Vector, input: &Vector| -> Vector {
+            Vector::from(vec![20.2 * (input[0] - state[0])])
+        };
+        let init = Vector::from(vec![0.0]);
+        let input = Vector::from(vec![-5.0]);
+
+        // a step of 0.2 s is far beyond the stability limit of explicit RK2
+        let rk2 = RK2Solver::new(0.2);
+        let x = VectorODESolver::integrate(&rk2, &dynamics, 0.0, 4.0, &init, &input);
+        assert!(x[0].abs() > 1e3);
+
+        let be = BackwardEulerSolver::new(0.2);
+        let x = VectorODESolver::integrate(&be, &dynamics, 0.0, 4.0, &init, &input);
+        assert!((x[0] + 5.0).abs() < 1e-6);
+
+        let sdirk = SDIRK2Solver::new(0.2);
+        let x = VectorODESolver::integrate(&sdirk, &dynamics, 0.0, 4.0, &init, &input);
+        assert!((x[0] + 5.0).abs() < 1e-6);
+
+        // stable but barely damped
+        let tr = TrapezoidalSolver::new(0.2);
+        let x = VectorODESolver::integrate(&tr, &dynamics, 0.0, 4.0, &init, &input);
+        assert!(x[0].abs() < 10.0);
+
+        // a coupled nonlinear system exercises the Newton iteration
+        let dynamics = |_t: f64, state: &Vector, _input: &Vector| -> Vector {
+            Vector::from(vec![-1000.0 * (state[0] - state[1].sin()), -state[1]])
+        };
+        let init = Vector::from(vec![2.0, 1.0]);
+        let x = VectorODESolver::integrate(&sdirk, &dynamics, 0.0, 1.0, &init, &Vector::zero(0));
+        assert!((x[1] - (-1.0_f64).exp()).abs() < 1e-2);
+        assert!((x[0] - x[1].sin()).abs() < 1e-2);
+    }
+}
@@ -1,4 +1,6 @@
+pub mod adaptive;
 pub(crate) mod basic;
+pub mod implicit;
 pub mod rk;
 
-pub use basic::{Dynamics, ODESolver, VectorDynamics, VectorODESolver};
+pub use basic::{substeps, Dynamics, ODESolver, Solver, VectorDynamics, VectorODESolver};
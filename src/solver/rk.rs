@@ -1,4 +1,4 @@
-use super::{Dynamics, ODESolver, VectorDynamics, VectorODESolver};
+use super::{Dynamics, ODESolver, Solver, VectorDynamics, VectorODESolver};
 use crate::utils::Vector;
 
 #[derive(Clone)]
@@ -16,10 +16,14 @@ impl RK1Solver {
     }
 }
 
-impl ODESolver for RK1Solver {
-    fn solve(&self, dynamics: &Dynamics, t: f64, state: f64, input: f64) -> f64 {
-        let dt = self.delta_t;
+impl Solver for RK1Solver {
+    fn delta_t(&self) -> f64 {
+        self.delta_t
+    }
+}
 
+impl ODESolver for RK1Solver {
+    fn solve_step(&self, dynamics: &Dynamics, t: f64, dt: f64, state: f64, input: f64) -> f64 {
         let k1 = dynamics(t, state, input);
         let state_next = state + k1 * dt;
 
@@ -28,9 +32,14 @@ impl ODESolver for RK1Solver {
 }
 
 impl VectorODESolver for RK1Solver {
-    fn solve(&self, dynamics: &VectorDynamics, t: f64, state: &Vector, input: &Vector) -> Vector {
-        let dt = self.delta_t;
-
+    fn solve_step(
+        &self,
+        dynamics: &VectorDynamics,
+        t: f64,
+        dt: f64,
+        state: &Vector,
+        input: &Vector,
+    ) -> Vector {
         let k1 = dynamics(t, state, input);
         let state_next = state.clone() + k1 * dt;
 
@@ -53,10 +62,14 @@ impl RK2Solver {
     }
 }
 
-impl ODESolver for RK2Solver {
-    fn solve(&self, dynamics: &Dynamics, t: f64, state: f64, input: f64) -> f64 {
-        let dt = self.delta_t;
+impl Solver for RK2Solver {
+    fn delta_t(&self) -> f64 {
+        self.delta_t
+    }
+}
 
+impl ODESolver for RK2Solver {
+    fn solve_step(&self, dynamics: &Dynamics, t: f64, dt: f64, state: f64, input: f64) -> f64 {
         let k1 = dynamics(t, state, input);
 
         let state_k2 = state + k1 * dt;
@@ -68,9 +81,14 @@ impl ODESolver for RK2Solver {
 }
 
 impl VectorODESolver for RK2Solver {
-    fn solve(&self, dynamics: &VectorDynamics, t: f64, state: &Vector, input: &Vector) -> Vector {
-        let dt = self.delta_t;
-
+    fn solve_step(
+        &self,
+        dynamics: &VectorDynamics,
+        t: f64,
+        dt: f64,
+        state: &Vector,
+        input: &Vector,
+    ) -> Vector {
         let k1 = dynamics(t, state, input);
 
         let state_k2 = state.clone() + k1.clone() * dt;
@@ -96,10 +114,14 @@ impl RK3Solver {
     }
 }
 
-impl ODESolver for RK3Solver {
-    fn solve(&self, dynamics: &Dynamics, t: f64, state: f64, input: f64) -> f64 {
-        let dt = self.delta_t;
+impl Solver for RK3Solver {
+    fn delta_t(&self) -> f64 {
+        self.delta_t
+    }
+}
 
+impl ODESolver for RK3Solver {
+    fn solve_step(&self, dynamics: &Dynamics, t: f64, dt: f64, state: f64, input: f64) -> f64 {
         let k1 = dynamics(t, state, input);
         let state_k2 = state + k1 * dt / 2.0;
         let k2 = dynamics(t + dt / 2.0, state_k2, input);
@@ -113,9 +135,14 @@ impl ODESolver for RK3Solver {
 }
 
 impl VectorODESolver for RK3Solver {
-    fn solve(&self, dynamics: &VectorDynamics, t: f64, state: &Vector, input: &Vector) -> Vector {
-        let dt = self.delta_t;
-
+    fn solve_step(
+        &self,
+        dynamics: &VectorDynamics,
+        t: f64,
+        dt: f64,
+        state: &Vector,
+        input: &Vector,
+    ) -> Vector {
         let k1 = dynamics(t, state, input);
         let state_k2 = state.clone() + k1.clone() * dt / 2.0;
         let k2 = dynamics(t + dt / 2.0, &state_k2, input);
@@ -143,10 +170,14 @@ impl RK4Solver {
     }
 }
 
-impl ODESolver for RK4Solver {
-    fn solve(&self, dynamics: &Dynamics, t: f64, state: f64, input: f64) -> f64 {
-        let dt = self.delta_t;
+impl Solver for RK4Solver {
+    fn delta_t(&self) -> f64 {
+        self.delta_t
+    }
+}
 
+impl ODESolver for RK4Solver {
+    fn solve_step(&self, dynamics: &Dynamics, t: f64, dt: f64, state: f64, input: f64) -> f64 {
         let k1 = dynamics(t, state, input);
         let state_k2 = state + k1 * dt / 2.0;
         let k2 = dynamics(t + dt / 2.0, state_k2, input);
@@ -162,9 +193,14 @@ impl ODESolver for RK4Solver {
 }
 
 impl VectorODESolver for RK4Solver {
-    fn solve(&self, dynamics: &VectorDynamics, t: f64, state: &Vector, input: &Vector) -> Vector {
-        let dt = self.delta_t;
-
+    fn solve_step(
+        &self,
+        dynamics: &VectorDynamics,
+        t: f64,
+        dt: f64,
+        state: &Vector,
+        input: &Vector,
+    ) -> Vector {
         let k1 = dynamics(t, state, input);
         let state_k2 = state.clone() + k1.clone() * dt / 2.0;
         let k2 = dynamics(t + dt / 2.0, &state_k2, input);
@@ -178,3 +214,47 @@ impl VectorODESolver for RK4Solver {
         state_next
     }
 }
+
+#[cfg(test)]
+mod solver_tests {
+    use super::*;
+    use crate::solver::substeps;
+
+    #[test]
+    fn test_substeps() {
+        assert_eq!(substeps(0.0, 0.01), (0, 0.0));
+        assert_eq!(substeps(-1.0, 0.01).0, 0);
+        assert_eq!(substeps(0.03, 0.01).0, 3);
+        assert_eq!(substeps(0.005, 0.01).0, 1);
+        let (n, h) = substeps(0.035, 0.01);
+        assert_eq!(n, 4);
+        assert!((h - 0.00875).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_integrate() {
+        // dx/dt = -x, x(0) = 1
+        let dynamics = |_t: f64, state: f64, _input: f64| -> f64 { -state };
+        let solver = RK4Solver::new(0.01);
+
+        let x = ODESolver::integrate(&solver, &dynamics, 0.0, 1.0, 1.0, 0.0);
+        assert!((x - (-1.0_f64).exp()).abs() < 1e-9);
+
+        // a single step of an arbitrary length
+        let x = ODESolver::solve_step(&solver, &dynamics, 0.0, 0.001, 1.0, 0.0);
+        assert!((x - (-0.001_f64).exp()).abs() < 1e-12);
+
+        let dynamics =
+            |_t: f64, state: &Vector, _input: &Vector| -> Vector { state.clone() * -1.0 };
+        let x = VectorODESolver::integrate(
+            &solver,
+            &dynamics,
+            0.0,
+            0.5,
+            &Vector::from(vec![1.0, 2.0]),
+            &Vector::zero(0),
+        );
+        assert!((x[0] - (-0.5_f64).exp()).abs() < 1e-9);
+        assert!((x[1] - 2.0 * (-0.5_f64).exp()).abs() < 1e-9);
+    }
+}
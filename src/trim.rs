@@ -248,7 +248,7 @@ fn trim_func(
 
     let control = [thrust, elevator, alileron, rudder];
 
-    let d_lef = get_lef(altitude, velocity, alpha);
+    let d_lef = get_lef(&*plane.borrow().atmosphere(), altitude, velocity, alpha);
 
     // Create weight function
     // npos_dot epos_dot alt_dot phi_dot theta_dot psi_dot V_dot alpha_dpt beta_dot P_dot Q_dot R_dot
@@ -1,4 +1,59 @@
+#[cfg(test)]
+use crate::{
+    components::flight::MechanicalModel,
+    model::ControlLimit,
+    plugin::{AerodynamicModel, AsPlugin},
+    trim::{trim, TrimOutput, TrimTarget},
+};
+#[cfg(test)]
+use std::{cell::RefCell, rc::Rc};
+
 #[cfg(test)]
 pub fn test_logger_init() {
     let _ = env_logger::builder().is_test(true).try_init();
 }
+
+/// The control limits the F-16 model is trimmed and flown with in tests
+#[cfg(test)]
+pub const TEST_CONTROL_LIMIT: ControlLimit = ControlLimit {
+    thrust_cmd_limit_top: 19000.0,
+    thrust_cmd_limit_bottom: 1000.0,
+    thrust_rate_limit: 10000.0,
+    ele_cmd_limit_top: 25.0,
+    ele_cmd_limit_bottom: -25.0,
+    ele_rate_limit: 60.0,
+    ail_cmd_limit_top: 21.5,
+    ail_cmd_limit_bottom: -21.5,
+    ail_rate_limit: 80.0,
+    rud_cmd_limit_top: 30.0,
+    rud_cmd_limit_bottom: -30.0,
+    rud_rate_limit: 120.0,
+    alpha_limit_top: 45.0,
+    alpha_limit_bottom: -20.0,
+    beta_limit_top: 30.0,
+    beta_limit_bottom: -30.0,
+};
+
+/// Load and install the F-16 model and trim it at 15000 ft and 500 ft/s,
+/// hand the model back to `test_model_fin` when done
+#[cfg(test)]
+pub fn test_model_init() -> (AerodynamicModel, TrimOutput) {
+    test_logger_init();
+    let model = AerodynamicModel::new("./models/f16_model");
+    assert!(model.is_ok());
+
+    let model = model.unwrap();
+    let res = model.plugin().install(&["./models/f16_model/data"]);
+    assert!(matches!(res, Ok(Ok(_))));
+
+    let plane = Rc::new(RefCell::new(MechanicalModel::new(&model).unwrap()));
+    let target = TrimTarget::new(15000.0, 500.0, None, None);
+    let trimmed = trim(plane, target, None, TEST_CONTROL_LIMIT, None, None).unwrap();
+    (model, trimmed)
+}
+
+#[cfg(test)]
+pub fn test_model_fin(model: AerodynamicModel) {
+    let res = model.plugin().uninstall();
+    assert!(matches!(res, Ok(Ok(_))));
+}
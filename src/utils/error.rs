@@ -38,6 +38,11 @@ pub enum FatalCoreError {
     Controller(String),
     Plugin(FatalPluginError),
     Nan,
+    InvalidInput(String),
+    /// (last update time, requested time)
+    NonMonotonicTime(f64, f64),
+    /// error raised by a user supplied function, e.g. a python controller
+    Callback(String),
 }
 
 impl FatalCoreError {}
@@ -49,6 +54,9 @@ impl std::error::Error for FatalCoreError {
             Self::Controller(_) => None,
             Self::Plugin(e) => Some(e),
             Self::Nan => None,
+            Self::InvalidInput(_) => None,
+            Self::NonMonotonicTime(..) => None,
+            Self::Callback(_) => None,
         }
     }
 }
@@ -60,6 +68,12 @@ impl std::fmt::Display for FatalCoreError {
             Self::Controller(e) => write!(f, "controller for plane {} not found", e),
             Self::Plugin(_) => write!(f, "{}", self.source().unwrap()),
             Self::Nan => write!(f, "NaN value"),
+            Self::InvalidInput(s) => write!(f, "invalid input: {s}"),
+            Self::NonMonotonicTime(last, t) => write!(
+                f,
+                "non-monotonic time: {t} is earlier than the last update time {last}"
+            ),
+            Self::Callback(s) => write!(f, "callback failed: {s}"),
         }
     }
 }
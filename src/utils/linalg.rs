@@ -0,0 +1,991 @@
+use super::{complex::Complex, error::FatalCoreError, matrix::Matrix, vector::Vector};
+
+/// Dense row major storage the decompositions work on
+type Rows = Vec<Vec<f64>>;
+
+fn to_rows(m: &Matrix) -> Rows {
+    m.data.iter().map(|r| r.data.clone()).collect()
+}
+
+fn cols(m: &Matrix) -> usize {
+    m.data.first().map_or(0, |r| r.dim())
+}
+
+fn max_abs(a: &Rows) -> f64 {
+    a.iter().flatten().fold(0.0, |m: f64, x| m.max(x.abs()))
+}
+
+fn check_finite(m: &Matrix) -> Result<(), FatalCoreError> {
+    if m.data
+        .iter()
+        .flat_map(|r| r.data.iter())
+        .all(|x| x.is_finite())
+    {
+        Ok(())
+    } else {
+        Err(FatalCoreError::Nan)
+    }
+}
+
+impl Matrix {
+    pub fn identity(n: usize) -> Self {
+        Self::from_diagonal(&vec![1.0; n])
+    }
+
+    pub fn from_diagonal(diagonal: &[f64]) -> Self {
+        let mut m = Self::new((diagonal.len(), diagonal.len()));
+        for (i, d) in diagonal.iter().enumerate() {
+            m[(i, i)] = *d;
+        }
+        m
+    }
+
+    pub fn diagonal(&self) -> Vector {
+        let n = self.dim().min(cols(self));
+        Vector::from((0..n).map(|i| self[(i, i)]).collect::<Vec<_>>())
+    }
+
+    pub fn is_square(&self) -> bool {
+        self.dim() > 0 && self.dim() == cols(self)
+    }
+
+    fn square(&self, what: &str) -> Result<usize, FatalCoreError> {
+        if self.is_square() {
+            Ok(self.dim())
+        } else {
+            Err(FatalCoreError::InvalidInput(format!(
+                "{what} needs a non-empty square matrix, got shape {:?}",
+                (self.dim(), cols(self))
+            )))
+        }
+    }
+
+    pub fn transpose(&self) -> Self {
+        let (r, c) = (self.dim(), cols(self));
+        Self::from(
+            (0..c)
+                .map(|j| (0..r).map(|i| self[(i, j)]).collect::<Vec<_>>())
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    /// matrix product `self * other`
+    pub fn dot(&self, other: &Self) -> Self {
+        assert!(cols(self) == other.dim());
+        let c = cols(other);
+        Self::from(
+            self.data
+                .iter()
+                .map(|row| {
+                    let mut out = vec![0.0; c];
+                    for (a, b) in row.data.iter().zip(other.data.iter()) {
+                        if *a != 0.0 {
+                            out.iter_mut()
+                                .zip(b.data.iter())
+                                .for_each(|(o, b)| *o += a * b);
+                        }
+                    }
+                    out
+                })
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    /// matrix vector product `self * x`
+    pub fn dot_vector(&self, x: &Vector) -> Vector {
+        assert!(cols(self) == x.dim());
+        Vector::from(
+            self.data
+                .iter()
+                .map(|row| row.data.iter().zip(x.data.iter()).map(|(a, b)| a * b).sum())
+                .collect::<Vec<f64>>(),
+        )
+    }
+
+    pub fn trace(&self) -> f64 {
+        self.diagonal().data.iter().sum()
+    }
+
+    /// Frobenius norm
+    pub fn norm(&self) -> f64 {
+        self.data.iter().map(|r| r.norm_sq()).sum::<f64>().sqrt()
+    }
+
+    /// maximum absolute row sum
+    pub fn norm_inf(&self) -> f64 {
+        self.data
+            .iter()
+            .map(|r| r.data.iter().map(|x| x.abs()).sum::<f64>())
+            .fold(0.0, f64::max)
+    }
+
+    /// LU decomposition with partial pivoting
+    pub fn lu(&self) -> Result<Lu, FatalCoreError> {
+        self.square("LU decomposition")?;
+        check_finite(self)?;
+        Ok(Lu::new(to_rows(self)))
+    }
+
+    /// Solve `self * x = b`
+    pub fn solve(&self, b: &Vector) -> Result<Vector, FatalCoreError> {
+        self.lu()?.solve(b)
+    }
+
+    /// Solve `self * X = b` column by column
+    pub fn solve_matrix(&self, b: &Matrix) -> Result<Matrix, FatalCoreError> {
+        self.lu()?.solve_matrix(b)
+    }
+
+    pub fn det(&self) -> Result<f64, FatalCoreError> {
+        Ok(self.lu()?.det())
+    }
+
+    pub fn inverse(&self) -> Result<Matrix, FatalCoreError> {
+        self.lu()?.inverse()
+    }
+
+    /// QR decomposition by Householder reflections, Q is square and R has the shape of self
+    pub fn qr(&self) -> Result<Qr, FatalCoreError> {
+        if self.dim() == 0 || cols(self) == 0 {
+            return Err(FatalCoreError::InvalidInput(
+                "QR decomposition needs a non-empty matrix".to_string(),
+            ));
+        }
+        check_finite(self)?;
+        Ok(Qr::new(to_rows(self)))
+    }
+
+    /// Eigenvalues by balancing, Hessenberg reduction and the shifted QR algorithm
+    pub fn eigenvalues(&self) -> Result<Vec<Complex>, FatalCoreError> {
+        self.square("eigen decomposition")?;
+        check_finite(self)?;
+        let mut a = to_rows(self);
+        balance(&mut a);
+        hessenberg(&mut a);
+        let mut values = hqr(a)?;
+        // descending real part, the positive imaginary part of a pair first
+        values.sort_by(|a, b| b.re.total_cmp(&a.re).then(b.im.total_cmp(&a.im)));
+        Ok(values)
+    }
+
+    /// Eigenvalues and right eigenvectors, the eigenvectors come from inverse iteration
+    /// and have unit norm with their largest component real and positive
+    pub fn eigen(&self) -> Result<Eigen, FatalCoreError> {
+        let values = self.eigenvalues()?;
+        let a = to_rows(self);
+        let scale = max_abs(&a).max(1.0);
+        let mut vectors: Vec<Vec<Complex>> = Vec::with_capacity(values.len());
+        for (k, lambda) in values.iter().enumerate() {
+            // keep the vectors of a repeated eigenvalue independent
+            let repeated: Vec<&Vec<Complex>> = (0..k)
+                .filter(|&j| (values[j] - *lambda).norm() <= 1e-8 * scale)
+                .map(|j| &vectors[j])
+                .collect();
+            let vector = inverse_iteration(&a, *lambda, &repeated, scale);
+            vectors.push(vector);
+        }
+        Ok(Eigen { values, vectors })
+    }
+
+    /// Thin singular value decomposition by one-sided Jacobi rotations
+    pub fn svd(&self) -> Result<Svd, FatalCoreError> {
+        if self.dim() == 0 || cols(self) == 0 {
+            return Err(FatalCoreError::InvalidInput(
+                "SVD needs a non-empty matrix".to_string(),
+            ));
+        }
+        check_finite(self)?;
+        if self.dim() < cols(self) {
+            let svd = jacobi_svd(to_rows(&self.transpose()))?;
+            Ok(Svd {
+                u: svd.v,
+                s: svd.s,
+                v: svd.u,
+            })
+        } else {
+            jacobi_svd(to_rows(self))
+        }
+    }
+
+    /// Matrix exponential by scaling and squaring with a (6, 6) Pade approximant
+    pub fn expm(&self) -> Result<Matrix, FatalCoreError> {
+        let n = self.square("matrix exponential")?;
+        check_finite(self)?;
+        let norm = self.norm_inf();
+        let s = if norm > 0.5 {
+            (norm.log2().floor() as i32 + 2).max(0)
+        } else {
+            0
+        };
+        let a = self.clone() / 2f64.powi(s);
+
+        const Q: usize = 6;
+        let mut x = a.clone();
+        let mut c = 0.5;
+        let mut e = Self::identity(n) + a.clone() * c;
+        let mut d = Self::identity(n) - a.clone() * c;
+        for k in 2..=Q {
+            c *= (Q - k + 1) as f64 / (k * (2 * Q - k + 1)) as f64;
+            x = a.dot(&x);
+            if k % 2 == 0 {
+                d += x.clone() * c;
+            } else {
+                d -= x.clone() * c;
+            }
+            e += x.clone() * c;
+        }
+        let mut e = d.solve_matrix(&e)?;
+        for _ in 0..s {
+            e = e.dot(&e);
+        }
+        Ok(e)
+    }
+}
+
+/// `P * A = L * U` with unit lower triangular L
+#[derive(Debug, Clone)]
+pub struct Lu {
+    lu: Rows,
+    perm: Vec<usize>,
+    sign: f64,
+    singular: bool,
+}
+
+impl Lu {
+    fn new(mut a: Rows) -> Self {
+        let n = a.len();
+        let tol = n as f64 * f64::EPSILON * max_abs(&a);
+        let mut perm: Vec<usize> = (0..n).collect();
+        let mut sign = 1.0;
+        let mut singular = false;
+        for k in 0..n {
+            let pivot = (k..n)
+                .max_by(|&i, &j| a[i][k].abs().total_cmp(&a[j][k].abs()))
+                .unwrap();
+            if pivot != k {
+                a.swap(k, pivot);
+                perm.swap(k, pivot);
+                sign = -sign;
+            }
+            if a[k][k].abs() <= tol {
+                singular = true;
+                continue;
+            }
+            let (upper, lower) = a.split_at_mut(k + 1);
+            let pivot_row = &upper[k];
+            for row in lower.iter_mut() {
+                let factor = row[k] / pivot_row[k];
+                row[k] = factor;
+                if factor == 0.0 {
+                    continue;
+                }
+                for (x, p) in row[k + 1..].iter_mut().zip(pivot_row[k + 1..].iter()) {
+                    *x -= factor * p;
+                }
+            }
+        }
+        Self {
+            lu: a,
+            perm,
+            sign,
+            singular,
+        }
+    }
+
+    /// a pivot is below `n * eps * max|a|`
+    pub fn is_singular(&self) -> bool {
+        self.singular
+    }
+
+    pub fn l(&self) -> Matrix {
+        let n = self.lu.len();
+        let mut l = Matrix::identity(n);
+        for i in 0..n {
+            for j in 0..i {
+                l[(i, j)] = self.lu[i][j];
+            }
+        }
+        l
+    }
+
+    pub fn u(&self) -> Matrix {
+        let n = self.lu.len();
+        let mut u = Matrix::new((n, n));
+        for i in 0..n {
+            for j in i..n {
+                u[(i, j)] = self.lu[i][j];
+            }
+        }
+        u
+    }
+
+    pub fn p(&self) -> Matrix {
+        let n = self.lu.len();
+        let mut p = Matrix::new((n, n));
+        for (i, j) in self.perm.iter().enumerate() {
+            p[(i, *j)] = 1.0;
+        }
+        p
+    }
+
+    pub fn det(&self) -> f64 {
+        self.sign * (0..self.lu.len()).map(|i| self.lu[i][i]).product::<f64>()
+    }
+
+    pub fn solve(&self, b: &Vector) -> Result<Vector, FatalCoreError> {
+        let n = self.lu.len();
+        if b.dim() != n {
+            return Err(FatalCoreError::InvalidInput(format!(
+                "right hand side has {} rows, expected {}",
+                b.dim(),
+                n
+            )));
+        }
+        if self.singular {
+            return Err(FatalCoreError::InvalidInput("singular matrix".to_string()));
+        }
+        let mut x: Vec<f64> = self.perm.iter().map(|&i| b[i]).collect();
+        for i in 0..n {
+            let sum: f64 = (0..i).map(|j| self.lu[i][j] * x[j]).sum();
+            x[i] -= sum;
+        }
+        for i in (0..n).rev() {
+            let sum: f64 = (i + 1..n).map(|j| self.lu[i][j] * x[j]).sum();
+            x[i] = (x[i] - sum) / self.lu[i][i];
+        }
+        Ok(Vector::from(x))
+    }
+
+    pub fn solve_matrix(&self, b: &Matrix) -> Result<Matrix, FatalCoreError> {
+        let columns = b.transpose();
+        let mut x = Vec::with_capacity(columns.dim());
+        for column in columns.data.iter() {
+            x.push(self.solve(column)?);
+        }
+        Ok(Matrix::from(x).transpose())
+    }
+
+    pub fn inverse(&self) -> Result<Matrix, FatalCoreError> {
+        self.solve_matrix(&Matrix::identity(self.lu.len()))
+    }
+}
+
+/// `A = Q * R` with orthogonal Q and upper triangular R
+#[derive(Debug, Clone)]
+pub struct Qr {
+    pub q: Matrix,
+    pub r: Matrix,
+}
+
+impl Qr {
+    #[allow(clippy::needless_range_loop)]
+    fn new(mut r: Rows) -> Self {
+        let (m, n) = (r.len(), r[0].len());
+        let mut q = to_rows(&Matrix::identity(m));
+        for k in 0..n.min(m - 1) {
+            let norm = (k..m).map(|i| r[i][k].powi(2)).sum::<f64>().sqrt();
+            if norm == 0.0 {
+                continue;
+            }
+            let alpha = if r[k][k] > 0.0 { -norm } else { norm };
+            let mut v: Vec<f64> = (k..m).map(|i| r[i][k]).collect();
+            v[0] -= alpha;
+            let v_norm = v.iter().map(|x| x * x).sum::<f64>().sqrt();
+            v.iter_mut().for_each(|x| *x /= v_norm);
+
+            // R = H R, Q = Q H with H = I - 2 v v'
+            for j in k..n {
+                let dot: f64 = v.iter().enumerate().map(|(i, v)| v * r[k + i][j]).sum();
+                v.iter()
+                    .enumerate()
+                    .for_each(|(i, v)| r[k + i][j] -= 2.0 * dot * v);
+            }
+            for row in q.iter_mut() {
+                let dot: f64 = v.iter().enumerate().map(|(i, v)| v * row[k + i]).sum();
+                v.iter()
+                    .enumerate()
+                    .for_each(|(i, v)| row[k + i] -= 2.0 * dot * v);
+            }
+            r[k][k] = alpha;
+            (k + 1..m).for_each(|i| r[i][k] = 0.0);
+        }
+        Self {
+            q: Matrix::from(q),
+            r: Matrix::from(r),
+        }
+    }
+}
+
+/// values: eigenvalues sorted by descending real part, a complex pair is adjacent
+/// vectors: the right eigenvector of every eigenvalue, a repeated eigenvalue of a
+/// defective matrix does not have a full set
+#[derive(Debug, Clone)]
+pub struct Eigen {
+    pub values: Vec<Complex>,
+    pub vectors: Vec<Vec<Complex>>,
+}
+
+/// `A = U * diag(s) * V'` with singular values in descending order
+#[derive(Debug, Clone)]
+pub struct Svd {
+    pub u: Matrix,
+    pub s: Vec<f64>,
+    pub v: Matrix,
+}
+
+impl Svd {
+    /// number of singular values above `tol`
+    pub fn rank(&self, tol: f64) -> usize {
+        self.s.iter().filter(|s| **s > tol).count()
+    }
+
+    /// ratio of the largest to the smallest singular value
+    pub fn cond(&self) -> f64 {
+        self.s[0] / self.s[self.s.len() - 1]
+    }
+}
+
+/// Scale rows and columns by powers of 2 so that their norms are close,
+/// this is a similarity transform and keeps the eigenvalues exact
+fn balance(a: &mut Rows) {
+    const RADIX: f64 = 2.0;
+    let n = a.len();
+    let mut done = false;
+    while !done {
+        done = true;
+        for i in 0..n {
+            let mut c = 0.0;
+            let mut r = 0.0;
+            for j in (0..n).filter(|&j| j != i) {
+                c += a[j][i].abs();
+                r += a[i][j].abs();
+            }
+            if c == 0.0 || r == 0.0 {
+                continue;
+            }
+            let s = c + r;
+            let mut f = 1.0;
+            let mut g = r / RADIX;
+            while c < g {
+                f *= RADIX;
+                c *= RADIX * RADIX;
+            }
+            g = r * RADIX;
+            while c > g {
+                f /= RADIX;
+                c /= RADIX * RADIX;
+            }
+            if (c + r) / f < 0.95 * s {
+                done = false;
+                a[i].iter_mut().for_each(|x| *x /= f);
+                a.iter_mut().for_each(|row| row[i] *= f);
+            }
+        }
+    }
+}
+
+/// Reduce to upper Hessenberg form by stabilized elementary similarity transforms
+#[allow(clippy::needless_range_loop)]
+fn hessenberg(a: &mut Rows) {
+    let n = a.len();
+    for m in 1..n.saturating_sub(1) {
+        let mut x: f64 = 0.0;
+        let mut i = m;
+        for j in m..n {
+            if a[j][m - 1].abs() > x.abs() {
+                x = a[j][m - 1];
+                i = j;
+            }
+        }
+        if i != m {
+            a.swap(i, m);
+            a.iter_mut().for_each(|row| row.swap(i, m));
+        }
+        if x != 0.0 {
+            for i in m + 1..n {
+                let y = a[i][m - 1] / x;
+                if y == 0.0 {
+                    continue;
+                }
+                for j in m..n {
+                    a[i][j] -= y * a[m][j];
+                }
+                for row in a.iter_mut() {
+                    row[m] += y * row[i];
+                }
+            }
+        }
+    }
+    for (i, row) in a.iter_mut().enumerate() {
+        row.iter_mut()
+            .take(i.saturating_sub(1))
+            .for_each(|x| *x = 0.0);
+    }
+}
+
+/// Eigenvalues of an upper Hessenberg matrix by the Francis double shift QR algorithm
+#[allow(clippy::needless_range_loop)]
+fn hqr(h: Rows) -> Result<Vec<Complex>, FatalCoreError> {
+    let n = h.len();
+    // 1-based copy keeps the indices of the classic formulation
+    let mut a = vec![vec![0.0; n + 1]; n + 1];
+    for i in 0..n {
+        a[i + 1][1..].copy_from_slice(&h[i]);
+    }
+    let mut values = vec![Complex::zero(); n + 1];
+
+    let mut anorm = 0.0;
+    for i in 1..=n {
+        for j in (i - 1).max(1)..=n {
+            anorm += a[i][j].abs();
+        }
+    }
+
+    let mut nn = n;
+    let mut t = 0.0;
+    while nn >= 1 {
+        let mut its = 0;
+        loop {
+            // look for a single small subdiagonal element
+            let mut l = nn;
+            while l >= 2 {
+                let mut s = a[l - 1][l - 1].abs() + a[l][l].abs();
+                if s == 0.0 {
+                    s = anorm;
+                }
+                if a[l][l - 1].abs() + s == s {
+                    a[l][l - 1] = 0.0;
+                    break;
+                }
+                l -= 1;
+            }
+            let mut x = a[nn][nn];
+            if l == nn {
+                // one root found
+                values[nn] = Complex::new(x + t, 0.0);
+                nn -= 1;
+                break;
+            }
+            let mut y = a[nn - 1][nn - 1];
+            let mut w = a[nn][nn - 1] * a[nn - 1][nn];
+            if l == nn - 1 {
+                // two roots found
+                let p = 0.5 * (y - x);
+                let q = p * p + w;
+                let z = q.abs().sqrt();
+                x += t;
+                if q >= 0.0 {
+                    let z = p + z.copysign(p);
+                    values[nn - 1] = Complex::new(x + z, 0.0);
+                    values[nn] = Complex::new(if z != 0.0 { x - w / z } else { x + z }, 0.0);
+                } else {
+                    values[nn - 1] = Complex::new(x + p, z);
+                    values[nn] = Complex::new(x + p, -z);
+                }
+                nn -= 2;
+                break;
+            }
+
+            if its == 30 {
+                return Err(FatalCoreError::InvalidInput(
+                    "eigenvalue iteration did not converge".to_string(),
+                ));
+            }
+            if its == 10 || its == 20 {
+                // exceptional shift
+                t += x;
+                for i in 1..=nn {
+                    a[i][i] -= x;
+                }
+                let s = a[nn][nn - 1].abs() + a[nn - 1][nn - 2].abs();
+                x = 0.75 * s;
+                y = x;
+                w = -0.4375 * s * s;
+            }
+            its += 1;
+
+            // look for two consecutive small subdiagonal elements
+            let mut m = nn - 2;
+            let (mut p, mut q, mut r);
+            loop {
+                let z = a[m][m];
+                r = x - z;
+                let s = y - z;
+                p = (r * s - w) / a[m + 1][m] + a[m][m + 1];
+                q = a[m + 1][m + 1] - z - r - s;
+                r = a[m + 2][m + 1];
+                let s = p.abs() + q.abs() + r.abs();
+                p /= s;
+                q /= s;
+                r /= s;
+                if m == l {
+                    break;
+                }
+                let u = a[m][m - 1].abs() * (q.abs() + r.abs());
+                let v = p.abs() * (a[m - 1][m - 1].abs() + z.abs() + a[m + 1][m + 1].abs());
+                if u + v == v {
+                    break;
+                }
+                m -= 1;
+            }
+            for i in m + 2..=nn {
+                a[i][i - 2] = 0.0;
+                if i != m + 2 {
+                    a[i][i - 3] = 0.0;
+                }
+            }
+
+            // double QR step on rows l..nn and columns m..nn
+            for k in m..nn {
+                if k != m {
+                    p = a[k][k - 1];
+                    q = a[k + 1][k - 1];
+                    r = if k != nn - 1 { a[k + 2][k - 1] } else { 0.0 };
+                    x = p.abs() + q.abs() + r.abs();
+                    if x != 0.0 {
+                        p /= x;
+                        q /= x;
+                        r /= x;
+                    }
+                }
+                let s = (p * p + q * q + r * r).sqrt().copysign(p);
+                if s == 0.0 {
+                    continue;
+                }
+                if k == m {
+                    if l != m {
+                        a[k][k - 1] = -a[k][k - 1];
+                    }
+                } else {
+                    a[k][k - 1] = -s * x;
+                }
+                p += s;
+                x = p / s;
+                y = q / s;
+                let z = r / s;
+                q /= p;
+                r /= p;
+                for j in k..=nn {
+                    let mut p = a[k][j] + q * a[k + 1][j];
+                    if k != nn - 1 {
+                        p += r * a[k + 2][j];
+                        a[k + 2][j] -= p * z;
+                    }
+                    a[k + 1][j] -= p * y;
+                    a[k][j] -= p * x;
+                }
+                for i in l..=nn.min(k + 3) {
+                    let mut p = x * a[i][k] + y * a[i][k + 1];
+                    if k != nn - 1 {
+                        p += z * a[i][k + 2];
+                        a[i][k + 2] -= p * r;
+                    }
+                    a[i][k + 1] -= p * q;
+                    a[i][k] -= p;
+                }
+            }
+        }
+    }
+    Ok(values.split_off(1))
+}
+
+/// Null vector of `A - lambda * I` by inverse iteration in complex arithmetic,
+/// orthogonal to `previous`, tiny pivots are replaced so the solve stays finite
+fn inverse_iteration(
+    a: &Rows,
+    lambda: Complex,
+    previous: &[&Vec<Complex>],
+    scale: f64,
+) -> Vec<Complex> {
+    let n = a.len();
+    let mut m: Vec<Vec<Complex>> = a
+        .iter()
+        .map(|row| row.iter().map(|x| Complex::from(*x)).collect())
+        .collect();
+    for (i, row) in m.iter_mut().enumerate() {
+        row[i] -= lambda;
+    }
+
+    // LU with partial pivoting
+    let tiny = f64::EPSILON * scale;
+    let mut perm: Vec<usize> = (0..n).collect();
+    for k in 0..n {
+        let pivot = (k..n)
+            .max_by(|&i, &j| m[i][k].norm().total_cmp(&m[j][k].norm()))
+            .unwrap();
+        m.swap(k, pivot);
+        perm.swap(k, pivot);
+        if m[k][k].norm() < tiny {
+            m[k][k] = Complex::from(tiny);
+        }
+        let (upper, lower) = m.split_at_mut(k + 1);
+        let pivot_row = &upper[k];
+        for row in lower.iter_mut() {
+            let factor = row[k] / pivot_row[k];
+            row[k] = factor;
+            for (x, p) in row[k + 1..].iter_mut().zip(pivot_row[k + 1..].iter()) {
+                *x -= factor * *p;
+            }
+        }
+    }
+
+    let orthogonalize = |x: &mut Vec<Complex>| {
+        for v in previous {
+            let dot = v
+                .iter()
+                .zip(x.iter())
+                .fold(Complex::zero(), |s, (v, x)| s + v.conj() * *x);
+            x.iter_mut().zip(v.iter()).for_each(|(x, v)| *x -= dot * *v);
+        }
+    };
+    let normalize = |x: &mut Vec<Complex>| {
+        let norm = x.iter().map(|x| x.norm_sq()).sum::<f64>().sqrt();
+        if norm > 0.0 {
+            x.iter_mut().for_each(|x| *x = *x / norm);
+        }
+    };
+
+    let mut x: Vec<Complex> = (0..n)
+        .map(|i| Complex::from(((i + 1) as f64).sqrt()))
+        .collect();
+    orthogonalize(&mut x);
+    normalize(&mut x);
+    for _ in 0..3 {
+        let mut y: Vec<Complex> = perm.iter().map(|&i| x[i]).collect();
+        for i in 0..n {
+            for j in 0..i {
+                let l = m[i][j] * y[j];
+                y[i] -= l;
+            }
+        }
+        for i in (0..n).rev() {
+            for j in i + 1..n {
+                let u = m[i][j] * y[j];
+                y[i] -= u;
+            }
+            y[i] = y[i] / m[i][i];
+        }
+        x = y;
+        orthogonalize(&mut x);
+        normalize(&mut x);
+    }
+
+    // fix the phase so that the largest component is real and positive
+    let k = (0..n)
+        .max_by(|&i, &j| x[i].norm().total_cmp(&x[j].norm()))
+        .unwrap();
+    let phase = x[k].conj() / x[k].norm();
+    if phase.norm().is_finite() {
+        x.iter_mut().for_each(|x| *x *= phase);
+        x[k].im = 0.0;
+    }
+    x
+}
+
+fn jacobi_svd(mut u: Rows) -> Result<Svd, FatalCoreError> {
+    let (m, n) = (u.len(), u[0].len());
+    let mut v = to_rows(&Matrix::identity(n));
+    let mut converged = false;
+    for _ in 0..60 {
+        let mut rotated = false;
+        for p in 0..n - 1 {
+            for q in p + 1..n {
+                let (mut alpha, mut beta, mut gamma) = (0.0, 0.0, 0.0);
+                for row in u.iter() {
+                    alpha += row[p] * row[p];
+                    beta += row[q] * row[q];
+                    gamma += row[p] * row[q];
+                }
+                if gamma.abs() <= f64::EPSILON * (alpha * beta).sqrt() {
+                    continue;
+                }
+                rotated = true;
+                let zeta = (beta - alpha) / (2.0 * gamma);
+                let t = 1.0_f64.copysign(zeta) / (zeta.abs() + (1.0 + zeta * zeta).sqrt());
+                let c = 1.0 / (1.0 + t * t).sqrt();
+                let s = c * t;
+                for row in u.iter_mut().chain(v.iter_mut()) {
+                    let (x, y) = (row[p], row[q]);
+                    row[p] = c * x - s * y;
+                    row[q] = s * x + c * y;
+                }
+            }
+        }
+        if !rotated {
+            converged = true;
+            break;
+        }
+    }
+    if !converged {
+        return Err(FatalCoreError::InvalidInput(
+            "SVD iteration did not converge".to_string(),
+        ));
+    }
+
+    let s: Vec<f64> = (0..n)
+        .map(|j| u.iter().map(|row| row[j] * row[j]).sum::<f64>().sqrt())
+        .collect();
+    let mut order: Vec<usize> = (0..n).collect();
+    order.sort_by(|&i, &j| s[j].total_cmp(&s[i]));
+    let u = (0..m)
+        .map(|i| {
+            order
+                .iter()
+                .map(|&j| if s[j] > 0.0 { u[i][j] / s[j] } else { 0.0 })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    let v = (0..n)
+        .map(|i| order.iter().map(|&j| v[i][j]).collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+    Ok(Svd {
+        u: Matrix::from(u),
+        s: order.iter().map(|&j| s[j]).collect(),
+        v: Matrix::from(v),
+    })
+}
+
+#[cfg(test)]
+mod linalg_tests {
+    use super::*;
+
+    fn assert_close(a: &Matrix, b: &Matrix, tol: f64) {
+        assert_eq!(a.shape(), b.shape());
+        let diff = (a.clone() - b.clone()).norm();
+        assert!(diff < tol, "{:?}\n{:?}", a, b);
+    }
+
+    #[test]
+    fn test_product() {
+        let a = Matrix::from(vec![vec![1.0, 2.0, 3.0], vec![4.0, 5.0, 6.0]]);
+        let b = a.transpose();
+        assert_eq!(b.shape(), (3, 2));
+        assert_eq!(
+            a.dot(&b),
+            Matrix::from(vec![vec![14.0, 32.0], vec![32.0, 77.0]])
+        );
+        let x = a.dot_vector(&Vector::from(vec![1.0, 0.0, -1.0]));
+        assert_eq!(x, Vector::from(vec![-2.0, -2.0]));
+        assert_eq!(Matrix::identity(2).dot(&a), a);
+    }
+
+    #[test]
+    fn test_lu() {
+        let a = Matrix::from(vec![
+            vec![0.0, 2.0, 1.0],
+            vec![1.0, 1.0, 0.0],
+            vec![3.0, 0.0, 1.0],
+        ]);
+        let x = a.solve(&Vector::from(vec![7.0, 3.0, 6.0])).unwrap();
+        for (x, e) in x.data.iter().zip([1.0, 2.0, 3.0].iter()) {
+            assert!((x - e).abs() < 1e-12);
+        }
+        let lu = a.lu().unwrap();
+        assert_close(&lu.p().dot(&a), &lu.l().dot(&lu.u()), 1e-12);
+        assert!((a.det().unwrap() + 5.0).abs() < 1e-12);
+        assert_close(&a.dot(&a.inverse().unwrap()), &Matrix::identity(3), 1e-12);
+
+        let singular = Matrix::from(vec![vec![1.0, 2.0], vec![2.0, 4.0]]);
+        assert!(singular.lu().unwrap().is_singular());
+        assert!(singular.solve(&Vector::from(vec![1.0, 2.0])).is_err());
+        assert_eq!(singular.det().unwrap(), 0.0);
+        assert!(Matrix::new((2, 3)).lu().is_err());
+    }
+
+    #[test]
+    fn test_qr() {
+        let a = Matrix::from(vec![
+            vec![12.0, -51.0, 4.0],
+            vec![6.0, 167.0, -68.0],
+            vec![-4.0, 24.0, -41.0],
+            vec![1.0, 1.0, 1.0],
+        ]);
+        let qr = a.qr().unwrap();
+        assert_eq!(qr.q.shape(), (4, 4));
+        assert_close(&qr.q.dot(&qr.r), &a, 1e-10);
+        assert_close(&qr.q.transpose().dot(&qr.q), &Matrix::identity(4), 1e-12);
+        for i in 0..4 {
+            for j in 0..i.min(3) {
+                assert_eq!(qr.r[(i, j)], 0.0);
+            }
+        }
+    }
+
+    #[test]
+    fn test_eigen() {
+        // real eigenvalues 2, 2 and 1
+        let a = Matrix::from(vec![
+            vec![2.0, 0.0, 0.0],
+            vec![1.0, 2.0, -1.0],
+            vec![1.0, 0.0, 1.0],
+        ]);
+        let values = a.eigenvalues().unwrap();
+        for (v, e) in values.iter().zip([2.0, 2.0, 1.0]) {
+            assert!((v.re - e).abs() < 1e-8 && v.im == 0.0, "{:?}", values);
+        }
+
+        // damped oscillator with wn = 2 and zeta = 0.25 plus a real pole at -5
+        let a = Matrix::from(vec![
+            vec![0.0, 1.0, 0.0],
+            vec![-4.0, -1.0, 0.0],
+            vec![1.0, 0.0, -5.0],
+        ]);
+        let eigen = a.eigen().unwrap();
+        let wd = 15f64.sqrt() / 2.0;
+        assert!((eigen.values[0] - Complex::new(-0.5, wd)).norm() < 1e-12);
+        assert!((eigen.values[1] - Complex::new(-0.5, -wd)).norm() < 1e-12);
+        assert!((eigen.values[2] - Complex::from(-5.0)).norm() < 1e-12);
+        for (lambda, vector) in eigen.values.iter().zip(eigen.vectors.iter()) {
+            let norm = vector.iter().map(|x| x.norm_sq()).sum::<f64>().sqrt();
+            assert!((norm - 1.0).abs() < 1e-12);
+            // A v = lambda v
+            for i in 0..3 {
+                let av = (0..3).fold(Complex::zero(), |s, j| s + vector[j] * a[(i, j)]);
+                assert!((av - *lambda * vector[i]).norm() < 1e-10);
+            }
+        }
+        assert!(eigen.vectors[2].iter().all(|x| x.is_real()));
+
+        // repeated eigenvalue with independent eigenvectors
+        let eigen = Matrix::from(vec![vec![0.0, 0.0], vec![0.0, 0.0]])
+            .eigen()
+            .unwrap();
+        let dot = eigen.vectors[0]
+            .iter()
+            .zip(eigen.vectors[1].iter())
+            .fold(Complex::zero(), |s, (a, b)| s + a.conj() * *b);
+        assert!(dot.norm() < 1e-12);
+    }
+
+    #[test]
+    fn test_svd() {
+        let a = Matrix::from(vec![vec![3.0, 2.0, 2.0], vec![2.0, 3.0, -2.0]]);
+        let svd = a.svd().unwrap();
+        assert!((svd.s[0] - 5.0).abs() < 1e-12);
+        assert!((svd.s[1] - 3.0).abs() < 1e-12);
+        assert_eq!(svd.u.shape(), (2, 2));
+        assert_eq!(svd.v.shape(), (3, 2));
+        let us = svd.u.dot(&Matrix::from_diagonal(&svd.s));
+        assert_close(&us.dot(&svd.v.transpose()), &a, 1e-12);
+        assert_eq!(svd.rank(1e-10), 2);
+        assert!((svd.cond() - 5.0 / 3.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_expm() {
+        // rotation generator
+        let a = Matrix::from(vec![vec![0.0, 1.0], vec![-1.0, 0.0]]);
+        let e = (a * 3.0).expm().unwrap();
+        let (c, s) = (3f64.cos(), 3f64.sin());
+        assert_close(&e, &Matrix::from(vec![vec![c, s], vec![-s, c]]), 1e-12);
+
+        // exp(diag(d)) = diag(exp(d)) for a large norm
+        let e = Matrix::from_diagonal(&[-20.0, 1.0]).expm().unwrap();
+        assert_close(
+            &e,
+            &Matrix::from_diagonal(&[(-20f64).exp(), 1f64.exp()]),
+            1e-12,
+        );
+        assert_eq!(Matrix::new((2, 2)).expm().unwrap(), Matrix::identity(2));
+    }
+}
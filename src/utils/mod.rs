@@ -1,10 +1,16 @@
+pub(crate) mod complex;
 pub(crate) mod dev;
 pub(crate) mod error;
+pub(crate) mod linalg;
 pub(crate) mod matrix;
+pub(crate) mod random;
 pub(crate) mod vector;
 
+pub use complex::Complex;
+pub use linalg::{Eigen, Lu, Qr, Svd};
 pub use matrix::Matrix;
+pub use random::Rng;
 pub use vector::Vector;
 
 #[cfg(test)]
-pub use dev::test_logger_init;
+pub use dev::{test_logger_init, test_model_fin, test_model_init, TEST_CONTROL_LIMIT};
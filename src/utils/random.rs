@@ -0,0 +1,76 @@
+use serde::{Deserialize, Serialize};
+
+/// A small seeded generator, xoshiro128** seeded by SplitMix64,
+/// the same seed gives the same sequence on every platform
+/// the state is kept in 32-bit words so it survives a TOML round trip
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Rng {
+    state: [u32; 4],
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        let mut x = seed;
+        let mut next = || {
+            x = x.wrapping_add(0x9e3779b97f4a7c15);
+            let mut z = x;
+            z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+            z ^ (z >> 31)
+        };
+        let (a, b) = (next(), next());
+        Self {
+            state: [a as u32, (a >> 32) as u32, b as u32, (b >> 32) as u32],
+        }
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        let s = &mut self.state;
+        let result = s[1].wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+        let t = s[1] << 9;
+        s[2] ^= s[0];
+        s[3] ^= s[1];
+        s[1] ^= s[2];
+        s[0] ^= s[3];
+        s[2] ^= t;
+        s[3] = s[3].rotate_left(11);
+        result
+    }
+
+    /// Uniform in [0, 1) with 53 random bits
+    pub fn uniform(&mut self) -> f64 {
+        let high = (self.next_u32() >> 5) as u64;
+        let low = (self.next_u32() >> 6) as u64;
+        ((high << 26) | low) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Standard normal by the Box-Muller transform
+    pub fn normal(&mut self) -> f64 {
+        let u_1 = 1.0 - self.uniform();
+        let u_2 = self.uniform();
+        (-2.0 * u_1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u_2).cos()
+    }
+}
+
+#[cfg(test)]
+mod random_tests {
+    use super::*;
+
+    #[test]
+    fn test_rng() {
+        let mut a = Rng::new(42);
+        let mut b = Rng::new(42);
+        assert!((0..100).all(|_| a.next_u32() == b.next_u32()));
+        assert_ne!(Rng::new(1).next_u32(), Rng::new(2).next_u32());
+
+        let n = 100000;
+        let samples: Vec<f64> = (0..n).map(|_| a.normal()).collect();
+        let mean = samples.iter().sum::<f64>() / n as f64;
+        let variance = samples.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / n as f64;
+        assert!(mean.abs() < 0.02);
+        assert!((variance - 1.0).abs() < 0.02);
+        assert!((0..1000)
+            .map(|_| a.uniform())
+            .all(|x| (0.0..1.0).contains(&x)));
+    }
+}